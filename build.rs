@@ -0,0 +1,34 @@
+use std::path::Path;
+use std::process::Command;
+
+fn main() {
+    let git_hash = command_output("git", &["rev-parse", "--short=12", "HEAD"]);
+    println!(
+        "cargo:rustc-env=SKILLCTL_GIT_HASH={}",
+        git_hash.as_deref().unwrap_or("unknown")
+    );
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let rustc_version = command_output(&rustc, &["--version"]);
+    println!(
+        "cargo:rustc-env=SKILLCTL_RUSTC_VERSION={}",
+        rustc_version.as_deref().unwrap_or("unknown")
+    );
+    if Path::new(".git/HEAD").exists() {
+        println!("cargo:rerun-if-changed=.git/HEAD");
+    }
+    if Path::new(".git/index").exists() {
+        println!("cargo:rerun-if-changed=.git/index");
+    }
+    println!("cargo:rerun-if-changed=build.rs");
+}
+
+/// Trimmed stdout of `program args`, or `None` when it cannot run or fails.
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let text = String::from_utf8(output.stdout).ok()?;
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
@@ -0,0 +1,83 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+use crate::sync::{PlanKind, PlanOp};
+
+/// Appends one JSON line per executed op to `audit.log_path`.
+#[derive(Debug, Clone)]
+pub struct AuditLog {
+    path: PathBuf,
+    command: String,
+    target: String,
+}
+
+#[derive(Debug, Serialize)]
+struct AuditEntry<'a> {
+    /// Seconds since the Unix epoch.
+    timestamp: u64,
+    command: &'a str,
+    target: &'a str,
+    skill: &'a str,
+    kind: &'static str,
+    dry_run: bool,
+    success: bool,
+}
+
+impl AuditLog {
+    pub fn new(path: &Path, command: impl Into<String>, target: impl Into<String>) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            command: command.into(),
+            target: target.into(),
+        }
+    }
+
+    /// Appends an entry for `op`. Write failures are reported on stderr and
+    /// never fail the operation being audited.
+    pub fn record(&self, op: &PlanOp, dry_run: bool, success: bool) {
+        let entry = AuditEntry {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or_default(),
+            command: &self.command,
+            target: &self.target,
+            skill: &op.skill,
+            kind: op.kind.label(),
+            dry_run,
+            success,
+        };
+        if let Err(err) = self.append(&entry) {
+            tracing::warn!(path = %self.path.display(), error = %err, "audit log write failed");
+            eprintln!(
+                "{}",
+                crate::tr!(
+                    "警告: 監査ログを書き込めません: {}: {}",
+                    "warning: failed to write audit log: {}: {}",
+                    self.path.display(),
+                    err
+                )
+            );
+        }
+    }
+
+    fn append(&self, entry: &AuditEntry<'_>) -> std::io::Result<()> {
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+        // O_APPEND plus a single write keeps concurrent lines from interleaving.
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        file.write_all(&line)
+    }
+}
+
+/// Whether `op` changes (or would change) files and so belongs in the log.
+pub(crate) fn is_audited(op: &PlanOp) -> bool {
+    op.kind != PlanKind::Skip
+}
@@ -0,0 +1,239 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use semver::Version;
+use tempfile::NamedTempFile;
+
+use crate::config::{SkillLayout, SkillsConfig};
+use crate::error::{AppError, AppResult};
+use crate::skill::{not_found_hint, validate_skill_id_with};
+use crate::status::list_skills_with;
+
+/// Front-matter key holding a skill's version.
+const VERSION_KEY: &str = "version";
+
+/// Which part of a skill's semver version `bump` increments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BumpLevel {
+    Major,
+    Minor,
+    #[default]
+    Patch,
+}
+
+/// The outcome of [`bump_skill`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bump {
+    /// The file whose front matter holds the version.
+    pub path: PathBuf,
+    pub previous: Version,
+    pub version: Version,
+}
+
+/// Increments the `version` in the front matter of `skill`'s skill file
+/// under `root` (`skill_file` in its directory, or the skill itself under
+/// the flat layout) and rewrites the file unless `dry_run` is set.
+pub fn bump_skill(
+    root: &Path,
+    skill: &str,
+    rules: &SkillsConfig,
+    skill_file: &str,
+    level: BumpLevel,
+    dry_run: bool,
+) -> AppResult<Bump> {
+    validate_skill_id_with(skill, rules)?;
+    let skills = list_skills_with(root, rules)?;
+    if !skills.iter().any(|name| name == skill) {
+        return Err(AppError::exec(
+            crate::tr!(
+                "skill が存在しません: {}",
+                "Skill does not exist: {}",
+                skill
+            ),
+            Some(not_found_hint(
+                skill,
+                skills.iter().map(String::as_str),
+                crate::tr!(
+                    "list で一覧を確認してください",
+                    "Run list to see available skills."
+                ),
+            )),
+        ));
+    }
+    let path = match rules.layout {
+        SkillLayout::Nested => root.join(skill).join(skill_file),
+        SkillLayout::Flat => root.join(skill),
+    };
+    let text = fs::read_to_string(&path).map_err(|err| {
+        AppError::io(
+            crate::tr!(
+                "skill ファイルを読み込めません: {}",
+                "Failed to read skill file: {}",
+                path.display()
+            ),
+            err,
+        )
+    })?;
+    let (bumped, previous, version) = bump_front_matter(&text, level).map_err(|message| {
+        AppError::exec(
+            format!("{}: {}", message, path.display()),
+            Some(crate::tr!(
+                "front matter に version: 1.2.3 の形式で version を記載してください",
+                "Add a version such as version: 1.2.3 to the front matter."
+            )),
+        )
+    })?;
+    if !dry_run {
+        write_atomic(&path, &bumped)?;
+    }
+    tracing::debug!(skill, %previous, %version, dry_run, "bumped skill version");
+    Ok(Bump {
+        path,
+        previous,
+        version,
+    })
+}
+
+/// Replaces `path` with `text` through a temp file in the same directory,
+/// keeping the file's permissions, so a crash never leaves it truncated.
+fn write_atomic(path: &Path, text: &str) -> AppResult<()> {
+    let write_error = |err: io::Error| {
+        AppError::io(
+            crate::tr!(
+                "skill ファイルを書き込めません: {}",
+                "Failed to write skill file: {}",
+                path.display()
+            ),
+            err,
+        )
+    };
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let mut temp = NamedTempFile::new_in(parent).map_err(write_error)?;
+    temp.write_all(text.as_bytes()).map_err(write_error)?;
+    let permissions = fs::metadata(path).map_err(write_error)?.permissions();
+    fs::set_permissions(temp.path(), permissions).map_err(write_error)?;
+    temp.persist(path).map_err(|err| write_error(err.error))?;
+    Ok(())
+}
+
+/// Rewrites the `version` value in the `---` front matter at the top of
+/// `text`, leaving every other byte (quotes, comments, line endings) as is.
+/// Returns the new text with the previous and new versions, or a message
+/// when there is no front matter, no top-level `version` or it is not semver.
+fn bump_front_matter(text: &str, level: BumpLevel) -> Result<(String, Version, Version), String> {
+    let mut lines = text.split_inclusive('\n');
+    let mut out = String::with_capacity(text.len() + 2);
+    match lines.next() {
+        Some(first) if first.trim_end() == "---" => out.push_str(first),
+        _ => {
+            return Err(crate::tr!("front matter がありません", "No front matter"));
+        }
+    }
+    let mut bumped = None;
+    for line in lines.by_ref() {
+        if line.trim_end() == "---" {
+            out.push_str(line);
+            break;
+        }
+        match line
+            .strip_prefix(VERSION_KEY)
+            .and_then(|rest| rest.strip_prefix(':'))
+        {
+            Some(rest) if bumped.is_none() => {
+                let (start, end) = scalar_span(rest);
+                let previous = Version::parse(&rest[start..end]).map_err(|err| {
+                    crate::tr!(
+                        "version が semver ではありません ({}: {})",
+                        "version is not valid semver ({}: {})",
+                        &rest[start..end],
+                        err
+                    )
+                })?;
+                let version = increment(&previous, level);
+                out.push_str(&line[..line.len() - rest.len()]);
+                out.push_str(&rest[..start]);
+                out.push_str(&version.to_string());
+                out.push_str(&rest[end..]);
+                bumped = Some((previous, version));
+            }
+            _ => out.push_str(line),
+        }
+    }
+    let Some((previous, version)) = bumped else {
+        return Err(crate::tr!(
+            "front matter に version がありません",
+            "No version in the front matter"
+        ));
+    };
+    out.extend(lines);
+    Ok((out, previous, version))
+}
+
+/// Byte range of the scalar after `key:`, without surrounding whitespace,
+/// quotes or a trailing `# comment`.
+fn scalar_span(rest: &str) -> (usize, usize) {
+    let start = rest.len() - rest.trim_start().len();
+    let value = &rest[start..];
+    if let Some(quote) = value.chars().next().filter(|c| *c == '"' || *c == '\'') {
+        let end = value[1..].find(quote).map_or(value.len(), |end| end + 1);
+        return (start + 1, start + end);
+    }
+    let end = value.find(" #").unwrap_or(value.len());
+    (start, start + value[..end].trim_end().len())
+}
+
+/// The next `level` release after `version`. A prerelease of that level is
+/// released instead (`1.2.3-rc.1` → `1.2.3` for patch, `1.3.0-rc.1` →
+/// `1.3.0` for minor); build metadata is dropped.
+fn increment(version: &Version, level: BumpLevel) -> Version {
+    let (major, minor, patch) = (version.major, version.minor, version.patch);
+    let pre = !version.pre.is_empty();
+    match level {
+        BumpLevel::Major if pre && minor == 0 && patch == 0 => Version::new(major, 0, 0),
+        BumpLevel::Major => Version::new(major + 1, 0, 0),
+        BumpLevel::Minor if pre && patch == 0 => Version::new(major, minor, 0),
+        BumpLevel::Minor => Version::new(major, minor + 1, 0),
+        BumpLevel::Patch if pre => Version::new(major, minor, patch),
+        BumpLevel::Patch => Version::new(major, minor, patch + 1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bump_front_matter_keeps_the_rest_of_the_file() {
+        let text = "---\r\nname: demo\r\nversion: \"1.2.3\" # keep\r\n---\r\nversion: 9.9.9\r\n";
+        let (bumped, previous, version) = bump_front_matter(text, BumpLevel::Major).unwrap();
+        assert_eq!(previous, Version::new(1, 2, 3));
+        assert_eq!(version, Version::new(2, 0, 0));
+        assert_eq!(
+            bumped,
+            "---\r\nname: demo\r\nversion: \"2.0.0\" # keep\r\n---\r\nversion: 9.9.9\r\n"
+        );
+
+        let (_, _, version) =
+            bump_front_matter("---\nversion: 1.2.3-rc.1\n---\n", BumpLevel::Patch).unwrap();
+        assert_eq!(version, Version::new(1, 2, 3));
+        let (_, _, version) =
+            bump_front_matter("---\nversion: 1.3.0-rc.1+b5\n---\n", BumpLevel::Minor).unwrap();
+        assert_eq!(version, Version::new(1, 3, 0));
+        let (_, _, version) =
+            bump_front_matter("---\nversion: 1.3.1-rc.1\n---\n", BumpLevel::Minor).unwrap();
+        assert_eq!(version, Version::new(1, 4, 0));
+    }
+
+    #[test]
+    fn bump_front_matter_rejects_missing_or_invalid_versions() {
+        assert!(bump_front_matter("# demo\n", BumpLevel::Patch).is_err());
+        assert!(
+            bump_front_matter("---\nname: demo\n---\nversion: 1.0.0\n", BumpLevel::Patch).is_err()
+        );
+        assert!(bump_front_matter("---\nversion: 1.2\n---\n", BumpLevel::Patch).is_err());
+    }
+}
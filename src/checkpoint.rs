@@ -0,0 +1,243 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tempfile::NamedTempFile;
+
+use crate::config::HashAlgo;
+use crate::digest::{DigestOptions, digest_dir, digest_file};
+use crate::error::{AppError, AppResult};
+use crate::sync::{Plan, PlanKind, PlanOp};
+
+/// Checkpoint written under the destination root by `push` / `import --resume`.
+pub const CHECKPOINT_FILE: &str = ".skillctl-checkpoint.toml";
+
+/// Note on ops turned into skips because a checkpoint records them as done.
+pub const CHECKPOINT_NOTE: &str = "checkpoint";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CheckpointFile {
+    #[serde(default)]
+    completed: Vec<CompletedOp>,
+}
+
+/// One op finished in an interrupted run: its kind and the digest of what it
+/// copied from (`None` for ops without a source, such as prune).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct CompletedOp {
+    skill: String,
+    kind: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    digest: Option<String>,
+}
+
+impl CompletedOp {
+    fn of(op: &PlanOp, digest: Option<String>) -> Self {
+        Self {
+            skill: op.skill.clone(),
+            kind: op.kind.label().to_string(),
+            digest,
+        }
+    }
+}
+
+/// Ops that already completed in an interrupted run, persisted after each op
+/// so a `--resume` re-run can skip them.
+///
+/// An entry only matches a freshly planned op of the same kind whose source
+/// still has the recorded digest, so a skill that changed since (or a stale
+/// checkpoint from an unrelated run) is synced again rather than skipped.
+#[derive(Debug)]
+pub struct Checkpoint {
+    path: PathBuf,
+    completed: BTreeMap<String, CompletedOp>,
+}
+
+impl Checkpoint {
+    /// Loads the checkpoint under `root`, starting empty when there is none.
+    pub fn load(root: &Path) -> AppResult<Self> {
+        let path = root.join(CHECKPOINT_FILE);
+        let completed = match fs::read_to_string(&path) {
+            Ok(raw) => toml::from_str::<CheckpointFile>(&raw)
+                .map_err(|err| {
+                    AppError::config_with_source(
+                        crate::tr!(
+                            "チェックポイントを解析できません: {}",
+                            "Cannot parse checkpoint: {}",
+                            path.display()
+                        ),
+                        Some(crate::tr!(
+                            "ファイルを削除して --resume なしで実行し直してください",
+                            "Delete the file and run again without --resume."
+                        )),
+                        err,
+                    )
+                })?
+                .completed
+                .into_iter()
+                .map(|entry| (entry.skill.clone(), entry))
+                .collect(),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(err) => {
+                return Err(AppError::io(
+                    crate::tr!(
+                        "チェックポイントを読み込めません: {}",
+                        "Cannot read checkpoint: {}",
+                        path.display()
+                    ),
+                    err,
+                ));
+            }
+        };
+        tracing::debug!(path = %path.display(), completed = completed.len(), "checkpoint loaded");
+        Ok(Self { path, completed })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether `op` is recorded as done: same skill and kind, and its source
+    /// still digests as it did when the op completed.
+    pub fn is_completed(&self, op: &PlanOp) -> bool {
+        let Some(entry) = self.completed.get(&op.skill) else {
+            return false;
+        };
+        entry.kind == op.kind.label()
+            && source_digest(op).is_ok_and(|digest| entry.digest == digest)
+    }
+
+    /// Turns ops of `plan` that the checkpoint records as done into skips
+    /// noted [`CHECKPOINT_NOTE`].
+    pub fn apply_to(&self, plan: &mut Plan) {
+        for op in &mut plan.ops {
+            if op.kind != PlanKind::Skip && self.is_completed(op) {
+                *op = PlanOp {
+                    kind: PlanKind::Skip,
+                    skill: op.skill.clone(),
+                    src: None,
+                    dest: None,
+                    note: Some(CHECKPOINT_NOTE.to_string()),
+                };
+            }
+        }
+    }
+
+    /// Marks `op` as done, with the digest of its source, and rewrites the
+    /// checkpoint atomically.
+    pub fn record(&mut self, op: &PlanOp) -> AppResult<()> {
+        let entry = CompletedOp::of(op, source_digest(op)?);
+        self.completed.insert(op.skill.clone(), entry);
+        self.save()
+    }
+
+    /// Removes the checkpoint after a fully successful run.
+    pub fn clear(self) -> AppResult<()> {
+        match fs::remove_file(&self.path) {
+            Ok(()) => Ok(()),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(self.write_error(err)),
+        }
+    }
+
+    fn save(&self) -> AppResult<()> {
+        let body = toml::to_string(&CheckpointFile {
+            completed: self.completed.values().cloned().collect(),
+        })
+        .map_err(|err| self.write_error(std::io::Error::other(err)))?;
+        let parent = self.path.parent().unwrap_or(Path::new("."));
+        let mut temp = NamedTempFile::new_in(parent).map_err(|err| self.write_error(err))?;
+        temp.write_all(body.as_bytes())
+            .map_err(|err| self.write_error(err))?;
+        temp.persist(&self.path)
+            .map_err(|err| self.write_error(err.error))?;
+        Ok(())
+    }
+
+    fn write_error(&self, err: std::io::Error) -> AppError {
+        AppError::io(
+            crate::tr!(
+                "チェックポイントを書き込めません: {}",
+                "Cannot write checkpoint: {}",
+                self.path.display()
+            ),
+            err,
+        )
+    }
+}
+
+/// Digest of what `op` copies from, independent of `hash.*` settings so a
+/// config change between runs cannot make a changed source look done.
+fn source_digest(op: &PlanOp) -> AppResult<Option<String>> {
+    let Some(src) = op.src.as_deref() else {
+        return Ok(None);
+    };
+    let options = DigestOptions {
+        include_mode: true,
+        ..DigestOptions::new(HashAlgo::Blake3)
+    };
+    let digest = if src.is_dir() {
+        digest_dir(src, &options)?
+    } else {
+        digest_file(src, &options)?
+    };
+    Ok(Some(digest))
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::hooks::PlanHooks;
+
+    fn op(kind: PlanKind, skill: &str, src: &Path) -> PlanOp {
+        PlanOp {
+            kind,
+            skill: skill.to_string(),
+            src: Some(src.join(skill)),
+            dest: None,
+            note: None,
+        }
+    }
+
+    #[test]
+    fn records_survive_reload_and_clear_removes_file() {
+        let dir = TempDir::new().unwrap();
+        let src = dir.path().join("global");
+        for skill in ["a", "b"] {
+            fs::create_dir_all(src.join(skill)).unwrap();
+            fs::write(src.join(skill).join("SKILL.md"), skill).unwrap();
+        }
+        let mut checkpoint = Checkpoint::load(dir.path()).unwrap();
+        checkpoint
+            .record(&op(PlanKind::Install, "a", &src))
+            .unwrap();
+
+        let reloaded = Checkpoint::load(dir.path()).unwrap();
+        assert!(reloaded.is_completed(&op(PlanKind::Install, "a", &src)));
+        assert!(!reloaded.is_completed(&op(PlanKind::Update, "a", &src)));
+        assert!(!reloaded.is_completed(&op(PlanKind::Install, "b", &src)));
+
+        let mut plan = Plan {
+            ops: vec![
+                op(PlanKind::Install, "a", &src),
+                op(PlanKind::Install, "b", &src),
+            ],
+            hooks: PlanHooks::default(),
+            source_root: None,
+        };
+        reloaded.apply_to(&mut plan);
+        assert_eq!(plan.ops[0].kind, PlanKind::Skip);
+        assert_eq!(plan.ops[0].note.as_deref(), Some(CHECKPOINT_NOTE));
+        assert_eq!(plan.ops[1].kind, PlanKind::Install);
+
+        fs::write(src.join("a/SKILL.md"), "changed").unwrap();
+        assert!(!reloaded.is_completed(&op(PlanKind::Install, "a", &src)));
+
+        reloaded.clear().unwrap();
+        assert!(!dir.path().join(CHECKPOINT_FILE).exists());
+    }
+}
@@ -1,24 +1,117 @@
+use std::collections::BTreeMap;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
 use std::process::ExitCode;
+use std::time::Duration;
 
-use clap::{ArgGroup, Parser, Subcommand};
+use clap::{ArgGroup, Args, CommandFactory, Parser, Subcommand};
+use clap_complete::{ArgValueCandidates, CompleteEnv, CompletionCandidate};
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::EnvFilter;
 
-use crate::config::Config;
-use crate::diff::run_diff;
-use crate::doctor::{doctor_root, group_issues_by_skill};
+use crate::audit::AuditLog;
+use crate::bump::{BumpLevel, bump_skill};
+use crate::checkpoint::{CHECKPOINT_NOTE, Checkpoint};
+use crate::color::{Color, ColorChoice, color_enabled, paint, set_color_choice};
+use crate::config::{Config, DigestEncoding, HashAlgo, Target, config_schema, resolve_in};
+use crate::diff::{DiffOptions, run_diff, run_diff_file};
+use crate::digest::{MAX_DIGEST_LEN, build_ignore_set, encode_digest};
+use crate::doctor::{
+    DoctorReport, Severity, count_issues_at_least, doctor_root_with, doctor_skill,
+    group_issues_by_skill, render_doctor_json,
+};
 use crate::error::{AppError, AppResult};
-use crate::status::{list_skills, render_status_table, status_for_target};
-use crate::sync::{Selection, execute_plan, plan_import, plan_push, summarize_plan};
+use crate::events::{Event, OutputFormat, StatusEventWriter, write_event};
+use crate::export::export_skill;
+use crate::lock::RootLock;
+use crate::output::{emit_line, emit_text, output, set_output_file};
+use crate::skill::{
+    init_skill, parse_rename_map, parse_skill_list, skill_key, validate_skill_id_with,
+};
+use crate::source::{is_archive_path, open_global_source, open_source};
+use crate::status::{
+    SkillTiming, State, StatusCounter, StatusSink, StatusSort, StatusTableWriter, TableStyle,
+    compare_targets, ensure_root_dir, list_skill_digests, list_skills_with, render_compare_table,
+    render_since_sync_table, render_timings, set_root_wait, since_sync, skill_digest,
+    sort_compare_rows, sort_since_sync_rows, sort_status_rows, status_for_target_selected,
+};
+use crate::sync::{
+    CONFLICT_NOTE, FORCE_NOTE, ImportConflict, ImportMode, Plan, PlanKind, PlanOp, PushOptions,
+    RenderOpts, Selection, execute_plan_with, plan_hash, plan_import_all, plan_import_with,
+    plan_push_with, porcelain_plan, prune_files, record_synced, render_plan, summarize_plan,
+};
+use crate::targets::{render_targets_json, render_targets_table, summarize_targets};
 
 #[derive(Debug, Parser)]
 #[command(name = "skillctl", version, about = "skill sync CLI")]
 pub struct Cli {
+    /// Log level for diagnostics on stderr (off/error/warn/info/debug/trace); falls back to RUST_LOG.
+    #[arg(long, global = true, value_name = "LEVEL")]
+    log_level: Option<LevelFilter>,
+    /// When to color output (auto: only on a terminal and when NO_COLOR is unset).
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        value_name = "WHEN",
+        default_value_t = ColorChoice::Auto
+    )]
+    color: ColorChoice,
+    /// Resolve relative paths (config, roots, file arguments) against DIR instead of the CWD.
+    #[arg(short = 'C', long, global = true, value_name = "DIR")]
+    chdir: Option<PathBuf>,
+    /// Output format; `ndjson` streams one JSON event per line, `json` is for `targets` and `doctor`, `plain` / `csv` for `status`.
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        value_name = "FORMAT",
+        default_value_t = OutputFormat::Text
+    )]
+    format: OutputFormat,
+    /// Wait up to SECS for a missing global or target root to appear before failing.
+    #[arg(long, global = true, value_name = "SECS")]
+    wait_root: Option<u64>,
+    /// Digest with at most N worker threads (`1` is sequential); overrides config's parallelism.
+    #[arg(short, long, global = true, value_name = "N")]
+    jobs: Option<NonZeroUsize>,
+    /// Encoding of digests in status and list output; overrides config's display.digest_encoding.
+    #[arg(long, global = true, value_enum, value_name = "ENCODING")]
+    output_digest_format: Option<DigestEncoding>,
+    /// Limit targets to those of config's [profiles.NAME] (`targets`, `--all`, `--target`).
+    #[arg(long, global = true, value_name = "NAME")]
+    profile: Option<String>,
+    /// Write the command's output to PATH (created or truncated) instead of stdout; for export, the archive path.
+    #[arg(short, long, global = true, value_name = "PATH")]
+    output: Option<PathBuf>,
     #[command(subcommand)]
     command: Commands,
 }
 
+/// Guards an apply against drift from a reviewed dry-run.
+#[derive(Debug, Args)]
+struct PlanCheckArgs {
+    /// Print `plan-hash <hash>` after the planned ops.
+    #[arg(long)]
+    plan_hash: bool,
+    /// Abort unless the recomputed plan hash equals HASH.
+    #[arg(long, value_name = "HASH")]
+    expect_plan: Option<String>,
+}
+
 #[derive(Debug, Subcommand)]
 enum Commands {
-    Targets,
+    /// Print version and build information.
+    Version {
+        /// Also print the git commit, rustc version and enabled features.
+        #[arg(long)]
+        verbose: bool,
+    },
+    Targets {
+        /// Print name, root, whether the root exists and the skill count.
+        #[arg(long)]
+        long: bool,
+    },
     #[command(group(
         ArgGroup::new("scope")
             .required(true)
@@ -27,76 +120,374 @@ enum Commands {
     List {
         #[arg(long)]
         global: bool,
-        #[arg(long)]
+        #[arg(long, add = ArgValueCandidates::new(target_candidates))]
         target: Option<String>,
+        /// Print `<skill>\t<full-digest>` instead of names only.
+        #[arg(long, alias = "include-digests")]
+        digests: bool,
     },
+    /// Print one skill's full digest.
     #[command(group(
         ArgGroup::new("scope")
             .required(true)
-            .args(["target", "all"])
+            .args(["global", "target"])
     ))]
-    Status {
+    Digest {
+        skill: String,
         #[arg(long)]
+        global: bool,
+        #[arg(long, add = ArgValueCandidates::new(target_candidates))]
         target: Option<String>,
+        /// Override hash.algo for this run.
+        #[arg(long, value_enum, value_name = "ALGO")]
+        algo: Option<HashAlgo>,
+        /// Disregard hash.ignore for this run and hash every file.
         #[arg(long)]
-        all: bool,
+        no_ignore: bool,
     },
+    /// Increment the semver `version` in a skill's front matter (patch by default).
     #[command(group(
         ArgGroup::new("scope")
             .required(true)
-            .args(["global", "target", "all"])
+            .args(["global", "target"])
     ))]
-    Doctor {
+    Bump {
+        skill: String,
         #[arg(long)]
         global: bool,
+        #[arg(long, add = ArgValueCandidates::new(target_candidates))]
+        target: Option<String>,
+        #[arg(long, conflicts_with_all = ["minor", "patch"])]
+        major: bool,
+        #[arg(long, conflicts_with = "patch")]
+        minor: bool,
+        #[arg(long)]
+        patch: bool,
+        /// Print the new version without rewriting the file.
         #[arg(long)]
+        dry_run: bool,
+    },
+    #[command(group(
+        ArgGroup::new("scope")
+            .required(true)
+            .args(["target", "all", "compare"])
+    ))]
+    Status {
+        #[arg(long, add = ArgValueCandidates::new(target_candidates))]
         target: Option<String>,
         #[arg(long)]
         all: bool,
+        /// Only show skills named in PATH, one per line (`-` for stdin).
+        #[arg(long, value_name = "PATH")]
+        skills_from: Option<PathBuf>,
+        /// Override hash.algo for this run.
+        #[arg(long, value_enum, value_name = "ALGO")]
+        algo: Option<HashAlgo>,
+        /// Disregard hash.ignore for this run and compare every file.
+        #[arg(long)]
+        no_ignore: bool,
+        /// Use PATH as the global root for this run instead of config's global_root.
+        #[arg(long, value_name = "PATH")]
+        global_root: Option<PathBuf>,
+        /// Print per-skill digest timings (slowest first) to stderr after the table.
+        #[arg(long)]
+        timings: bool,
+        /// Row order; `state` groups diff, missing, extra, same (each by name).
+        #[arg(long, value_enum, value_name = "ORDER", default_value_t = StatusSort::Name)]
+        sort: StatusSort,
+        /// Show a missing and an extra skill differing only by case as one `renamed` row.
+        #[arg(long)]
+        detect_renames: bool,
+        /// With --all, show only these skills under each target (repeatable); unknown names are skipped.
+        #[arg(
+            long,
+            value_name = "SKILL",
+            requires = "all",
+            conflicts_with = "skills_from"
+        )]
+        only: Vec<String>,
+        /// List the added, removed and modified files under each diff skill.
+        #[arg(long, conflicts_with_all = ["compare", "count", "since_sync"])]
+        detail: bool,
+        /// Compare paths, sizes and mtimes instead of content (approximate `likely-*` states).
+        #[arg(long, conflicts_with_all = ["compare", "detail", "since_sync"])]
+        fast: bool,
+        /// Print only the number of skills in STATE (comma-separated or repeated) instead of the table.
+        #[arg(long, value_enum, value_delimiter = ',', value_name = "STATE")]
+        count: Vec<State>,
+        /// Print whole digests instead of the shortened `abc...xyz` form.
+        #[arg(long)]
+        full_digest: bool,
+        /// Compare two targets with each other, ignoring global.
+        #[arg(
+            long,
+            num_args = 2,
+            value_names = ["TARGET_A", "TARGET_B"],
+            conflicts_with_all = ["count", "detect_renames", "global_root", "timings"],
+            add = ArgValueCandidates::new(target_candidates)
+        )]
+        compare: Vec<String>,
+        /// Classify global skills against the digests recorded by the last push to --target.
+        #[arg(
+            long,
+            requires = "target",
+            conflicts_with_all = ["all", "compare", "count", "detect_renames", "timings"]
+        )]
+        since_sync: bool,
+        /// Re-render the table whenever the global or target root changes.
+        #[cfg(feature = "watch")]
+        #[arg(long, conflicts_with_all = ["all", "count", "compare", "since_sync"])]
+        watch: bool,
     },
     #[command(group(
-        ArgGroup::new("selection")
+        ArgGroup::new("scope")
             .required(true)
-            .args(["skill", "all"])
+            .args(["global", "target", "all"])
     ))]
-    Push {
+    Doctor {
+        /// Check only this skill.
         skill: Option<String>,
         #[arg(long)]
+        global: bool,
+        #[arg(long, add = ArgValueCandidates::new(target_candidates))]
+        target: Option<String>,
+        #[arg(long)]
         all: bool,
+        /// Exit non-zero when an issue of SEVERITY or worse is found.
+        #[arg(long, value_enum, value_name = "SEVERITY")]
+        fail_on: Option<Severity>,
+        /// Also flag names invalid on Windows and paths over doctor.max_path_len.
         #[arg(long)]
-        target: String,
+        portability: bool,
+        /// Also flag files that start with a BOM or are not valid UTF-8.
+        #[arg(long)]
+        check_encoding: bool,
+    },
+    #[cfg_attr(
+        not(feature = "interactive"),
+        command(group(
+            ArgGroup::new("selection")
+                .required(true)
+                .args(["skills", "all", "skills_from", "prune_only"])
+        ))
+    )]
+    #[cfg_attr(
+        feature = "interactive",
+        command(group(
+            ArgGroup::new("selection")
+                .required(true)
+                .args(["skills", "all", "skills_from", "prune_only", "interactive"])
+        ))
+    )]
+    Push {
+        /// Skills to push (one or more).
+        #[arg(value_name = "SKILL")]
+        skills: Vec<String>,
+        #[arg(long)]
+        all: bool,
+        /// Read newline-separated skill names from PATH (`-` for stdin).
+        #[arg(long, value_name = "PATH")]
+        skills_from: Option<PathBuf>,
+        /// Pick the skills to push from a checklist (requires a terminal).
+        #[cfg(feature = "interactive")]
+        #[arg(long)]
+        interactive: bool,
+        #[arg(
+            long,
+            required_unless_present = "target_env",
+            add = ArgValueCandidates::new(target_candidates)
+        )]
+        target: Option<String>,
+        /// Read the target name from the environment variable VAR.
+        #[arg(
+            long,
+            value_name = "VAR",
+            alias = "target-from-env",
+            conflicts_with = "target"
+        )]
+        target_env: Option<String>,
         #[arg(long)]
         dry_run: bool,
+        /// Execute the plan when sync.require_apply makes dry-run the default.
+        #[arg(long, conflicts_with = "dry_run")]
+        apply: bool,
+        /// Plan without changing anything and exit 1 if any skill would be installed, updated or pruned.
+        #[arg(long = "check", conflicts_with_all = ["apply", "init_global", "resume"])]
+        check_sync: bool,
         #[arg(long)]
         prune: bool,
+        /// Only remove target skills missing from global; never install or update.
+        #[arg(long, conflicts_with = "force")]
+        prune_only: bool,
+        /// Only install or update skills that are missing or differ; never prune.
+        #[arg(long, conflicts_with_all = ["prune", "prune_only", "force"])]
+        changed_only: bool,
+        /// Re-copy skills even when global and target already match.
+        #[arg(long)]
+        force: bool,
+        /// Create the named skill in global from a template first if it does not exist.
+        #[arg(long)]
+        init_global: bool,
+        /// Print ops as stable `<kind>\t<skill>\t<note>` lines.
+        #[arg(long)]
+        porcelain: bool,
+        /// Under each prune op, list every file it deletes.
+        #[arg(long)]
+        list_files: bool,
+        /// Follow each summary line with why the op was planned.
+        #[arg(long, conflicts_with = "porcelain")]
+        explain: bool,
+        /// Wait for a concurrent push/import on the same root instead of failing.
+        #[arg(long)]
+        wait: bool,
+        /// Record completed ops in a checkpoint and skip those already recorded.
+        #[arg(long)]
+        resume: bool,
+        /// After the run, list skills skipped as extra, diff or missing on stderr.
+        #[arg(long)]
+        warn_skips: bool,
+        /// Override hash.algo for this run.
+        #[arg(long, value_enum, value_name = "ALGO")]
+        algo: Option<HashAlgo>,
+        /// Disregard hash.ignore for this run and compare every file.
+        #[arg(long)]
+        no_ignore: bool,
+        /// Use PATH as the global root for this run instead of config's global_root.
+        #[arg(long, value_name = "PATH")]
+        global_root: Option<PathBuf>,
+        #[command(flatten)]
+        check: PlanCheckArgs,
     },
     #[command(group(
         ArgGroup::new("selection")
             .required(true)
-            .args(["skill", "all"])
+            .args(["skill", "all", "skills_from"])
     ))]
     Import {
         skill: Option<String>,
         #[arg(long)]
         all: bool,
-        #[arg(long)]
-        from: String,
+        /// Read newline-separated skill names from PATH (`-` for stdin).
+        #[arg(long, value_name = "PATH")]
+        skills_from: Option<PathBuf>,
+        #[arg(
+            long,
+            required_unless_present = "from_all",
+            add = ArgValueCandidates::new(target_candidates)
+        )]
+        from: Option<String>,
+        /// Import from every target, skipping skills whose content differs between them.
+        #[arg(long, requires = "all", conflicts_with = "from")]
+        from_all: bool,
+        /// With --from-all, take conflicting skills from this target.
+        #[arg(long, value_name = "TARGET", requires = "from_all", add = ArgValueCandidates::new(target_candidates))]
+        prefer: Option<String>,
+        /// Import target skills under new names read from PATH as `old=new` lines.
+        #[arg(long, value_name = "PATH", conflicts_with = "from_all")]
+        rename_map: Option<PathBuf>,
         #[arg(long)]
         dry_run: bool,
+        /// Execute the plan when sync.require_apply makes dry-run the default.
+        #[arg(long, conflicts_with = "dry_run")]
+        apply: bool,
         #[arg(long)]
         overwrite: bool,
+        /// Copy target files over global but keep global-only files.
+        #[arg(long, conflicts_with = "overwrite")]
+        merge: bool,
+        /// Print ops as stable `<kind>\t<skill>\t<note>` lines.
+        #[arg(long)]
+        porcelain: bool,
+        /// Follow each summary line with why the op was planned.
+        #[arg(long, conflicts_with = "porcelain")]
+        explain: bool,
+        /// Wait for a concurrent push/import on the same root instead of failing.
+        #[arg(long)]
+        wait: bool,
+        /// Record completed ops in a checkpoint and skip those already recorded.
+        #[arg(long)]
+        resume: bool,
+        /// After the run, list skills skipped as extra, diff, missing or conflict on stderr.
+        #[arg(long)]
+        warn_skips: bool,
+        /// Override hash.algo for this run.
+        #[arg(long, value_enum, value_name = "ALGO")]
+        algo: Option<HashAlgo>,
+        /// Disregard hash.ignore for this run and compare every file.
+        #[arg(long)]
+        no_ignore: bool,
+        /// Use PATH as the global root for this run instead of config's global_root.
+        #[arg(long, value_name = "PATH")]
+        global_root: Option<PathBuf>,
+        #[command(flatten)]
+        check: PlanCheckArgs,
     },
     Diff {
         skill: String,
-        #[arg(long)]
+        #[arg(long, add = ArgValueCandidates::new(target_candidates))]
         target: String,
+        #[arg(long)]
+        dry_run: bool,
+        /// Stay silent and skip diff.command when both digests match.
+        #[arg(long)]
+        summary_only: bool,
+        /// Run diff.command even when --summary-only finds matching digests.
+        #[arg(long)]
+        force: bool,
+        /// Run diff.command without a terminal: stdin from the null device, output printed after it exits.
+        #[arg(long)]
+        batch: bool,
+        /// Diff only RELPATH (relative to the skill directory) on each side.
+        #[arg(long, value_name = "RELPATH")]
+        file: Option<PathBuf>,
+        /// Disregard hash.ignore for this run when comparing digests.
+        #[arg(long)]
+        no_ignore: bool,
+        /// Use PATH as the global root for this run instead of config's global_root.
+        #[arg(long, value_name = "PATH")]
+        global_root: Option<PathBuf>,
     },
+    #[command(group(
+        ArgGroup::new("scope")
+            .required(true)
+            .args(["global", "target"])
+    ))]
+    Export {
+        skill: String,
+        #[arg(long)]
+        global: bool,
+        #[arg(long, add = ArgValueCandidates::new(target_candidates))]
+        target: Option<String>,
+    },
+    /// Inspect the config file format.
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum ConfigCommand {
+    /// Print a JSON Schema of the config file for editor validation.
+    Schema,
 }
 
 pub fn run() -> ExitCode {
+    // Answers `COMPLETE=<shell> skillctl ...` requests from the shell and exits.
+    CompleteEnv::with_factory(Cli::command).complete();
     let cli = Cli::parse();
+    init_logging(cli.log_level);
+    let format = cli.format;
     match execute(cli) {
         Ok(()) => ExitCode::SUCCESS,
+        Err(err) if format == OutputFormat::Ndjson => {
+            // Errors go to stdout even with --output, like text errors to stderr.
+            if let Err(write_err) = write_event(&mut std::io::stdout().lock(), &Event::error(&err))
+            {
+                eprintln!("{}", crate::tr!("エラー: {}", "error: {}", write_err));
+            }
+            err.exit_code()
+        }
         Err(err) => {
             eprintln!("{}", crate::tr!("エラー: {}", "error: {}", err));
             if let Some(hint) = err.hint() {
@@ -107,15 +498,166 @@ pub fn run() -> ExitCode {
     }
 }
 
+/// Completion candidates for `--target` / `--from`: the configured target
+/// names, or none when the config cannot be loaded.
+fn target_candidates() -> Vec<CompletionCandidate> {
+    Config::load_default()
+        .map(|config| target_candidates_from(&config))
+        .unwrap_or_default()
+}
+
+pub(crate) fn target_candidates_from(config: &Config) -> Vec<CompletionCandidate> {
+    config
+        .targets
+        .iter()
+        .map(|target| {
+            CompletionCandidate::new(&target.name)
+                .help(Some(target.root.display().to_string().into()))
+        })
+        .collect()
+}
+
+fn init_logging(level: Option<LevelFilter>) {
+    let filter = match level {
+        Some(level) => EnvFilter::new(level.to_string()),
+        None => EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("off")),
+    };
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .with_target(false)
+        .without_time()
+        .try_init();
+}
+
 fn execute(cli: Cli) -> AppResult<()> {
-    let config = Config::load_default()?;
-    match cli.command {
-        Commands::Targets => {
-            for target in &config.targets {
-                println!("{}", target.name);
+    let format = cli.format;
+    let ndjson = format == OutputFormat::Ndjson;
+    if format == OutputFormat::Json
+        && !matches!(
+            cli.command,
+            Commands::Targets { .. } | Commands::Doctor { .. }
+        )
+    {
+        return Err(AppError::config(
+            crate::tr!(
+                "--format json は targets と doctor でのみ使えます",
+                "--format json is only supported by targets and doctor"
+            ),
+            Some(crate::tr!(
+                "--format ndjson を使ってください",
+                "Use --format ndjson instead."
+            )),
+        ));
+    }
+    if matches!(format, OutputFormat::Plain | OutputFormat::Csv)
+        && !matches!(cli.command, Commands::Status { .. })
+    {
+        return Err(AppError::config(
+            crate::tr!(
+                "--format plain / csv は status でのみ使えます",
+                "--format plain / csv is only supported by status"
+            ),
+            Some(crate::tr!(
+                "--format text または ndjson を使ってください",
+                "Use --format text or ndjson instead."
+            )),
+        ));
+    }
+    let base = cli.chdir.as_deref();
+    // export writes its archive to --output; everything else redirects stdout there.
+    if let Some(path) = &cli.output
+        && !matches!(cli.command, Commands::Export { .. })
+    {
+        set_output_file(&resolve_in(base, path.clone()))?;
+    }
+    set_color_choice(cli.color);
+    if let Some(secs) = cli.wait_root {
+        set_root_wait(Duration::from_secs(secs));
+    }
+    if let Commands::Version { verbose } = cli.command {
+        if ndjson {
+            return emit_version(verbose);
+        }
+        emit_text(version_text(verbose))?;
+        return Ok(());
+    }
+    if let Commands::Config {
+        command: ConfigCommand::Schema,
+    } = cli.command
+    {
+        return emit_line(config_schema()?);
+    }
+    let mut config = match base {
+        Some(dir) => {
+            if !dir.is_dir() {
+                return Err(AppError::config(
+                    crate::tr!(
+                        "--chdir のディレクトリが存在しません: {}",
+                        "--chdir directory does not exist: {}",
+                        dir.display()
+                    ),
+                    Some(crate::tr!(
+                        "存在するディレクトリを指定してください",
+                        "Specify an existing directory."
+                    )),
+                ));
             }
+            Config::load_default_in(dir)?
         }
-        Commands::List { global, target } => {
+        None => Config::load_default()?,
+    };
+    if let Some(jobs) = cli.jobs {
+        config.parallelism = Some(jobs.get());
+    }
+    if let Some(encoding) = cli.output_digest_format {
+        config.display.digest_encoding = encoding;
+    }
+    if let Some(profile) = &cli.profile {
+        config.select_profile(profile)?;
+    }
+    for (first, second, root) in config.shared_target_roots() {
+        eprintln!(
+            "{}",
+            crate::tr!(
+                "警告: ターゲット {} と {} が同じ root を指しています: {}",
+                "warning: targets {} and {} share the same root: {}",
+                first,
+                second,
+                root.display()
+            )
+        );
+    }
+    match cli.command {
+        Commands::Version { .. } | Commands::Config { .. } => {
+            unreachable!("handled before loading config")
+        }
+        Commands::Targets { long } => match format {
+            OutputFormat::Json => {
+                emit_line(render_targets_json(&summarize_targets(&config))?)?;
+            }
+            OutputFormat::Ndjson => {
+                for summary in &summarize_targets(&config) {
+                    emit(&Event::Target(summary))?;
+                }
+            }
+            OutputFormat::Text if long => {
+                emit_text(render_targets_table(&summarize_targets(&config)))?;
+            }
+            OutputFormat::Text => {
+                for target in &config.targets {
+                    emit_line(&target.name)?;
+                }
+            }
+            OutputFormat::Plain | OutputFormat::Csv => {
+                unreachable!("rejected for commands other than status")
+            }
+        },
+        Commands::List {
+            global,
+            target,
+            digests,
+        } => {
             let root = if global {
                 &config.global_root
             } else {
@@ -130,19 +672,175 @@ fn execute(cli: Cli) -> AppResult<()> {
                 })?;
                 &config.target_by_name(&name)?.root
             };
-            let skills = list_skills(root)?;
-            for skill in skills {
-                println!("{}", skill);
-            }
-        }
-        Commands::Status { target, all } => {
-            if all {
-                for t in &config.targets {
-                    println!("{}", crate::tr!("ターゲット: {}", "Target: {}", t.name));
-                    let rows = status_for_target(&config, t)?;
-                    let table = render_status_table(&rows)?;
-                    print!("{}", table);
+            let source = if global {
+                open_global_source(&config)?
+            } else {
+                open_source(root, &config.skills)?
+            };
+            if digests {
+                for (skill, digest) in list_skill_digests(&config, &*source)? {
+                    let digest = encode_digest(&digest, config.display.digest_encoding);
+                    if ndjson {
+                        emit(&Event::Skill {
+                            skill: &skill,
+                            digest: Some(&digest),
+                        })?;
+                    } else {
+                        emit_line(format_args!("{}\t{}", skill, digest))?;
+                    }
                 }
+            } else {
+                for skill in source.list_skills()? {
+                    if ndjson {
+                        emit(&Event::Skill {
+                            skill: &skill,
+                            digest: None,
+                        })?;
+                    } else {
+                        emit_line(skill)?;
+                    }
+                }
+            }
+        }
+        Commands::Digest {
+            skill,
+            global,
+            target,
+            algo,
+            no_ignore,
+        } => {
+            override_algo(&mut config, algo);
+            override_ignore(&mut config, no_ignore);
+            let source = match target {
+                Some(name) if !global => {
+                    open_source(&config.target_by_name(&name)?.root, &config.skills)?
+                }
+                _ => open_global_source(&config)?,
+            };
+            let digest = skill_digest(&config, &*source, &skill)?;
+            let digest = encode_digest(&digest, config.display.digest_encoding);
+            if ndjson {
+                emit(&Event::Skill {
+                    skill: &skill,
+                    digest: Some(&digest),
+                })?;
+            } else {
+                emit_line(digest)?;
+            }
+        }
+        Commands::Bump {
+            skill,
+            global,
+            target,
+            major,
+            minor,
+            patch: _,
+            dry_run,
+        } => {
+            let root = match target {
+                Some(name) if !global => &config.target_by_name(&name)?.root,
+                _ => &config.global_root,
+            };
+            if is_archive_path(root) {
+                return Err(AppError::config(
+                    crate::tr!(
+                        "アーカイブの skill は bump できません: {}",
+                        "Cannot bump a skill inside an archive: {}",
+                        root.display()
+                    ),
+                    None,
+                ));
+            }
+            let dry_run = env_dry_run(dry_run);
+            let level = if major {
+                BumpLevel::Major
+            } else if minor {
+                BumpLevel::Minor
+            } else {
+                BumpLevel::Patch
+            };
+            let _lock = if dry_run {
+                None
+            } else {
+                RootLock::acquire(root, false)?
+            };
+            let bump = bump_skill(
+                root,
+                &skill,
+                &config.skills,
+                &config.doctor.skill_file,
+                level,
+                dry_run,
+            )?;
+            if ndjson {
+                emit(&Event::Bump {
+                    skill: &skill,
+                    previous: &bump.previous.to_string(),
+                    version: &bump.version.to_string(),
+                    dry_run,
+                })?;
+            } else {
+                emit_line(format_args!("{} -> {}", bump.previous, bump.version))?;
+            }
+        }
+        Commands::Status {
+            target,
+            all,
+            skills_from,
+            algo,
+            no_ignore,
+            global_root,
+            timings,
+            sort,
+            detect_renames,
+            only,
+            detail,
+            fast,
+            count,
+            full_digest,
+            compare,
+            since_sync,
+            #[cfg(feature = "watch")]
+            watch,
+        } => {
+            override_algo(&mut config, algo);
+            override_ignore(&mut config, no_ignore);
+            if let Some(path) = global_root {
+                config.override_global_root(&path, base)?;
+            }
+            config.status.detect_renames |= detect_renames;
+            config.status.detail |= detail;
+            config.status.fast |= fast;
+            if full_digest {
+                config.display.digest_len = MAX_DIGEST_LEN;
+            }
+            let listed = skills_from
+                .map(|path| read_skill_list(&path, base, &config))
+                .transpose()?;
+            let selection = listed.as_deref().map_or(Selection::All, Selection::Many);
+            if matches!(format, OutputFormat::Plain | OutputFormat::Csv)
+                && (!compare.is_empty() || since_sync)
+            {
+                return Err(AppError::config(
+                    crate::tr!(
+                        "--format plain / csv は status --target / --all でのみ使えます",
+                        "--format plain / csv is only supported by status --target / --all"
+                    ),
+                    None,
+                ));
+            }
+            if let [left, right] = compare.as_slice() {
+                let left = config.target_by_name(left)?;
+                let right = config.target_by_name(right)?;
+                return print_compare(&config, left, right, selection, sort, format);
+            }
+            if since_sync && let Some(name) = &target {
+                let target = config.target_by_name(name)?;
+                return print_since_sync(&config, target, selection, sort, format);
+            }
+            let mut timings = timings.then(Vec::new);
+            let targets = if all {
+                config.targets.iter().collect::<Vec<_>>()
             } else {
                 let name = target.ok_or_else(|| {
                     AppError::config(
@@ -154,22 +852,100 @@ fn execute(cli: Cli) -> AppResult<()> {
                     )
                 })?;
                 let target = config.target_by_name(&name)?;
-                let rows = status_for_target(&config, target)?;
-                let table = render_status_table(&rows)?;
-                print!("{}", table);
+                #[cfg(feature = "watch")]
+                if watch {
+                    if format != OutputFormat::Text {
+                        return Err(AppError::config(
+                            crate::tr!(
+                                "--watch は --format text でのみ使えます",
+                                "--watch only supports --format text"
+                            ),
+                            None,
+                        ));
+                    }
+                    if cli.output.is_some() {
+                        return Err(AppError::config(
+                            crate::tr!(
+                                "--watch は --output と併用できません",
+                                "--watch cannot be used with --output"
+                            ),
+                            None,
+                        ));
+                    }
+                    return watch_status(&config, target, selection, sort);
+                }
+                vec![target]
+            };
+            if !count.is_empty() {
+                let mut counter = StatusCounter::new(&count);
+                for t in targets {
+                    let only = only_present(&config, t, &only)?;
+                    let selection = only.as_deref().map_or(selection, Selection::Many);
+                    status_for_target_selected(
+                        &config,
+                        t,
+                        selection,
+                        timings.as_mut(),
+                        &mut counter,
+                    )?;
+                }
+                if ndjson {
+                    emit(&Event::Count {
+                        count: counter.count(),
+                    })?;
+                } else {
+                    emit_line(counter.count())?;
+                }
+            } else {
+                for (position, t) in targets.into_iter().enumerate() {
+                    if all && !ndjson && format != OutputFormat::Csv {
+                        emit_line(crate::tr!("ターゲット: {}", "Target: {}", t.name))?;
+                    }
+                    let only = only_present(&config, t, &only)?;
+                    let selection = only.as_deref().map_or(selection, Selection::Many);
+                    print_status(
+                        &config,
+                        t,
+                        selection,
+                        sort,
+                        timings.as_mut(),
+                        format,
+                        all.then_some(position),
+                    )?;
+                }
+            }
+            if let Some(timings) = timings {
+                eprint!("{}", render_timings(&timings));
+            }
+            if config.status.fast && !ndjson {
+                eprintln!(
+                    "{}",
+                    crate::tr!(
+                        "注意: --fast の結果は近似です（パス・サイズ・更新日時のみを比較）",
+                        "note: --fast results are approximate (only paths, sizes and mtimes are compared)"
+                    )
+                );
             }
         }
         Commands::Doctor {
+            skill,
             global,
             target,
             all,
+            fail_on,
+            portability,
+            check_encoding,
         } => {
-            if global {
-                run_doctor(crate::tr!("グローバル", "Global"), &config.global_root)?;
+            config.doctor.portability |= portability;
+            config.doctor.check_encoding |= check_encoding;
+            let scopes = if global {
+                vec![(DoctorScope::Global, &config.global_root)]
             } else if all {
-                for t in &config.targets {
-                    run_doctor(crate::tr!("ターゲット: {}", "Target: {}", t.name), &t.root)?;
-                }
+                config
+                    .targets
+                    .iter()
+                    .map(|t| (DoctorScope::Target(&t.name), &t.root))
+                    .collect()
             } else {
                 let name = target.ok_or_else(|| {
                     AppError::config(
@@ -181,49 +957,175 @@ fn execute(cli: Cli) -> AppResult<()> {
                     )
                 })?;
                 let target = config.target_by_name(&name)?;
-                run_doctor(
-                    crate::tr!("ターゲット: {}", "Target: {}", target.name),
-                    &target.root,
-                )?;
+                vec![(DoctorScope::Target(&target.name), &target.root)]
+            };
+            let mut reports = Vec::new();
+            for (scope, root) in scopes {
+                let report = run_doctor(&config, scope, root, skill.as_deref(), format)?;
+                reports.push((scope.name(), report));
+            }
+            if format == OutputFormat::Json {
+                emit_line(render_doctor_json(&reports)?)?;
+            }
+            if let Some(threshold) = fail_on {
+                let reports = reports.iter().map(|(_, report)| report).collect::<Vec<_>>();
+                let count = count_issues_at_least(&reports, threshold);
+                if count > 0 {
+                    return Err(AppError::exec(
+                        crate::tr!(
+                            "{} 以上の問題が {} 件あります",
+                            "{} issue(s) at severity {} or above",
+                            threshold,
+                            count
+                        ),
+                        Some(crate::tr!(
+                            "出力の issue を修正するか doctor.severity を見直してください",
+                            "Fix the reported issues or adjust doctor.severity."
+                        )),
+                    ));
+                }
             }
         }
         Commands::Push {
-            skill,
+            skills,
             all,
+            skills_from,
+            #[cfg(feature = "interactive")]
+            interactive,
             target,
+            target_env,
             dry_run,
+            apply,
+            check_sync,
             prune,
+            prune_only,
+            changed_only,
+            force,
+            init_global,
+            porcelain,
+            list_files,
+            explain,
+            wait,
+            resume,
+            warn_skips,
+            algo,
+            no_ignore,
+            global_root,
+            check,
         } => {
+            override_algo(&mut config, algo);
+            override_ignore(&mut config, no_ignore);
+            if let Some(path) = global_root {
+                config.override_global_root(&path, base)?;
+            }
+            let target = match target_env {
+                Some(var) => target_name_from_env(&var)?,
+                None => target.unwrap_or_default(),
+            };
             let target = config.target_by_name(&target)?;
-            let selection = if all {
+            let listed = skills_from
+                .map(|path| read_skill_list(&path, base, &config))
+                .transpose()?;
+            #[cfg(feature = "interactive")]
+            let listed = match listed {
+                None if interactive => Some(pick_skills(&config, target)?),
+                listed => listed,
+            };
+            let selection = if all || prune_only {
                 Selection::All
+            } else if let Some(listed) = &listed {
+                Selection::Many(listed)
+            } else if skills.is_empty() {
+                return Err(AppError::config(
+                    crate::tr!("skill が指定されていません", "skill is not specified"),
+                    Some(crate::tr!(
+                        "push <skill>... を指定してください",
+                        "Specify push <skill>..."
+                    )),
+                ));
             } else {
-                Selection::One(skill.as_deref().ok_or_else(|| {
-                    AppError::config(
-                        crate::tr!("skill が指定されていません", "skill is not specified"),
-                        Some(crate::tr!(
-                            "push <skill> を指定してください",
-                            "Specify push <skill>"
-                        )),
-                    )
-                })?)
+                Selection::Many(&skills)
             };
-            let plan = plan_push(&config, target, selection, prune)?;
-            for line in summarize_plan(&plan) {
-                println!("{}", line);
+            let dry_run = check_sync || resolve_dry_run(&config, dry_run, apply);
+            if init_global {
+                init_global_skill(&config, selection, dry_run)?;
+            }
+            let _lock = lock_root(&target.root, dry_run, wait)?;
+            let mut plan = plan_push_with(
+                &config,
+                target,
+                selection,
+                PushOptions {
+                    prune,
+                    force,
+                    prune_only,
+                    changed_only,
+                },
+            )?;
+            let checkpoint = load_checkpoint(&target.root, resume, &mut plan)?;
+            let audit = audit_log(&config, "push", &target.name);
+            apply_plan(
+                &config,
+                &plan,
+                PlanRun {
+                    dry_run,
+                    output: PlanOutput::new(porcelain, format),
+                    list_files,
+                    explain: explain.then_some(PlanDirection::Push),
+                },
+                &check,
+                audit.as_ref(),
+                checkpoint,
+            )?;
+            if !dry_run {
+                record_synced(&config, target, &plan)?;
+            }
+            if warn_skips {
+                warn_skipped(&plan);
+            }
+            if check_sync {
+                check_in_sync(&plan, target)?;
             }
-            execute_plan(&plan, dry_run)?;
         }
         Commands::Import {
             skill,
             all,
+            skills_from,
             from,
+            // Implied by `from` being unset; clap requires one of the two.
+            from_all: _,
+            prefer,
+            rename_map,
             dry_run,
+            apply,
             overwrite,
+            merge,
+            porcelain,
+            explain,
+            wait,
+            resume,
+            warn_skips,
+            algo,
+            no_ignore,
+            global_root,
+            check,
         } => {
-            let target = config.target_by_name(&from)?;
+            override_algo(&mut config, algo);
+            override_ignore(&mut config, no_ignore);
+            if let Some(path) = global_root {
+                config.override_global_root(&path, base)?;
+            }
+            let target = from
+                .as_deref()
+                .map(|name| config.target_by_name(name))
+                .transpose()?;
+            let listed = skills_from
+                .map(|path| read_skill_list(&path, base, &config))
+                .transpose()?;
             let selection = if all {
                 Selection::All
+            } else if let Some(listed) = &listed {
+                Selection::Many(listed)
             } else {
                 Selection::One(skill.as_deref().ok_or_else(|| {
                     AppError::config(
@@ -235,41 +1137,1084 @@ fn execute(cli: Cli) -> AppResult<()> {
                     )
                 })?)
             };
-            let plan = plan_import(&config, target, selection, overwrite)?;
-            for line in summarize_plan(&plan) {
-                println!("{}", line);
+            let mode = if merge {
+                ImportMode::Merge
+            } else if overwrite {
+                ImportMode::Overwrite
+            } else {
+                ImportMode::AddOnly
+            };
+            let renames = match rename_map {
+                Some(path) => read_rename_map(&path, base, &config)?,
+                None => BTreeMap::new(),
+            };
+            let dry_run = resolve_dry_run(&config, dry_run, apply);
+            let _lock = lock_root(&config.global_root, dry_run, wait)?;
+            let (mut plan, from) = match target {
+                Some(target) => (
+                    plan_import_with(&config, target, selection, mode, &renames)?,
+                    target.name.as_str(),
+                ),
+                None => {
+                    let (plan, conflicts) = plan_import_all(&config, mode, prefer.as_deref())?;
+                    warn_conflicts(&conflicts);
+                    (plan, ALL_TARGETS)
+                }
+            };
+            let checkpoint = load_checkpoint(&config.global_root, resume, &mut plan)?;
+            let audit = audit_log(&config, "import", from);
+            apply_plan(
+                &config,
+                &plan,
+                PlanRun {
+                    dry_run,
+                    output: PlanOutput::new(porcelain, format),
+                    list_files: false,
+                    explain: explain.then_some(PlanDirection::Import),
+                },
+                &check,
+                audit.as_ref(),
+                checkpoint,
+            )?;
+            if warn_skips {
+                warn_skipped(&plan);
             }
-            execute_plan(&plan, dry_run)?;
         }
-        Commands::Diff { skill, target } => {
+        Commands::Diff {
+            skill,
+            target,
+            dry_run,
+            summary_only,
+            force,
+            batch,
+            file,
+            no_ignore,
+            global_root,
+        } => {
+            override_ignore(&mut config, no_ignore);
+            if let Some(path) = global_root {
+                config.override_global_root(&path, base)?;
+            }
             let target = config.target_by_name(&target)?;
-            run_diff(&config, target, &skill)?;
+            let options = DiffOptions {
+                dry_run,
+                ndjson,
+                summary_only,
+                force,
+                batch,
+            };
+            match file {
+                Some(file) => run_diff_file(&config, target, &skill, &file, options)?,
+                None => run_diff(&config, target, &skill, options)?,
+            }
+        }
+        Commands::Export {
+            skill,
+            global,
+            target,
+        } => {
+            let output = cli.output.ok_or_else(|| {
+                AppError::config(
+                    crate::tr!("出力先が指定されていません", "output is not specified"),
+                    Some(crate::tr!(
+                        "export <skill> -o <file> を指定してください",
+                        "Specify export <skill> -o <file>"
+                    )),
+                )
+            })?;
+            let root = if global {
+                &config.global_root
+            } else {
+                let name = target.ok_or_else(|| {
+                    AppError::config(
+                        crate::tr!("target が指定されていません", "target is not specified"),
+                        Some(crate::tr!(
+                            "export --target <name> を指定してください",
+                            "Specify export --target <name>"
+                        )),
+                    )
+                })?;
+                &config.target_by_name(&name)?.root
+            };
+            let ignore = build_ignore_set(&config.hash.ignore)?;
+            let output = resolve_in(base, output);
+            export_skill(root, &skill, &config.skills, ignore.as_ref(), &output)?;
+            if ndjson {
+                emit(&Event::Export {
+                    skill: &skill,
+                    output: &output,
+                })?;
+            }
         }
     }
     Ok(())
 }
 
-fn run_doctor(label: String, root: &std::path::Path) -> AppResult<()> {
-    println!("{}", label);
-    let report = doctor_root(root)?;
-    let by_skill = group_issues_by_skill(&report.issues);
-    for skill in &report.skills {
-        if let Some(issues) = by_skill.get(skill.as_str()) {
-            for issue in issues {
-                println!("issue {} {}", skill, issue.message);
+/// Lets the user toggle which global skills to push, pre-checking missing and diff ones.
+#[cfg(feature = "interactive")]
+fn pick_skills(config: &Config, target: &Target) -> AppResult<Vec<String>> {
+    use std::io::IsTerminal;
+
+    use dialoguer::MultiSelect;
+
+    use crate::status::status_for_target;
+
+    if !std::io::stdin().is_terminal() || !std::io::stderr().is_terminal() {
+        return Err(AppError::config(
+            crate::tr!(
+                "--interactive には端末が必要です",
+                "--interactive requires a terminal"
+            ),
+            Some(crate::tr!(
+                "skill 名、--all または --skills-from で明示的に指定してください",
+                "Pass skill names, --all or --skills-from explicitly."
+            )),
+        ));
+    }
+    let rows = status_for_target(config, target)?
+        .into_iter()
+        .filter(|row| row.state != State::Extra)
+        .collect::<Vec<_>>();
+    let items = rows
+        .iter()
+        .map(|row| format!("{} ({})", row.label(), row.state))
+        .collect::<Vec<_>>();
+    let defaults = rows
+        .iter()
+        .map(|row| matches!(row.state, State::Missing | State::Diff | State::LikelyDiff))
+        .collect::<Vec<_>>();
+    let picked = MultiSelect::new()
+        .with_prompt(crate::tr!(
+            "push する skill を選択 (Space: 切替, Enter: 確定)",
+            "Select skills to push (Space: toggle, Enter: confirm)"
+        ))
+        .items(&items)
+        .defaults(&defaults)
+        .interact_opt()
+        .map_err(|err| {
+            AppError::exec_with_source(
+                crate::tr!("skill の選択に失敗しました", "Failed to select skills"),
+                None,
+                err,
+            )
+        })?
+        .unwrap_or_default();
+    if picked.is_empty() {
+        return Err(AppError::config(
+            crate::tr!("skill が選択されていません", "No skills selected"),
+            None,
+        ));
+    }
+    Ok(picked
+        .into_iter()
+        .map(|index| rows[index].skill.clone())
+        .collect())
+}
+
+/// Re-runs `status` on debounced filesystem events until Ctrl-C.
+#[cfg(feature = "watch")]
+fn watch_status(
+    config: &Config,
+    target: &Target,
+    selection: Selection<'_>,
+    sort: StatusSort,
+) -> AppResult<()> {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::mpsc;
+
+    use notify_debouncer_full::new_debouncer;
+    use notify_debouncer_full::notify::RecursiveMode;
+
+    let watch_error = |err: &dyn std::fmt::Display| {
+        AppError::exec(
+            crate::tr!("監視を開始できません", "Failed to start watching"),
+            Some(err.to_string()),
+        )
+    };
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let flag = Arc::clone(&stop);
+    ctrlc::set_handler(move || flag.store(true, Ordering::SeqCst))
+        .map_err(|err| watch_error(&err))?;
+
+    let (tx, rx) = mpsc::channel();
+    let mut debouncer =
+        new_debouncer(Duration::from_millis(300), None, tx).map_err(|err| watch_error(&err))?;
+    for root in [&config.global_root, &target.root] {
+        debouncer
+            .watch(root, RecursiveMode::Recursive)
+            .map_err(|err| watch_error(&err))?;
+    }
+
+    render_watched_status(config, target, selection, sort);
+    while !stop.load(Ordering::SeqCst) {
+        match rx.recv_timeout(Duration::from_millis(200)) {
+            // Reading the roots for status emits access events; only re-render on changes.
+            Ok(Ok(events)) => {
+                if events.iter().any(|event| !event.kind.is_access()) {
+                    render_watched_status(config, target, selection, sort);
+                }
             }
-        } else {
-            println!("ok {}", skill);
+            Ok(Err(errors)) => {
+                for err in errors {
+                    eprintln!("{}", crate::tr!("エラー: {}", "error: {}", err));
+                }
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
         }
     }
+    Ok(())
+}
+
+#[cfg(feature = "watch")]
+fn render_watched_status(
+    config: &Config,
+    target: &Target,
+    selection: Selection<'_>,
+    sort: StatusSort,
+) {
+    print!("\x1B[2J\x1B[H");
     println!(
+        "{}",
+        crate::tr!("ターゲット: {}", "Target: {}", target.name)
+    );
+    match print_status(
+        config,
+        target,
+        selection,
+        sort,
+        None,
+        OutputFormat::Text,
+        None,
+    ) {
+        Ok(()) => {}
+        Err(err) => {
+            eprintln!("{}", crate::tr!("エラー: {}", "error: {}", err));
+            if let Some(hint) = err.hint() {
+                eprintln!("{}", crate::tr!("ヒント: {}", "help: {}", hint));
+            }
+        }
+    }
+}
+
+/// Streams the status table to the primary output; sorting by state buffers the rows first.
+///
+/// `all_position` is the target's index under `status --all`: there, CSV
+/// gets a `TARGET` column and only the first target writes the header.
+fn print_status(
+    config: &Config,
+    target: &Target,
+    selection: Selection<'_>,
+    sort: StatusSort,
+    timings: Option<&mut Vec<SkillTiming>>,
+    format: OutputFormat,
+    all_position: Option<usize>,
+) -> AppResult<()> {
+    let out = output();
+    let mut writer: Box<dyn StatusSink> = if format == OutputFormat::Ndjson {
+        Box::new(
+            StatusEventWriter::new(&target.name, out)
+                .with_digest_encoding(config.display.digest_encoding),
+        )
+    } else {
+        let mut writer = StatusTableWriter::new(out)
+            .with_style(match format {
+                OutputFormat::Plain => TableStyle::Plain,
+                OutputFormat::Csv => TableStyle::Csv,
+                _ => TableStyle::Aligned,
+            })
+            .with_color(color_enabled())
+            .with_digest_len(config.display.digest_len)
+            .with_digest_encoding(config.display.digest_encoding)
+            .with_fast(config.status.fast);
+        if let Some(position) = all_position
+            && format == OutputFormat::Csv
+        {
+            writer = writer
+                .with_target_column(&target.name)
+                .with_header(position == 0);
+        }
+        Box::new(writer)
+    };
+    if sort == StatusSort::Name {
+        return status_for_target_selected(config, target, selection, timings, &mut *writer);
+    }
+    let mut rows = Vec::new();
+    status_for_target_selected(config, target, selection, timings, &mut rows)?;
+    sort_status_rows(&mut rows, sort);
+    let names = rows
+        .iter()
+        .map(|row| row.skill.as_str())
+        .collect::<Vec<_>>();
+    writer.begin(&names)?;
+    for row in rows {
+        writer.row(row)?;
+    }
+    Ok(())
+}
+
+fn print_compare(
+    config: &Config,
+    left: &Target,
+    right: &Target,
+    selection: Selection<'_>,
+    sort: StatusSort,
+    format: OutputFormat,
+) -> AppResult<()> {
+    let mut rows = compare_targets(config, left, right, selection)?;
+    sort_compare_rows(&mut rows, sort);
+    let encoding = config.display.digest_encoding;
+    for row in &mut rows {
+        for digest in [&mut row.left_digest, &mut row.right_digest] {
+            *digest = digest.as_deref().map(|d| encode_digest(d, encoding));
+        }
+    }
+    if format != OutputFormat::Ndjson {
+        emit_text(render_compare_table(
+            &left.name,
+            &right.name,
+            &rows,
+            config.display.digest_len,
+            color_enabled(),
+        ))?;
+        return Ok(());
+    }
+    for row in &rows {
+        emit(&Event::Compare {
+            left: &left.name,
+            right: &right.name,
+            skill: &row.skill,
+            state: row.state.label(&left.name, &right.name),
+            left_digest: row.left_digest.as_deref(),
+            right_digest: row.right_digest.as_deref(),
+        })?;
+    }
+    Ok(())
+}
+
+fn print_since_sync(
+    config: &Config,
+    target: &Target,
+    selection: Selection<'_>,
+    sort: StatusSort,
+    format: OutputFormat,
+) -> AppResult<()> {
+    let mut rows = since_sync(config, target, selection)?;
+    sort_since_sync_rows(&mut rows, sort);
+    let encoding = config.display.digest_encoding;
+    for row in &mut rows {
+        row.digest = encode_digest(&row.digest, encoding);
+        row.synced_digest = row
+            .synced_digest
+            .as_deref()
+            .map(|d| encode_digest(d, encoding));
+    }
+    if format != OutputFormat::Ndjson {
+        emit_text(render_since_sync_table(
+            &rows,
+            config.display.digest_len,
+            color_enabled(),
+        ))?;
+        return Ok(());
+    }
+    for row in &rows {
+        emit(&Event::SinceSync {
+            target: &target.name,
+            skill: &row.skill,
+            state: row.state.label(),
+            digest: &row.digest,
+            synced_digest: row.synced_digest.as_deref(),
+        })?;
+    }
+    Ok(())
+}
+
+/// `push --init-global`: creates the single named skill in global when it is
+/// not there yet, so the push installs the new skill.
+fn init_global_skill(config: &Config, selection: Selection<'_>, dry_run: bool) -> AppResult<()> {
+    let skill = match selection.names().as_deref() {
+        Some([skill]) => *skill,
+        _ => {
+            return Err(AppError::config(
+                crate::tr!(
+                    "--init-global には skill 名を 1 つだけ指定してください",
+                    "--init-global needs exactly one skill name"
+                ),
+                Some(crate::tr!(
+                    "push <skill> --init-global --target <name> の形で指定してください",
+                    "Use push <skill> --init-global --target <name>."
+                )),
+            ));
+        }
+    };
+    validate_skill_id_with(skill, &config.skills)?;
+    let key = skill_key(skill, &config.skills);
+    if list_skills_with(&config.global_root, &config.skills)?
+        .iter()
+        .any(|existing| skill_key(existing, &config.skills) == key)
+    {
+        return Ok(());
+    }
+    if dry_run {
+        return Err(AppError::config(
+            crate::tr!(
+                "dry-run では global に skill を作成できません: {}",
+                "Cannot create a skill in global during a dry run: {}",
+                skill
+            ),
+            Some(crate::tr!(
+                "--dry-run を外すか --apply を指定してください",
+                "Drop --dry-run or pass --apply."
+            )),
+        ));
+    }
+    let path = init_skill(
+        &config.global_root,
+        skill,
+        &config.skills,
+        &config.doctor.skill_file,
+    )?;
+    eprintln!(
         "{}",
         crate::tr!(
-            "checked: {} issues: {}",
-            "checked: {} issues: {}",
-            report.skills.len(),
-            report.issues.len()
+            "global に skill を作成しました: {}",
+            "Created skill in global: {}",
+            path.display()
         )
     );
     Ok(())
 }
+
+/// Locks the root a push/import writes to, before planning so the plan sees
+/// the result of any run it waited for. Dry-runs never lock.
+fn lock_root(root: &Path, dry_run: bool, wait: bool) -> AppResult<Option<RootLock>> {
+    if dry_run {
+        return Ok(None);
+    }
+    ensure_root_dir(root)?;
+    RootLock::acquire(root, wait)
+}
+
+/// Target name for `--target-env <VAR>`; an unset or blank variable is a config error.
+fn target_name_from_env(var: &str) -> AppResult<String> {
+    match std::env::var(var) {
+        Ok(name) if !name.trim().is_empty() => Ok(name.trim().to_string()),
+        _ => Err(AppError::config(
+            crate::tr!(
+                "環境変数 {} にターゲット名が設定されていません",
+                "Environment variable {} does not hold a target name",
+                var
+            ),
+            Some(crate::tr!(
+                "{} にターゲット名を設定するか --target を指定してください",
+                "Set {} to a target name or pass --target.",
+                var
+            )),
+        )),
+    }
+}
+
+/// Set to anything but empty, `0` or `false` to force every push/import into dry-run.
+const DRY_RUN_ENV: &str = "SKILLCTL_DRY_RUN";
+
+fn dry_run_forced_by_env() -> bool {
+    std::env::var(DRY_RUN_ENV).is_ok_and(|value| {
+        let value = value.trim();
+        !value.is_empty() && value != "0" && !value.eq_ignore_ascii_case("false")
+    })
+}
+
+/// `dry_run`, or `true` with a note on stderr when [`DRY_RUN_ENV`] forces
+/// every mutating command into dry-run.
+fn env_dry_run(dry_run: bool) -> bool {
+    if dry_run || !dry_run_forced_by_env() {
+        return dry_run;
+    }
+    eprintln!(
+        "{}",
+        crate::tr!(
+            "{} が設定されているため dry-run として実行します",
+            "Running as dry-run because {} is set",
+            DRY_RUN_ENV
+        )
+    );
+    true
+}
+
+/// With `sync.require_apply`, push/import only mutate when `--apply` is given.
+fn resolve_dry_run(config: &Config, dry_run: bool, apply: bool) -> bool {
+    if env_dry_run(dry_run) {
+        return true;
+    }
+    if !config.sync.require_apply || apply {
+        return false;
+    }
+    eprintln!(
+        "{}",
+        crate::tr!(
+            "sync.require_apply のため dry-run として実行します（実行するには --apply を指定してください）",
+            "Running as dry-run because sync.require_apply is set (pass --apply to execute)"
+        )
+    );
+    true
+}
+
+/// How `push` / `import` print their plan.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PlanOutput {
+    Summary,
+    Porcelain,
+    Events,
+}
+
+impl PlanOutput {
+    fn new(porcelain: bool, format: OutputFormat) -> Self {
+        if format == OutputFormat::Ndjson {
+            Self::Events
+        } else if porcelain {
+            Self::Porcelain
+        } else {
+            Self::Summary
+        }
+    }
+}
+
+/// Files a prune op would delete when `list_files` is set; empty otherwise.
+fn prune_files_of(op: &PlanOp, list_files: bool) -> AppResult<Vec<String>> {
+    match &op.dest {
+        Some(dest) if list_files && op.kind == PlanKind::Prune => prune_files(dest),
+        _ => Ok(Vec::new()),
+    }
+}
+
+/// Which way a plan copies, for `--explain`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PlanDirection {
+    Push,
+    Import,
+}
+
+/// Why `op` was planned, for `--explain`.
+fn explain_op(op: &PlanOp, direction: PlanDirection) -> String {
+    use PlanDirection::{Import, Push};
+    match (direction, op.kind, op.note.as_deref()) {
+        (_, PlanKind::Skip, Some(CHECKPOINT_NOTE)) => crate::tr!(
+            "再開したチェックポイントで完了済みです",
+            "already done in the resumed checkpoint"
+        ),
+        (Push, PlanKind::Install, _) => {
+            crate::tr!("ターゲットに存在しません", "not present in target")
+        }
+        (Push, PlanKind::Update, Some(FORCE_NOTE)) => crate::tr!(
+            "global と一致していますが --force により再コピーします",
+            "target already matches global; copied again because of --force"
+        ),
+        (Push, PlanKind::Update, _) => crate::tr!(
+            "ターゲットと global が異なるため global で置き換えます",
+            "target and global differ; global replaces target"
+        ),
+        (Push, PlanKind::Skip, Some(_)) => crate::tr!(
+            "ターゲットにのみ存在します。削除するには --prune を指定してください",
+            "only present in target; use --prune to remove it"
+        ),
+        (Push, PlanKind::Prune, _) => crate::tr!(
+            "ターゲットにのみ存在するため --prune により削除します",
+            "only present in target; removed because of --prune"
+        ),
+        (Import, PlanKind::Install, _) => {
+            crate::tr!("global に存在しません", "not present in global")
+        }
+        (Import, PlanKind::Update, _) => crate::tr!(
+            "ターゲットと global が異なるため --overwrite によりターゲットで置き換えます",
+            "target and global differ; target replaces global because of --overwrite"
+        ),
+        (_, PlanKind::Merge, _) => crate::tr!(
+            "ターゲットと global が異なるため --merge によりターゲットのファイルを上書きコピーします（global のみのファイルは残ります）",
+            "target and global differ; target files are copied over global because of --merge, keeping global-only files"
+        ),
+        (Import, PlanKind::Skip, Some(CONFLICT_NOTE)) => crate::tr!(
+            "ターゲット間で内容が異なります。取り込み元を選ぶには --prefer <target> を指定してください",
+            "targets hold different content; use --prefer <target> to pick one"
+        ),
+        (Import, PlanKind::Skip, Some("diff")) => crate::tr!(
+            "ターゲットと global が異なります。置き換えるには --overwrite を指定してください",
+            "target and global differ; use --overwrite to replace"
+        ),
+        (Import, PlanKind::Skip, Some(_)) => {
+            crate::tr!("ターゲットに存在しません", "not present in target")
+        }
+        (_, PlanKind::Skip, _) => {
+            crate::tr!("ターゲットと global は同じです", "target and global match")
+        }
+        (Import, PlanKind::Prune, _) => {
+            crate::tr!("global にのみ存在します", "only present in global")
+        }
+    }
+}
+
+/// One text line per op: [`porcelain_plan`], [`summarize_plan`], or with
+/// `explain` the summary with [`explain_op`] appended to the note.
+fn plan_lines(plan: &Plan, porcelain: bool, explain: Option<PlanDirection>) -> Vec<String> {
+    match explain {
+        _ if porcelain => porcelain_plan(plan),
+        Some(direction) => plan
+            .ops
+            .iter()
+            .map(|op| {
+                let why = explain_op(op, direction);
+                match &op.note {
+                    Some(note) => format!("{} {} ({}: {})", op.kind.label(), op.skill, note, why),
+                    None => format!("{} {} ({})", op.kind.label(), op.skill, why),
+                }
+            })
+            .collect(),
+        None => summarize_plan(plan),
+    }
+}
+
+/// Renders `lines` (see [`plan_lines`]) with the files of each prune op on
+/// the lines below it when `list_files` is set: `  <path>` in the summary,
+/// `file\t<skill>\t<path>` in porcelain.
+fn render_plan_lines(
+    plan: &Plan,
+    lines: Vec<String>,
+    porcelain: bool,
+    list_files: bool,
+) -> AppResult<String> {
+    let mut out = String::new();
+    for (op, line) in plan.ops.iter().zip(lines) {
+        out.push_str(&line);
+        out.push('\n');
+        for path in prune_files_of(op, list_files)? {
+            if porcelain {
+                out.push_str(&format!("file\t{}\t{}\n", op.skill, path));
+            } else {
+                out.push_str(&format!("  {}\n", path));
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// How [`apply_plan`] prints and runs a plan.
+#[derive(Debug, Clone, Copy)]
+struct PlanRun {
+    dry_run: bool,
+    output: PlanOutput,
+    /// List the files each prune op deletes below it (`push --list-files`).
+    list_files: bool,
+    /// Say why each op was planned (`--explain`); summary output only.
+    explain: Option<PlanDirection>,
+}
+
+fn apply_plan(
+    config: &Config,
+    plan: &Plan,
+    run: PlanRun,
+    check: &PlanCheckArgs,
+    audit: Option<&AuditLog>,
+    checkpoint: Option<Checkpoint>,
+) -> AppResult<()> {
+    let PlanRun {
+        dry_run,
+        output,
+        list_files,
+        explain,
+    } = run;
+    match output {
+        PlanOutput::Summary | PlanOutput::Porcelain if list_files || explain.is_some() => {
+            let porcelain = output == PlanOutput::Porcelain;
+            let lines = plan_lines(plan, porcelain, explain);
+            emit_text(render_plan_lines(plan, lines, porcelain, list_files)?)?;
+        }
+        PlanOutput::Summary | PlanOutput::Porcelain => {
+            let opts = RenderOpts {
+                porcelain: output == PlanOutput::Porcelain,
+                ..RenderOpts::default()
+            };
+            emit_text(render_plan(plan, opts))?;
+        }
+        PlanOutput::Events => {
+            for op in &plan.ops {
+                emit(&Event::plan_op(op))?;
+                for path in prune_files_of(op, list_files)? {
+                    emit(&Event::PruneFile {
+                        skill: &op.skill,
+                        path: &path,
+                    })?;
+                }
+            }
+        }
+    }
+    if check.plan_hash || check.expect_plan.is_some() {
+        let hash = plan_hash(config, plan)?;
+        if check.plan_hash && output == PlanOutput::Events {
+            emit(&Event::PlanHash { hash: &hash })?;
+        } else if check.plan_hash {
+            emit_line(format_args!("plan-hash {}", hash))?;
+        }
+        if let Some(expected) = &check.expect_plan
+            && *expected != hash
+        {
+            return Err(AppError::exec(
+                crate::tr!(
+                    "プランが変わっています (expected: {}, actual: {})",
+                    "Plan has changed (expected: {}, actual: {})",
+                    expected,
+                    hash
+                ),
+                Some(crate::tr!(
+                    "--dry-run --plan-hash で確認し直してください",
+                    "Review it again with --dry-run --plan-hash."
+                )),
+            ));
+        }
+    }
+    let mut out = (output == PlanOutput::Events).then(crate::output::output);
+    let events = out.as_deref_mut().map(|out| out as &mut dyn std::io::Write);
+    match checkpoint {
+        Some(mut checkpoint) if !dry_run => {
+            execute_plan_with(
+                plan,
+                false,
+                &config.sync,
+                audit,
+                Some(&mut checkpoint),
+                events,
+            )?;
+            checkpoint.clear()
+        }
+        _ => execute_plan_with(plan, dry_run, &config.sync, audit, None, events),
+    }
+}
+
+/// `push --check`: fails with the drift exit code when `plan` would change
+/// the target.
+fn check_in_sync(plan: &Plan, target: &Target) -> AppResult<()> {
+    let pending = plan
+        .ops
+        .iter()
+        .filter(|op| {
+            matches!(
+                op.kind,
+                PlanKind::Install | PlanKind::Update | PlanKind::Prune
+            )
+        })
+        .count();
+    if pending == 0 {
+        return Ok(());
+    }
+    Err(AppError::drift(
+        crate::tr!(
+            "ターゲット {} が global と同期していません（{} 件の変更が必要）",
+            "Target {} is out of sync with global ({} pending op(s))",
+            target.name,
+            pending
+        ),
+        Some(crate::tr!(
+            "push --target {} で同期してください",
+            "Run push --target {} to sync it.",
+            target.name
+        )),
+    ))
+}
+
+/// Prints `N skills skipped: a (extra), b (diff)` on stderr for skips that
+/// need attention; unchanged and checkpointed skills are left out.
+fn warn_skipped(plan: &Plan) {
+    let skipped: Vec<String> = plan
+        .ops
+        .iter()
+        .filter(|op| op.kind == PlanKind::Skip)
+        .filter_map(|op| {
+            op.note
+                .as_deref()
+                .filter(|note| *note != CHECKPOINT_NOTE)
+                .map(|note| format!("{} ({})", op.skill, note))
+        })
+        .collect();
+    if skipped.is_empty() {
+        return;
+    }
+    eprintln!(
+        "{}",
+        crate::tr!(
+            "{} 件の skill をスキップしました: {}",
+            "{} skills skipped: {}",
+            skipped.len(),
+            skipped.join(", ")
+        )
+    );
+}
+
+/// Prints one stderr warning per skill `import --from-all` skipped because
+/// targets disagree on its content.
+fn warn_conflicts(conflicts: &[ImportConflict]) {
+    for conflict in conflicts {
+        eprintln!(
+            "{}",
+            crate::tr!(
+                "警告: skill {} の内容がターゲット間で異なるためスキップします: {}（--prefer <target> で取り込み元を選べます）",
+                "warning: skipping skill {}: its content differs between targets {} (use --prefer <target> to pick one)",
+                conflict.skill,
+                conflict.targets.join(", ")
+            )
+        );
+    }
+}
+
+/// Writes one `--format ndjson` event to stdout.
+fn emit(event: &Event<'_>) -> AppResult<()> {
+    write_event(&mut output(), event)
+}
+
+/// With `--resume`, loads the checkpoint under `root` and marks the ops it
+/// records as done in `plan`.
+fn load_checkpoint(root: &Path, resume: bool, plan: &mut Plan) -> AppResult<Option<Checkpoint>> {
+    if !resume {
+        return Ok(None);
+    }
+    let checkpoint = Checkpoint::load(root)?;
+    checkpoint.apply_to(plan);
+    Ok(Some(checkpoint))
+}
+
+/// Cargo features compiled into this binary.
+fn enabled_features() -> Vec<&'static str> {
+    let mut features = Vec::new();
+    if cfg!(feature = "watch") {
+        features.push("watch");
+    }
+    if cfg!(feature = "interactive") {
+        features.push("interactive");
+    }
+    features
+}
+
+fn emit_version(verbose: bool) -> AppResult<()> {
+    let features = enabled_features();
+    emit(&Event::Version {
+        version: env!("CARGO_PKG_VERSION"),
+        commit: verbose.then_some(env!("SKILLCTL_GIT_HASH")),
+        rustc: verbose.then_some(env!("SKILLCTL_RUSTC_VERSION")),
+        features: verbose.then_some(features.as_slice()),
+    })
+}
+
+fn version_text(verbose: bool) -> String {
+    let mut text = format!("skillctl {}\n", env!("CARGO_PKG_VERSION"));
+    if verbose {
+        let features = enabled_features();
+        text.push_str(&format!("commit: {}\n", env!("SKILLCTL_GIT_HASH")));
+        text.push_str(&format!("rustc: {}\n", env!("SKILLCTL_RUSTC_VERSION")));
+        text.push_str(&format!(
+            "features: {}\n",
+            if features.is_empty() {
+                "-".to_string()
+            } else {
+                features.join(",")
+            }
+        ));
+    }
+    text
+}
+
+/// Target name `import --from-all` records in the audit log.
+const ALL_TARGETS: &str = "*";
+
+fn audit_log(config: &Config, command: &str, target: &str) -> Option<AuditLog> {
+    config
+        .audit
+        .log_path
+        .as_deref()
+        .map(|path| AuditLog::new(path, command, target))
+}
+
+/// Reads `--skills-from`: a file, or stdin for `-`.
+fn read_skill_list(path: &Path, base: Option<&Path>, config: &Config) -> AppResult<Vec<String>> {
+    let text = if path == Path::new("-") {
+        std::io::read_to_string(std::io::stdin()).map_err(|err| {
+            AppError::io(
+                crate::tr!(
+                    "標準入力から skill 一覧を読み込めません",
+                    "Failed to read the skill list from stdin"
+                ),
+                err,
+            )
+        })?
+    } else {
+        let path = resolve_in(base, path.to_path_buf());
+        std::fs::read_to_string(&path).map_err(|err| {
+            AppError::io(
+                crate::tr!(
+                    "skill 一覧を読み込めません: {}",
+                    "Failed to read the skill list: {}",
+                    path.display()
+                ),
+                err,
+            )
+        })?
+    };
+    parse_skill_list(&text, &config.skills)
+}
+
+fn read_rename_map(
+    path: &Path,
+    base: Option<&Path>,
+    config: &Config,
+) -> AppResult<BTreeMap<String, String>> {
+    let path = resolve_in(base, path.to_path_buf());
+    let text = std::fs::read_to_string(&path).map_err(|err| {
+        AppError::io(
+            crate::tr!(
+                "rename-map を読み込めません: {}",
+                "Failed to read the rename map: {}",
+                path.display()
+            ),
+            err,
+        )
+    })?;
+    parse_rename_map(&text, &config.skills)
+}
+
+/// Narrows `status --only` to the names present in global or `target`,
+/// noting the rest on stderr; `None` when `--only` was not given.
+fn only_present(
+    config: &Config,
+    target: &Target,
+    only: &[String],
+) -> AppResult<Option<Vec<String>>> {
+    if only.is_empty() {
+        return Ok(None);
+    }
+    let mut present = std::collections::BTreeSet::new();
+    for source in [
+        open_global_source(config)?,
+        open_source(&target.root, &config.skills)?,
+    ] {
+        present.extend(
+            source
+                .list_skills()?
+                .iter()
+                .map(|skill| skill_key(skill, &config.skills)),
+        );
+    }
+    let mut kept = Vec::new();
+    for skill in only {
+        if validate_skill_id_with(skill, &config.skills).is_ok()
+            && present.contains(&skill_key(skill, &config.skills))
+        {
+            kept.push(skill.clone());
+        } else {
+            eprintln!(
+                "{}",
+                crate::tr!(
+                    "{} に skill がないためスキップしました: {}",
+                    "Skipped skill not found for {}: {}",
+                    target.name,
+                    skill
+                )
+            );
+        }
+    }
+    Ok(Some(kept))
+}
+
+fn override_algo(config: &mut Config, algo: Option<HashAlgo>) {
+    if let Some(algo) = algo {
+        config.hash.algo = algo;
+    }
+}
+
+fn override_ignore(config: &mut Config, no_ignore: bool) {
+    if no_ignore {
+        config.hash.ignore.clear();
+    }
+}
+
+/// Which root `doctor` checks, for its heading and events.
+#[derive(Debug, Clone, Copy)]
+enum DoctorScope<'a> {
+    Global,
+    Target(&'a str),
+}
+
+impl<'a> DoctorScope<'a> {
+    /// `global` or the target name, as in JSON output and events.
+    fn name(self) -> &'a str {
+        match self {
+            DoctorScope::Global => "global",
+            DoctorScope::Target(name) => name,
+        }
+    }
+}
+
+/// Checks one root and prints its report, except for `--format json`,
+/// which the caller renders once for every root.
+fn run_doctor(
+    config: &Config,
+    scope: DoctorScope<'_>,
+    root: &std::path::Path,
+    skill: Option<&str>,
+    format: OutputFormat,
+) -> AppResult<DoctorReport> {
+    if format == OutputFormat::Text {
+        match scope {
+            DoctorScope::Global => emit_line(crate::tr!("グローバル", "Global"))?,
+            DoctorScope::Target(name) => {
+                emit_line(crate::tr!("ターゲット: {}", "Target: {}", name))?
+            }
+        }
+    }
+    let report = match skill {
+        Some(skill) => doctor_skill(root, skill, &config.skills, &config.doctor)?,
+        None => doctor_root_with(root, &config.skills, &config.doctor)?,
+    };
+    let by_skill = group_issues_by_skill(&report.issues);
+    if format == OutputFormat::Json {
+        return Ok(report);
+    }
+    if format == OutputFormat::Ndjson {
+        for skill in &report.skills {
+            emit(&Event::Doctor {
+                scope: scope.name(),
+                skill,
+                issues: by_skill.get(skill.as_str()).cloned().unwrap_or_default(),
+            })?;
+        }
+        emit(&Event::DoctorSummary {
+            scope: scope.name(),
+            checked: report.skills.len(),
+            issues: report.issues.len(),
+        })?;
+        return Ok(report);
+    }
+    for skill in &report.skills {
+        if let Some(issues) = by_skill.get(skill.as_str()) {
+            for issue in issues {
+                emit_line(format_args!(
+                    "{} {} [{}] {}",
+                    paint("issue", Color::Red, color_enabled()),
+                    skill,
+                    issue.severity,
+                    issue.message
+                ))?;
+            }
+        } else {
+            emit_line(format_args!(
+                "{} {}",
+                paint("ok", Color::Green, color_enabled()),
+                skill
+            ))?;
+        }
+    }
+    emit_line(crate::tr!(
+        "checked: {} issues: {}",
+        "checked: {} issues: {}",
+        report.skills.len(),
+        report.issues.len()
+    ))?;
+    Ok(report)
+}
@@ -0,0 +1,92 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// When to emit ANSI colors on the primary output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum ColorChoice {
+    /// Color when the output is a terminal and `NO_COLOR` is unset or empty.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+const UNSET: u8 = 0;
+const ON: u8 = 1;
+const OFF: u8 = 2;
+
+static POLICY: AtomicU8 = AtomicU8::new(UNSET);
+
+/// Resolves `choice` once for the whole process.
+pub fn set_color_choice(choice: ColorChoice) {
+    let enabled = resolve(
+        choice,
+        std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()),
+        crate::output::output_is_terminal(),
+    );
+    POLICY.store(if enabled { ON } else { OFF }, Ordering::Relaxed);
+}
+
+/// Whether colorized output is enabled; `auto` is resolved lazily if
+/// [`set_color_choice`] was never called.
+pub fn color_enabled() -> bool {
+    match POLICY.load(Ordering::Relaxed) {
+        ON => true,
+        OFF => false,
+        _ => {
+            set_color_choice(ColorChoice::Auto);
+            POLICY.load(Ordering::Relaxed) == ON
+        }
+    }
+}
+
+fn resolve(choice: ColorChoice, no_color: bool, is_terminal: bool) -> bool {
+    match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => !no_color && is_terminal,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    Red,
+    Green,
+    Yellow,
+    Magenta,
+    Cyan,
+}
+
+/// Wraps `text` in the ANSI sequence for `color` when `enabled`.
+pub fn paint(text: &str, color: Color, enabled: bool) -> String {
+    if !enabled {
+        return text.to_string();
+    }
+    let code = match color {
+        Color::Red => 31,
+        Color::Green => 32,
+        Color::Yellow => 33,
+        Color::Magenta => 35,
+        Color::Cyan => 36,
+    };
+    format!("\x1B[{code}m{text}\x1B[0m")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn auto_respects_no_color_and_terminal() {
+        assert!(resolve(ColorChoice::Auto, false, true));
+        assert!(!resolve(ColorChoice::Auto, true, true));
+        assert!(!resolve(ColorChoice::Auto, false, false));
+        assert!(resolve(ColorChoice::Always, true, false));
+        assert!(!resolve(ColorChoice::Never, false, true));
+    }
+
+    #[test]
+    fn paint_wraps_only_when_enabled() {
+        assert_eq!(paint("diff", Color::Red, false), "diff");
+        assert_eq!(paint("diff", Color::Red, true), "\x1B[31mdiff\x1B[0m");
+    }
+}
@@ -1,18 +1,21 @@
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::fs;
 use std::io::ErrorKind;
 use std::path::{Path, PathBuf};
 
 use globset::Glob;
-use serde::Deserialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 
+use crate::digest::{DEFAULT_DIGEST_LEN, MAX_DIGEST_LEN, split_negation};
+use crate::doctor::{IssueKind, Severity};
 use crate::error::{AppError, AppResult};
 
 const CONFIG_PATH_ENV: &str = "SKILLCTL_CONFIG";
 const XDG_CONFIG_HOME_ENV: &str = "XDG_CONFIG_HOME";
 const DEFAULT_CONFIG_DIR: &str = "~/.config";
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
 pub struct Config {
     pub global_root: PathBuf,
     pub targets: Vec<Target>,
@@ -20,23 +23,86 @@ pub struct Config {
     pub hash: HashConfig,
     #[serde(default)]
     pub diff: DiffConfig,
+    #[serde(default)]
+    pub sync: SyncConfig,
+    #[serde(default)]
+    pub skills: SkillsConfig,
+    #[serde(default)]
+    pub doctor: DoctorConfig,
+    #[serde(default)]
+    pub audit: AuditConfig,
+    #[serde(default)]
+    pub status: StatusConfig,
+    #[serde(default)]
+    pub display: DisplayConfig,
+    /// Named subsets of `targets`, selected with the global `--profile`.
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+    /// Reject targets whose roots resolve to the same directory instead of
+    /// only warning about them.
+    #[serde(default)]
+    pub strict_targets: bool,
+    /// Worker threads for digesting skills in `status`; unset uses the CPU
+    /// count and `1` digests sequentially.
+    #[serde(default)]
+    pub parallelism: Option<usize>,
+    /// Git revision to read global skills from, set when `global_root` was
+    /// given as `git:<path>#<ref>` (`global_root` then holds `<path>`).
+    #[serde(skip)]
+    pub global_git_ref: Option<String>,
+}
+
+/// A named subset of targets (`[profiles.<name>]`).
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+pub struct Profile {
+    /// Names of the targets in scope under this profile.
+    pub targets: Vec<String>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
 pub struct Target {
     pub name: String,
     pub root: PathBuf,
+    /// Command run before a push to this target mutates anything.
+    #[serde(default)]
+    pub pre_push: Vec<String>,
+    /// Command run after all of a push's ops succeeded.
+    #[serde(default)]
+    pub post_push: Vec<String>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
 pub struct HashConfig {
     #[serde(default)]
     pub algo: HashAlgo,
     #[serde(default)]
     pub ignore: Vec<String>,
+    /// Newline-separated patterns file merged into `ignore` at load time.
+    #[serde(default)]
+    pub ignore_file: Option<PathBuf>,
+    /// Hash text files with CRLF collapsed to LF.
+    #[serde(default)]
+    pub normalize_eol: bool,
+    /// Fold each file's permission bits into the digest (Unix only).
+    #[serde(default)]
+    pub include_mode: bool,
+    /// Fold empty directory paths into the digest.
+    #[serde(default)]
+    pub include_empty_dirs: bool,
+    /// Digest skill directories as the root of a per-directory Merkle tree.
+    #[serde(default)]
+    pub merkle: bool,
+    /// File keeping Merkle trees between `status` runs; requires `merkle`.
+    #[serde(default)]
+    pub merkle_cache: Option<PathBuf>,
+    /// Files larger than this many bytes are hashed by size only, not content.
+    #[serde(default)]
+    pub skip_larger_than: Option<u64>,
 }
 
-#[derive(Debug, Clone, Copy, Deserialize, Default)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, Default, clap::ValueEnum,
+)]
 #[serde(rename_all = "lowercase")]
 pub enum HashAlgo {
     #[default]
@@ -44,10 +110,209 @@ pub enum HashAlgo {
     Sha256,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+/// How digests are written in command output (`display.digest_encoding`).
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, Default, clap::ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum DigestEncoding {
+    #[default]
+    Hex,
+    /// Standard base64 (RFC 4648) with `=` padding.
+    Base64,
+    /// Base32 (RFC 4648): upper-case letters and digits 2-7 with `=` padding.
+    Base32,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
 pub struct DiffConfig {
     #[serde(default = "default_diff_command")]
     pub command: Vec<String>,
+    /// Working directory for the diff command; inherits the process CWD when unset.
+    #[serde(default)]
+    pub cwd: Option<PathBuf>,
+    /// Allow a command without `{left}` / `{right}` that reads
+    /// `SKILLCTL_LEFT` / `SKILLCTL_RIGHT` instead.
+    #[serde(default)]
+    pub use_env: bool,
+    /// Kill the diff command after this many seconds; unset waits forever.
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+}
+
+/// Naming rules applied to skill ids.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct SkillsConfig {
+    /// Allow ids starting with `.`.
+    #[serde(default)]
+    pub allow_hidden: bool,
+    /// Characters allowed in addition to letters and digits.
+    #[serde(default = "default_allowed_chars")]
+    pub allowed_chars: String,
+    /// Compare skill names across roots ignoring ASCII/Unicode case.
+    #[serde(default)]
+    pub case_insensitive: bool,
+    #[serde(default)]
+    pub layout: SkillLayout,
+    /// Treat a top-level symlink to a directory as a skill (nested layout only).
+    #[serde(default)]
+    pub allow_symlink_dirs: bool,
+}
+
+/// What counts as a skill directly under a root.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SkillLayout {
+    /// Each top-level directory is a skill.
+    #[default]
+    Nested,
+    /// Each top-level regular file is a skill.
+    Flat,
+}
+
+/// Settings for `doctor` checks.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct DoctorConfig {
+    /// File name every skill directory must contain.
+    #[serde(default = "default_skill_file")]
+    pub skill_file: String,
+    /// Severity overrides per issue kind; unlisted kinds keep their default.
+    #[serde(default)]
+    pub severity: BTreeMap<IssueKind, Severity>,
+    /// Skills allowed to lack `skill_file` (e.g. pure data).
+    #[serde(default)]
+    pub exempt: Vec<String>,
+    /// Flag names that are invalid on Windows and overlong paths.
+    #[serde(default)]
+    pub portability: bool,
+    /// Longest `<skill>/<path>` (UTF-16 units) the portability check accepts.
+    #[serde(default = "default_max_path_len")]
+    pub max_path_len: usize,
+    /// Flag files that start with a byte-order mark or are not valid UTF-8.
+    #[serde(default)]
+    pub check_encoding: bool,
+    /// Files larger than this many bytes are skipped by the encoding check.
+    #[serde(default = "default_encoding_max_bytes")]
+    pub encoding_max_bytes: u64,
+}
+
+/// Settings for `status`.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+pub struct StatusConfig {
+    /// Report a missing and an extra skill whose names differ only by case
+    /// as one `renamed` row.
+    #[serde(default)]
+    pub detect_renames: bool,
+    /// List the files that differ for each `diff` skill.
+    #[serde(default)]
+    pub detail: bool,
+    /// Compare path/size/mtime fingerprints instead of content digests;
+    /// states become `likely-same` / `likely-diff`.
+    #[serde(default)]
+    pub fast: bool,
+}
+
+/// Settings for human-readable output.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct DisplayConfig {
+    /// Characters kept at each end of a shortened digest (`abc...xyz`).
+    #[serde(default = "default_digest_len")]
+    pub digest_len: usize,
+    /// Encoding of digests in `status` and `list --digests` output.
+    #[serde(default)]
+    pub digest_encoding: DigestEncoding,
+}
+
+fn default_digest_len() -> usize {
+    DEFAULT_DIGEST_LEN
+}
+
+impl Default for DisplayConfig {
+    fn default() -> Self {
+        Self {
+            digest_len: default_digest_len(),
+            digest_encoding: DigestEncoding::default(),
+        }
+    }
+}
+
+/// Settings for the push/import audit log.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+pub struct AuditConfig {
+    /// JSON lines file that receives one entry per executed op; disabled when unset.
+    pub log_path: Option<PathBuf>,
+}
+
+fn default_skill_file() -> String {
+    "SKILL.md".to_string()
+}
+
+fn default_max_path_len() -> usize {
+    260
+}
+
+fn default_encoding_max_bytes() -> u64 {
+    1024 * 1024
+}
+
+fn default_allowed_chars() -> String {
+    "-_.".to_string()
+}
+
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+pub struct SyncConfig {
+    #[serde(default)]
+    pub retry: RetryConfig,
+    #[serde(default)]
+    pub update_mode: UpdateMode,
+    /// Treat push/import as dry-run unless `--apply` is passed.
+    #[serde(default)]
+    pub require_apply: bool,
+    #[serde(default)]
+    pub reflink: ReflinkMode,
+    /// Cap on bytes written per second by each op; unlimited when unset.
+    #[serde(default)]
+    pub throttle_bytes_per_sec: Option<u64>,
+}
+
+/// Whether file copies try a copy-on-write clone (FICLONE / clonefile) first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ReflinkMode {
+    /// Clone when the filesystem supports it, otherwise copy bytes.
+    #[default]
+    Auto,
+    /// Clone or fail.
+    Always,
+    /// Always copy bytes.
+    Never,
+}
+
+/// How an `Update` op rewrites an existing skill directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateMode {
+    /// Copy to a temp dir and swap it in (atomic per skill).
+    #[default]
+    Replace,
+    /// Rewrite only changed/added files in place and delete removed ones.
+    Incremental,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, JsonSchema)]
+pub struct RetryConfig {
+    #[serde(default = "default_retry_attempts")]
+    pub attempts: u32,
+    #[serde(default = "default_retry_backoff_ms")]
+    pub backoff_ms: u64,
+}
+
+fn default_retry_attempts() -> u32 {
+    1
+}
+
+fn default_retry_backoff_ms() -> u64 {
+    100
 }
 
 fn default_diff_command() -> Vec<String> {
@@ -66,6 +331,63 @@ impl Default for HashConfig {
         Self {
             algo: HashAlgo::Blake3,
             ignore: Vec::new(),
+            ignore_file: None,
+            normalize_eol: false,
+            include_mode: false,
+            include_empty_dirs: false,
+            merkle: false,
+            merkle_cache: None,
+            skip_larger_than: None,
+        }
+    }
+}
+
+impl Default for SkillsConfig {
+    fn default() -> Self {
+        Self {
+            allow_hidden: false,
+            allowed_chars: default_allowed_chars(),
+            case_insensitive: false,
+            layout: SkillLayout::Nested,
+            allow_symlink_dirs: false,
+        }
+    }
+}
+
+impl Default for DoctorConfig {
+    fn default() -> Self {
+        Self {
+            skill_file: default_skill_file(),
+            severity: BTreeMap::new(),
+            exempt: Vec::new(),
+            portability: false,
+            max_path_len: default_max_path_len(),
+            check_encoding: false,
+            encoding_max_bytes: default_encoding_max_bytes(),
+        }
+    }
+}
+
+impl DoctorConfig {
+    /// Severity of `kind`, from `[doctor.severity]` or the kind's default.
+    pub fn severity_of(&self, kind: IssueKind) -> Severity {
+        self.severity
+            .get(&kind)
+            .copied()
+            .unwrap_or_else(|| kind.default_severity())
+    }
+
+    /// Whether `skill` is listed in `doctor.exempt`.
+    pub fn is_exempt(&self, skill: &str) -> bool {
+        self.exempt.iter().any(|name| name == skill)
+    }
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            attempts: default_retry_attempts(),
+            backoff_ms: default_retry_backoff_ms(),
         }
     }
 }
@@ -74,21 +396,45 @@ impl Default for DiffConfig {
     fn default() -> Self {
         Self {
             command: default_diff_command(),
+            cwd: None,
+            use_env: false,
+            timeout_secs: None,
         }
     }
 }
 
 impl Config {
     pub fn load_default() -> AppResult<Self> {
+        Self::load_default_with(None)
+    }
+
+    /// Like [`Config::load_default`], but relative paths (`SKILLCTL_CONFIG`,
+    /// roots, `diff.cwd`, `audit.log_path`) resolve against `base` instead of
+    /// the process working directory, which is left unchanged.
+    pub fn load_default_in(base: &Path) -> AppResult<Self> {
+        Self::load_default_with(Some(base))
+    }
+
+    fn load_default_with(base: Option<&Path>) -> AppResult<Self> {
         let path = if let Some(path) = env_var_non_empty(CONFIG_PATH_ENV) {
-            expand_path(&path)?
+            resolve_in(base, expand_path(&path)?)
         } else {
             default_config_path()?
         };
-        Self::load_from_path(&path)
+        Self::load(&path, base)
     }
 
     pub fn load_from_path(path: &Path) -> AppResult<Self> {
+        Self::load(path, None)
+    }
+
+    /// Like [`Config::load_from_path`], resolving relative paths in the
+    /// config against `base`; `diff.cwd` defaults to `base`.
+    pub fn load_from_path_in(path: &Path, base: &Path) -> AppResult<Self> {
+        Self::load(path, Some(base))
+    }
+
+    fn load(path: &Path, base: Option<&Path>) -> AppResult<Self> {
         let content = fs::read_to_string(path).map_err(|err| {
             let (message, hint) = match err.kind() {
                 ErrorKind::NotFound => (
@@ -126,20 +472,47 @@ impl Config {
             AppError::config(message, hint)
         })?;
         let mut config: Config = toml::from_str(&content).map_err(|err| {
-            AppError::config(
+            AppError::config_with_source(
                 crate::tr!(
                     "設定ファイルの解析に失敗しました: {}",
                     "Failed to parse config file: {}",
                     path.display()
                 ),
                 Some(err.to_string()),
+                err,
             )
         })?;
-        config.expand_paths()?;
+        config.expand_paths(base)?;
+        config.merge_ignore_file()?;
         config.validate()?;
         Ok(config)
     }
 
+    /// Replaces `global_root` with `path`, expanded and resolved like the config value.
+    pub fn override_global_root(&mut self, path: &Path, base: Option<&Path>) -> AppResult<()> {
+        self.global_root = path.to_path_buf();
+        self.global_git_ref = None;
+        self.expand_global_root(base)
+    }
+
+    /// Splits a `git:<path>#<ref>` global root into `global_root` and
+    /// `global_git_ref`, then expands and resolves the path.
+    fn expand_global_root(&mut self, base: Option<&Path>) -> AppResult<()> {
+        if let Some((path, git_ref)) = split_git_root(&self.global_root)? {
+            self.global_root = path;
+            self.global_git_ref = Some(git_ref);
+        }
+        self.global_root = resolve_in(base, expand_path_pathbuf(&self.global_root)?);
+        Ok(())
+    }
+
+    /// Worker threads to use: `parallelism`, or the CPU count when unset.
+    pub fn jobs(&self) -> usize {
+        self.parallelism.unwrap_or_else(|| {
+            std::thread::available_parallelism().map_or(1, std::num::NonZeroUsize::get)
+        })
+    }
+
     pub fn target_by_name(&self, name: &str) -> AppResult<&Target> {
         self.targets.iter().find(|t| t.name == name).ok_or_else(|| {
             AppError::config(
@@ -156,14 +529,113 @@ impl Config {
         })
     }
 
-    fn expand_paths(&mut self) -> AppResult<()> {
-        self.global_root = expand_path_pathbuf(&self.global_root)?;
+    /// Keeps only the targets of profile `name` (`--profile`), in config
+    /// order; an unknown profile is a config error.
+    pub fn select_profile(&mut self, name: &str) -> AppResult<()> {
+        let profile = self.profiles.get(name).ok_or_else(|| {
+            AppError::config(
+                crate::tr!(
+                    "プロファイルが見つかりません: {}",
+                    "Profile not found: {}",
+                    name
+                ),
+                Some(if self.profiles.is_empty() {
+                    crate::tr!(
+                        "config.toml に [profiles.<name>] を追加してください",
+                        "Add [profiles.<name>] to config.toml"
+                    )
+                } else {
+                    crate::tr!(
+                        "利用可能なプロファイル: {}",
+                        "Available profiles: {}",
+                        self.profiles.keys().cloned().collect::<Vec<_>>().join(", ")
+                    )
+                }),
+            )
+        })?;
+        let names = profile.targets.iter().cloned().collect::<HashSet<_>>();
+        self.targets.retain(|target| names.contains(&target.name));
+        Ok(())
+    }
+
+    /// Pairs of distinct targets whose roots resolve to the same directory
+    /// (see [`canonical_root`]), as `(first, second, root)` in config order.
+    pub fn shared_target_roots(&self) -> Vec<(&str, &str, PathBuf)> {
+        let mut owners: BTreeMap<PathBuf, &str> = BTreeMap::new();
+        let mut shared = Vec::new();
+        for target in &self.targets {
+            let root = canonical_root(&target.root);
+            match owners.get(&root) {
+                Some(first) => shared.push((*first, target.name.as_str(), root)),
+                None => {
+                    owners.insert(root, &target.name);
+                }
+            }
+        }
+        shared
+    }
+
+    fn expand_paths(&mut self, base: Option<&Path>) -> AppResult<()> {
+        self.expand_global_root(base)?;
         for target in &mut self.targets {
-            target.root = expand_path_pathbuf(&target.root)?;
+            target.root = resolve_in(base, expand_path_pathbuf(&target.root)?);
+        }
+        match &self.diff.cwd {
+            Some(cwd) => self.diff.cwd = Some(resolve_in(base, expand_path_pathbuf(cwd)?)),
+            None => self.diff.cwd = base.map(Path::to_path_buf),
+        }
+        if let Some(log_path) = &self.audit.log_path {
+            self.audit.log_path = Some(resolve_in(base, expand_path_pathbuf(log_path)?));
+        }
+        if let Some(cache) = &self.hash.merkle_cache {
+            self.hash.merkle_cache = Some(resolve_in(base, expand_path_pathbuf(cache)?));
+        }
+        if let Some(file) = &self.hash.ignore_file {
+            self.hash.ignore_file = Some(resolve_in(base, expand_path_pathbuf(file)?));
         }
         Ok(())
     }
 
+    /// Appends the patterns of `hash.ignore_file` to `hash.ignore`; blank
+    /// lines and `#` comments are skipped.
+    fn merge_ignore_file(&mut self) -> AppResult<()> {
+        let Some(path) = &self.hash.ignore_file else {
+            return Ok(());
+        };
+        let text = fs::read_to_string(path).map_err(|err| {
+            if err.kind() == ErrorKind::NotFound {
+                AppError::config_with_source(
+                    crate::tr!(
+                        "hash.ignore_file が見つかりません: {}",
+                        "hash.ignore_file not found: {}",
+                        path.display()
+                    ),
+                    Some(crate::tr!(
+                        "ファイルを作成するか hash.ignore_file を修正してください",
+                        "Create the file or fix hash.ignore_file."
+                    )),
+                    err,
+                )
+            } else {
+                AppError::io(
+                    crate::tr!(
+                        "hash.ignore_file を読み込めません: {}",
+                        "Failed to read hash.ignore_file: {}",
+                        path.display()
+                    ),
+                    err,
+                )
+            }
+        })?;
+        self.hash.ignore.extend(
+            text.lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(String::from),
+        );
+        Ok(())
+    }
+
     fn validate(&self) -> AppResult<()> {
         if self.diff.command.is_empty() {
             return Err(AppError::config(
@@ -176,30 +648,158 @@ impl Config {
         }
         let has_left = self.diff.command.iter().any(|arg| arg.contains("{left}"));
         let has_right = self.diff.command.iter().any(|arg| arg.contains("{right}"));
-        if !has_left || !has_right {
+        if !self.diff.use_env && (!has_left || !has_right) {
             return Err(AppError::config(
                 crate::tr!(
                     "diff.command に {{left}} と {{right}} が必要です",
                     "diff.command must include {{left}} and {{right}}"
                 ),
                 Some(crate::tr!(
-                    "config.toml の diff.command に両方のプレースホルダを含めてください",
-                    "Include both placeholders in diff.command in config.toml"
+                    "config.toml の diff.command に両方のプレースホルダを含めるか、diff.use_env = true を設定してください",
+                    "Include both placeholders in diff.command in config.toml, or set diff.use_env = true"
+                )),
+            ));
+        }
+        if let Some(cwd) = &self.diff.cwd
+            && !cwd.is_dir()
+        {
+            return Err(AppError::config(
+                crate::tr!(
+                    "diff.cwd が存在しません: {}",
+                    "diff.cwd does not exist: {}",
+                    cwd.display()
+                ),
+                Some(crate::tr!(
+                    "既存のディレクトリを指定してください",
+                    "Set an existing directory."
+                )),
+            ));
+        }
+        if self.parallelism == Some(0) {
+            return Err(AppError::config(
+                crate::tr!(
+                    "parallelism は 1 以上にしてください",
+                    "parallelism must be at least 1"
+                ),
+                Some(crate::tr!(
+                    "CPU 数に合わせる場合は parallelism を削除してください",
+                    "Remove parallelism to use the CPU count."
+                )),
+            ));
+        }
+        if self.diff.timeout_secs == Some(0) {
+            return Err(AppError::config(
+                crate::tr!(
+                    "diff.timeout_secs は 1 以上にしてください",
+                    "diff.timeout_secs must be at least 1"
+                ),
+                Some(crate::tr!(
+                    "タイムアウトしない場合は diff.timeout_secs を削除してください",
+                    "Remove diff.timeout_secs to wait without a limit."
+                )),
+            ));
+        }
+        if self.hash.merkle_cache.is_some() && !self.hash.merkle {
+            return Err(AppError::config(
+                crate::tr!(
+                    "hash.merkle_cache には hash.merkle = true が必要です",
+                    "hash.merkle_cache requires hash.merkle = true"
+                ),
+                Some(crate::tr!(
+                    "hash.merkle = true を設定するか hash.merkle_cache を削除してください",
+                    "Set hash.merkle = true or remove hash.merkle_cache."
                 )),
             ));
         }
         for pattern in &self.hash.ignore {
-            Glob::new(pattern).map_err(|err| {
-                AppError::config(
+            Glob::new(split_negation(pattern).1).map_err(|err| {
+                AppError::config_with_source(
                     crate::tr!(
                         "ignore パターンが不正です: {}",
                         "Invalid ignore pattern: {}",
                         pattern
                     ),
                     Some(err.to_string()),
+                    err,
                 )
             })?;
         }
+        if let Some(c) = self
+            .skills
+            .allowed_chars
+            .chars()
+            .find(|c| c.is_whitespace() || matches!(c, '/' | '\\'))
+        {
+            return Err(AppError::config(
+                crate::tr!(
+                    "skills.allowed_chars に使用できない文字があります: {:?}",
+                    "skills.allowed_chars contains a forbidden character: {:?}",
+                    c
+                ),
+                Some(crate::tr!(
+                    "空白やパス区切りは指定できません",
+                    "Whitespace and path separators are not allowed."
+                )),
+            ));
+        }
+        let skill_file = &self.doctor.skill_file;
+        if skill_file.is_empty()
+            || skill_file == "."
+            || skill_file == ".."
+            || skill_file.contains(['/', '\\'])
+        {
+            return Err(AppError::config(
+                crate::tr!(
+                    "doctor.skill_file はファイル名のみ指定できます: {:?}",
+                    "doctor.skill_file must be a single file name: {:?}",
+                    skill_file
+                ),
+                Some(crate::tr!(
+                    "パス区切りを含まない名前（例: \"SKILL.md\"）を指定してください",
+                    "Use a name without path separators, e.g. \"SKILL.md\"."
+                )),
+            ));
+        }
+        if self.sync.retry.attempts == 0 {
+            return Err(AppError::config(
+                crate::tr!(
+                    "sync.retry.attempts は 1 以上にしてください",
+                    "sync.retry.attempts must be at least 1"
+                ),
+                Some(crate::tr!(
+                    "リトライしない場合は 1 を設定してください",
+                    "Set 1 to disable retries."
+                )),
+            ));
+        }
+        if !(DEFAULT_DIGEST_LEN..=MAX_DIGEST_LEN).contains(&self.display.digest_len) {
+            return Err(AppError::config(
+                crate::tr!(
+                    "display.digest_len は {} 以上 {} 以下にしてください: {}",
+                    "display.digest_len must be between {} and {}: {}",
+                    DEFAULT_DIGEST_LEN,
+                    MAX_DIGEST_LEN,
+                    self.display.digest_len
+                ),
+                Some(crate::tr!(
+                    "{} で digest 全体が表示されます",
+                    "{} shows the whole digest.",
+                    MAX_DIGEST_LEN
+                )),
+            ));
+        }
+        if self.sync.throttle_bytes_per_sec == Some(0) {
+            return Err(AppError::config(
+                crate::tr!(
+                    "sync.throttle_bytes_per_sec は 1 以上にしてください",
+                    "sync.throttle_bytes_per_sec must be at least 1"
+                ),
+                Some(crate::tr!(
+                    "制限しない場合は設定を削除してください",
+                    "Remove the setting to disable throttling."
+                )),
+            ));
+        }
         if self.targets.is_empty() {
             return Err(AppError::config(
                 crate::tr!("targets が空です", "targets is empty"),
@@ -234,22 +834,111 @@ impl Config {
                 ));
             }
         }
+        for (name, profile) in &self.profiles {
+            if let Some(unknown) = profile
+                .targets
+                .iter()
+                .find(|target| !seen.contains(target.as_str()))
+            {
+                return Err(AppError::config(
+                    crate::tr!(
+                        "profiles.{}.targets に存在しないターゲットがあります: {}",
+                        "profiles.{}.targets names an unknown target: {}",
+                        name,
+                        unknown
+                    ),
+                    Some(crate::tr!(
+                        "targets に定義された name を指定してください",
+                        "Use names defined in targets."
+                    )),
+                ));
+            }
+        }
+        if self.strict_targets
+            && let Some((first, second, root)) = self.shared_target_roots().into_iter().next()
+        {
+            return Err(AppError::config(
+                crate::tr!(
+                    "ターゲット {} と {} が同じ root を指しています: {}",
+                    "Targets {} and {} share the same root: {}",
+                    first,
+                    second,
+                    root.display()
+                ),
+                Some(crate::tr!(
+                    "どちらかのターゲットを削除するか、別の root を指定してください",
+                    "Remove one of the targets or point it at another root."
+                )),
+            ));
+        }
         Ok(())
     }
 }
 
-fn expand_path(path: &str) -> AppResult<PathBuf> {
-    let expanded = shellexpand::full(path).map_err(|err| {
-        AppError::config(
+/// JSON Schema of the config file (`skillctl config schema`), derived from
+/// the serde types above.
+pub fn config_schema() -> AppResult<String> {
+    serde_json::to_string_pretty(&schemars::schema_for!(Config)).map_err(|err| {
+        AppError::exec_with_source(
             crate::tr!(
-                "パス展開に失敗しました: {}",
-                "Failed to expand path: {}",
-                path
+                "設定スキーマの出力に失敗しました",
+                "Failed to render the config schema"
             ),
-            Some(err.to_string()),
+            None,
+            err,
         )
-    })?;
-    Ok(PathBuf::from(expanded.as_ref()))
+    })
+}
+
+/// Prefix of a global root read from a git revision: `git:<path>#<ref>`.
+pub const GIT_ROOT_PREFIX: &str = "git:";
+
+/// Parses `git:<path>#<ref>` into the repository path and the revision;
+/// other roots return `None`. The last `#` separates the revision.
+pub fn split_git_root(root: &Path) -> AppResult<Option<(PathBuf, String)>> {
+    let Some(spec) = root
+        .to_str()
+        .and_then(|root| root.strip_prefix(GIT_ROOT_PREFIX))
+    else {
+        return Ok(None);
+    };
+    match spec.rsplit_once('#') {
+        Some((path, git_ref)) if !path.is_empty() && !git_ref.is_empty() => {
+            Ok(Some((PathBuf::from(path), git_ref.to_string())))
+        }
+        _ => Err(AppError::config(
+            crate::tr!(
+                "git の global_root が不正です: {}",
+                "Invalid git global_root: {}",
+                root.display()
+            ),
+            Some(crate::tr!(
+                "git:<リポジトリのパス>#<ブランチ・タグ・コミット> の形式で指定してください",
+                "Use git:<repository path>#<branch, tag or commit>."
+            )),
+        )),
+    }
+}
+
+/// `path` with symlinks and `..` resolved, so two spellings of one directory
+/// compare equal; paths that cannot be resolved (e.g. missing) are kept as is.
+pub fn canonical_root(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn expand_path(path: &str) -> AppResult<PathBuf> {
+    let expanded = shellexpand::full(path).map_err(|err| {
+        AppError::config_with_source(
+            crate::tr!(
+                "パス展開に失敗しました: {}",
+                "Failed to expand path: {}",
+                path
+            ),
+            Some(err.to_string()),
+            err,
+        )
+    })?;
+    Ok(PathBuf::from(expanded.as_ref()))
 }
 
 fn expand_path_pathbuf(path: &Path) -> AppResult<PathBuf> {
@@ -257,6 +946,15 @@ fn expand_path_pathbuf(path: &Path) -> AppResult<PathBuf> {
     expand_path(&raw)
 }
 
+/// Joins a relative `path` onto `base`; absolute paths and `base = None`
+/// leave it unchanged.
+pub(crate) fn resolve_in(base: Option<&Path>, path: PathBuf) -> PathBuf {
+    match base {
+        Some(base) if path.is_relative() => base.join(path),
+        _ => path,
+    }
+}
+
 fn env_var_non_empty(name: &str) -> Option<String> {
     std::env::var(name).ok().filter(|value| !value.is_empty())
 }
@@ -350,6 +1048,58 @@ root = "/tmp/skills"
         assert!(matches!(err, AppError::Config { .. }));
     }
 
+    #[test]
+    fn config_digest_len_defaults_and_is_bounded() {
+        let dir = TempDir::new().unwrap();
+        let body = |display: &str| {
+            format!(
+                r#"
+global_root = "/tmp/global"
+[[targets]]
+name = "t1"
+root = "/tmp/skills"
+{display}
+"#
+            )
+        };
+        let config = Config::load_from_path(&write_config(&dir, &body(""))).unwrap();
+        assert_eq!(config.display.digest_len, 3);
+        let config =
+            Config::load_from_path(&write_config(&dir, &body("[display]\ndigest_len = 8")))
+                .unwrap();
+        assert_eq!(config.display.digest_len, 8);
+        for len in [2, 33] {
+            let display = format!("[display]\ndigest_len = {len}");
+            let err = Config::load_from_path(&write_config(&dir, &body(&display))).unwrap_err();
+            assert!(matches!(err, AppError::Config { .. }));
+        }
+    }
+
+    #[test]
+    fn config_merkle_cache_requires_merkle() {
+        let dir = TempDir::new().unwrap();
+        let body = |merkle: bool| {
+            format!(
+                r#"
+global_root = "/tmp/global"
+[[targets]]
+name = "t1"
+root = "/tmp/skills"
+[hash]
+merkle = {merkle}
+merkle_cache = "/tmp/skillctl-merkle.json"
+"#
+            )
+        };
+        let err = Config::load_from_path(&write_config(&dir, &body(false))).unwrap_err();
+        assert!(matches!(err, AppError::Config { .. }));
+        let config = Config::load_from_path(&write_config(&dir, &body(true))).unwrap();
+        assert_eq!(
+            config.hash.merkle_cache,
+            Some(PathBuf::from("/tmp/skillctl-merkle.json"))
+        );
+    }
+
     #[test]
     fn config_errors_when_missing_targets() {
         let dir = TempDir::new().unwrap();
@@ -398,6 +1148,46 @@ root = "/tmp/skills2"
         assert!(matches!(err, AppError::Config { .. }));
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn config_detects_targets_sharing_a_root_via_symlink() {
+        let dir = TempDir::new().unwrap();
+        let real = dir.path().join("skills");
+        fs::create_dir_all(&real).unwrap();
+        std::os::unix::fs::symlink(&real, dir.path().join("alias")).unwrap();
+        let body = format!(
+            r#"
+global_root = "/tmp/global"
+{{strict}}
+
+[[targets]]
+name = "t1"
+root = "{root}/skills"
+
+[[targets]]
+name = "t2"
+root = "{root}/alias"
+
+[[targets]]
+name = "t3"
+root = "{root}/other"
+"#,
+            root = dir.path().display()
+        );
+
+        let path = write_config(&dir, &body.replace("{strict}", ""));
+        let config = Config::load_from_path(&path).unwrap();
+        assert_eq!(
+            config.shared_target_roots(),
+            vec![("t1", "t2", fs::canonicalize(&real).unwrap())]
+        );
+
+        let path = write_config(&dir, &body.replace("{strict}", "strict_targets = true"));
+        let err = Config::load_from_path(&path).unwrap_err();
+        assert!(matches!(err, AppError::Config { .. }));
+        assert!(err.to_string().contains("t1"));
+    }
+
     #[test]
     fn config_errors_when_hash_algo_invalid() {
         let dir = TempDir::new().unwrap();
@@ -418,6 +1208,46 @@ algo = "md5"
         assert!(matches!(err, AppError::Config { .. }));
     }
 
+    #[test]
+    fn config_merges_ignore_file_patterns() {
+        let dir = TempDir::new().unwrap();
+        let global_root = dir.path().join("global");
+        let target_root = dir.path().join("target");
+        fs::create_dir_all(global_root.join("skill1")).unwrap();
+        fs::create_dir_all(target_root.join("skill1")).unwrap();
+        fs::write(global_root.join("skill1/SKILL.md"), "same").unwrap();
+        fs::write(target_root.join("skill1/SKILL.md"), "same").unwrap();
+        fs::write(target_root.join("skill1/notes.tmp"), "scratch").unwrap();
+        let body = format!(
+            r#"
+global_root = "{}"
+[[targets]]
+name = "t1"
+root = "{}"
+[hash]
+ignore = [".git/**"]
+ignore_file = "shared.ignore"
+"#,
+            global_root.display(),
+            target_root.display()
+        );
+        let path = write_config(&dir, &body);
+
+        let err = Config::load_from_path_in(&path, dir.path()).unwrap_err();
+        assert!(matches!(err, AppError::Config { .. }));
+        assert!(err.to_string().contains("shared.ignore"));
+
+        fs::write(dir.path().join("shared.ignore"), "# shared\n\n**/*.tmp\n").unwrap();
+        let config = Config::load_from_path_in(&path, dir.path()).unwrap();
+        assert_eq!(config.hash.ignore, [".git/**", "**/*.tmp"]);
+        let rows = crate::status::status_for_target(&config, &config.targets[0]).unwrap();
+        assert_eq!(rows[0].state, crate::status::State::Same);
+
+        fs::write(dir.path().join("shared.ignore"), "[\n").unwrap();
+        let err = Config::load_from_path_in(&path, dir.path()).unwrap_err();
+        assert!(matches!(err, AppError::Config { .. }));
+    }
+
     #[test]
     fn config_errors_when_ignore_invalid() {
         let dir = TempDir::new().unwrap();
@@ -478,6 +1308,313 @@ command = ["git", "diff", "--no-index", "--", "/tmp/a", "{right}"]
         assert!(matches!(err, AppError::Config { .. }));
     }
 
+    #[test]
+    fn config_allows_command_without_placeholders_with_use_env() {
+        let dir = TempDir::new().unwrap();
+        let path = write_config(
+            &dir,
+            r#"
+global_root = "/tmp/global"
+
+[[targets]]
+name = "t1"
+root = "/tmp/skills"
+
+[diff]
+command = ["mytool"]
+use_env = true
+"#,
+        );
+        let config = Config::load_from_path(&path).unwrap();
+        assert!(config.diff.use_env);
+    }
+
+    #[test]
+    fn config_errors_when_retry_attempts_zero() {
+        let dir = TempDir::new().unwrap();
+        let path = write_config(
+            &dir,
+            r#"
+global_root = "/tmp/global"
+
+[[targets]]
+name = "t1"
+root = "/tmp/skills"
+
+[sync.retry]
+attempts = 0
+"#,
+        );
+        let err = Config::load_from_path(&path).unwrap_err();
+        assert!(matches!(err, AppError::Config { .. }));
+    }
+
+    #[test]
+    fn config_errors_when_throttle_is_zero() {
+        let dir = TempDir::new().unwrap();
+        let path = write_config(
+            &dir,
+            r#"
+global_root = "/tmp/global"
+
+[[targets]]
+name = "t1"
+root = "/tmp/skills"
+
+[sync]
+throttle_bytes_per_sec = 0
+"#,
+        );
+        let err = Config::load_from_path(&path).unwrap_err();
+        assert!(matches!(err, AppError::Config { .. }));
+    }
+
+    #[test]
+    fn config_reads_skill_rules() {
+        let dir = TempDir::new().unwrap();
+        let path = write_config(
+            &dir,
+            r#"
+global_root = "/tmp/global"
+
+[[targets]]
+name = "t1"
+root = "/tmp/skills"
+
+[skills]
+allow_hidden = true
+allowed_chars = "-_.+"
+"#,
+        );
+        let config = Config::load_from_path(&path).unwrap();
+        assert!(config.skills.allow_hidden);
+        assert_eq!(config.skills.allowed_chars, "-_.+");
+    }
+
+    #[test]
+    fn config_errors_when_allowed_chars_has_separator() {
+        let dir = TempDir::new().unwrap();
+        let path = write_config(
+            &dir,
+            r#"
+global_root = "/tmp/global"
+
+[[targets]]
+name = "t1"
+root = "/tmp/skills"
+
+[skills]
+allowed_chars = "-/"
+"#,
+        );
+        let err = Config::load_from_path(&path).unwrap_err();
+        assert!(matches!(err, AppError::Config { .. }));
+    }
+
+    #[test]
+    fn config_errors_when_skill_file_has_separator() {
+        let dir = TempDir::new().unwrap();
+        let path = write_config(
+            &dir,
+            r#"
+global_root = "/tmp/global"
+
+[[targets]]
+name = "t1"
+root = "/tmp/skills"
+
+[doctor]
+skill_file = "docs/README.md"
+"#,
+        );
+        let err = Config::load_from_path(&path).unwrap_err();
+        assert!(matches!(err, AppError::Config { .. }));
+    }
+
+    #[test]
+    fn config_parses_doctor_severity_overrides() {
+        let dir = TempDir::new().unwrap();
+        let path = write_config(
+            &dir,
+            r#"
+global_root = "/tmp/global"
+
+[[targets]]
+name = "t1"
+root = "/tmp/skills"
+
+[doctor.severity]
+symlink = "error"
+missing_skill_file = "info"
+"#,
+        );
+        let config = Config::load_from_path(&path).unwrap();
+        assert_eq!(
+            config.doctor.severity_of(IssueKind::Symlink),
+            Severity::Error
+        );
+        assert_eq!(
+            config.doctor.severity_of(IssueKind::MissingSkillFile),
+            Severity::Info
+        );
+        assert_eq!(
+            config.doctor.severity_of(IssueKind::UnsupportedFileType),
+            Severity::Error
+        );
+
+        let path = write_config(
+            &dir,
+            r#"
+global_root = "/tmp/global"
+
+[[targets]]
+name = "t1"
+root = "/tmp/skills"
+
+[doctor.severity]
+symlinks = "error"
+"#,
+        );
+        let err = Config::load_from_path(&path).unwrap_err();
+        assert!(matches!(err, AppError::Config { .. }));
+    }
+
+    #[test]
+    fn config_profiles_select_known_targets() {
+        let dir = TempDir::new().unwrap();
+        let path = write_config(
+            &dir,
+            r#"
+global_root = "/abs/global"
+
+[[targets]]
+name = "t1"
+root = "/abs/one"
+
+[[targets]]
+name = "t2"
+root = "/abs/two"
+
+[profiles.work]
+targets = ["t2"]
+"#,
+        );
+        let mut config = Config::load_from_path_in(&path, dir.path()).unwrap();
+        config.select_profile("work").unwrap();
+        assert_eq!(config.targets.len(), 1);
+        assert_eq!(config.targets[0].name, "t2");
+        let err = config.select_profile("home").unwrap_err();
+        assert!(matches!(err, AppError::Config { .. }));
+
+        let path = write_config(
+            &dir,
+            r#"
+global_root = "/abs/global"
+
+[[targets]]
+name = "t1"
+root = "/abs/one"
+
+[profiles.work]
+targets = ["t1", "t3"]
+"#,
+        );
+        let err = Config::load_from_path_in(&path, dir.path()).unwrap_err();
+        assert!(err.to_string().contains("t3"));
+    }
+
+    #[test]
+    fn config_splits_git_global_root() {
+        let dir = TempDir::new().unwrap();
+        let path = write_config(
+            &dir,
+            r#"
+global_root = "git:repos/skills#release/v1"
+
+[[targets]]
+name = "t1"
+root = "/abs/skills"
+"#,
+        );
+        let config = Config::load_from_path_in(&path, dir.path()).unwrap();
+        assert_eq!(config.global_root, dir.path().join("repos/skills"));
+        assert_eq!(config.global_git_ref.as_deref(), Some("release/v1"));
+
+        let path = write_config(
+            &dir,
+            r#"
+global_root = "git:repos/skills"
+
+[[targets]]
+name = "t1"
+root = "/abs/skills"
+"#,
+        );
+        let err = Config::load_from_path_in(&path, dir.path()).unwrap_err();
+        assert!(matches!(err, AppError::Config { .. }));
+    }
+
+    #[test]
+    fn config_resolves_relative_paths_against_base() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("work")).unwrap();
+        let path = write_config(
+            &dir,
+            r#"
+global_root = "global"
+
+[[targets]]
+name = "t1"
+root = "/abs/skills"
+
+[diff]
+cwd = "work"
+"#,
+        );
+        let config = Config::load_from_path_in(&path, dir.path()).unwrap();
+        assert_eq!(config.global_root, dir.path().join("global"));
+        assert_eq!(config.targets[0].root, PathBuf::from("/abs/skills"));
+        assert_eq!(config.diff.cwd, Some(dir.path().join("work")));
+
+        let body = fs::read_to_string(&path)
+            .unwrap()
+            .replace("cwd = \"work\"", "");
+        fs::write(&path, body).unwrap();
+        let config = Config::load_from_path_in(&path, dir.path()).unwrap();
+        assert_eq!(config.diff.cwd.as_deref(), Some(dir.path()));
+        let config = Config::load_from_path(&path).unwrap();
+        assert_eq!(config.global_root, PathBuf::from("global"));
+        assert_eq!(config.diff.cwd, None);
+    }
+
+    #[test]
+    fn config_expands_and_validates_diff_cwd() {
+        let dir = TempDir::new().unwrap();
+        let cwd = dir.path().join("diff-cwd");
+        fs::create_dir_all(&cwd).unwrap();
+        let body = format!(
+            r#"
+global_root = "/tmp/global"
+
+[[targets]]
+name = "t1"
+root = "/tmp/skills"
+
+[diff]
+cwd = "{}"
+"#,
+            cwd.display().to_string().replace('\\', "\\\\")
+        );
+        let path = write_config(&dir, &body);
+        let config = Config::load_from_path(&path).unwrap();
+        assert_eq!(config.diff.cwd.as_deref(), Some(cwd.as_path()));
+
+        let missing = body.replace("diff-cwd\"", "missing\"");
+        let path = write_config(&dir, &missing);
+        let err = Config::load_from_path(&path).unwrap_err();
+        assert!(matches!(err, AppError::Config { .. }));
+    }
+
     #[test]
     fn config_default_diff_command_is_set() {
         let dir = TempDir::new().unwrap();
@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::{Mutex, OnceLock};
@@ -5,14 +6,27 @@ use std::sync::{Mutex, OnceLock};
 use tempfile::TempDir;
 
 use crate::Config;
-use crate::config::{DiffConfig, HashAlgo, HashConfig, Target};
-use crate::diff::run_diff;
-use crate::digest::digest_dir;
+use crate::checkpoint::{CHECKPOINT_FILE, CHECKPOINT_NOTE, Checkpoint};
+use crate::cli::target_candidates_from;
+use crate::config::{
+    AuditConfig, DiffConfig, DisplayConfig, DoctorConfig, HashAlgo, HashConfig, SkillLayout,
+    SkillsConfig, StatusConfig, SyncConfig, Target, UpdateMode,
+};
+use crate::diff::{DiffOptions, run_diff};
+use crate::digest::{DigestOptions, FileChange, FileState, digest_dir};
 use crate::error::AppError;
-use crate::status::{State, list_skills, status_for_target};
+use crate::merkle::build_tree;
+use crate::source::open_global_source;
+use crate::status::{
+    State, StatusRow, StatusSink, StatusTableWriter, SyncState, list_skill_digests,
+    list_skills_with, render_status_table, render_timings, since_sync, status_for_target,
+    status_for_target_streaming,
+};
+use crate::store::{DigestStore, SYNC_STATE_FILE};
 use crate::sync::{
-    PlanKind, Selection, execute_plan, fail_next_publish_rename_for_test,
-    fail_next_restore_rename_for_test, plan_import, plan_push,
+    FORCE_NOTE, ImportMode, PlanKind, PushOptions, Selection, execute_plan, execute_plan_with,
+    fail_next_publish_rename_for_test, fail_next_restore_rename_for_test, plan_all, plan_import,
+    plan_push, plan_push_with, record_synced, summarize_plan,
 };
 
 fn make_config(global_root: PathBuf, target_root: PathBuf) -> Config {
@@ -21,14 +35,36 @@ fn make_config(global_root: PathBuf, target_root: PathBuf) -> Config {
         targets: vec![Target {
             name: "t1".to_string(),
             root: target_root,
+            pre_push: Vec::new(),
+            post_push: Vec::new(),
         }],
         hash: HashConfig {
             algo: HashAlgo::Blake3,
             ignore: Vec::new(),
+            ignore_file: None,
+            normalize_eol: false,
+            include_mode: false,
+            include_empty_dirs: false,
+            merkle: false,
+            merkle_cache: None,
+            skip_larger_than: None,
         },
         diff: DiffConfig {
             command: vec!["diff".to_string()],
+            cwd: None,
+            use_env: false,
+            timeout_secs: None,
         },
+        sync: SyncConfig::default(),
+        skills: SkillsConfig::default(),
+        doctor: DoctorConfig::default(),
+        audit: AuditConfig::default(),
+        status: StatusConfig::default(),
+        display: DisplayConfig::default(),
+        profiles: BTreeMap::new(),
+        strict_targets: false,
+        parallelism: None,
+        global_git_ref: None,
     }
 }
 
@@ -41,14 +77,29 @@ fn write_file(path: &Path, contents: &str) {
 
 fn snapshot_root(root: &Path, algo: HashAlgo) -> Vec<(String, String)> {
     let mut out = Vec::new();
-    let skills = list_skills(root).unwrap();
+    let skills = list_skills_with(root, &SkillsConfig::default()).unwrap();
     for skill in skills {
-        let digest = digest_dir(&root.join(&skill), algo, None).unwrap();
+        let digest = digest_dir(&root.join(&skill), &DigestOptions::new(algo)).unwrap();
         out.push((skill, digest));
     }
     out
 }
 
+fn write_tar(path: &Path, files: &[(&str, &str)]) {
+    let file = fs::File::create(path).unwrap();
+    let mut builder = tar::Builder::new(file);
+    for (name, contents) in files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, name, contents.as_bytes())
+            .unwrap();
+    }
+    builder.finish().unwrap();
+}
+
 fn sync_test_lock() -> std::sync::MutexGuard<'static, ()> {
     static LOCK: OnceLock<Mutex<()>> = OnceLock::new();
     LOCK.get_or_init(|| Mutex::new(())).lock().unwrap()
@@ -95,6 +146,298 @@ fn status_end_to_end() {
     assert_eq!(lookup.remove("skill_extra"), Some(State::Extra));
 }
 
+#[test]
+fn status_honors_skill_pinned_hash_algo() {
+    let global_dir = TempDir::new().unwrap();
+    let target_dir = TempDir::new().unwrap();
+    let global_root = global_dir.path();
+    let target_root = target_dir.path();
+
+    // Only the global copy carries the pin; the target copy follows it.
+    write_file(&global_root.join("skill_same/.skillhash"), "sha256\n");
+    write_file(&global_root.join("skill_same/file.txt"), "same");
+    write_file(&target_root.join("skill_same/file.txt"), "same");
+    write_file(&global_root.join("skill_diff/.skillhash"), "sha256\n");
+    write_file(&global_root.join("skill_diff/file.txt"), "g");
+    write_file(&target_root.join("skill_diff/file.txt"), "t");
+
+    let config = make_config(global_root.to_path_buf(), target_root.to_path_buf());
+    assert_eq!(config.hash.algo, HashAlgo::Blake3);
+    let target = &config.targets[0];
+    let rows = status_for_target(&config, target).unwrap();
+
+    let same = rows.iter().find(|row| row.skill == "skill_same").unwrap();
+    assert_eq!(same.state, State::Same);
+    let sha256 = digest_dir(
+        &target_root.join("skill_same"),
+        &DigestOptions::new(HashAlgo::Sha256),
+    )
+    .unwrap();
+    assert_eq!(same.global_digest.as_deref(), Some(sha256.as_str()));
+    assert_eq!(same.target_digest.as_deref(), Some(sha256.as_str()));
+    let diff = rows.iter().find(|row| row.skill == "skill_diff").unwrap();
+    assert_eq!(diff.state, State::Diff);
+}
+
+#[test]
+fn status_with_merkle_cache_tracks_changes_between_runs() {
+    let global_dir = TempDir::new().unwrap();
+    let target_dir = TempDir::new().unwrap();
+    let cache_dir = TempDir::new().unwrap();
+    let global_root = global_dir.path();
+    let target_root = target_dir.path();
+
+    write_file(&global_root.join("skill1/SKILL.md"), "doc");
+    write_file(&global_root.join("skill1/refs/a.md"), "a");
+    write_file(&target_root.join("skill1/SKILL.md"), "doc");
+    write_file(&target_root.join("skill1/refs/a.md"), "a");
+
+    let mut config = make_config(global_root.to_path_buf(), target_root.to_path_buf());
+    config.hash.merkle = true;
+    let cache_path = cache_dir.path().join("merkle.json");
+    config.hash.merkle_cache = Some(cache_path.clone());
+    let target = config.targets[0].clone();
+
+    let rows = status_for_target(&config, &target).unwrap();
+    assert_eq!(rows[0].state, State::Same);
+    assert!(cache_path.is_file());
+
+    write_file(&target_root.join("skill1/refs/a.md"), "changed");
+    let rows = status_for_target(&config, &target).unwrap();
+    assert_eq!(rows[0].state, State::Diff);
+    let tree = build_tree(
+        &target_root.join("skill1"),
+        &DigestOptions::from_config(&config.hash, None),
+        None,
+    )
+    .unwrap()
+    .tree;
+    assert_eq!(rows[0].target_digest.as_deref(), Some(tree.root_hash()));
+}
+
+#[test]
+fn status_timings_cover_every_skill() {
+    let global_dir = TempDir::new().unwrap();
+    let target_dir = TempDir::new().unwrap();
+    let global_root = global_dir.path();
+    let target_root = target_dir.path();
+
+    write_file(&global_root.join("skill_same/file.txt"), "same");
+    write_file(&target_root.join("skill_same/file.txt"), "same");
+    write_file(&global_root.join("skill_missing/file.txt"), "m");
+    write_file(&target_root.join("skill_extra/file.txt"), "e");
+
+    let config = make_config(global_root.to_path_buf(), target_root.to_path_buf());
+    let target = &config.targets[0];
+    let mut timings = Vec::new();
+    let mut rows = Vec::new();
+    status_for_target_streaming(&config, target, Some(&mut timings), &mut rows).unwrap();
+
+    let mut timed = timings
+        .iter()
+        .map(|timing| (timing.target.as_str(), timing.skill.as_str()))
+        .collect::<Vec<_>>();
+    timed.sort();
+    assert_eq!(
+        timed,
+        vec![
+            ("t1", "skill_extra"),
+            ("t1", "skill_missing"),
+            ("t1", "skill_same")
+        ]
+    );
+    assert_eq!(rows.len(), timings.len());
+    assert_eq!(render_timings(&timings).lines().count(), 3);
+}
+
+#[test]
+fn status_streaming_emits_rows_without_collecting() {
+    struct CountingSink<W: std::io::Write> {
+        table: StatusTableWriter<W>,
+        skills: usize,
+        rows: usize,
+        last: Option<String>,
+    }
+
+    impl<W: std::io::Write> StatusSink for CountingSink<W> {
+        fn begin(&mut self, skills: &[&str]) -> crate::AppResult<()> {
+            self.skills = skills.len();
+            self.table.begin(skills)
+        }
+
+        fn row(&mut self, row: StatusRow) -> crate::AppResult<()> {
+            assert!(self.last.as_deref() < Some(row.skill.as_str()));
+            self.last = Some(row.skill.clone());
+            self.rows += 1;
+            self.table.row(row)
+        }
+    }
+
+    let global_dir = TempDir::new().unwrap();
+    let target_dir = TempDir::new().unwrap();
+    let global_root = global_dir.path();
+    let target_root = target_dir.path();
+    for i in 0..2000 {
+        write_file(&global_root.join(format!("skill_{i:05}/SKILL.md")), "doc");
+        if i % 3 == 0 {
+            write_file(&target_root.join(format!("skill_{i:05}/SKILL.md")), "doc");
+        }
+    }
+
+    let config = make_config(global_root.to_path_buf(), target_root.to_path_buf());
+    let target = &config.targets[0];
+    let mut sink = CountingSink {
+        table: StatusTableWriter::new(Vec::new()),
+        skills: 0,
+        rows: 0,
+        last: None,
+    };
+    status_for_target_streaming(&config, target, None, &mut sink).unwrap();
+    assert_eq!(sink.skills, 2000);
+    assert_eq!(sink.rows, 2000);
+
+    let streamed = String::from_utf8(sink.table.into_inner()).unwrap();
+    let collected = render_status_table(&status_for_target(&config, target).unwrap()).unwrap();
+    assert_eq!(streamed, collected);
+    assert_eq!(streamed.lines().count(), 2001);
+}
+
+fn git(repo: &Path, args: &[&str]) {
+    let status = std::process::Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+        .args(args)
+        .status()
+        .unwrap();
+    assert!(status.success(), "git {args:?} failed");
+}
+
+#[test]
+fn git_global_root_reads_skills_at_ref() {
+    let repo_dir = TempDir::new().unwrap();
+    let target_dir = TempDir::new().unwrap();
+    let repo = repo_dir.path();
+    let target_root = target_dir.path();
+
+    git(repo, &["init", "-q"]);
+    write_file(&repo.join("skill1/SKILL.md"), "v1");
+    write_file(&repo.join("skill1/scripts/run.sh"), "run");
+    write_file(&repo.join("README.md"), "not a skill");
+    git(repo, &["add", "-A"]);
+    git(repo, &["commit", "-q", "-m", "first"]);
+    git(repo, &["tag", "v1"]);
+    write_file(&repo.join("skill1/SKILL.md"), "v2");
+    write_file(&repo.join("skill2/SKILL.md"), "new");
+    git(repo, &["add", "-A"]);
+    git(repo, &["commit", "-q", "-m", "second"]);
+    // Uncommitted edits in the work tree are not seen through a ref.
+    write_file(&repo.join("skill1/SKILL.md"), "dirty");
+
+    write_file(&target_root.join("skill1/SKILL.md"), "v1");
+    write_file(&target_root.join("skill1/scripts/run.sh"), "run");
+
+    let mut config = make_config(repo.to_path_buf(), target_root.to_path_buf());
+    config.global_git_ref = Some("v1".to_string());
+    let global = open_global_source(&config).unwrap();
+    assert_eq!(global.list_skills().unwrap(), vec!["skill1"]);
+    let target = &config.targets[0];
+    let rows = status_for_target(&config, target).unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].state, State::Same);
+
+    config.global_git_ref = Some("HEAD".to_string());
+    let global = open_global_source(&config).unwrap();
+    assert_eq!(global.list_skills().unwrap(), vec!["skill1", "skill2"]);
+    let states = status_for_target(&config, target)
+        .unwrap()
+        .into_iter()
+        .map(|row| (row.skill, row.state))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        states,
+        vec![
+            ("skill1".to_string(), State::Diff),
+            ("skill2".to_string(), State::Missing)
+        ]
+    );
+    let extracted = global.skill_dir("skill1").unwrap();
+    assert_eq!(
+        fs::read_to_string(extracted.path().join("SKILL.md")).unwrap(),
+        "v2"
+    );
+
+    config.global_git_ref = Some("no-such-ref".to_string());
+    let err = open_global_source(&config).err().unwrap();
+    assert!(matches!(err, AppError::Config { .. }));
+}
+
+#[test]
+fn git_global_root_in_subdirectory_lists_that_subtree() {
+    let repo_dir = TempDir::new().unwrap();
+    let target_dir = TempDir::new().unwrap();
+    let repo = repo_dir.path();
+
+    git(repo, &["init", "-q"]);
+    write_file(&repo.join("skills/skill1/SKILL.md"), "v1");
+    write_file(&repo.join("skills/skill1/.skillhash"), "sha256\n");
+    write_file(&repo.join("docs/guide/index.md"), "not a skill");
+    git(repo, &["add", "-A"]);
+    git(repo, &["commit", "-q", "-m", "first"]);
+
+    let mut config = make_config(repo.join("skills"), target_dir.path().to_path_buf());
+    config.global_git_ref = Some("HEAD".to_string());
+    let global = open_global_source(&config).unwrap();
+    assert_eq!(global.list_skills().unwrap(), vec!["skill1"]);
+    assert_eq!(
+        global.list_skills().unwrap(),
+        list_skills_with(&config.global_root, &config.skills).unwrap()
+    );
+    assert_eq!(
+        global.pinned_algo("skill1").unwrap(),
+        Some(HashAlgo::Sha256)
+    );
+    let options = DigestOptions::new(HashAlgo::Blake3);
+    assert_eq!(
+        global.digest_skill("skill1", &options).unwrap(),
+        digest_dir(&repo.join("skills/skill1"), &options).unwrap()
+    );
+}
+
+#[test]
+fn list_skill_digests_match_digest_dir() {
+    let global_dir = TempDir::new().unwrap();
+    let target_dir = TempDir::new().unwrap();
+    let global_root = global_dir.path();
+
+    write_file(&global_root.join("skill_a/file.txt"), "a");
+    write_file(&global_root.join("skill_a/skip.tmp"), "noise");
+    write_file(&global_root.join("skill_b/nested/file.txt"), "b");
+
+    let mut config = make_config(global_root.to_path_buf(), target_dir.path().to_path_buf());
+    config.hash.algo = HashAlgo::Sha256;
+    config.hash.ignore = vec!["**/*.tmp".to_string()];
+    let ignore = crate::digest::build_ignore_set(&config.hash.ignore).unwrap();
+
+    let global = open_global_source(&config).unwrap();
+    let listed = list_skill_digests(&config, &*global).unwrap();
+    let expected = ["skill_a", "skill_b"]
+        .into_iter()
+        .map(|skill| {
+            let digest = digest_dir(
+                &global_root.join(skill),
+                &DigestOptions {
+                    ignore: ignore.as_ref(),
+                    ..DigestOptions::new(HashAlgo::Sha256)
+                },
+            )
+            .unwrap();
+            (skill.to_string(), digest)
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(listed, expected);
+}
+
 #[test]
 fn push_dry_run_is_immutable() {
     let global_dir = TempDir::new().unwrap();
@@ -110,11 +453,110 @@ fn push_dry_run_is_immutable() {
     let target = &config.targets[0];
     let before = snapshot_root(target_root, config.hash.algo);
     let plan = plan_push(&config, target, Selection::All, false).unwrap();
-    execute_plan(&plan, true).unwrap();
+    execute_plan(&plan, true, &config.sync).unwrap();
     let after = snapshot_root(target_root, config.hash.algo);
     assert_eq!(before, after);
 }
 
+#[test]
+fn since_sync_reports_global_changes_after_push() {
+    let global_dir = TempDir::new().unwrap();
+    let target_dir = TempDir::new().unwrap();
+    let global_root = global_dir.path();
+    let target_root = target_dir.path();
+
+    write_file(&global_root.join("skill_kept/file.txt"), "k");
+    write_file(&global_root.join("skill_edited/file.txt"), "before");
+
+    let config = make_config(global_root.to_path_buf(), target_root.to_path_buf());
+    let target = &config.targets[0];
+    let states = || {
+        since_sync(&config, target, Selection::All)
+            .unwrap()
+            .into_iter()
+            .map(|row| (row.skill, row.state))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        states(),
+        [
+            ("skill_edited".to_string(), SyncState::NeverSynced),
+            ("skill_kept".to_string(), SyncState::NeverSynced),
+        ]
+    );
+
+    let plan = plan_push(&config, target, Selection::All, false).unwrap();
+    execute_plan(&plan, false, &config.sync).unwrap();
+    record_synced(&config, target, &plan).unwrap();
+    write_file(&global_root.join("skill_edited/file.txt"), "after");
+    write_file(&global_root.join("skill_new/file.txt"), "n");
+
+    assert_eq!(
+        states(),
+        [
+            ("skill_edited".to_string(), SyncState::Changed),
+            ("skill_kept".to_string(), SyncState::Unchanged),
+            ("skill_new".to_string(), SyncState::NeverSynced),
+        ]
+    );
+    // The target still holds the old copy; only global is compared.
+    assert_eq!(
+        fs::read_to_string(target_root.join("skill_edited/file.txt")).unwrap(),
+        "before"
+    );
+}
+
+#[test]
+fn since_sync_asks_for_resync_after_algo_switch() {
+    let global_dir = TempDir::new().unwrap();
+    let target_dir = TempDir::new().unwrap();
+    let global_root = global_dir.path();
+    let target_root = target_dir.path();
+
+    write_file(&global_root.join("skill_plain/file.txt"), "p");
+    write_file(&global_root.join("skill_pinned/file.txt"), "s");
+    write_file(&global_root.join("skill_pinned/.skillhash"), "sha256\n");
+
+    let mut config = make_config(global_root.to_path_buf(), target_root.to_path_buf());
+    let plan = plan_push(&config, &config.targets[0], Selection::All, false).unwrap();
+    execute_plan(&plan, false, &config.sync).unwrap();
+    record_synced(&config, &config.targets[0], &plan).unwrap();
+
+    config.hash.algo = HashAlgo::Sha256;
+    let rows = since_sync(&config, &config.targets[0], Selection::All).unwrap();
+    let states = rows
+        .iter()
+        .map(|row| (row.skill.as_str(), row.state))
+        .collect::<Vec<_>>();
+    // The pinned skill was recorded under its pin, so the switch does not affect it.
+    assert_eq!(
+        states,
+        [
+            ("skill_pinned", SyncState::Unchanged),
+            ("skill_plain", SyncState::ResyncNeeded),
+        ]
+    );
+    assert_eq!(rows[1].synced_digest, None);
+
+    // The next push records the skill again under the new algorithm.
+    let plan = plan_push(&config, &config.targets[0], Selection::All, false).unwrap();
+    execute_plan(&plan, false, &config.sync).unwrap();
+    record_synced(&config, &config.targets[0], &plan).unwrap();
+    let store = DigestStore::load(&target_root.join(SYNC_STATE_FILE)).unwrap();
+    let sha256 = digest_dir(
+        &global_root.join("skill_plain"),
+        &DigestOptions::new(HashAlgo::Sha256),
+    )
+    .unwrap();
+    assert_eq!(
+        store.get("skill_plain", HashAlgo::Sha256),
+        Some(sha256.as_str())
+    );
+    assert_eq!(store.get("skill_plain", HashAlgo::Blake3), None);
+    let rows = since_sync(&config, &config.targets[0], Selection::All).unwrap();
+    assert!(rows.iter().all(|row| row.state == SyncState::Unchanged));
+}
+
 #[test]
 fn replace_dir_keeps_existing_dest_when_final_swap_fails() {
     let _lock = sync_test_lock();
@@ -131,9 +573,9 @@ fn replace_dir_keeps_existing_dest_when_final_swap_fails() {
     let plan = plan_push(&config, target, Selection::All, false).unwrap();
 
     fail_next_publish_rename_for_test();
-    let err = execute_plan(&plan, false).unwrap_err();
+    let err = execute_plan(&plan, false, &config.sync).unwrap_err();
 
-    assert!(matches!(err, AppError::Exec { .. }));
+    assert!(matches!(err, AppError::Io { .. }));
     let restored = fs::read_to_string(target_root.join("skill_diff/file.txt")).unwrap();
     assert_eq!(restored, "target");
 }
@@ -155,7 +597,7 @@ fn replace_dir_reports_manual_recovery_when_restore_fails() {
 
     fail_next_publish_rename_for_test();
     fail_next_restore_rename_for_test();
-    let err = execute_plan(&plan, false).unwrap_err();
+    let err = execute_plan(&plan, false, &config.sync).unwrap_err();
 
     let hint = err.hint().unwrap_or_default();
     assert!(hint.contains("Manually rename") || hint.contains("手動で"));
@@ -179,10 +621,174 @@ fn diff_rejects_symlink_skill_root() {
     let config = make_config(global_root.to_path_buf(), target_root.to_path_buf());
     let target = &config.targets[0];
 
-    let err = run_diff(&config, target, "skill_link").unwrap_err();
+    let err = run_diff(&config, target, "skill_link", DiffOptions::default()).unwrap_err();
     assert!(matches!(err, AppError::Exec { .. }));
 }
 
+#[cfg(unix)]
+#[test]
+fn push_refuses_target_root_symlinked_into_global() {
+    use std::os::unix::fs::symlink;
+
+    let global_dir = TempDir::new().unwrap();
+    let target_dir = TempDir::new().unwrap();
+    let global_root = global_dir.path();
+    let target_root = target_dir.path().join("skills");
+
+    write_file(&global_root.join("skill_a/SKILL.md"), "a");
+    write_file(&global_root.join("skill_a/sub/data.txt"), "keep");
+    symlink(global_root.join("skill_a"), &target_root).unwrap();
+
+    let config = make_config(global_root.to_path_buf(), target_root);
+    let target = &config.targets[0];
+
+    let plan = plan_push(&config, target, Selection::All, true).unwrap();
+    assert!(plan.ops.iter().any(|op| op.kind == PlanKind::Prune));
+    let err = execute_plan(&plan, false, &config.sync).unwrap_err();
+    assert!(matches!(err, AppError::Exec { .. }));
+    assert!(err.to_string().contains("skill_a"));
+
+    assert_eq!(
+        fs::read_to_string(global_root.join("skill_a/sub/data.txt")).unwrap(),
+        "keep"
+    );
+    assert!(!global_root.join("skill_a/skill_a").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn diff_runs_in_configured_cwd() {
+    let global_dir = TempDir::new().unwrap();
+    let target_dir = TempDir::new().unwrap();
+    let cwd_dir = TempDir::new().unwrap();
+    let global_root = global_dir.path();
+    let target_root = target_dir.path();
+
+    write_file(&global_root.join("skill1/file.txt"), "g");
+    write_file(&target_root.join("skill1/file.txt"), "t");
+
+    let mut config = make_config(global_root.to_path_buf(), target_root.to_path_buf());
+    config.diff.command = [
+        "sh",
+        "-c",
+        "echo ran > diff-out.txt",
+        "--",
+        "{left}",
+        "{right}",
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect();
+    config.diff.cwd = Some(cwd_dir.path().to_path_buf());
+    let target = &config.targets[0];
+
+    run_diff(&config, target, "skill1", DiffOptions::default()).unwrap();
+    assert_eq!(
+        fs::read_to_string(cwd_dir.path().join("diff-out.txt")).unwrap(),
+        "ran\n"
+    );
+}
+
+#[test]
+fn diff_exports_dirs_as_env_vars() {
+    let global_dir = TempDir::new().unwrap();
+    let target_dir = TempDir::new().unwrap();
+    let out_dir = TempDir::new().unwrap();
+    let global_root = global_dir.path();
+    let target_root = target_dir.path();
+
+    write_file(&global_root.join("skill1/file.txt"), "g");
+    write_file(&target_root.join("skill1/file.txt"), "t");
+
+    let mut config = make_config(global_root.to_path_buf(), target_root.to_path_buf());
+    config.diff.command = [
+        "sh",
+        "-c",
+        "printf '%s\\n%s\\n' \"$SKILLCTL_LEFT\" \"$SKILLCTL_RIGHT\" > env-out.txt",
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect();
+    config.diff.cwd = Some(out_dir.path().to_path_buf());
+    config.diff.use_env = true;
+    let target = &config.targets[0];
+
+    run_diff(&config, target, "skill1", DiffOptions::default()).unwrap();
+    assert_eq!(
+        fs::read_to_string(out_dir.path().join("env-out.txt")).unwrap(),
+        format!(
+            "{}\n{}\n",
+            global_root.join("skill1").display(),
+            target_root.join("skill1").display()
+        )
+    );
+}
+
+#[test]
+fn diff_summary_only_skips_command_for_same_skill() {
+    let global_dir = TempDir::new().unwrap();
+    let target_dir = TempDir::new().unwrap();
+    let cwd_dir = TempDir::new().unwrap();
+    let global_root = global_dir.path();
+    let target_root = target_dir.path();
+
+    write_file(&global_root.join("same/file.txt"), "x");
+    write_file(&target_root.join("same/file.txt"), "x");
+    write_file(&global_root.join("changed/file.txt"), "g");
+    write_file(&target_root.join("changed/file.txt"), "t");
+
+    let mut config = make_config(global_root.to_path_buf(), target_root.to_path_buf());
+    config.diff.command = ["sh", "-c", "echo ran >> diff-out.txt; exit 1"]
+        .into_iter()
+        .map(String::from)
+        .collect();
+    config.diff.cwd = Some(cwd_dir.path().to_path_buf());
+    let target = &config.targets[0];
+    let out = cwd_dir.path().join("diff-out.txt");
+    let summary_only = DiffOptions {
+        summary_only: true,
+        ..DiffOptions::default()
+    };
+
+    run_diff(&config, target, "same", summary_only).unwrap();
+    assert!(!out.exists());
+    run_diff(&config, target, "same", DiffOptions::default()).unwrap();
+    assert_eq!(fs::read_to_string(&out).unwrap(), "ran\n");
+    fs::remove_file(&out).unwrap();
+
+    run_diff(&config, target, "changed", summary_only).unwrap();
+    assert_eq!(fs::read_to_string(&out).unwrap(), "ran\n");
+
+    let forced = DiffOptions {
+        force: true,
+        ..summary_only
+    };
+    run_diff(&config, target, "same", forced).unwrap();
+    assert_eq!(fs::read_to_string(&out).unwrap(), "ran\nran\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn diff_kills_command_after_timeout() {
+    let global_dir = TempDir::new().unwrap();
+    let target_dir = TempDir::new().unwrap();
+    let global_root = global_dir.path();
+    let target_root = target_dir.path();
+
+    write_file(&global_root.join("skill1/file.txt"), "g");
+    write_file(&target_root.join("skill1/file.txt"), "t");
+
+    let mut config = make_config(global_root.to_path_buf(), target_root.to_path_buf());
+    config.diff.command = ["sleep", "10"].into_iter().map(String::from).collect();
+    config.diff.timeout_secs = Some(1);
+    let target = &config.targets[0];
+
+    let started = std::time::Instant::now();
+    let err = run_diff(&config, target, "skill1", DiffOptions::default()).unwrap_err();
+    assert!(matches!(err, AppError::Exec { .. }));
+    assert!(started.elapsed() < std::time::Duration::from_secs(5));
+}
+
 #[test]
 fn push_rejects_invalid_skill_name() {
     let global_dir = TempDir::new().unwrap();
@@ -263,7 +869,7 @@ fn push_execute_converges() {
     let config = make_config(global_root.to_path_buf(), target_root.to_path_buf());
     let target = &config.targets[0];
     let plan = plan_push(&config, target, Selection::All, false).unwrap();
-    execute_plan(&plan, false).unwrap();
+    execute_plan(&plan, false, &config.sync).unwrap();
 
     let rows = status_for_target(&config, target).unwrap();
     let mut lookup = rows
@@ -275,6 +881,558 @@ fn push_execute_converges() {
     assert_eq!(lookup.remove("skill_diff"), Some(State::Same));
 }
 
+#[test]
+fn push_case_insensitive_updates_target_with_other_case() {
+    let global_dir = TempDir::new().unwrap();
+    let target_dir = TempDir::new().unwrap();
+    let global_root = global_dir.path();
+    let target_root = target_dir.path();
+
+    write_file(&global_root.join("MySkill/file.txt"), "new");
+    write_file(&target_root.join("myskill/file.txt"), "old");
+
+    let mut config = make_config(global_root.to_path_buf(), target_root.to_path_buf());
+    config.skills.case_insensitive = true;
+    let target = &config.targets[0];
+
+    let rows = status_for_target(&config, target).unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].skill, "MySkill");
+    assert_eq!(rows[0].state, State::Diff);
+
+    let plan = plan_push(&config, target, Selection::One("myskill"), false).unwrap();
+    assert_eq!(plan.ops.len(), 1);
+    assert_eq!(plan.ops[0].kind, PlanKind::Update);
+    assert_eq!(plan.ops[0].skill, "MySkill");
+    assert_eq!(
+        plan.ops[0].dest.as_deref(),
+        Some(target_root.join("myskill").as_path())
+    );
+    execute_plan(&plan, false, &config.sync).unwrap();
+
+    assert_eq!(
+        fs::read_to_string(target_root.join("myskill/file.txt")).unwrap(),
+        "new"
+    );
+    let rows = status_for_target(&config, target).unwrap();
+    assert_eq!(rows[0].state, State::Same);
+}
+
+#[test]
+fn status_detect_renames_pairs_case_only_differences() {
+    let global_dir = TempDir::new().unwrap();
+    let target_dir = TempDir::new().unwrap();
+    let global_root = global_dir.path();
+    let target_root = target_dir.path();
+
+    write_file(&global_root.join("Foo/file.txt"), "same");
+    write_file(&target_root.join("foo/file.txt"), "same");
+    // Ambiguous: two target names for one global name stay missing/extra.
+    write_file(&global_root.join("Bar/file.txt"), "b");
+    write_file(&target_root.join("bar/file.txt"), "b");
+    write_file(&target_root.join("BAR/file.txt"), "b");
+
+    let mut config = make_config(global_root.to_path_buf(), target_root.to_path_buf());
+    let target = config.targets[0].clone();
+
+    let states = |config: &Config| {
+        status_for_target(config, &target)
+            .unwrap()
+            .into_iter()
+            .map(|row| (row.label(), row.state))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        states(&config),
+        vec![
+            ("BAR".to_string(), State::Extra),
+            ("Bar".to_string(), State::Missing),
+            ("Foo".to_string(), State::Missing),
+            ("bar".to_string(), State::Extra),
+            ("foo".to_string(), State::Extra),
+        ]
+    );
+
+    config.status.detect_renames = true;
+    assert_eq!(
+        states(&config),
+        vec![
+            ("BAR".to_string(), State::Extra),
+            ("Bar".to_string(), State::Missing),
+            ("Foo -> foo".to_string(), State::Renamed),
+            ("bar".to_string(), State::Extra),
+        ]
+    );
+    let rows = status_for_target(&config, &target).unwrap();
+    let renamed = rows.iter().find(|row| row.skill == "Foo").unwrap();
+    assert_eq!(renamed.renamed_to.as_deref(), Some("foo"));
+    assert_eq!(renamed.global_digest, renamed.target_digest);
+}
+
+#[test]
+fn status_detail_lists_file_changes_for_diff_skills() {
+    let global_dir = TempDir::new().unwrap();
+    let target_dir = TempDir::new().unwrap();
+    let global_root = global_dir.path();
+    let target_root = target_dir.path();
+
+    write_file(&global_root.join("skill/SKILL.md"), "new");
+    write_file(&target_root.join("skill/SKILL.md"), "old");
+    write_file(&global_root.join("skill/docs/guide.md"), "guide");
+    write_file(&target_root.join("skill/notes.txt"), "notes");
+    write_file(&global_root.join("skill/same.txt"), "same");
+    write_file(&target_root.join("skill/same.txt"), "same");
+    write_file(&global_root.join("other/file.txt"), "same");
+    write_file(&target_root.join("other/file.txt"), "same");
+
+    let mut config = make_config(global_root.to_path_buf(), target_root.to_path_buf());
+    let target = config.targets[0].clone();
+
+    let rows = status_for_target(&config, &target).unwrap();
+    assert!(rows.iter().all(|row| row.file_changes.is_empty()));
+
+    config.status.detail = true;
+    let rows = status_for_target(&config, &target).unwrap();
+    let changes = |skill: &str| {
+        rows.iter()
+            .find(|row| row.skill == skill)
+            .unwrap()
+            .file_changes
+            .clone()
+    };
+    let change = |path: &str, state| FileChange {
+        path: path.to_string(),
+        state,
+    };
+    assert_eq!(
+        changes("skill"),
+        vec![
+            change("SKILL.md", FileState::Modified),
+            change("docs/guide.md", FileState::Removed),
+            change("notes.txt", FileState::Added),
+        ]
+    );
+    assert!(changes("other").is_empty());
+}
+
+#[test]
+fn status_fast_misses_same_size_edit_that_full_digest_catches() {
+    let global_dir = TempDir::new().unwrap();
+    let target_dir = TempDir::new().unwrap();
+    let global_root = global_dir.path();
+    let target_root = target_dir.path();
+
+    write_file(&global_root.join("edited/file.txt"), "new");
+    write_file(&target_root.join("edited/file.txt"), "old");
+    write_file(&global_root.join("grown/file.txt"), "longer");
+    write_file(&target_root.join("grown/file.txt"), "short");
+    let mtime = std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_000_000);
+    for root in [global_root, target_root] {
+        for skill in ["edited", "grown"] {
+            fs::File::options()
+                .write(true)
+                .open(root.join(skill).join("file.txt"))
+                .unwrap()
+                .set_modified(mtime)
+                .unwrap();
+        }
+    }
+
+    let mut config = make_config(global_root.to_path_buf(), target_root.to_path_buf());
+    let target = config.targets[0].clone();
+    let states = |config: &Config| {
+        status_for_target(config, &target)
+            .unwrap()
+            .into_iter()
+            .map(|row| (row.skill, row.state))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        states(&config),
+        vec![
+            ("edited".to_string(), State::Diff),
+            ("grown".to_string(), State::Diff),
+        ]
+    );
+    config.status.fast = true;
+    assert_eq!(
+        states(&config),
+        vec![
+            ("edited".to_string(), State::LikelySame),
+            ("grown".to_string(), State::LikelyDiff),
+        ]
+    );
+}
+
+#[test]
+fn case_insensitive_errors_on_collision_within_root() {
+    let global_dir = TempDir::new().unwrap();
+    let target_dir = TempDir::new().unwrap();
+    let global_root = global_dir.path();
+
+    write_file(&global_root.join("MySkill/file.txt"), "a");
+    if global_root.join("myskill").exists() {
+        return;
+    }
+    write_file(&global_root.join("myskill/file.txt"), "b");
+
+    let mut config = make_config(global_root.to_path_buf(), target_dir.path().to_path_buf());
+    config.skills.case_insensitive = true;
+    let target = &config.targets[0];
+
+    let err = plan_push(&config, target, Selection::All, false).unwrap_err();
+    assert!(matches!(err, AppError::Exec { .. }));
+    let err = status_for_target(&config, target).unwrap_err();
+    assert!(matches!(err, AppError::Exec { .. }));
+}
+
+#[test]
+fn flat_layout_treats_top_level_files_as_skills() {
+    let global_dir = TempDir::new().unwrap();
+    let target_dir = TempDir::new().unwrap();
+    let global_root = global_dir.path();
+    let target_root = target_dir.path();
+
+    write_file(&global_root.join("same.md"), "same");
+    write_file(&target_root.join("same.md"), "same");
+    write_file(&global_root.join("changed.md"), "new");
+    write_file(&target_root.join("changed.md"), "old");
+    write_file(&global_root.join("missing.md"), "m");
+    write_file(&target_root.join("extra.md"), "e");
+    write_file(&global_root.join(".hidden.md"), "h");
+    write_file(&global_root.join("nested/SKILL.md"), "ignored");
+
+    let mut config = make_config(global_root.to_path_buf(), target_root.to_path_buf());
+    config.skills.layout = SkillLayout::Flat;
+    let target = &config.targets[0];
+
+    let lookup = status_for_target(&config, target)
+        .unwrap()
+        .into_iter()
+        .map(|row| (row.skill, row.state))
+        .collect::<std::collections::BTreeMap<_, _>>();
+    assert_eq!(
+        lookup.into_iter().collect::<Vec<_>>(),
+        vec![
+            ("changed.md".to_string(), State::Diff),
+            ("extra.md".to_string(), State::Extra),
+            ("missing.md".to_string(), State::Missing),
+            ("same.md".to_string(), State::Same),
+        ]
+    );
+
+    let plan = plan_push(&config, target, Selection::All, true).unwrap();
+    execute_plan(&plan, false, &config.sync).unwrap();
+
+    assert_eq!(
+        fs::read_to_string(target_root.join("changed.md")).unwrap(),
+        "new"
+    );
+    assert_eq!(
+        fs::read_to_string(target_root.join("missing.md")).unwrap(),
+        "m"
+    );
+    assert!(!target_root.join("extra.md").exists());
+    assert!(!target_root.join("nested").exists());
+    let rows = status_for_target(&config, target).unwrap();
+    assert!(rows.iter().all(|row| row.state == State::Same));
+}
+
+#[test]
+fn push_incremental_update_rewrites_only_changed_files() {
+    let global_dir = TempDir::new().unwrap();
+    let target_dir = TempDir::new().unwrap();
+    let global_root = global_dir.path();
+    let target_root = target_dir.path();
+
+    write_file(&global_root.join("skill1/same.txt"), "same");
+    write_file(&global_root.join("skill1/changed.txt"), "new");
+    write_file(&global_root.join("skill1/nested/added.txt"), "added");
+    write_file(&target_root.join("skill1/same.txt"), "same");
+    write_file(&target_root.join("skill1/changed.txt"), "old");
+    write_file(&target_root.join("skill1/removed/file.txt"), "gone");
+
+    let old_mtime = std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_000_000);
+    fs::File::options()
+        .write(true)
+        .open(target_root.join("skill1/same.txt"))
+        .unwrap()
+        .set_modified(old_mtime)
+        .unwrap();
+
+    let mut config = make_config(global_root.to_path_buf(), target_root.to_path_buf());
+    config.sync.update_mode = UpdateMode::Incremental;
+    let target = &config.targets[0];
+    let plan = plan_push(&config, target, Selection::All, false).unwrap();
+    assert_eq!(plan.ops[0].kind, PlanKind::Update);
+    execute_plan(&plan, false, &config.sync).unwrap();
+
+    let same_meta = fs::metadata(target_root.join("skill1/same.txt")).unwrap();
+    assert_eq!(same_meta.modified().unwrap(), old_mtime);
+    assert_eq!(
+        fs::read_to_string(target_root.join("skill1/changed.txt")).unwrap(),
+        "new"
+    );
+    assert!(!target_root.join("skill1/removed").exists());
+    assert_eq!(
+        snapshot_root(target_root, config.hash.algo),
+        snapshot_root(global_root, config.hash.algo)
+    );
+}
+
+#[test]
+fn push_recreates_empty_dirs_when_hashed() {
+    let global_dir = TempDir::new().unwrap();
+    let target_dir = TempDir::new().unwrap();
+    let global_root = global_dir.path();
+    let target_root = target_dir.path();
+
+    write_file(&global_root.join("skill1/file.txt"), "same");
+    fs::create_dir_all(global_root.join("skill1/templates")).unwrap();
+    write_file(&target_root.join("skill1/file.txt"), "same");
+
+    let mut config = make_config(global_root.to_path_buf(), target_root.to_path_buf());
+    let target = &config.targets[0];
+    let plan = plan_push(&config, target, Selection::All, false).unwrap();
+    assert_eq!(plan.ops[0].kind, PlanKind::Skip);
+
+    config.hash.include_empty_dirs = true;
+    let target = &config.targets[0];
+    let plan = plan_push(&config, target, Selection::All, false).unwrap();
+    assert_eq!(plan.ops[0].kind, PlanKind::Update);
+    execute_plan(&plan, false, &config.sync).unwrap();
+
+    assert!(target_root.join("skill1/templates").is_dir());
+    let rows = status_for_target(&config, target).unwrap();
+    assert_eq!(rows[0].state, State::Same);
+}
+
+#[test]
+fn push_force_recopies_matching_skill() {
+    let global_dir = TempDir::new().unwrap();
+    let target_dir = TempDir::new().unwrap();
+    let global_root = global_dir.path();
+    let target_root = target_dir.path();
+
+    write_file(&global_root.join("skill1/file.txt"), "same");
+    write_file(&target_root.join("skill1/file.txt"), "same");
+    let old_mtime = std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_000_000);
+    fs::File::options()
+        .write(true)
+        .open(target_root.join("skill1/file.txt"))
+        .unwrap()
+        .set_modified(old_mtime)
+        .unwrap();
+
+    let mut config = make_config(global_root.to_path_buf(), target_root.to_path_buf());
+    config.sync.update_mode = UpdateMode::Incremental;
+    let target = &config.targets[0];
+
+    let plan = plan_push(&config, target, Selection::All, false).unwrap();
+    assert_eq!(plan.ops[0].kind, PlanKind::Skip);
+
+    let force = PushOptions {
+        force: true,
+        ..PushOptions::default()
+    };
+    let plan = plan_push_with(&config, target, Selection::All, force).unwrap();
+    assert_eq!(plan.ops[0].kind, PlanKind::Update);
+    assert_eq!(plan.ops[0].note.as_deref(), Some(FORCE_NOTE));
+    execute_plan(&plan, true, &config.sync).unwrap();
+    let meta = fs::metadata(target_root.join("skill1/file.txt")).unwrap();
+    assert_eq!(meta.modified().unwrap(), old_mtime);
+
+    execute_plan(&plan, false, &config.sync).unwrap();
+    let meta = fs::metadata(target_root.join("skill1/file.txt")).unwrap();
+    assert_ne!(meta.modified().unwrap(), old_mtime);
+    assert_eq!(
+        fs::read_to_string(target_root.join("skill1/file.txt")).unwrap(),
+        "same"
+    );
+}
+
+#[test]
+fn push_resume_skips_ops_completed_before_failure() {
+    let global_dir = TempDir::new().unwrap();
+    let target_dir = TempDir::new().unwrap();
+    let global_root = global_dir.path();
+    let target_root = target_dir.path();
+
+    for skill in ["a", "b", "c"] {
+        write_file(&global_root.join(format!("{skill}/file.txt")), skill);
+        write_file(&target_root.join(format!("{skill}/file.txt")), "stale");
+    }
+
+    let config = make_config(global_root.to_path_buf(), target_root.to_path_buf());
+    let target = &config.targets[0];
+    let force = PushOptions {
+        force: true,
+        ..PushOptions::default()
+    };
+    fail_push_at_b(&config, force);
+
+    let mut plan = plan_push_with(&config, target, Selection::All, force).unwrap();
+    let mut checkpoint = Checkpoint::load(target_root).unwrap();
+    checkpoint.apply_to(&mut plan);
+    let kinds = plan
+        .ops
+        .iter()
+        .map(|op| (op.skill.as_str(), op.kind, op.note.as_deref()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        kinds,
+        vec![
+            ("a", PlanKind::Skip, Some(CHECKPOINT_NOTE)),
+            ("b", PlanKind::Update, None),
+            ("c", PlanKind::Update, None),
+        ]
+    );
+    execute_plan_with(
+        &plan,
+        false,
+        &config.sync,
+        None,
+        Some(&mut checkpoint),
+        None,
+    )
+    .unwrap();
+    checkpoint.clear().unwrap();
+    assert!(!target_root.join(CHECKPOINT_FILE).exists());
+
+    let rows = status_for_target(&config, target).unwrap();
+    assert!(rows.iter().all(|row| row.state == State::Same));
+    assert_eq!(rows.len(), 3);
+}
+
+#[test]
+fn push_resume_reruns_ops_whose_source_changed() {
+    let global_dir = TempDir::new().unwrap();
+    let target_dir = TempDir::new().unwrap();
+    let global_root = global_dir.path();
+    let target_root = target_dir.path();
+
+    for skill in ["a", "b", "c"] {
+        write_file(&global_root.join(format!("{skill}/file.txt")), skill);
+        write_file(&target_root.join(format!("{skill}/file.txt")), "stale");
+    }
+
+    let config = make_config(global_root.to_path_buf(), target_root.to_path_buf());
+    let target = &config.targets[0];
+    let force = PushOptions {
+        force: true,
+        ..PushOptions::default()
+    };
+    fail_push_at_b(&config, force);
+    // a changes in global before the resumed run.
+    write_file(&global_root.join("a/file.txt"), "a2");
+
+    let mut plan = plan_push_with(&config, target, Selection::All, force).unwrap();
+    let mut checkpoint = Checkpoint::load(target_root).unwrap();
+    checkpoint.apply_to(&mut plan);
+    assert!(plan.ops.iter().all(|op| op.kind == PlanKind::Update));
+    execute_plan_with(
+        &plan,
+        false,
+        &config.sync,
+        None,
+        Some(&mut checkpoint),
+        None,
+    )
+    .unwrap();
+    checkpoint.clear().unwrap();
+
+    assert_eq!(
+        fs::read_to_string(target_root.join("a/file.txt")).unwrap(),
+        "a2"
+    );
+    let rows = status_for_target(&config, target).unwrap();
+    assert!(rows.iter().all(|row| row.state == State::Same));
+}
+
+/// Runs a checkpointed push of `a`, `b`, `c` that fails at `b` (it vanishes
+/// after planning), leaving `a` done and recorded and `c` untouched.
+fn fail_push_at_b(config: &Config, options: PushOptions) {
+    let global_root = &config.global_root;
+    let target_root = &config.targets[0].root;
+    let plan = plan_push_with(config, &config.targets[0], Selection::All, options).unwrap();
+    assert_eq!(plan.ops.len(), 3);
+
+    fs::rename(global_root.join("b"), global_root.join("b.bak")).unwrap();
+    let mut checkpoint = Checkpoint::load(target_root).unwrap();
+    execute_plan_with(
+        &plan,
+        false,
+        &config.sync,
+        None,
+        Some(&mut checkpoint),
+        None,
+    )
+    .unwrap_err();
+    assert!(target_root.join(CHECKPOINT_FILE).exists());
+    assert_eq!(
+        fs::read_to_string(target_root.join("a/file.txt")).unwrap(),
+        "a"
+    );
+    assert_eq!(
+        fs::read_to_string(target_root.join("c/file.txt")).unwrap(),
+        "stale"
+    );
+    fs::rename(global_root.join("b.bak"), global_root.join("b")).unwrap();
+}
+
+#[test]
+fn plan_all_plans_every_target_without_side_effects() {
+    let global_dir = TempDir::new().unwrap();
+    let first_dir = TempDir::new().unwrap();
+    let second_dir = TempDir::new().unwrap();
+    let global_root = global_dir.path();
+
+    write_file(&global_root.join("skill1/file.txt"), "g");
+    write_file(&second_dir.path().join("skill1/file.txt"), "g");
+
+    let mut config = make_config(global_root.to_path_buf(), first_dir.path().to_path_buf());
+    config.targets.push(Target {
+        name: "t2".to_string(),
+        root: second_dir.path().to_path_buf(),
+        pre_push: Vec::new(),
+        post_push: Vec::new(),
+    });
+
+    let plans = plan_all(&config, Selection::All, false).unwrap();
+    let summary = plans
+        .iter()
+        .map(|(name, plan)| (name.as_str(), plan.ops[0].kind))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        summary,
+        vec![("t1", PlanKind::Install), ("t2", PlanKind::Skip)]
+    );
+    assert!(!first_dir.path().join("skill1").exists());
+}
+
+#[test]
+fn plan_all_error_names_failing_target() {
+    let global_dir = TempDir::new().unwrap();
+    let first_dir = TempDir::new().unwrap();
+    write_file(&global_dir.path().join("skill1/file.txt"), "g");
+
+    let mut config = make_config(
+        global_dir.path().to_path_buf(),
+        first_dir.path().to_path_buf(),
+    );
+    config.targets.push(Target {
+        name: "t2".to_string(),
+        root: first_dir.path().join("missing"),
+        pre_push: Vec::new(),
+        post_push: Vec::new(),
+    });
+
+    let err = plan_all(&config, Selection::All, false).unwrap_err();
+    assert!(matches!(err, AppError::Config { .. }));
+    assert!(err.to_string().contains("t2"));
+}
+
 #[cfg(unix)]
 #[test]
 fn push_errors_when_target_not_writable() {
@@ -291,9 +1449,83 @@ fn push_errors_when_target_not_writable() {
     let config = make_config(global_root.to_path_buf(), target_root.to_path_buf());
     let target = &config.targets[0];
     let plan = plan_push(&config, target, Selection::All, false).unwrap();
-    let err = execute_plan(&plan, false).unwrap_err();
+    let err = execute_plan(&plan, false, &config.sync).unwrap_err();
+
+    assert!(matches!(err, AppError::Io { .. }));
+}
+
+/// `sh -c` hook that appends `<label> {target}` to `log` only when
+/// `test <check> skill_dir` holds (`-e` or `! -e`).
+#[cfg(unix)]
+fn sentinel_hook(label: &str, check: &str, skill_dir: &Path, log: &Path) -> Vec<String> {
+    vec![
+        "sh".to_string(),
+        "-c".to_string(),
+        format!("test {check} \"$1\" && echo \"{label} $2\" >> \"$3\""),
+        "hook".to_string(),
+        skill_dir.display().to_string(),
+        "{target}".to_string(),
+        log.display().to_string(),
+    ]
+}
+
+#[cfg(unix)]
+#[test]
+fn push_hooks_run_around_mutations() {
+    let global_dir = TempDir::new().unwrap();
+    let target_dir = TempDir::new().unwrap();
+    let log_dir = TempDir::new().unwrap();
+    let global_root = global_dir.path();
+    let target_root = target_dir.path();
+    let log = log_dir.path().join("hooks.log");
+    let skill_dir = target_root.join("skill_missing");
+
+    write_file(&global_root.join("skill_missing/file.txt"), "m");
+
+    let mut config = make_config(global_root.to_path_buf(), target_root.to_path_buf());
+    config.targets[0].pre_push = sentinel_hook("pre", "! -e", &skill_dir, &log);
+    config.targets[0].post_push = sentinel_hook("post", "-e", &skill_dir, &log);
+    let target = &config.targets[0];
+
+    let plan = plan_push(&config, target, Selection::All, false).unwrap();
+    execute_plan(&plan, true, &config.sync).unwrap();
+    assert!(!log.exists());
+
+    execute_plan(&plan, false, &config.sync).unwrap();
+    assert_eq!(fs::read_to_string(&log).unwrap(), "pre t1\npost t1\n");
+
+    // Nothing left to change: the hooks stay quiet.
+    let plan = plan_push(&config, target, Selection::All, false).unwrap();
+    execute_plan(&plan, false, &config.sync).unwrap();
+    assert_eq!(fs::read_to_string(&log).unwrap(), "pre t1\npost t1\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn push_hook_failures_abort_before_or_fail_after_mutations() {
+    let global_dir = TempDir::new().unwrap();
+    let target_dir = TempDir::new().unwrap();
+    let global_root = global_dir.path();
+    let target_root = target_dir.path();
+
+    write_file(&global_root.join("skill_missing/file.txt"), "m");
+
+    let fail = vec!["sh".to_string(), "-c".to_string(), "exit 3".to_string()];
+    let mut config = make_config(global_root.to_path_buf(), target_root.to_path_buf());
+    config.targets[0].pre_push = fail.clone();
+    let plan = plan_push(&config, &config.targets[0], Selection::All, false).unwrap();
+    let err = execute_plan(&plan, false, &config.sync).unwrap_err();
+    assert!(matches!(err, AppError::Exec { .. }));
+    assert!(err.to_string().contains("pre_push"));
+    assert!(!target_root.join("skill_missing").exists());
 
+    config.targets[0].pre_push = Vec::new();
+    config.targets[0].post_push = fail;
+    let plan = plan_push(&config, &config.targets[0], Selection::All, false).unwrap();
+    let err = execute_plan(&plan, false, &config.sync).unwrap_err();
     assert!(matches!(err, AppError::Exec { .. }));
+    assert!(err.to_string().contains("post_push"));
+    assert!(target_root.join("skill_missing/file.txt").is_file());
 }
 
 #[test]
@@ -310,12 +1542,20 @@ fn import_execute_add_only() {
     let config = make_config(global_root.to_path_buf(), target_root.to_path_buf());
     let target = &config.targets[0];
 
-    let before = digest_dir(&global_root.join("skill_keep"), config.hash.algo, None).unwrap();
+    let before = digest_dir(
+        &global_root.join("skill_keep"),
+        &DigestOptions::new(config.hash.algo),
+    )
+    .unwrap();
 
-    let plan = plan_import(&config, target, Selection::All, false).unwrap();
-    execute_plan(&plan, false).unwrap();
+    let plan = plan_import(&config, target, Selection::All, ImportMode::AddOnly).unwrap();
+    execute_plan(&plan, false, &config.sync).unwrap();
 
-    let after = digest_dir(&global_root.join("skill_keep"), config.hash.algo, None).unwrap();
+    let after = digest_dir(
+        &global_root.join("skill_keep"),
+        &DigestOptions::new(config.hash.algo),
+    )
+    .unwrap();
 
     assert_eq!(before, after);
     assert!(global_root.join("skill_extra").is_dir());
@@ -333,7 +1573,13 @@ fn import_selection_one_missing_in_target_errors_even_if_other_exists() {
     let config = make_config(global_root.to_path_buf(), target_root.to_path_buf());
     let target = &config.targets[0];
 
-    let err = plan_import(&config, target, Selection::One("missing"), false).unwrap_err();
+    let err = plan_import(
+        &config,
+        target,
+        Selection::One("missing"),
+        ImportMode::AddOnly,
+    )
+    .unwrap_err();
     assert!(matches!(err, AppError::Exec { .. }));
 }
 
@@ -347,7 +1593,13 @@ fn import_rejects_invalid_skill_name() {
     );
     let target = &config.targets[0];
 
-    let err = plan_import(&config, target, Selection::One("../bad"), false).unwrap_err();
+    let err = plan_import(
+        &config,
+        target,
+        Selection::One("../bad"),
+        ImportMode::AddOnly,
+    )
+    .unwrap_err();
     assert!(matches!(err, AppError::Config { .. }));
 }
 
@@ -366,11 +1618,73 @@ fn push_prune_removes_extra() {
     let target = &config.targets[0];
 
     let plan = plan_push(&config, target, Selection::All, true).unwrap();
-    execute_plan(&plan, false).unwrap();
+    execute_plan(&plan, false, &config.sync).unwrap();
 
     assert!(!target_root.join("skill_extra").exists());
 }
 
+#[test]
+fn push_prune_note_reports_file_count_and_size() {
+    let global_dir = TempDir::new().unwrap();
+    let target_dir = TempDir::new().unwrap();
+    let global_root = global_dir.path();
+    let target_root = target_dir.path();
+
+    write_file(&target_root.join("skill_extra/a.txt"), &"a".repeat(1_500));
+    write_file(
+        &target_root.join("skill_extra/sub/b.txt"),
+        &"b".repeat(2_000),
+    );
+    write_file(&target_root.join("skill_tiny/only.txt"), "x");
+
+    let config = make_config(global_root.to_path_buf(), target_root.to_path_buf());
+    let target = &config.targets[0];
+
+    let plan = plan_push(&config, target, Selection::All, true).unwrap();
+    assert_eq!(
+        summarize_plan(&plan),
+        vec![
+            "prune skill_extra (2 files, 3.5 kB)",
+            "prune skill_tiny (1 file, 1 B)",
+        ]
+    );
+    assert!(target_root.join("skill_extra/sub/b.txt").exists());
+}
+
+#[test]
+fn import_merge_keeps_global_only_files() {
+    let global_dir = TempDir::new().unwrap();
+    let target_dir = TempDir::new().unwrap();
+    let global_root = global_dir.path();
+    let target_root = target_dir.path();
+
+    write_file(&global_root.join("skill_keep/file.txt"), "global");
+    write_file(&global_root.join("skill_keep/global_only.txt"), "mine");
+    write_file(&target_root.join("skill_keep/file.txt"), "target");
+    write_file(&target_root.join("skill_keep/nested/new.txt"), "new");
+
+    let config = make_config(global_root.to_path_buf(), target_root.to_path_buf());
+    let target = &config.targets[0];
+
+    let plan = plan_import(&config, target, Selection::All, ImportMode::Merge).unwrap();
+    assert_eq!(plan.ops[0].kind, PlanKind::Merge);
+    execute_plan(&plan, false, &config.sync).unwrap();
+
+    let skill = global_root.join("skill_keep");
+    assert_eq!(
+        fs::read_to_string(skill.join("file.txt")).unwrap(),
+        "target"
+    );
+    assert_eq!(
+        fs::read_to_string(skill.join("nested/new.txt")).unwrap(),
+        "new"
+    );
+    assert_eq!(
+        fs::read_to_string(skill.join("global_only.txt")).unwrap(),
+        "mine"
+    );
+}
+
 #[test]
 fn import_overwrite_replaces() {
     let global_dir = TempDir::new().unwrap();
@@ -384,13 +1698,19 @@ fn import_overwrite_replaces() {
     let config = make_config(global_root.to_path_buf(), target_root.to_path_buf());
     let target = &config.targets[0];
 
-    let plan = plan_import(&config, target, Selection::All, true).unwrap();
-    execute_plan(&plan, false).unwrap();
+    let plan = plan_import(&config, target, Selection::All, ImportMode::Overwrite).unwrap();
+    execute_plan(&plan, false, &config.sync).unwrap();
 
-    let global_digest =
-        digest_dir(&global_root.join("skill_keep"), config.hash.algo, None).unwrap();
-    let target_digest =
-        digest_dir(&target_root.join("skill_keep"), config.hash.algo, None).unwrap();
+    let global_digest = digest_dir(
+        &global_root.join("skill_keep"),
+        &DigestOptions::new(config.hash.algo),
+    )
+    .unwrap();
+    let target_digest = digest_dir(
+        &target_root.join("skill_keep"),
+        &DigestOptions::new(config.hash.algo),
+    )
+    .unwrap();
 
     assert_eq!(global_digest, target_digest);
 }
@@ -410,8 +1730,8 @@ fn import_dry_run_is_immutable() {
     let target = &config.targets[0];
 
     let before = snapshot_root(global_root, config.hash.algo);
-    let plan = plan_import(&config, target, Selection::All, false).unwrap();
-    execute_plan(&plan, true).unwrap();
+    let plan = plan_import(&config, target, Selection::All, ImportMode::AddOnly).unwrap();
+    execute_plan(&plan, true, &config.sync).unwrap();
     let after = snapshot_root(global_root, config.hash.algo);
 
     assert_eq!(before, after);
@@ -429,7 +1749,7 @@ fn diff_errors_when_missing() {
     let config = make_config(global_root.to_path_buf(), target_root.to_path_buf());
     let target = &config.targets[0];
 
-    let err = run_diff(&config, target, "skill_missing").unwrap_err();
+    let err = run_diff(&config, target, "skill_missing", DiffOptions::default()).unwrap_err();
     assert!(matches!(err, AppError::Exec { .. }));
 }
 
@@ -443,7 +1763,7 @@ fn diff_rejects_invalid_skill_name() {
     );
     let target = &config.targets[0];
 
-    let err = run_diff(&config, target, "../bad").unwrap_err();
+    let err = run_diff(&config, target, "../bad", DiffOptions::default()).unwrap_err();
     assert!(matches!(err, AppError::Config { .. }));
 }
 
@@ -461,7 +1781,7 @@ fn diff_errors_when_command_missing() {
     config.diff.command = vec!["__no_such_command__".to_string()];
     let target = &config.targets[0];
 
-    let err = run_diff(&config, target, "skill_diff").unwrap_err();
+    let err = run_diff(&config, target, "skill_diff", DiffOptions::default()).unwrap_err();
     assert!(matches!(err, AppError::Exec { .. }));
 }
 
@@ -480,7 +1800,7 @@ fn diff_errors_when_exit_code_gt1() {
     config.diff.command = vec!["sh".to_string(), "-c".to_string(), "exit 2".to_string()];
     let target = &config.targets[0];
 
-    let err = run_diff(&config, target, "skill_diff").unwrap_err();
+    let err = run_diff(&config, target, "skill_diff", DiffOptions::default()).unwrap_err();
     assert!(matches!(err, AppError::Exec { .. }));
 }
 
@@ -498,7 +1818,7 @@ fn diff_runs_when_command_ok() {
     config.diff.command = diff_success_command();
     let target = &config.targets[0];
 
-    run_diff(&config, target, "skill_diff").unwrap();
+    run_diff(&config, target, "skill_diff", DiffOptions::default()).unwrap();
 }
 
 #[test]
@@ -528,3 +1848,103 @@ fn status_errors_when_target_missing() {
     let err = status_for_target(&config, target).unwrap_err();
     assert!(matches!(err, AppError::Config { .. }));
 }
+
+#[test]
+fn status_reads_tar_archive_target() {
+    let global_dir = TempDir::new().unwrap();
+    let archive_dir = TempDir::new().unwrap();
+    let global_root = global_dir.path();
+    let archive = archive_dir.path().join("skills.tar");
+
+    write_file(&global_root.join("skill_same/file.txt"), "same");
+    write_file(&global_root.join("skill_diff/file.txt"), "g");
+    write_file(&global_root.join("skill_missing/file.txt"), "m");
+    write_tar(
+        &archive,
+        &[
+            ("skill_same/file.txt", "same"),
+            ("skill_diff/file.txt", "t"),
+            ("skill_extra/file.txt", "e"),
+        ],
+    );
+
+    let config = make_config(global_root.to_path_buf(), archive);
+    let target = &config.targets[0];
+    let rows = status_for_target(&config, target).unwrap();
+    let mut lookup = rows
+        .into_iter()
+        .map(|row| (row.skill, row.state))
+        .collect::<std::collections::HashMap<_, _>>();
+
+    assert_eq!(lookup.remove("skill_same"), Some(State::Same));
+    assert_eq!(lookup.remove("skill_diff"), Some(State::Diff));
+    assert_eq!(lookup.remove("skill_missing"), Some(State::Missing));
+    assert_eq!(lookup.remove("skill_extra"), Some(State::Extra));
+}
+
+#[cfg(unix)]
+#[test]
+fn diff_extracts_tar_archive_target() {
+    let global_dir = TempDir::new().unwrap();
+    let archive_dir = TempDir::new().unwrap();
+    let global_root = global_dir.path();
+    let archive = archive_dir.path().join("skills.tar");
+
+    write_file(&global_root.join("skill_diff/file.txt"), "g");
+    write_tar(&archive, &[("skill_diff/file.txt", "t")]);
+
+    let mut config = make_config(global_root.to_path_buf(), archive);
+    config.diff.command = vec![
+        "sh".to_string(),
+        "-c".to_string(),
+        "grep -q t \"$2/file.txt\" || exit 2".to_string(),
+        "--".to_string(),
+        "{left}".to_string(),
+        "{right}".to_string(),
+    ];
+    let target = &config.targets[0];
+
+    run_diff(&config, target, "skill_diff", DiffOptions::default()).unwrap();
+}
+
+#[test]
+fn push_rejects_tar_archive_target() {
+    let global_dir = TempDir::new().unwrap();
+    let archive_dir = TempDir::new().unwrap();
+    let global_root = global_dir.path();
+    let archive = archive_dir.path().join("skills.tar");
+
+    write_file(&global_root.join("skill_missing/file.txt"), "m");
+    write_tar(&archive, &[("skill_extra/file.txt", "e")]);
+
+    let config = make_config(global_root.to_path_buf(), archive);
+    let target = &config.targets[0];
+
+    let err = plan_push(&config, target, Selection::All, false).unwrap_err();
+    assert!(matches!(err, AppError::Config { .. }));
+}
+
+#[test]
+fn target_candidates_list_configured_targets() {
+    let global_dir = TempDir::new().unwrap();
+    let target_dir = TempDir::new().unwrap();
+    let mut config = make_config(
+        global_dir.path().to_path_buf(),
+        target_dir.path().to_path_buf(),
+    );
+    config.targets.push(Target {
+        name: "t2".to_string(),
+        root: target_dir.path().join("second"),
+        pre_push: Vec::new(),
+        post_push: Vec::new(),
+    });
+
+    let names = target_candidates_from(&config)
+        .iter()
+        .map(|candidate| candidate.get_value().to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        vec![config.targets[0].name.clone(), "t2".to_string()]
+    );
+}
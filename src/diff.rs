@@ -1,17 +1,164 @@
 use std::fs;
-use std::path::Path;
-use std::process::Command;
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
 
-use crate::config::{Config, Target};
+use wait_timeout::ChildExt;
+
+use crate::config::{Config, SkillLayout, Target};
+use crate::digest::{DigestOptions, FileState, build_ignore_set, dirs_equal};
 use crate::error::{AppError, AppResult};
-use crate::skill::validate_skill_id;
+use crate::events::{Event, write_event};
+use crate::output::{emit_bytes, emit_line, output, output_stdio};
+use crate::skill::{not_found_hint, validate_skill_id_with};
+use crate::source::open_source;
+use crate::status::list_skills_with;
+
+/// Environment variable holding the `{left}` (global) path for the diff command.
+pub const DIFF_LEFT_ENV: &str = "SKILLCTL_LEFT";
+/// Environment variable holding the `{right}` (target) path for the diff command.
+pub const DIFF_RIGHT_ENV: &str = "SKILLCTL_RIGHT";
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DiffOptions {
+    /// Print the resolved command instead of running it.
+    pub dry_run: bool,
+    /// Report through `--format ndjson` events on stdout; the command's own
+    /// stdout goes to stderr.
+    pub ndjson: bool,
+    /// Skip the command and print nothing when both sides' digests match
+    /// (`--format ndjson` still reports the skill); ignored with `dry_run`.
+    pub summary_only: bool,
+    /// Run the command even when `summary_only` finds matching digests.
+    pub force: bool,
+    /// Run the command with stdin from the null device and its output
+    /// captured, printing it once the command exits.
+    pub batch: bool,
+}
+
+pub fn run_diff(
+    config: &Config,
+    target: &Target,
+    skill: &str,
+    options: DiffOptions,
+) -> AppResult<()> {
+    run_diff_in(config, target, skill, None, options)
+}
 
-pub fn run_diff(config: &Config, target: &Target, skill: &str) -> AppResult<()> {
-    validate_skill_id(skill)?;
+/// Like [`run_diff`], but hands the diff command only `file` (relative to
+/// the skill directory) on each side. A file present on one side only is
+/// reported as `added` / `removed` without running the command.
+pub fn run_diff_file(
+    config: &Config,
+    target: &Target,
+    skill: &str,
+    file: &Path,
+    options: DiffOptions,
+) -> AppResult<()> {
+    run_diff_in(config, target, skill, Some(file), options)
+}
+
+#[tracing::instrument(skip(config, target, options), fields(target = %target.name))]
+fn run_diff_in(
+    config: &Config,
+    target: &Target,
+    skill: &str,
+    file: Option<&Path>,
+    options: DiffOptions,
+) -> AppResult<()> {
+    validate_skill_id_with(skill, &config.skills)?;
+    if let Some(file) = file {
+        validate_rel_file(file, config.skills.layout)?;
+    }
     let left = config.global_root.join(skill);
-    let right = target.root.join(skill);
-    ensure_normal_skill_dir(&left, skill)?;
-    ensure_normal_skill_dir(&right, skill)?;
+    let target_source = open_source(&target.root, &config.skills)?;
+    let right_dir = target_source.skill_dir(skill)?;
+    let right = right_dir.path();
+    let flat = config.skills.layout == SkillLayout::Flat;
+    if fs::symlink_metadata(&left).is_err() && fs::symlink_metadata(right).is_err() {
+        let mut candidates =
+            list_skills_with(&config.global_root, &config.skills).unwrap_or_default();
+        candidates.extend(target_source.list_skills().unwrap_or_default());
+        candidates.sort();
+        candidates.dedup();
+        return Err(AppError::exec(
+            crate::tr!(
+                "skill が存在しません: {}",
+                "Skill does not exist: {}",
+                skill
+            ),
+            Some(not_found_hint(
+                skill,
+                candidates.iter().map(String::as_str),
+                crate::tr!(
+                    "list で一覧を確認してください",
+                    "Run list to see available skills."
+                ),
+            )),
+        ));
+    }
+    ensure_normal_skill_dir(&left, skill, flat, config.skills.allow_symlink_dirs)?;
+    ensure_normal_skill_dir(right, skill, flat, config.skills.allow_symlink_dirs)?;
+    let (left, right) = match file {
+        Some(file) => {
+            let left = link_target(&left).join(file);
+            let right = link_target(right).join(file);
+            let state = match (left.exists(), right.exists()) {
+                (true, true) => None,
+                (true, false) => Some(FileState::Removed),
+                (false, true) => Some(FileState::Added),
+                (false, false) => {
+                    return Err(AppError::exec(
+                        crate::tr!(
+                            "global にもターゲットにもファイルが存在しません: {}",
+                            "File exists in neither global nor target: {}",
+                            Path::new(skill).join(file).display()
+                        ),
+                        Some(crate::tr!(
+                            "skill ディレクトリからの相対パスを指定してください",
+                            "Give the path relative to the skill directory."
+                        )),
+                    ));
+                }
+            };
+            if let Some(state) = state {
+                if options.ndjson {
+                    write_event(
+                        &mut output(),
+                        &Event::Diff {
+                            skill,
+                            differs: true,
+                        },
+                    )?;
+                } else {
+                    emit_line(format_args!("{} {}", state, file.display()))?;
+                }
+                return Ok(());
+            }
+            (left, right)
+        }
+        None => (left, right.to_path_buf()),
+    };
+    let right = right.as_path();
+    if options.summary_only
+        && !options.force
+        && !options.dry_run
+        && digests_match(config, &left, right)?
+    {
+        tracing::debug!(skill, "digests match; diff command skipped");
+        if options.ndjson {
+            write_event(
+                &mut output(),
+                &Event::Diff {
+                    skill,
+                    differs: false,
+                },
+            )?;
+        }
+        return Ok(());
+    }
     let command = &config.diff.command;
     if command.is_empty() {
         return Err(AppError::config(
@@ -22,13 +169,32 @@ pub fn run_diff(config: &Config, target: &Target, skill: &str) -> AppResult<()>
             )),
         ));
     }
+    let left_arg = path_to_arg(&link_target(&left));
+    let right_arg = path_to_arg(&link_target(right));
     let mut args = Vec::new();
     for arg in command {
         let replaced = arg
-            .replace("{left}", &path_to_arg(&left))
-            .replace("{right}", &path_to_arg(&right));
+            .replace("{left}", &left_arg)
+            .replace("{right}", &right_arg);
         args.push(replaced);
     }
+    tracing::debug!(args = ?args, dry_run = options.dry_run, "diff command");
+    if options.dry_run && options.ndjson {
+        return write_event(&mut output(), &Event::DiffCommand { argv: &args });
+    }
+    if options.dry_run {
+        if config.diff.use_env {
+            let mut shown = vec![
+                format!("{DIFF_LEFT_ENV}={left_arg}"),
+                format!("{DIFF_RIGHT_ENV}={right_arg}"),
+            ];
+            shown.extend(args);
+            emit_line(format_command(&shown))?;
+        } else {
+            emit_line(format_command(&args))?;
+        }
+        return Ok(());
+    }
     let mut iter = args.into_iter();
     let program = iter.next().ok_or_else(|| {
         AppError::config(
@@ -39,15 +205,38 @@ pub fn run_diff(config: &Config, target: &Target, skill: &str) -> AppResult<()>
             )),
         )
     })?;
-    let status = Command::new(program).args(iter).status().map_err(|err| {
-        AppError::exec(
+    let mut cmd = Command::new(program);
+    cmd.args(iter)
+        .env(DIFF_LEFT_ENV, &left_arg)
+        .env(DIFF_RIGHT_ENV, &right_arg);
+    if let Some(cwd) = &config.diff.cwd {
+        cmd.current_dir(cwd);
+    }
+    if options.batch {
+        cmd.stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+    } else if options.ndjson {
+        cmd.stdout(std::io::stderr());
+    } else if let Some(stdout) = output_stdio()? {
+        cmd.stdout(stdout);
+    }
+    let mut child = cmd.spawn().map_err(|err| {
+        AppError::exec_with_source(
             crate::tr!(
                 "diff コマンドの起動に失敗しました",
                 "Failed to start diff command"
             ),
             Some(err.to_string()),
+            err,
         )
     })?;
+    let captured = options.batch.then(|| Captured::start(&mut child));
+    let status = wait_diff(&mut child, config.diff.timeout_secs);
+    if let Some(captured) = captured {
+        captured.replay(options.ndjson)?;
+    }
+    let status = status?;
     if let Some(code) = status.code() {
         if code > 1 {
             return Err(AppError::exec(
@@ -62,6 +251,15 @@ pub fn run_diff(config: &Config, target: &Target, skill: &str) -> AppResult<()>
                 )),
             ));
         }
+        if options.ndjson {
+            write_event(
+                &mut output(),
+                &Event::Diff {
+                    skill,
+                    differs: code == 1,
+                },
+            )?;
+        }
     } else if !status.success() {
         return Err(AppError::exec(
             crate::tr!(
@@ -77,11 +275,178 @@ pub fn run_diff(config: &Config, target: &Target, skill: &str) -> AppResult<()>
     Ok(())
 }
 
+/// The stdout and stderr of a `--batch` diff command, drained on threads
+/// while it runs so a full pipe never blocks it.
+struct Captured {
+    stdout: JoinHandle<Vec<u8>>,
+    stderr: JoinHandle<Vec<u8>>,
+}
+
+impl Captured {
+    fn start(child: &mut Child) -> Self {
+        Self {
+            stdout: drain(child.stdout.take()),
+            stderr: drain(child.stderr.take()),
+        }
+    }
+
+    /// Prints the captured stdout to the primary output (stderr with
+    /// `ndjson`), then the captured stderr to stderr.
+    fn replay(self, ndjson: bool) -> AppResult<()> {
+        let stdout = self.stdout.join().unwrap_or_default();
+        let stderr = self.stderr.join().unwrap_or_default();
+        let write_error = |err| {
+            AppError::io(
+                crate::tr!(
+                    "diff コマンドの出力を書き込めません",
+                    "Cannot write diff command output"
+                ),
+                err,
+            )
+        };
+        if ndjson {
+            std::io::stderr().write_all(&stdout).map_err(write_error)?;
+        } else {
+            emit_bytes(&stdout)?;
+        }
+        std::io::stderr().write_all(&stderr).map_err(write_error)
+    }
+}
+
+fn drain(pipe: Option<impl Read + Send + 'static>) -> JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut pipe) = pipe {
+            // A read error leaves whatever was read so far.
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        buf
+    })
+}
+
+/// Waits for the diff command, killing it once `timeout_secs` have passed.
+fn wait_diff(child: &mut Child, timeout_secs: Option<u64>) -> AppResult<ExitStatus> {
+    let wait_error = |err: std::io::Error| {
+        AppError::exec_with_source(
+            crate::tr!(
+                "diff コマンドの終了待ちに失敗しました",
+                "Failed to wait for diff command"
+            ),
+            Some(err.to_string()),
+            err,
+        )
+    };
+    let Some(secs) = timeout_secs else {
+        return child.wait().map_err(wait_error);
+    };
+    if let Some(status) = child
+        .wait_timeout(Duration::from_secs(secs))
+        .map_err(wait_error)?
+    {
+        return Ok(status);
+    }
+    tracing::debug!(secs, "diff command timed out; killing it");
+    // The child may exit between the timeout and the kill; reap it either way.
+    let _ = child.kill();
+    child.wait().map_err(wait_error)?;
+    Err(AppError::exec(
+        crate::tr!(
+            "diff コマンドが {} 秒以内に終了しなかったため停止しました",
+            "diff command did not finish within {} seconds and was killed",
+            secs
+        ),
+        Some(crate::tr!(
+            "diff.command を確認するか diff.timeout_secs を延ばしてください",
+            "Check diff.command or raise diff.timeout_secs."
+        )),
+    ))
+}
+
+/// Whether both sides exist and would produce the same digest, using the
+/// same `hash` settings as status.
+fn digests_match(config: &Config, left: &Path, right: &Path) -> AppResult<bool> {
+    if fs::symlink_metadata(left).is_err() || fs::symlink_metadata(right).is_err() {
+        return Ok(false);
+    }
+    let ignore = build_ignore_set(&config.hash.ignore)?;
+    let options = DigestOptions::from_config(&config.hash, ignore.as_ref());
+    dirs_equal(&link_target(left), &link_target(right), &options)
+}
+
+/// Rejects a `--file` path that is empty, absolute or leaves the skill
+/// directory, and any `--file` under the flat layout.
+fn validate_rel_file(file: &Path, layout: SkillLayout) -> AppResult<()> {
+    if layout == SkillLayout::Flat {
+        return Err(AppError::config(
+            crate::tr!(
+                "flat レイアウトでは --file を使えません",
+                "--file cannot be used with the flat layout"
+            ),
+            Some(crate::tr!(
+                "flat レイアウトの skill は 1 ファイルなので diff <skill> を使ってください",
+                "Flat-layout skills are single files; run diff <skill> instead."
+            )),
+        ));
+    }
+    let escapes = file
+        .components()
+        .any(|part| !matches!(part, Component::Normal(_) | Component::CurDir));
+    let names_file = file
+        .components()
+        .any(|part| matches!(part, Component::Normal(_)));
+    if escapes || !names_file {
+        return Err(AppError::config(
+            crate::tr!(
+                "--file は skill 内の相対パスで指定してください: {}",
+                "--file must be a relative path inside the skill: {}",
+                file.display()
+            ),
+            Some(crate::tr!(
+                "絶対パスや .. は使えません",
+                "Absolute paths and .. are not allowed."
+            )),
+        ));
+    }
+    Ok(())
+}
+
+fn format_command(args: &[String]) -> String {
+    args.iter()
+        .map(|arg| {
+            if !arg.is_empty()
+                && !arg
+                    .chars()
+                    .any(|c| c.is_whitespace() || matches!(c, '\'' | '"' | '\\'))
+            {
+                arg.clone()
+            } else {
+                format!("'{}'", arg.replace('\'', "'\\''"))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The directory a symlinked skill (`skills.allow_symlink_dirs`) points to,
+/// so the diff command compares contents rather than the link itself.
+fn link_target(path: &Path) -> PathBuf {
+    if path.is_symlink() {
+        fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+    } else {
+        path.to_path_buf()
+    }
+}
+
 fn path_to_arg(path: &Path) -> String {
     path.to_string_lossy().to_string()
 }
 
-fn ensure_normal_skill_dir(path: &Path, skill: &str) -> AppResult<()> {
+fn ensure_normal_skill_dir(
+    path: &Path,
+    skill: &str,
+    flat: bool,
+    allow_symlink_dirs: bool,
+) -> AppResult<()> {
     let metadata = fs::symlink_metadata(path).map_err(|err| {
         let hint = if err.kind() == std::io::ErrorKind::NotFound {
             crate::tr!(
@@ -101,6 +466,9 @@ fn ensure_normal_skill_dir(path: &Path, skill: &str) -> AppResult<()> {
         )
     })?;
     if metadata.file_type().is_symlink() {
+        if allow_symlink_dirs && !flat && path.is_dir() {
+            return Ok(());
+        }
         return Err(AppError::exec(
             crate::tr!(
                 "diff の対象は通常ディレクトリである必要があります: {}",
@@ -113,6 +481,9 @@ fn ensure_normal_skill_dir(path: &Path, skill: &str) -> AppResult<()> {
             )),
         ));
     }
+    if flat && metadata.is_file() {
+        return Ok(());
+    }
     if !metadata.is_dir() {
         return Err(AppError::exec(
             crate::tr!(
@@ -128,3 +499,19 @@ fn ensure_normal_skill_dir(path: &Path, skill: &str) -> AppResult<()> {
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_command_quotes_when_needed() {
+        let args = vec![
+            "git".to_string(),
+            "a b".to_string(),
+            "it's".to_string(),
+            String::new(),
+        ];
+        assert_eq!(format_command(&args), "git 'a b' 'it'\\''s' ''");
+    }
+}
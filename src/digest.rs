@@ -1,46 +1,461 @@
+use std::borrow::Cow;
 use std::cmp::Ordering;
+use std::collections::BTreeMap;
 use std::fs::File;
 use std::io::{BufReader, Read};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::Serialize;
 use sha2::Digest as Sha2Digest;
 use walkdir::WalkDir;
 
-use crate::config::HashAlgo;
+use crate::config::{DigestEncoding, HashAlgo, HashConfig};
 use crate::error::{AppError, AppResult};
+use crate::merkle::{Fingerprint, TreeCache, build_tree, root_hash_of_entries};
 
-pub fn build_ignore_set(patterns: &[String]) -> AppResult<Option<GlobSet>> {
+/// Settings that shape a skill digest.
+#[derive(Debug, Clone, Copy)]
+pub struct DigestOptions<'a> {
+    pub algo: HashAlgo,
+    pub ignore: Option<&'a IgnoreSet>,
+    /// Hash text files with CRLF collapsed to LF.
+    pub normalize_eol: bool,
+    /// Hash each file's permission bits after its content (Unix only).
+    pub include_mode: bool,
+    /// Hash the paths of empty directories alongside the files.
+    pub include_empty_dirs: bool,
+    /// Digest directories as the root of a Merkle tree (`hash.merkle`).
+    pub merkle: bool,
+    /// Merkle trees from earlier runs to reuse unchanged file hashes from.
+    pub tree_cache: Option<&'a TreeCache>,
+    /// Hash files above this many bytes by size alone (`hash.skip_larger_than`).
+    pub skip_larger_than: Option<u64>,
+}
+
+impl<'a> DigestOptions<'a> {
+    /// Byte-for-byte hashing with `algo` and no ignore set.
+    pub fn new(algo: HashAlgo) -> Self {
+        Self {
+            algo,
+            ignore: None,
+            normalize_eol: false,
+            include_mode: false,
+            include_empty_dirs: false,
+            merkle: false,
+            tree_cache: None,
+            skip_larger_than: None,
+        }
+    }
+
+    /// Options from the `[hash]` config section, with its ignore set already built.
+    pub fn from_config(hash: &HashConfig, ignore: Option<&'a IgnoreSet>) -> Self {
+        Self {
+            ignore,
+            normalize_eol: hash.normalize_eol,
+            include_mode: hash.include_mode,
+            include_empty_dirs: hash.include_empty_dirs,
+            merkle: hash.merkle,
+            skip_larger_than: hash.skip_larger_than,
+            ..Self::new(hash.algo)
+        }
+    }
+}
+
+/// Per-skill marker pinning the hash algorithm used for that skill's digest.
+pub const SKILL_HASH_FILE: &str = ".skillhash";
+
+/// Reads the algorithm pinned by `dir/.skillhash`, if the marker exists.
+pub fn pinned_algo(dir: &Path) -> AppResult<Option<HashAlgo>> {
+    let marker = dir.join(SKILL_HASH_FILE);
+    if !marker.is_file() {
+        return Ok(None);
+    }
+    let text = std::fs::read_to_string(&marker).map_err(|err| {
+        AppError::io(
+            crate::tr!(
+                "ファイルの読み込みに失敗しました: {}",
+                "Failed to read file: {}",
+                marker.display()
+            ),
+            err,
+        )
+    })?;
+    parse_pinned_algo(&marker, &text).map(Some)
+}
+
+pub(crate) fn parse_pinned_algo(marker: &Path, text: &str) -> AppResult<HashAlgo> {
+    match text.trim() {
+        "blake3" => Ok(HashAlgo::Blake3),
+        "sha256" => Ok(HashAlgo::Sha256),
+        other => Err(AppError::config(
+            crate::tr!(
+                "{} のハッシュアルゴリズムが不正です: {}",
+                "Invalid hash algorithm in {}: {}",
+                marker.display(),
+                other
+            ),
+            Some(crate::tr!(
+                "blake3 か sha256 を記述してください",
+                "Write either blake3 or sha256."
+            )),
+        )),
+    }
+}
+
+/// Compiled `hash.ignore` patterns.
+///
+/// As in `.gitignore`, a pattern starting with `!` re-includes paths matched
+/// by an earlier pattern, and the last pattern matching a path decides.
+#[derive(Debug, Clone)]
+pub struct IgnoreSet {
+    globs: GlobSet,
+    /// Whether the pattern at each index of `globs` is a `!` re-inclusion.
+    negated: Vec<bool>,
+}
+
+impl IgnoreSet {
+    /// Whether `path` (relative to the skill root) is ignored.
+    pub fn is_match(&self, path: impl AsRef<Path>) -> bool {
+        if !self.negated.contains(&true) {
+            return self.globs.is_match(path);
+        }
+        self.globs
+            .matches(path)
+            .into_iter()
+            .max()
+            .is_some_and(|index| !self.negated[index])
+    }
+}
+
+/// Splits a leading `!` (re-inclusion) off an ignore pattern.
+pub(crate) fn split_negation(pattern: &str) -> (bool, &str) {
+    match pattern.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, pattern),
+    }
+}
+
+pub fn build_ignore_set(patterns: &[String]) -> AppResult<Option<IgnoreSet>> {
     if patterns.is_empty() {
         return Ok(None);
     }
     let mut builder = GlobSetBuilder::new();
+    let mut negated = Vec::with_capacity(patterns.len());
     for pattern in patterns {
-        let glob = Glob::new(pattern).map_err(|err| {
-            AppError::config(
+        let (negate, glob) = split_negation(pattern);
+        let glob = Glob::new(glob).map_err(|err| {
+            AppError::config_with_source(
                 crate::tr!(
                     "ignore パターンが不正です: {}",
                     "Invalid ignore pattern: {}",
                     pattern
                 ),
                 Some(err.to_string()),
+                err,
             )
         })?;
         builder.add(glob);
+        negated.push(negate);
     }
     let set = builder.build().map_err(|err| {
-        AppError::config(
+        AppError::config_with_source(
             crate::tr!(
                 "ignore パターンの構築に失敗しました",
                 "Failed to build ignore patterns"
             ),
             Some(err.to_string()),
+            err,
         )
     })?;
-    Ok(Some(set))
+    Ok(Some(IgnoreSet {
+        globs: set,
+        negated,
+    }))
+}
+
+/// Digests a skill directory; a `.skillhash` pin overrides `options.algo`.
+///
+/// With `options.merkle` this is the root hash of the skill's Merkle tree.
+#[tracing::instrument(skip(options), fields(path = %path.display()))]
+pub fn digest_dir(path: &Path, options: &DigestOptions) -> AppResult<String> {
+    if options.merkle {
+        return match options.tree_cache {
+            Some(cache) => cache.digest_dir(path, options),
+            None => build_tree(path, options, None).map(|build| build.tree.root_hash().to_string()),
+        };
+    }
+    let mut entries = collect_hashed_files(path, options.ignore)?
+        .into_iter()
+        .map(|(rel, full)| (rel, Some(full)))
+        .collect::<Vec<_>>();
+    if options.include_empty_dirs {
+        let empty_dirs = collect_empty_dirs(path, options.ignore)?;
+        entries.extend(empty_dirs.into_iter().map(|rel| (rel, None)));
+        entries.sort_by(|a, b| compare_rel_paths(&a.0, &b.0));
+    }
+    let algo = pinned_algo(path)?.unwrap_or(options.algo);
+    tracing::trace!(entries = entries.len(), ?algo, "digest files");
+    let mut hasher = DigestHasher::new(algo);
+    for (rel, full) in entries {
+        hash_rel_path(&mut hasher, &rel);
+        let Some(full) = full else {
+            hash_empty_dir(&mut hasher);
+            continue;
+        };
+        hasher.update(b"\0");
+        hash_file(&mut hasher, &full, options)?;
+        hasher.update(b"\0");
+        if options.include_mode {
+            hash_mode(&mut hasher, file_mode(&full)?);
+        }
+    }
+    let digest = hasher.finalize_hex();
+    tracing::trace!(%digest, "digest computed");
+    Ok(digest)
+}
+
+/// Digests a single-file skill (flat layout): the file content alone.
+#[tracing::instrument(skip(options), fields(path = %path.display()))]
+pub fn digest_file(path: &Path, options: &DigestOptions) -> AppResult<String> {
+    if !path.is_file() {
+        return Err(AppError::exec(
+            crate::tr!(
+                "ファイルが見つかりません: {}",
+                "File not found: {}",
+                path.display()
+            ),
+            Some(crate::tr!(
+                "対象パスを確認してください",
+                "Check the target path."
+            )),
+        ));
+    }
+    file_digest(path, options)
+}
+
+/// Cheap stand-in for [`digest_dir`] (`status --fast`): hashes the sorted
+/// relative paths with each file's size and mtime (and mode with
+/// `include_mode`) without reading any content.
+///
+/// Equal fingerprints only make equal content likely: a same-size edit that
+/// keeps the mtime is missed, and copies with fresh mtimes never match.
+pub fn fingerprint_dir(path: &Path, options: &DigestOptions) -> AppResult<String> {
+    let files = collect_hashed_files(path, options.ignore)?;
+    let mut hasher = DigestHasher::new(options.algo);
+    for (rel, full) in files {
+        hash_rel_path(&mut hasher, &rel);
+        hash_fingerprint(&mut hasher, &full, options)?;
+    }
+    Ok(hasher.finalize_hex())
+}
+
+/// [`fingerprint_dir`] for a single file (flat layout).
+pub fn fingerprint_file(path: &Path, options: &DigestOptions) -> AppResult<String> {
+    let mut hasher = DigestHasher::new(options.algo);
+    hash_fingerprint(&mut hasher, path, options)?;
+    Ok(hasher.finalize_hex())
+}
+
+fn hash_fingerprint(
+    hasher: &mut DigestHasher,
+    path: &Path,
+    options: &DigestOptions,
+) -> AppResult<()> {
+    let line = match Fingerprint::of(path)? {
+        Some(print) if options.include_mode => {
+            format!("\0{}\0{}\0{:?}\n", print.len, print.mtime_ns, print.mode)
+        }
+        Some(print) => format!("\0{}\0{}\n", print.len, print.mtime_ns),
+        None => "\0?\n".to_string(),
+    };
+    hasher.update(line.as_bytes());
+    Ok(())
+}
+
+/// How one file differs between a global (left) and a target (right) skill
+/// directory, in the orientation of the diff command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileState {
+    /// Only in the target.
+    Added,
+    /// Only in global.
+    Removed,
+    /// In both, with different file digests.
+    Modified,
+}
+
+impl std::fmt::Display for FileState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            FileState::Added => "added",
+            FileState::Removed => "removed",
+            FileState::Modified => "modified",
+        })
+    }
 }
 
-pub fn digest_dir(path: &Path, algo: HashAlgo, ignore: Option<&GlobSet>) -> AppResult<String> {
+/// A file whose presence or digest differs between two skill directories.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileChange {
+    /// Path relative to the skill directory, `/`-separated.
+    pub path: String,
+    pub state: FileState,
+}
+
+/// Lists the files that differ between `global` and `target` under
+/// `options`, in path order.
+///
+/// Uses the same file set and per-file digests as [`digest_dir`], so two
+/// directories with different digests report at least one change unless
+/// they differ only in empty directories or `.skillhash` pins.
+pub fn file_changes(
+    global: &Path,
+    target: &Path,
+    options: &DigestOptions,
+) -> AppResult<Vec<FileChange>> {
+    let left = collect_hashed_files(global, options.ignore)?
+        .into_iter()
+        .collect::<BTreeMap<_, _>>();
+    let right = collect_hashed_files(target, options.ignore)?
+        .into_iter()
+        .collect::<BTreeMap<_, _>>();
+    let mut rels = left.keys().chain(right.keys()).collect::<Vec<_>>();
+    rels.sort();
+    rels.dedup();
+    let mut changes = Vec::new();
+    for rel in rels {
+        let state = match (left.get(rel), right.get(rel)) {
+            (Some(_), None) => FileState::Removed,
+            (None, Some(_)) => FileState::Added,
+            (Some(l), Some(r)) if file_digest(l, options)? != file_digest(r, options)? => {
+                FileState::Modified
+            }
+            _ => continue,
+        };
+        let path = rel
+            .components()
+            .map(|part| part.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        changes.push(FileChange { path, state });
+    }
+    Ok(changes)
+}
+
+/// Returns whether `a` and `b` would produce the same digest under `options`.
+///
+/// Both trees are walked in digest order and the comparison stops at the first
+/// differing path or file, so large trailing files are skipped on early mismatches.
+/// Trees pinning different algorithms via `.skillhash` never compare equal.
+#[tracing::instrument(skip(options), fields(a = %a.display(), b = %b.display()))]
+pub fn dirs_equal(a: &Path, b: &Path, options: &DigestOptions) -> AppResult<bool> {
+    if a.is_file() && b.is_file() {
+        // Flat layout: each skill is a single file.
+        return Ok(digest_file(a, options)? == digest_file(b, options)?);
+    }
+    if let (Some(left), Some(right)) = (pinned_algo(a)?, pinned_algo(b)?)
+        && left != right
+    {
+        tracing::trace!(?left, ?right, "pinned algorithms differ");
+        return Ok(false);
+    }
+    if options.include_empty_dirs
+        && collect_empty_dirs(a, options.ignore)? != collect_empty_dirs(b, options.ignore)?
+    {
+        tracing::trace!("empty directories differ");
+        return Ok(false);
+    }
+    let left = collect_hashed_files(a, options.ignore)?;
+    let right = collect_hashed_files(b, options.ignore)?;
+    if left.len() != right.len() || left.iter().zip(&right).any(|(l, r)| l.0 != r.0) {
+        return Ok(false);
+    }
+    for ((rel, left_path), (_, right_path)) in left.iter().zip(&right) {
+        if !options.normalize_eol && file_len(left_path)? != file_len(right_path)? {
+            tracing::trace!(path = %rel.display(), "size differs");
+            return Ok(false);
+        }
+        if file_digest(left_path, options)? != file_digest(right_path, options)? {
+            tracing::trace!(path = %rel.display(), "content differs");
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+fn file_len(path: &Path) -> AppResult<u64> {
+    std::fs::metadata(path)
+        .map(|meta| meta.len())
+        .map_err(|err| {
+            AppError::io(
+                crate::tr!(
+                    "ファイルの読み込みに失敗しました: {}",
+                    "Failed to read file: {}",
+                    path.display()
+                ),
+                err,
+            )
+        })
+}
+
+pub(crate) fn file_digest(path: &Path, options: &DigestOptions) -> AppResult<String> {
+    let mut hasher = DigestHasher::new(options.algo);
+    hash_file(&mut hasher, path, options)?;
+    if options.include_mode {
+        hash_mode(&mut hasher, file_mode(path)?);
+    }
+    Ok(hasher.finalize_hex())
+}
+
+/// Permission bits of `path`; `None` where the platform has no Unix modes.
+pub(crate) fn file_mode(path: &Path) -> AppResult<Option<u32>> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let meta = std::fs::metadata(path).map_err(|err| {
+            AppError::io(
+                crate::tr!(
+                    "ファイルの読み込みに失敗しました: {}",
+                    "Failed to read file: {}",
+                    path.display()
+                ),
+                err,
+            )
+        })?;
+        Ok(Some(meta.permissions().mode() & 0o7777))
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = path;
+        Ok(None)
+    }
+}
+
+fn hash_mode(hasher: &mut DigestHasher, mode: Option<u32>) {
+    if let Some(mode) = mode {
+        hasher.update(format!("{:o}", mode & 0o7777).as_bytes());
+        hasher.update(b"\0");
+    }
+}
+
+/// Like [`collect_files`], minus the top-level `.skillhash` marker.
+pub(crate) fn collect_hashed_files(
+    path: &Path,
+    ignore: Option<&IgnoreSet>,
+) -> AppResult<Vec<(PathBuf, PathBuf)>> {
+    let mut files = collect_files(path, ignore)?;
+    files.retain(|(rel, _)| rel != Path::new(SKILL_HASH_FILE));
+    Ok(files)
+}
+
+/// Lists regular files under `path` as `(relative, full)` pairs in digest order.
+pub(crate) fn collect_files(
+    path: &Path,
+    ignore: Option<&IgnoreSet>,
+) -> AppResult<Vec<(PathBuf, PathBuf)>> {
     if !path.is_dir() {
         return Err(AppError::exec(
             crate::tr!(
@@ -55,15 +470,17 @@ pub fn digest_dir(path: &Path, algo: HashAlgo, ignore: Option<&GlobSet>) -> AppR
         ));
     }
     let mut files = Vec::new();
-    for entry in WalkDir::new(path).follow_links(false) {
+    // The root itself may be a symlink (`skills.allow_symlink_dirs`); only
+    // links below it are rejected.
+    for entry in WalkDir::new(path).min_depth(1).follow_links(false) {
         let entry = entry.map_err(|err| {
-            AppError::exec(
+            AppError::io(
                 crate::tr!(
                     "ファイル走査に失敗しました: {}",
                     "Failed to scan files: {}",
                     path.display()
                 ),
-                Some(err.to_string()),
+                err.into(),
             )
         })?;
         if entry.file_type().is_dir() {
@@ -83,13 +500,14 @@ pub fn digest_dir(path: &Path, algo: HashAlgo, ignore: Option<&GlobSet>) -> AppR
             ));
         }
         let rel = entry.path().strip_prefix(path).map_err(|err| {
-            AppError::exec(
+            AppError::exec_with_source(
                 crate::tr!(
                     "相対パスの取得に失敗しました: {}",
                     "Failed to get relative path: {}",
                     entry.path().display()
                 ),
                 Some(err.to_string()),
+                err,
             )
         })?;
         if let Some(set) = ignore
@@ -100,25 +518,200 @@ pub fn digest_dir(path: &Path, algo: HashAlgo, ignore: Option<&GlobSet>) -> AppR
         files.push((rel.to_path_buf(), entry.path().to_path_buf()));
     }
     files.sort_by(|a, b| compare_rel_paths(&a.0, &b.0));
+    Ok(files)
+}
 
-    let mut hasher = DigestHasher::new(algo);
-    for (rel, full) in files {
+/// Lists directories under `path` (not `path` itself) that have no entries at all.
+pub(crate) fn collect_empty_dirs(
+    path: &Path,
+    ignore: Option<&IgnoreSet>,
+) -> AppResult<Vec<PathBuf>> {
+    let scan_error = |err: std::io::Error| {
+        AppError::io(
+            crate::tr!(
+                "ファイル走査に失敗しました: {}",
+                "Failed to scan files: {}",
+                path.display()
+            ),
+            err,
+        )
+    };
+    let mut dirs = Vec::new();
+    for entry in WalkDir::new(path).min_depth(1).follow_links(false) {
+        let entry = entry.map_err(|err| scan_error(err.into()))?;
+        if !entry.file_type().is_dir() {
+            continue;
+        }
+        let mut children = std::fs::read_dir(entry.path()).map_err(scan_error)?;
+        if children.next().is_some() {
+            continue;
+        }
+        let Ok(rel) = entry.path().strip_prefix(path) else {
+            continue;
+        };
+        if ignore.is_some_and(|set| set.is_match(rel)) {
+            continue;
+        }
+        dirs.push(rel.to_path_buf());
+    }
+    dirs.sort_by(|a, b| compare_rel_paths(a, b));
+    Ok(dirs)
+}
+
+/// Terminates an empty directory's path; a file path is followed by `\0` instead.
+fn hash_empty_dir(hasher: &mut DigestHasher) {
+    hasher.update(b"/\0");
+}
+
+/// Digests in-memory `(relative, content, mode)` entries and empty directory
+/// paths like [`digest_dir`].
+pub(crate) fn digest_entries(
+    files: Vec<(PathBuf, &[u8], Option<u32>)>,
+    empty_dirs: Vec<PathBuf>,
+    options: &DigestOptions,
+) -> String {
+    let mut entries = files
+        .into_iter()
+        .filter(|(rel, _, _)| rel != Path::new(SKILL_HASH_FILE))
+        .map(|(rel, data, mode)| (rel, Some((data, mode))))
+        .collect::<Vec<_>>();
+    if options.include_empty_dirs {
+        entries.extend(empty_dirs.into_iter().map(|rel| (rel, None)));
+    }
+    if let Some(set) = options.ignore {
+        entries.retain(|(rel, _)| !set.is_match(rel));
+    }
+    if options.merkle {
+        let mut files = Vec::new();
+        let mut empty_dirs = Vec::new();
+        for (rel, file) in entries {
+            match file {
+                Some((data, mode)) => files.push((rel, bytes_digest(data, mode, options))),
+                None => empty_dirs.push(rel),
+            }
+        }
+        return root_hash_of_entries(files, empty_dirs, options);
+    }
+    entries.sort_by(|a, b| compare_rel_paths(&a.0, &b.0));
+
+    let mut hasher = DigestHasher::new(options.algo);
+    for (rel, file) in entries {
         hash_rel_path(&mut hasher, &rel);
+        let Some((data, mode)) = file else {
+            hash_empty_dir(&mut hasher);
+            continue;
+        };
         hasher.update(b"\0");
-        hash_file(&mut hasher, &full)?;
+        hash_bytes(&mut hasher, data, options);
         hasher.update(b"\0");
+        if options.include_mode {
+            hash_mode(&mut hasher, mode);
+        }
     }
-    Ok(hasher.finalize_hex())
+    hasher.finalize_hex()
+}
+
+/// Like [`file_digest`] for an in-memory file.
+fn bytes_digest(data: &[u8], mode: Option<u32>, options: &DigestOptions) -> String {
+    let mut hasher = DigestHasher::new(options.algo);
+    hash_bytes(&mut hasher, data, options);
+    if options.include_mode {
+        hash_mode(&mut hasher, mode);
+    }
+    hasher.finalize_hex()
+}
+
+/// Hashes an in-memory buffer with `algo`.
+pub(crate) fn digest_bytes(algo: HashAlgo, data: &[u8]) -> String {
+    let mut hasher = DigestHasher::new(algo);
+    hasher.update(data);
+    hasher.finalize_hex()
 }
 
-pub fn short_digest(digest: &str) -> String {
-    if digest.len() <= 6 {
+/// Characters kept at each end of a shortened digest unless `display.digest_len` says otherwise.
+pub const DEFAULT_DIGEST_LEN: usize = 3;
+
+/// Largest `display.digest_len`: both ends of a 64-character digest, i.e. all of it.
+pub const MAX_DIGEST_LEN: usize = 32;
+
+/// Shortens `digest` to its first and last `len` characters around `...`;
+/// digests no longer than both ends are returned whole.
+pub fn short_digest(digest: &str, len: usize) -> String {
+    if digest.len() <= len * 2 {
         digest.to_string()
     } else {
-        format!("{}...{}", &digest[..3], &digest[digest.len() - 3..])
+        format!("{}...{}", &digest[..len], &digest[digest.len() - len..])
+    }
+}
+
+/// Widest output of [`short_digest`] for 32-byte digests written in `encoding`.
+pub(crate) fn short_digest_width(len: usize, encoding: DigestEncoding) -> usize {
+    let full = match encoding {
+        DigestEncoding::Hex => 64,
+        DigestEncoding::Base64 => 44,
+        DigestEncoding::Base32 => 56,
+    };
+    (len * 2 + 3).min(full)
+}
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// Re-encodes a hex digest for display (`display.digest_encoding`).
+///
+/// Digests are computed, compared and stored as hex; only output changes.
+/// Anything that is not valid hex is returned unchanged.
+pub fn encode_digest(digest: &str, encoding: DigestEncoding) -> String {
+    if encoding == DigestEncoding::Hex {
+        return digest.to_string();
+    }
+    let Some(bytes) = decode_hex(digest) else {
+        return digest.to_string();
+    };
+    match encoding {
+        DigestEncoding::Hex => unreachable!("returned above"),
+        DigestEncoding::Base64 => encode_base(&bytes, BASE64_ALPHABET, 6, 4),
+        DigestEncoding::Base32 => encode_base(&bytes, BASE32_ALPHABET, 5, 8),
     }
 }
 
+fn decode_hex(digest: &str) -> Option<Vec<u8>> {
+    if !digest.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..digest.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(digest.get(index..index + 2)?, 16).ok())
+        .collect()
+}
+
+/// RFC 4648 encoding with `bits` per symbol, padded with `=` to a multiple of `block`.
+fn encode_base(bytes: &[u8], alphabet: &[u8], bits: u32, block: usize) -> String {
+    let mut out = String::new();
+    let mut buffer = 0u32;
+    let mut pending = 0u32;
+    let mask = (1 << bits) - 1;
+    for &byte in bytes {
+        buffer = (buffer << 8) | u32::from(byte);
+        pending += 8;
+        while pending >= bits {
+            pending -= bits;
+            out.push(char::from(alphabet[((buffer >> pending) & mask) as usize]));
+        }
+        buffer &= (1 << pending) - 1;
+    }
+    if pending > 0 {
+        out.push(char::from(
+            alphabet[((buffer << (bits - pending)) & mask) as usize],
+        ));
+    }
+    while !out.len().is_multiple_of(block) {
+        out.push('=');
+    }
+    out
+}
+
 fn compare_rel_paths(a: &Path, b: &Path) -> Ordering {
     #[cfg(unix)]
     {
@@ -149,55 +742,91 @@ fn compare_rel_paths(a: &Path, b: &Path) -> Ordering {
     }
 }
 
-fn hash_rel_path(hasher: &mut DigestHasher, path: &Path) {
-    #[cfg(unix)]
-    {
-        use std::os::unix::ffi::OsStrExt;
+/// Leads a length-prefixed path component; never occurs in a plain one.
+const RAW_COMPONENT: u8 = 0xff;
 
-        for (index, part) in path.components().enumerate() {
-            if index > 0 {
-                hasher.update(b"/");
-            }
-            hasher.update(part.as_os_str().as_bytes());
+fn hash_rel_path(hasher: &mut DigestHasher, path: &Path) {
+    let mut out = Vec::new();
+    for (index, part) in path.components().enumerate() {
+        if index > 0 {
+            out.push(b'/');
         }
+        encode_component(part.as_os_str().as_encoded_bytes(), &mut out);
     }
-    #[cfg(not(unix))]
-    {
-        let mut out = String::new();
-        for (index, part) in path.components().enumerate() {
-            if index > 0 {
-                out.push('/');
-            }
-            out.push_str(part.as_os_str().to_string_lossy().as_ref());
-        }
-        hasher.update(out.as_bytes());
+    hasher.update(&out);
+}
+
+/// Appends one path component to the digest stream.
+///
+/// Plain ASCII components (no `/` or NUL) are written verbatim, which keeps
+/// digests of ASCII trees unchanged. Anything else is written as
+/// [`RAW_COMPONENT`], a little-endian `u64` length and the raw bytes, so two
+/// different paths can never produce the same encoding.
+pub(crate) fn encode_component(bytes: &[u8], out: &mut Vec<u8>) {
+    let plain = !bytes.is_empty()
+        && bytes
+            .iter()
+            .all(|&byte| byte.is_ascii() && byte != b'/' && byte != 0);
+    if plain {
+        out.extend_from_slice(bytes);
+    } else {
+        out.push(RAW_COMPONENT);
+        out.extend_from_slice(&(bytes.len() as u64).to_le_bytes());
+        out.extend_from_slice(bytes);
     }
 }
 
-fn hash_file(hasher: &mut DigestHasher, path: &Path) -> AppResult<()> {
-    let file = File::open(path).map_err(|err| {
-        AppError::exec(
+/// Whether a file of `len` bytes is hashed by size alone under `options`.
+fn is_size_only(len: u64, options: &DigestOptions) -> bool {
+    options.skip_larger_than.is_some_and(|cap| len > cap)
+}
+
+/// Hashed in place of the content of a file above `hash.skip_larger_than`,
+/// so the digest still follows the file's size.
+fn hash_size_only(hasher: &mut DigestHasher, len: u64) {
+    hasher.update(format!("skillctl:size-only:{len}").as_bytes());
+}
+
+/// Hashes an in-memory file's content the way [`hash_file`] hashes one on disk.
+fn hash_bytes(hasher: &mut DigestHasher, data: &[u8], options: &DigestOptions) {
+    if is_size_only(data.len() as u64, options) {
+        hash_size_only(hasher, data.len() as u64);
+    } else if options.normalize_eol {
+        hasher.update(&normalize_text_eol(data));
+    } else {
+        hasher.update(data);
+    }
+}
+
+fn hash_file(hasher: &mut DigestHasher, path: &Path, options: &DigestOptions) -> AppResult<()> {
+    let read_error = |err: std::io::Error| {
+        AppError::io(
             crate::tr!(
                 "ファイルの読み込みに失敗しました: {}",
                 "Failed to read file: {}",
                 path.display()
             ),
-            Some(err.to_string()),
+            err,
         )
-    })?;
+    };
+    if options.skip_larger_than.is_some() {
+        let len = file_len(path)?;
+        if is_size_only(len, options) {
+            hash_size_only(hasher, len);
+            return Ok(());
+        }
+    }
+    if options.normalize_eol {
+        // Text detection needs the whole file, so normalization gives up streaming.
+        let data = std::fs::read(path).map_err(read_error)?;
+        hasher.update(&normalize_text_eol(&data));
+        return Ok(());
+    }
+    let file = File::open(path).map_err(read_error)?;
     let mut reader = BufReader::new(file);
     let mut buf = [0u8; 8192];
     loop {
-        let read = reader.read(&mut buf).map_err(|err| {
-            AppError::exec(
-                crate::tr!(
-                    "ファイルの読み込みに失敗しました: {}",
-                    "Failed to read file: {}",
-                    path.display()
-                ),
-                Some(err.to_string()),
-            )
-        })?;
+        let read = reader.read(&mut buf).map_err(read_error)?;
         if read == 0 {
             break;
         }
@@ -206,21 +835,37 @@ fn hash_file(hasher: &mut DigestHasher, path: &Path) -> AppResult<()> {
     Ok(())
 }
 
+/// Strips `\r` before `\n` when `data` looks like text (valid UTF-8 without NUL).
+fn normalize_text_eol(data: &[u8]) -> Cow<'_, [u8]> {
+    let is_text = !data.contains(&0) && std::str::from_utf8(data).is_ok();
+    if !is_text || !data.windows(2).any(|pair| pair == b"\r\n") {
+        return Cow::Borrowed(data);
+    }
+    let mut out = Vec::with_capacity(data.len());
+    for (index, &byte) in data.iter().enumerate() {
+        if byte == b'\r' && data.get(index + 1) == Some(&b'\n') {
+            continue;
+        }
+        out.push(byte);
+    }
+    Cow::Owned(out)
+}
+
 #[allow(clippy::large_enum_variant)]
-enum DigestHasher {
+pub(crate) enum DigestHasher {
     Blake3(blake3::Hasher),
     Sha256(sha2::Sha256),
 }
 
 impl DigestHasher {
-    fn new(algo: HashAlgo) -> Self {
+    pub(crate) fn new(algo: HashAlgo) -> Self {
         match algo {
             HashAlgo::Blake3 => Self::Blake3(blake3::Hasher::new()),
             HashAlgo::Sha256 => Self::Sha256(sha2::Sha256::new()),
         }
     }
 
-    fn update(&mut self, data: &[u8]) {
+    pub(crate) fn update(&mut self, data: &[u8]) {
         match self {
             Self::Blake3(hasher) => {
                 hasher.update(data);
@@ -231,7 +876,7 @@ impl DigestHasher {
         }
     }
 
-    fn finalize_hex(self) -> String {
+    pub(crate) fn finalize_hex(self) -> String {
         match self {
             Self::Blake3(hasher) => hasher.finalize().to_hex().to_string(),
             Self::Sha256(hasher) => {
@@ -266,9 +911,9 @@ mod tests {
         let dir = TempDir::new().unwrap();
         let path = dir.path().join("a.txt");
         fs::write(&path, "hello").unwrap();
-        let first = digest_dir(dir.path(), HashAlgo::Blake3, None).unwrap();
+        let first = digest_dir(dir.path(), &DigestOptions::new(HashAlgo::Blake3)).unwrap();
         fs::write(&path, "hello").unwrap();
-        let second = digest_dir(dir.path(), HashAlgo::Blake3, None).unwrap();
+        let second = digest_dir(dir.path(), &DigestOptions::new(HashAlgo::Blake3)).unwrap();
         assert_eq!(first, second);
     }
 
@@ -277,12 +922,270 @@ mod tests {
         let dir = TempDir::new().unwrap();
         let path = dir.path().join("a.txt");
         fs::write(&path, "hello").unwrap();
-        let first = digest_dir(dir.path(), HashAlgo::Sha256, None).unwrap();
+        let first = digest_dir(dir.path(), &DigestOptions::new(HashAlgo::Sha256)).unwrap();
         fs::write(&path, "hello2").unwrap();
-        let second = digest_dir(dir.path(), HashAlgo::Sha256, None).unwrap();
+        let second = digest_dir(dir.path(), &DigestOptions::new(HashAlgo::Sha256)).unwrap();
         assert_ne!(first, second);
     }
 
+    #[test]
+    fn digest_uses_pinned_algo_and_skips_marker() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("a.txt"), "hello").unwrap();
+        let blake3 = digest_dir(dir.path(), &DigestOptions::new(HashAlgo::Blake3)).unwrap();
+        let sha256 = digest_dir(dir.path(), &DigestOptions::new(HashAlgo::Sha256)).unwrap();
+
+        fs::write(dir.path().join(SKILL_HASH_FILE), "sha256\n").unwrap();
+        assert_eq!(pinned_algo(dir.path()).unwrap(), Some(HashAlgo::Sha256));
+        let pinned = digest_dir(dir.path(), &DigestOptions::new(HashAlgo::Blake3)).unwrap();
+        assert_eq!(pinned, sha256);
+        assert_ne!(pinned, blake3);
+
+        fs::write(dir.path().join(SKILL_HASH_FILE), "md5").unwrap();
+        let err = digest_dir(dir.path(), &DigestOptions::new(HashAlgo::Blake3)).unwrap_err();
+        assert!(matches!(err, AppError::Config { .. }));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn digest_include_mode_tracks_executable_bit() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = TempDir::new().unwrap();
+        let copy = TempDir::new().unwrap();
+        let path = dir.path().join("run.sh");
+        fs::write(&path, "echo hi").unwrap();
+        fs::write(copy.path().join("run.sh"), "echo hi").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+        fs::set_permissions(
+            copy.path().join("run.sh"),
+            fs::Permissions::from_mode(0o644),
+        )
+        .unwrap();
+
+        let plain = DigestOptions::new(HashAlgo::Blake3);
+        let with_mode = DigestOptions {
+            include_mode: true,
+            ..plain
+        };
+        let before_plain = digest_dir(dir.path(), &plain).unwrap();
+        let before_mode = digest_dir(dir.path(), &with_mode).unwrap();
+        assert_ne!(before_plain, before_mode);
+
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        assert_eq!(digest_dir(dir.path(), &plain).unwrap(), before_plain);
+        assert_ne!(digest_dir(dir.path(), &with_mode).unwrap(), before_mode);
+        assert!(dirs_equal(dir.path(), copy.path(), &plain).unwrap());
+        assert!(!dirs_equal(dir.path(), copy.path(), &with_mode).unwrap());
+    }
+
+    #[test]
+    fn digest_include_empty_dirs_tracks_empty_subdirectory() {
+        let dir = TempDir::new().unwrap();
+        let copy = TempDir::new().unwrap();
+        fs::write(dir.path().join("a.txt"), "hello").unwrap();
+        fs::write(copy.path().join("a.txt"), "hello").unwrap();
+
+        let plain = DigestOptions::new(HashAlgo::Blake3);
+        let with_dirs = DigestOptions {
+            include_empty_dirs: true,
+            ..plain
+        };
+        let before_plain = digest_dir(dir.path(), &plain).unwrap();
+        let before_dirs = digest_dir(dir.path(), &with_dirs).unwrap();
+        assert_eq!(before_plain, before_dirs);
+
+        fs::create_dir_all(dir.path().join("templates")).unwrap();
+        assert_eq!(digest_dir(dir.path(), &plain).unwrap(), before_plain);
+        assert_ne!(digest_dir(dir.path(), &with_dirs).unwrap(), before_dirs);
+        assert!(dirs_equal(dir.path(), copy.path(), &plain).unwrap());
+        assert!(!dirs_equal(dir.path(), copy.path(), &with_dirs).unwrap());
+
+        // A directory that gains a file is no longer empty.
+        fs::write(dir.path().join("templates/b.txt"), "b").unwrap();
+        fs::create_dir_all(copy.path().join("templates")).unwrap();
+        fs::write(copy.path().join("templates/b.txt"), "b").unwrap();
+        assert!(dirs_equal(dir.path(), copy.path(), &with_dirs).unwrap());
+    }
+
+    #[test]
+    fn digest_normalize_eol_matches_crlf_and_lf() {
+        let crlf = TempDir::new().unwrap();
+        let lf = TempDir::new().unwrap();
+        fs::write(crlf.path().join("a.md"), "line1\r\nline2\r\n").unwrap();
+        fs::write(lf.path().join("a.md"), "line1\nline2\n").unwrap();
+
+        let raw = DigestOptions::new(HashAlgo::Blake3);
+        assert_ne!(
+            digest_dir(crlf.path(), &raw).unwrap(),
+            digest_dir(lf.path(), &raw).unwrap()
+        );
+
+        let normalized = DigestOptions {
+            normalize_eol: true,
+            ..raw
+        };
+        assert_eq!(
+            digest_dir(crlf.path(), &normalized).unwrap(),
+            digest_dir(lf.path(), &normalized).unwrap()
+        );
+    }
+
+    #[test]
+    fn digest_normalize_eol_keeps_binary_bytes() {
+        let crlf = TempDir::new().unwrap();
+        let lf = TempDir::new().unwrap();
+        fs::write(crlf.path().join("a.bin"), b"\0data\r\n").unwrap();
+        fs::write(lf.path().join("a.bin"), b"\0data\n").unwrap();
+        fs::write(crlf.path().join("b.bin"), b"\xff\r\n").unwrap();
+        fs::write(lf.path().join("b.bin"), b"\xff\n").unwrap();
+
+        let normalized = DigestOptions {
+            normalize_eol: true,
+            ..DigestOptions::new(HashAlgo::Blake3)
+        };
+        assert_ne!(
+            digest_dir(crlf.path(), &normalized).unwrap(),
+            digest_dir(lf.path(), &normalized).unwrap()
+        );
+        assert_eq!(normalize_text_eol(b"\0\r\n").as_ref(), b"\0\r\n");
+        assert_eq!(normalize_text_eol(b"a\r\rb\r\n").as_ref(), b"a\r\rb\n");
+    }
+
+    #[test]
+    fn dirs_equal_matches_digest_equality() {
+        let a = TempDir::new().unwrap();
+        let b = TempDir::new().unwrap();
+        fs::create_dir_all(a.path().join("nested")).unwrap();
+        fs::create_dir_all(b.path().join("nested")).unwrap();
+        fs::write(a.path().join("nested/a.txt"), "hello").unwrap();
+        fs::write(b.path().join("nested/a.txt"), "hello").unwrap();
+        fs::write(a.path().join("z.txt"), "tail").unwrap();
+        fs::write(b.path().join("z.txt"), "tail").unwrap();
+        let options = DigestOptions::new(HashAlgo::Blake3);
+        assert!(dirs_equal(a.path(), b.path(), &options).unwrap());
+
+        fs::write(b.path().join("z.txt"), "TAIL").unwrap();
+        assert!(!dirs_equal(a.path(), b.path(), &options).unwrap());
+        assert_ne!(
+            digest_dir(a.path(), &options).unwrap(),
+            digest_dir(b.path(), &options).unwrap()
+        );
+    }
+
+    #[test]
+    fn dirs_equal_detects_structural_differences() {
+        let a = TempDir::new().unwrap();
+        let b = TempDir::new().unwrap();
+        fs::write(a.path().join("a.txt"), "same").unwrap();
+        fs::write(b.path().join("b.txt"), "same").unwrap();
+        let options = DigestOptions::new(HashAlgo::Sha256);
+        assert!(!dirs_equal(a.path(), b.path(), &options).unwrap());
+
+        fs::write(b.path().join("a.txt"), "same").unwrap();
+        assert!(!dirs_equal(a.path(), b.path(), &options).unwrap());
+
+        let ignore = build_ignore_set(&["b.txt".to_string()]).unwrap().unwrap();
+        let ignoring = DigestOptions {
+            ignore: Some(&ignore),
+            ..options
+        };
+        assert!(dirs_equal(a.path(), b.path(), &ignoring).unwrap());
+    }
+
+    #[test]
+    fn dirs_equal_honors_normalize_eol() {
+        let a = TempDir::new().unwrap();
+        let b = TempDir::new().unwrap();
+        fs::write(a.path().join("a.md"), "x\r\ny\r\n").unwrap();
+        fs::write(b.path().join("a.md"), "x\ny\n").unwrap();
+        let raw = DigestOptions::new(HashAlgo::Blake3);
+        assert!(!dirs_equal(a.path(), b.path(), &raw).unwrap());
+        let normalized = DigestOptions {
+            normalize_eol: true,
+            ..raw
+        };
+        assert!(dirs_equal(a.path(), b.path(), &normalized).unwrap());
+    }
+
+    #[test]
+    fn digest_hashes_files_above_cap_by_size_only() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("small.txt"), "abc").unwrap();
+        fs::write(dir.path().join("big.bin"), "0123456789").unwrap();
+        let capped = DigestOptions {
+            skip_larger_than: Some(4),
+            ..DigestOptions::new(HashAlgo::Blake3)
+        };
+        let before = digest_dir(dir.path(), &capped).unwrap();
+
+        fs::write(dir.path().join("big.bin"), "9876543210").unwrap();
+        assert_eq!(digest_dir(dir.path(), &capped).unwrap(), before);
+        assert_ne!(
+            digest_dir(dir.path(), &DigestOptions::new(HashAlgo::Blake3)).unwrap(),
+            before
+        );
+
+        fs::write(dir.path().join("small.txt"), "abd").unwrap();
+        assert_ne!(digest_dir(dir.path(), &capped).unwrap(), before);
+        fs::write(dir.path().join("small.txt"), "abc").unwrap();
+
+        fs::rename(dir.path().join("big.bin"), dir.path().join("moved.bin")).unwrap();
+        assert_ne!(digest_dir(dir.path(), &capped).unwrap(), before);
+        fs::remove_file(dir.path().join("moved.bin")).unwrap();
+        assert_ne!(digest_dir(dir.path(), &capped).unwrap(), before);
+    }
+
+    #[test]
+    fn digest_honors_negated_ignore_patterns() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("SKILL.md"), "skill").unwrap();
+        fs::write(dir.path().join("keep.log"), "keep").unwrap();
+        fs::write(dir.path().join("debug.log"), "debug").unwrap();
+        let ignore = build_ignore_set(&["**/*.log".to_string(), "!keep.log".to_string()])
+            .unwrap()
+            .unwrap();
+        assert!(ignore.is_match("debug.log"));
+        assert!(!ignore.is_match("keep.log"));
+        let options = DigestOptions {
+            ignore: Some(&ignore),
+            ..DigestOptions::new(HashAlgo::Blake3)
+        };
+        let before = digest_dir(dir.path(), &options).unwrap();
+
+        fs::write(dir.path().join("debug.log"), "changed").unwrap();
+        assert_eq!(digest_dir(dir.path(), &options).unwrap(), before);
+        fs::write(dir.path().join("keep.log"), "changed").unwrap();
+        assert_ne!(digest_dir(dir.path(), &options).unwrap(), before);
+    }
+
+    #[test]
+    fn encode_digest_renders_each_encoding() {
+        // RFC 4648 test vectors ("f", "foobar").
+        assert_eq!(encode_digest("66", DigestEncoding::Base64), "Zg==");
+        assert_eq!(encode_digest("66", DigestEncoding::Base32), "MY======");
+        assert_eq!(
+            encode_digest("666f6f626172", DigestEncoding::Base64),
+            "Zm9vYmFy"
+        );
+        assert_eq!(
+            encode_digest("666f6f626172", DigestEncoding::Base32),
+            "MZXW6YTBOI======"
+        );
+
+        let digest = digest_bytes(HashAlgo::Sha256, b"");
+        assert_eq!(encode_digest(&digest, DigestEncoding::Hex), digest);
+        assert_eq!(
+            encode_digest(&digest, DigestEncoding::Base64),
+            "47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU="
+        );
+        assert_eq!(
+            encode_digest(&digest, DigestEncoding::Base32),
+            "4OYMIQUY7QOBJGX36TEJS35ZEQT24QPEMSNZGTFESWMRW6CSXBKQ===="
+        );
+        assert_eq!(encode_digest("not-hex", DigestEncoding::Base64), "not-hex");
+    }
+
     #[test]
     fn digest_ignores_patterns() {
         let dir = TempDir::new().unwrap();
@@ -291,9 +1194,23 @@ mod tests {
         let ignore = build_ignore_set(&["**/*.tmp".to_string()])
             .unwrap()
             .unwrap();
-        let first = digest_dir(dir.path(), HashAlgo::Blake3, Some(&ignore)).unwrap();
+        let first = digest_dir(
+            dir.path(),
+            &DigestOptions {
+                ignore: Some(&ignore),
+                ..DigestOptions::new(HashAlgo::Blake3)
+            },
+        )
+        .unwrap();
         fs::write(dir.path().join("skip.tmp"), "changed").unwrap();
-        let second = digest_dir(dir.path(), HashAlgo::Blake3, Some(&ignore)).unwrap();
+        let second = digest_dir(
+            dir.path(),
+            &DigestOptions {
+                ignore: Some(&ignore),
+                ..DigestOptions::new(HashAlgo::Blake3)
+            },
+        )
+        .unwrap();
         assert_eq!(first, second);
     }
 
@@ -304,8 +1221,52 @@ mod tests {
         fs::create_dir_all(dir_a.path().join("a")).unwrap();
         fs::write(dir_a.path().join("a/b.txt"), "x").unwrap();
         fs::write(dir_b.path().join("ab.txt"), "x").unwrap();
-        let first = digest_dir(dir_a.path(), HashAlgo::Blake3, None).unwrap();
-        let second = digest_dir(dir_b.path(), HashAlgo::Blake3, None).unwrap();
+        let first = digest_dir(dir_a.path(), &DigestOptions::new(HashAlgo::Blake3)).unwrap();
+        let second = digest_dir(dir_b.path(), &DigestOptions::new(HashAlgo::Blake3)).unwrap();
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn encode_component_keeps_ascii_and_separates_ambiguous_names() {
+        let encode = |parts: &[&[u8]]| {
+            let mut out = Vec::new();
+            for (index, part) in parts.iter().enumerate() {
+                if index > 0 {
+                    out.push(b'/');
+                }
+                encode_component(part, &mut out);
+            }
+            out
+        };
+        assert_eq!(encode(&[b"a", b"b.txt"]), b"a/b.txt");
+        // A component literally named `a/b` must not read as nested `a` / `b`.
+        assert_ne!(encode(&[b"a/b"]), encode(&[b"a", b"b"]));
+        // Lossy decoding used to map invalid UTF-8 onto U+FFFD.
+        assert_ne!(encode(&[b"\xff"]), encode(&["\u{fffd}".as_bytes()]));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn digest_distinguishes_lossy_colliding_trees() {
+        use std::ffi::OsString;
+        use std::os::unix::ffi::OsStringExt;
+
+        let dir_a = TempDir::new().unwrap();
+        let dir_b = TempDir::new().unwrap();
+        fs::create_dir_all(dir_a.path().join(OsString::from_vec(vec![b'd', 0xff]))).unwrap();
+        fs::write(
+            dir_a
+                .path()
+                .join(OsString::from_vec(vec![b'd', 0xff]))
+                .join("x"),
+            "x",
+        )
+        .unwrap();
+        fs::create_dir_all(dir_b.path().join("d\u{fffd}")).unwrap();
+        fs::write(dir_b.path().join("d\u{fffd}/x"), "x").unwrap();
+
+        let first = digest_dir(dir_a.path(), &DigestOptions::new(HashAlgo::Blake3)).unwrap();
+        let second = digest_dir(dir_b.path(), &DigestOptions::new(HashAlgo::Blake3)).unwrap();
         assert_ne!(first, second);
     }
 
@@ -323,8 +1284,8 @@ mod tests {
         fs::write(&file_a, "x").unwrap();
         fs::write(&file_b, "x").unwrap();
 
-        let first = digest_dir(dir_a.path(), HashAlgo::Blake3, None).unwrap();
-        let second = digest_dir(dir_b.path(), HashAlgo::Blake3, None).unwrap();
+        let first = digest_dir(dir_a.path(), &DigestOptions::new(HashAlgo::Blake3)).unwrap();
+        let second = digest_dir(dir_b.path(), &DigestOptions::new(HashAlgo::Blake3)).unwrap();
 
         assert_ne!(first, second);
     }
@@ -335,9 +1296,9 @@ mod tests {
             let dir = TempDir::new().unwrap();
             let path = dir.path().join("a.txt");
             fs::write(&path, &bytes).unwrap();
-            let first = digest_dir(dir.path(), HashAlgo::Blake3, None).unwrap();
+            let first = digest_dir(dir.path(), &DigestOptions::new(HashAlgo::Blake3)).unwrap();
             fs::write(&path, &bytes).unwrap();
-            let second = digest_dir(dir.path(), HashAlgo::Blake3, None).unwrap();
+            let second = digest_dir(dir.path(), &DigestOptions::new(HashAlgo::Blake3)).unwrap();
             prop_assert_eq!(first, second);
         }
 
@@ -350,9 +1311,9 @@ mod tests {
             let dir = TempDir::new().unwrap();
             let path = dir.path().join("a.txt");
             fs::write(&path, &a).unwrap();
-            let first = digest_dir(dir.path(), HashAlgo::Sha256, None).unwrap();
+            let first = digest_dir(dir.path(), &DigestOptions::new(HashAlgo::Sha256)).unwrap();
             fs::write(&path, &b).unwrap();
-            let second = digest_dir(dir.path(), HashAlgo::Sha256, None).unwrap();
+            let second = digest_dir(dir.path(), &DigestOptions::new(HashAlgo::Sha256)).unwrap();
             prop_assert_ne!(first, second);
         }
 
@@ -368,8 +1329,8 @@ mod tests {
                 fs::write(dir_b.path().join(format!("file_{i}.txt")), bytes).unwrap();
             }
 
-            let first = digest_dir(dir_a.path(), HashAlgo::Blake3, None).unwrap();
-            let second = digest_dir(dir_b.path(), HashAlgo::Blake3, None).unwrap();
+            let first = digest_dir(dir_a.path(), &DigestOptions::new(HashAlgo::Blake3)).unwrap();
+            let second = digest_dir(dir_b.path(), &DigestOptions::new(HashAlgo::Blake3)).unwrap();
             prop_assert_eq!(first, second);
         }
     }
@@ -381,7 +1342,7 @@ mod tests {
             fs::write(dir.path().join(format!("file_{i}.txt")), "x").unwrap();
         }
         let start = Instant::now();
-        let _ = digest_dir(dir.path(), HashAlgo::Blake3, None).unwrap();
+        let _ = digest_dir(dir.path(), &DigestOptions::new(HashAlgo::Blake3)).unwrap();
         assert!(start.elapsed() < Duration::from_secs(5));
     }
 }
@@ -1,21 +1,110 @@
 use std::collections::BTreeMap;
+use std::fmt;
 use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
 
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use walkdir::WalkDir;
 
+use crate::config::{DoctorConfig, SkillsConfig};
 use crate::error::{AppError, AppResult};
-use crate::skill::validate_skill_id;
+use crate::skill::{not_found_hint, validate_skill_id_with};
 use crate::status::{RootEntryKind, root_entries};
 
-#[derive(Debug, Clone)]
+/// How serious a doctor issue is; ordered from least to most severe.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+    JsonSchema,
+    clap::ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        })
+    }
+}
+
+/// What a doctor issue is about; keys of `[doctor.severity]`.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    /// The skill directory itself, or an entry inside it, is a symlink.
+    Symlink,
+    InvalidName,
+    /// Another skill's name differs only by case.
+    CaseCollision,
+    MissingSkillFile,
+    SkillFileSymlink,
+    SkillFileNotRegular,
+    /// An entry that is neither a file nor a directory (socket, FIFO, ...).
+    UnsupportedFileType,
+    /// A name invalid on Windows or a path over `doctor.max_path_len`
+    /// (`doctor --portability`).
+    NotPortable,
+    /// A file starting with a byte-order mark or that is not valid UTF-8
+    /// (`doctor --check-encoding`).
+    BadEncoding,
+}
+
+impl IssueKind {
+    /// Severity used when `[doctor.severity]` does not list the kind.
+    pub fn default_severity(self) -> Severity {
+        match self {
+            IssueKind::Symlink
+            | IssueKind::SkillFileSymlink
+            | IssueKind::CaseCollision
+            | IssueKind::NotPortable
+            | IssueKind::BadEncoding => Severity::Warning,
+            IssueKind::InvalidName
+            | IssueKind::MissingSkillFile
+            | IssueKind::SkillFileNotRegular
+            | IssueKind::UnsupportedFileType => Severity::Error,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct DoctorIssue {
     pub skill: String,
+    pub kind: IssueKind,
+    pub severity: Severity,
     pub message: String,
 }
 
-#[derive(Debug, Clone)]
+impl DoctorIssue {
+    fn new(skill: &str, kind: IssueKind, message: String, rules: &DoctorConfig) -> Self {
+        Self {
+            skill: skill.to_string(),
+            kind,
+            severity: rules.severity_of(kind),
+            message,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct DoctorReport {
     pub root: PathBuf,
     pub skills: Vec<String>,
@@ -23,32 +112,103 @@ pub struct DoctorReport {
 }
 
 pub fn doctor_root(root: &Path) -> AppResult<DoctorReport> {
-    let entries = root_entries(root)?;
-    let skills = entries
+    doctor_root_with(root, &SkillsConfig::default(), &DoctorConfig::default())
+}
+
+/// Like [`doctor_root`], validating names against `naming` (`skills.*`) and
+/// using `doctor.skill_file` from `rules`.
+pub fn doctor_root_with(
+    root: &Path,
+    naming: &SkillsConfig,
+    rules: &DoctorConfig,
+) -> AppResult<DoctorReport> {
+    doctor_entries(root, None, naming, rules)
+}
+
+/// Like [`doctor_root_with`], but checks only `skill`; errors if it is not under `root`.
+pub fn doctor_skill(
+    root: &Path,
+    skill: &str,
+    naming: &SkillsConfig,
+    rules: &DoctorConfig,
+) -> AppResult<DoctorReport> {
+    validate_skill_id_with(skill, naming)?;
+    doctor_entries(root, Some(skill), naming, rules)
+}
+
+fn doctor_entries(
+    root: &Path,
+    only: Option<&str>,
+    naming: &SkillsConfig,
+    rules: &DoctorConfig,
+) -> AppResult<DoctorReport> {
+    let entries = root_entries(root)?
+        .into_iter()
+        .filter(|entry| entry.kind != RootEntryKind::File)
+        .collect::<Vec<_>>();
+    let all_skills = entries
         .iter()
         .map(|entry| entry.name.clone())
         .collect::<Vec<_>>();
     let mut issues = Vec::new();
+    check_case_collisions(&all_skills, rules, &mut issues);
+    let entries = match only {
+        Some(skill) => {
+            let selected = entries
+                .into_iter()
+                .filter(|entry| entry.name == skill)
+                .collect::<Vec<_>>();
+            if selected.is_empty() {
+                return Err(AppError::exec(
+                    crate::tr!(
+                        "skill が存在しません: {}",
+                        "Skill does not exist: {}",
+                        skill
+                    ),
+                    Some(not_found_hint(
+                        skill,
+                        all_skills.iter().map(String::as_str),
+                        crate::tr!(
+                            "{} 直下の skill 名を確認してください",
+                            "Check skill names under {}.",
+                            root.display()
+                        ),
+                    )),
+                ));
+            }
+            issues.retain(|issue| issue.skill == skill);
+            selected
+        }
+        None => entries,
+    };
+    let skills = entries
+        .iter()
+        .map(|entry| entry.name.clone())
+        .collect::<Vec<_>>();
     for entry in entries {
         let skill = entry.name;
         if entry.kind == RootEntryKind::Symlink {
-            issues.push(DoctorIssue {
-                skill,
-                message: crate::tr!(
+            issues.push(DoctorIssue::new(
+                &skill,
+                IssueKind::Symlink,
+                crate::tr!(
                     "シンボリックリンクは未対応です",
                     "Symlinks are not supported"
                 ),
-            });
+                rules,
+            ));
             continue;
         }
-        if let Err(err) = validate_skill_id(&skill) {
-            issues.push(DoctorIssue {
-                skill: skill.clone(),
-                message: err.to_string(),
-            });
+        if let Err(err) = validate_skill_id_with(&skill, naming) {
+            issues.push(DoctorIssue::new(
+                &skill,
+                IssueKind::InvalidName,
+                err.to_string(),
+                rules,
+            ));
         }
-        check_skill_md(&entry.path, &skill, &mut issues)?;
-        check_skill_contents(&entry.path, &skill, &mut issues)?;
+        check_skill_md(&entry.path, &skill, rules, &mut issues)?;
+        check_skill_contents(&entry.path, &skill, rules, &mut issues)?;
     }
     Ok(DoctorReport {
         root: root.to_path_buf(),
@@ -57,42 +217,84 @@ pub fn doctor_root(root: &Path) -> AppResult<DoctorReport> {
     })
 }
 
-fn check_skill_md(skill_root: &Path, skill: &str, issues: &mut Vec<DoctorIssue>) -> AppResult<()> {
-    let skill_md = skill_root.join("SKILL.md");
+/// Flags skills whose names differ only by case; they collide on case-insensitive
+/// filesystems and under `skills.case_insensitive`.
+fn check_case_collisions(skills: &[String], rules: &DoctorConfig, issues: &mut Vec<DoctorIssue>) {
+    let mut by_key: BTreeMap<String, Vec<&str>> = BTreeMap::new();
+    for skill in skills {
+        by_key.entry(skill.to_lowercase()).or_default().push(skill);
+    }
+    for names in by_key.values().filter(|names| names.len() > 1) {
+        for skill in names {
+            let others = names
+                .iter()
+                .filter(|other| *other != skill)
+                .copied()
+                .collect::<Vec<_>>()
+                .join(", ");
+            issues.push(DoctorIssue::new(
+                skill,
+                IssueKind::CaseCollision,
+                crate::tr!(
+                    "大文字小文字のみ異なる skill があります: {}",
+                    "Differs only by case from: {}",
+                    others
+                ),
+                rules,
+            ));
+        }
+    }
+}
+
+fn check_skill_md(
+    skill_root: &Path,
+    skill: &str,
+    rules: &DoctorConfig,
+    issues: &mut Vec<DoctorIssue>,
+) -> AppResult<()> {
+    let skill_file = rules.skill_file.as_str();
+    let skill_md = skill_root.join(skill_file);
     match fs::symlink_metadata(&skill_md) {
         Ok(meta) => {
             if meta.file_type().is_symlink() {
-                issues.push(DoctorIssue {
-                    skill: skill.to_string(),
-                    message: crate::tr!(
-                        "SKILL.md がシンボリックリンクです",
-                        "SKILL.md is a symlink"
-                    ),
-                });
+                issues.push(DoctorIssue::new(
+                    skill,
+                    IssueKind::SkillFileSymlink,
+                    crate::tr!("{} がシンボリックリンクです", "{} is a symlink", skill_file),
+                    rules,
+                ));
             } else if !meta.is_file() {
-                issues.push(DoctorIssue {
-                    skill: skill.to_string(),
-                    message: crate::tr!(
-                        "SKILL.md が通常ファイルではありません",
-                        "SKILL.md is not a regular file"
+                issues.push(DoctorIssue::new(
+                    skill,
+                    IssueKind::SkillFileNotRegular,
+                    crate::tr!(
+                        "{} が通常ファイルではありません",
+                        "{} is not a regular file",
+                        skill_file
                     ),
-                });
+                    rules,
+                ));
             }
         }
+        // Exempt skills may lack the file, but one that exists is still checked.
+        Err(err) if err.kind() == io::ErrorKind::NotFound && rules.is_exempt(skill) => {}
         Err(err) if err.kind() == io::ErrorKind::NotFound => {
-            issues.push(DoctorIssue {
-                skill: skill.to_string(),
-                message: crate::tr!("SKILL.md が見つかりません", "SKILL.md is missing"),
-            });
+            issues.push(DoctorIssue::new(
+                skill,
+                IssueKind::MissingSkillFile,
+                crate::tr!("{} が見つかりません", "{} is missing", skill_file),
+                rules,
+            ));
         }
         Err(err) => {
-            return Err(AppError::exec(
+            return Err(AppError::io(
                 crate::tr!(
-                    "SKILL.md の確認に失敗しました: {}",
-                    "Failed to inspect SKILL.md: {}",
+                    "{} の確認に失敗しました: {}",
+                    "Failed to inspect {}: {}",
+                    skill_file,
                     skill_md.display()
                 ),
-                Some(err.to_string()),
+                err,
             ));
         }
     }
@@ -102,59 +304,237 @@ fn check_skill_md(skill_root: &Path, skill: &str, issues: &mut Vec<DoctorIssue>)
 fn check_skill_contents(
     skill_root: &Path,
     skill: &str,
+    rules: &DoctorConfig,
     issues: &mut Vec<DoctorIssue>,
 ) -> AppResult<()> {
     for entry in WalkDir::new(skill_root).follow_links(false) {
         let entry = entry.map_err(|err| {
-            AppError::exec(
+            AppError::io(
                 crate::tr!(
                     "skill の走査に失敗しました: {}",
                     "Failed to scan skill: {}",
                     skill_root.display()
                 ),
-                Some(err.to_string()),
+                err.into(),
             )
         })?;
         let rel = entry.path().strip_prefix(skill_root).map_err(|err| {
-            AppError::exec(
+            AppError::io(
                 crate::tr!(
                     "相対パスの取得に失敗しました: {}",
                     "Failed to get relative path: {}",
                     entry.path().display()
                 ),
-                Some(err.to_string()),
+                io::Error::other(err),
             )
         })?;
+        if rules.portability {
+            check_portable(skill, rel, rules, issues);
+        }
         if rel.as_os_str().is_empty() {
             continue;
         }
-        if rel == Path::new("SKILL.md") {
+        if rules.check_encoding && entry.file_type().is_file() {
+            check_encoding(skill, entry.path(), rel, rules, issues)?;
+        }
+        if rel == Path::new(&rules.skill_file) {
             continue;
         }
         let file_type = entry.file_type();
         if file_type.is_symlink() {
-            issues.push(DoctorIssue {
-                skill: skill.to_string(),
-                message: crate::tr!(
+            issues.push(DoctorIssue::new(
+                skill,
+                IssueKind::Symlink,
+                crate::tr!(
                     "シンボリックリンクは未対応です: {}",
                     "Symlinks are not supported: {}",
                     rel.display()
                 ),
-            });
+                rules,
+            ));
         } else if !file_type.is_dir() && !file_type.is_file() {
-            issues.push(DoctorIssue {
-                skill: skill.to_string(),
-                message: crate::tr!(
+            issues.push(DoctorIssue::new(
+                skill,
+                IssueKind::UnsupportedFileType,
+                crate::tr!(
                     "未対応のファイル種別です: {}",
                     "Unsupported file type: {}",
                     rel.display()
                 ),
-            });
+                rules,
+            ));
         }
     }
     Ok(())
 }
 
+/// Device names Windows reserves in every directory, with or without an
+/// extension.
+const WINDOWS_RESERVED: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Flags the last component of `rel` (the skill name itself when `rel` is
+/// empty) if Windows cannot create it, and `<skill>/<rel>` if it is longer
+/// than `doctor.max_path_len`.
+fn check_portable(skill: &str, rel: &Path, rules: &DoctorConfig, issues: &mut Vec<DoctorIssue>) {
+    let shown = Path::new(skill).join(rel);
+    let name = match rel.file_name() {
+        Some(name) => name.to_string_lossy(),
+        None => skill.into(),
+    };
+    if let Some(reason) = windows_name_problem(&name) {
+        issues.push(DoctorIssue::new(
+            skill,
+            IssueKind::NotPortable,
+            crate::tr!(
+                "Windows で使えない名前です ({}): {}",
+                "Name is invalid on Windows ({}): {}",
+                reason,
+                shown.display()
+            ),
+            rules,
+        ));
+    }
+    let len = shown.to_string_lossy().encode_utf16().count();
+    if len > rules.max_path_len {
+        issues.push(DoctorIssue::new(
+            skill,
+            IssueKind::NotPortable,
+            crate::tr!(
+                "パスが長すぎます ({} > {}): {}",
+                "Path is too long ({} > {}): {}",
+                len,
+                rules.max_path_len,
+                shown.display()
+            ),
+            rules,
+        ));
+    }
+}
+
+/// Byte-order marks, longest first so UTF-32 LE is not taken for UTF-16 LE.
+const BOMS: &[(&[u8], &str)] = &[
+    (&[0x00, 0x00, 0xFE, 0xFF], "UTF-32 BE"),
+    (&[0xFF, 0xFE, 0x00, 0x00], "UTF-32 LE"),
+    (&[0xEF, 0xBB, 0xBF], "UTF-8"),
+    (&[0xFE, 0xFF], "UTF-16 BE"),
+    (&[0xFF, 0xFE], "UTF-16 LE"),
+];
+
+/// Flags `rel` if it starts with a byte-order mark or is not valid UTF-8.
+/// Files over `doctor.encoding_max_bytes` are skipped as likely binaries.
+fn check_encoding(
+    skill: &str,
+    path: &Path,
+    rel: &Path,
+    rules: &DoctorConfig,
+    issues: &mut Vec<DoctorIssue>,
+) -> AppResult<()> {
+    let read_error = |err: io::Error| {
+        AppError::io(
+            crate::tr!(
+                "ファイルの読み込みに失敗しました: {}",
+                "Failed to read file: {}",
+                path.display()
+            ),
+            err,
+        )
+    };
+    if fs::metadata(path).map_err(read_error)?.len() > rules.encoding_max_bytes {
+        return Ok(());
+    }
+    let bytes = fs::read(path).map_err(read_error)?;
+    if let Some(reason) = encoding_problem(&bytes) {
+        issues.push(DoctorIssue::new(
+            skill,
+            IssueKind::BadEncoding,
+            crate::tr!(
+                "エンコーディングの問題があります ({}): {}",
+                "Encoding problem ({}): {}",
+                reason,
+                rel.display()
+            ),
+            rules,
+        ));
+    }
+    Ok(())
+}
+
+/// What is wrong with the encoding of `bytes`, if anything.
+fn encoding_problem(bytes: &[u8]) -> Option<String> {
+    if let Some((_, name)) = BOMS.iter().find(|(bom, _)| bytes.starts_with(bom)) {
+        return Some(crate::tr!("{} の BOM", "{} byte-order mark", name));
+    }
+    std::str::from_utf8(bytes).err().map(|err| {
+        crate::tr!(
+            "{} バイト目が UTF-8 として不正",
+            "invalid UTF-8 at byte {}",
+            err.valid_up_to()
+        )
+    })
+}
+
+/// Why Windows rejects `name` as a file or directory name, if it does.
+fn windows_name_problem(name: &str) -> Option<String> {
+    if let Some(c) = name
+        .chars()
+        .find(|c| c.is_control() || r#"<>:"/\|?*"#.contains(*c))
+    {
+        return Some(crate::tr!(
+            "使用できない文字 {:?}",
+            "illegal character {:?}",
+            c
+        ));
+    }
+    if name.ends_with('.') || name.ends_with(' ') {
+        return Some(crate::tr!("末尾がドットか空白", "trailing dot or space"));
+    }
+    let stem = name.split('.').next().unwrap_or(name).trim_end();
+    if WINDOWS_RESERVED
+        .iter()
+        .any(|reserved| stem.eq_ignore_ascii_case(reserved))
+    {
+        return Some(crate::tr!("予約名 {}", "reserved name {}", stem));
+    }
+    None
+}
+
+/// Issues of `reports` at or above `threshold` (`doctor --fail-on`).
+pub fn count_issues_at_least(reports: &[&DoctorReport], threshold: Severity) -> usize {
+    reports
+        .iter()
+        .flat_map(|report| &report.issues)
+        .filter(|issue| issue.severity >= threshold)
+        .count()
+}
+
+#[derive(Serialize)]
+struct ScopedReport<'a> {
+    scope: &'a str,
+    #[serde(flatten)]
+    report: &'a DoctorReport,
+}
+
+/// Renders `(scope, report)` pairs as a pretty-printed JSON array for
+/// `doctor --format json`.
+pub fn render_doctor_json(reports: &[(&str, DoctorReport)]) -> AppResult<String> {
+    let reports = reports
+        .iter()
+        .map(|(scope, report)| ScopedReport { scope, report })
+        .collect::<Vec<_>>();
+    serde_json::to_string_pretty(&reports).map_err(|err| {
+        AppError::io(
+            crate::tr!(
+                "doctor の JSON 出力に失敗しました",
+                "Failed to render doctor report as JSON"
+            ),
+            err.into(),
+        )
+    })
+}
+
 pub fn group_issues_by_skill(issues: &[DoctorIssue]) -> BTreeMap<&str, Vec<&DoctorIssue>> {
     let mut map: BTreeMap<&str, Vec<&DoctorIssue>> = BTreeMap::new();
     for issue in issues {
@@ -183,6 +563,162 @@ mod tests {
         assert!(report.issues[0].message.contains("SKILL.md"));
     }
 
+    #[test]
+    fn doctor_skill_reports_only_named_skill() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("skill1")).unwrap();
+        fs::create_dir_all(root.join("skill2")).unwrap();
+
+        let rules = DoctorConfig::default();
+        let report = doctor_skill(root, "skill2", &SkillsConfig::default(), &rules).unwrap();
+        assert_eq!(report.skills, vec!["skill2".to_string()]);
+        assert_eq!(report.issues.len(), 1);
+        assert_eq!(report.issues[0].skill, "skill2");
+
+        let err = doctor_skill(root, "skill3", &SkillsConfig::default(), &rules).unwrap_err();
+        assert!(matches!(err, AppError::Exec { .. }));
+        assert!(doctor_skill(root, "../skill1", &SkillsConfig::default(), &rules).is_err());
+    }
+
+    #[test]
+    fn doctor_validates_names_against_skill_rules() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        for skill in [".hidden", "a+b"] {
+            fs::create_dir_all(root.join(skill)).unwrap();
+            fs::write(root.join(skill).join("SKILL.md"), "ok").unwrap();
+        }
+        let rules = DoctorConfig::default();
+
+        let report = doctor_root_with(root, &SkillsConfig::default(), &rules).unwrap();
+        assert_eq!(
+            report
+                .issues
+                .iter()
+                .filter(|issue| issue.kind == IssueKind::InvalidName)
+                .count(),
+            2
+        );
+
+        let naming = SkillsConfig {
+            allow_hidden: true,
+            allowed_chars: "-_.+".to_string(),
+            ..SkillsConfig::default()
+        };
+        let report = doctor_root_with(root, &naming, &rules).unwrap();
+        assert!(report.issues.is_empty());
+        let report = doctor_skill(root, ".hidden", &naming, &rules).unwrap();
+        assert_eq!(report.skills, vec![".hidden".to_string()]);
+        assert!(report.issues.is_empty());
+    }
+
+    #[test]
+    fn doctor_uses_configured_skill_file() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("skill1")).unwrap();
+        fs::write(root.join("skill1/SKILL.md"), "ok").unwrap();
+        let rules = DoctorConfig {
+            skill_file: "README.md".to_string(),
+            ..DoctorConfig::default()
+        };
+
+        let report = doctor_root_with(root, &SkillsConfig::default(), &rules).unwrap();
+        assert_eq!(report.issues.len(), 1);
+        assert!(report.issues[0].message.contains("README.md"));
+
+        fs::write(root.join("skill1/README.md"), "ok").unwrap();
+        let report = doctor_root_with(root, &SkillsConfig::default(), &rules).unwrap();
+        assert_eq!(report.issues.len(), 0);
+    }
+
+    #[test]
+    fn doctor_exempt_skills_may_lack_skill_file() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("data_only")).unwrap();
+        fs::write(root.join("data_only/table.csv"), "a,b").unwrap();
+        fs::create_dir_all(root.join("needs_doc")).unwrap();
+        fs::write(root.join("needs_doc/notes.txt"), "x").unwrap();
+        fs::create_dir_all(root.join("odd_doc/SKILL.md")).unwrap();
+        fs::write(root.join("odd_doc/SKILL.md/inner.txt"), "x").unwrap();
+        let rules = DoctorConfig {
+            exempt: vec!["data_only".to_string(), "odd_doc".to_string()],
+            ..DoctorConfig::default()
+        };
+
+        let report = doctor_root_with(root, &SkillsConfig::default(), &rules).unwrap();
+        let kinds = report
+            .issues
+            .iter()
+            .map(|issue| (issue.skill.as_str(), issue.kind))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [
+                ("needs_doc", IssueKind::MissingSkillFile),
+                ("odd_doc", IssueKind::SkillFileNotRegular),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn doctor_assigns_default_and_configured_severities() {
+        use std::os::unix::fs::symlink;
+
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("missing")).unwrap();
+        fs::create_dir_all(root.join("linked")).unwrap();
+        fs::write(root.join("linked/SKILL.md"), "ok").unwrap();
+        symlink(root.join("linked/SKILL.md"), root.join("linked/alias.md")).unwrap();
+
+        let severities = |report: &DoctorReport| {
+            report
+                .issues
+                .iter()
+                .map(|issue| (issue.skill.clone(), issue.kind, issue.severity))
+                .collect::<Vec<_>>()
+        };
+        let report = doctor_root(root).unwrap();
+        assert_eq!(
+            severities(&report),
+            vec![
+                ("linked".to_string(), IssueKind::Symlink, Severity::Warning),
+                (
+                    "missing".to_string(),
+                    IssueKind::MissingSkillFile,
+                    Severity::Error
+                ),
+            ]
+        );
+        assert_eq!(count_issues_at_least(&[&report], Severity::Error), 1);
+        assert_eq!(count_issues_at_least(&[&report], Severity::Warning), 2);
+
+        let rules = DoctorConfig {
+            severity: BTreeMap::from([
+                (IssueKind::Symlink, Severity::Error),
+                (IssueKind::MissingSkillFile, Severity::Info),
+            ]),
+            ..DoctorConfig::default()
+        };
+        let report = doctor_root_with(root, &SkillsConfig::default(), &rules).unwrap();
+        assert_eq!(
+            severities(&report),
+            vec![
+                ("linked".to_string(), IssueKind::Symlink, Severity::Error),
+                (
+                    "missing".to_string(),
+                    IssueKind::MissingSkillFile,
+                    Severity::Info
+                ),
+            ]
+        );
+        assert_eq!(count_issues_at_least(&[&report], Severity::Warning), 1);
+    }
+
     #[test]
     fn doctor_ok_when_skill_md_present() {
         let dir = TempDir::new().unwrap();
@@ -194,6 +730,28 @@ mod tests {
         assert_eq!(report.issues.len(), 0);
     }
 
+    #[test]
+    fn doctor_reports_case_collisions() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("MySkill")).unwrap();
+        fs::write(root.join("MySkill/SKILL.md"), "ok").unwrap();
+        if root.join("myskill").exists() {
+            // Case-insensitive filesystem: the collision cannot be created.
+            return;
+        }
+        fs::create_dir_all(root.join("myskill")).unwrap();
+        fs::write(root.join("myskill/SKILL.md"), "ok").unwrap();
+
+        let report = doctor_root(root).unwrap();
+        let flagged = report
+            .issues
+            .iter()
+            .map(|issue| issue.skill.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(flagged, vec!["MySkill", "myskill"]);
+    }
+
     #[cfg(unix)]
     #[test]
     fn doctor_reports_root_symlink_skill_instead_of_aborting() {
@@ -256,4 +814,109 @@ mod tests {
         let message = &report.issues[0].message;
         assert!(message.contains("未対応") || message.contains("Unsupported"));
     }
+
+    // `:` cannot appear in a Windows file name, so this only runs elsewhere.
+    #[cfg(unix)]
+    #[test]
+    fn doctor_portability_flags_windows_invalid_names() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("skill1/docs")).unwrap();
+        fs::write(root.join("skill1/SKILL.md"), "ok").unwrap();
+        fs::write(root.join("skill1/aux.txt"), "x").unwrap();
+        fs::write(root.join("skill1/docs/a:b.md"), "x").unwrap();
+        fs::write(root.join("skill1/notes.txt"), "x").unwrap();
+
+        assert!(doctor_root(root).unwrap().issues.is_empty());
+
+        let rules = DoctorConfig {
+            portability: true,
+            ..DoctorConfig::default()
+        };
+        let report = doctor_root_with(root, &SkillsConfig::default(), &rules).unwrap();
+        let issues = report
+            .issues
+            .iter()
+            .map(|issue| (issue.kind, issue.severity, issue.message.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(issues.len(), 2, "{issues:?}");
+        assert!(issues.iter().all(|(kind, severity, _)| {
+            *kind == IssueKind::NotPortable && *severity == Severity::Warning
+        }));
+        assert!(
+            issues
+                .iter()
+                .any(|(_, _, message)| message.contains("aux.txt")
+                    && (message.contains("AUX") || message.contains("aux")))
+        );
+        assert!(
+            issues
+                .iter()
+                .any(|(_, _, message)| message.contains("docs/a:b.md"))
+        );
+    }
+
+    #[test]
+    fn doctor_portability_flags_paths_over_max_len() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("skill1")).unwrap();
+        fs::write(root.join("skill1/SKILL.md"), "ok").unwrap();
+        fs::write(root.join("skill1/long-file-name.txt"), "x").unwrap();
+        let rules = DoctorConfig {
+            portability: true,
+            max_path_len: "skill1/SKILL.md".len(),
+            ..DoctorConfig::default()
+        };
+
+        let report = doctor_root_with(root, &SkillsConfig::default(), &rules).unwrap();
+        assert_eq!(report.issues.len(), 1);
+        assert_eq!(report.issues[0].kind, IssueKind::NotPortable);
+        assert!(report.issues[0].message.contains("long-file-name.txt"));
+    }
+
+    #[test]
+    fn doctor_check_encoding_flags_bom_but_not_valid_utf8() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("skill1")).unwrap();
+        fs::write(
+            root.join("skill1/SKILL.md"),
+            "\u{feff}---\nname: skill1\n---\n",
+        )
+        .unwrap();
+        fs::write(root.join("skill1/notes.md"), "日本語のメモ\n").unwrap();
+        fs::write(root.join("skill1/big.bin"), [0xFFu8; 64]).unwrap();
+
+        assert!(doctor_root(root).unwrap().issues.is_empty());
+
+        let rules = DoctorConfig {
+            check_encoding: true,
+            encoding_max_bytes: 32,
+            ..DoctorConfig::default()
+        };
+        let report = doctor_root_with(root, &SkillsConfig::default(), &rules).unwrap();
+        assert_eq!(report.issues.len(), 1, "{:?}", report.issues);
+        let issue = &report.issues[0];
+        assert_eq!(issue.kind, IssueKind::BadEncoding);
+        assert_eq!(issue.severity, Severity::Warning);
+        assert!(issue.message.contains("SKILL.md"));
+        assert!(issue.message.contains("UTF-8"));
+    }
+
+    #[test]
+    fn encoding_problem_detects_utf16_and_invalid_utf8() {
+        assert!(encoding_problem("plain ascii\n".as_bytes()).is_none());
+        assert!(
+            encoding_problem(&[0xFF, 0xFE, b'a', 0x00])
+                .unwrap()
+                .contains("UTF-16 LE")
+        );
+        assert!(
+            encoding_problem(&[0xFF, 0xFE, 0x00, 0x00])
+                .unwrap()
+                .contains("UTF-32 LE")
+        );
+        assert!(encoding_problem(b"ok\xC3(").unwrap().contains('2'));
+    }
 }
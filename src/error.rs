@@ -1,3 +1,5 @@
+use std::error::Error as StdError;
+use std::io;
 use std::process::ExitCode;
 
 #[derive(Debug, thiserror::Error)]
@@ -6,21 +8,55 @@ pub enum AppError {
     Config {
         message: String,
         hint: Option<String>,
+        #[source]
+        source: Option<BoxError>,
     },
     #[error("{message}")]
     Exec {
         message: String,
         hint: Option<String>,
+        #[source]
+        source: Option<BoxError>,
+    },
+    /// A check found the target out of sync (`push --check`); nothing failed.
+    #[error("{message}")]
+    Drift {
+        message: String,
+        hint: Option<String>,
+    },
+    /// A filesystem operation failed; `context` says which one.
+    #[error("{context}: {source}")]
+    Io {
+        context: String,
+        #[source]
+        source: io::Error,
     },
 }
 
 pub type AppResult<T> = Result<T, AppError>;
 
+/// Underlying cause kept on `Config` / `Exec` for `Error::source`.
+pub type BoxError = Box<dyn StdError + Send + Sync + 'static>;
+
 impl AppError {
     pub fn config(message: impl Into<String>, hint: Option<String>) -> Self {
         Self::Config {
             message: message.into(),
             hint,
+            source: None,
+        }
+    }
+
+    /// Like [`AppError::config`], keeping `source` as the error's cause.
+    pub fn config_with_source(
+        message: impl Into<String>,
+        hint: Option<String>,
+        source: impl Into<BoxError>,
+    ) -> Self {
+        Self::Config {
+            message: message.into(),
+            hint,
+            source: Some(source.into()),
         }
     }
 
@@ -28,19 +64,113 @@ impl AppError {
         Self::Exec {
             message: message.into(),
             hint,
+            source: None,
+        }
+    }
+
+    /// Like [`AppError::exec`], keeping `source` as the error's cause.
+    pub fn exec_with_source(
+        message: impl Into<String>,
+        hint: Option<String>,
+        source: impl Into<BoxError>,
+    ) -> Self {
+        Self::Exec {
+            message: message.into(),
+            hint,
+            source: Some(source.into()),
+        }
+    }
+
+    pub fn drift(message: impl Into<String>, hint: Option<String>) -> Self {
+        Self::Drift {
+            message: message.into(),
+            hint,
+        }
+    }
+
+    pub fn io(context: impl Into<String>, source: io::Error) -> Self {
+        Self::Io {
+            context: context.into(),
+            source,
+        }
+    }
+
+    /// Prefixes the message with `context`, keeping the variant and hint.
+    pub fn with_context(self, context: impl std::fmt::Display) -> Self {
+        match self {
+            Self::Config {
+                message,
+                hint,
+                source,
+            } => Self::Config {
+                message: format!("{context}: {message}"),
+                hint,
+                source,
+            },
+            Self::Exec {
+                message,
+                hint,
+                source,
+            } => Self::Exec {
+                message: format!("{context}: {message}"),
+                hint,
+                source,
+            },
+            Self::Drift { message, hint } => Self::Drift {
+                message: format!("{context}: {message}"),
+                hint,
+            },
+            Self::Io {
+                context: inner,
+                source,
+            } => Self::Io {
+                context: format!("{context}: {inner}"),
+                source,
+            },
         }
     }
 
     pub fn hint(&self) -> Option<&str> {
         match self {
-            Self::Config { hint, .. } | Self::Exec { hint, .. } => hint.as_deref(),
+            Self::Config { hint, .. } | Self::Exec { hint, .. } | Self::Drift { hint, .. } => {
+                hint.as_deref()
+            }
+            Self::Io { .. } => None,
         }
     }
 
     pub fn exit_code(&self) -> ExitCode {
+        ExitCode::from(self.exit_status())
+    }
+
+    /// Numeric form of [`AppError::exit_code`].
+    pub fn exit_status(&self) -> u8 {
         match self {
-            Self::Config { .. } => ExitCode::from(3),
-            Self::Exec { .. } => ExitCode::from(4),
+            Self::Drift { .. } => 1,
+            Self::Config { .. } => 3,
+            Self::Exec { .. } => 4,
+            Self::Io { .. } => 5,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn source_returns_wrapped_io_error() {
+        let io_err = io::Error::new(io::ErrorKind::PermissionDenied, "denied");
+        let err = AppError::exec_with_source("copy failed", Some(io_err.to_string()), io_err)
+            .with_context("push");
+
+        assert_eq!(err.to_string(), "push: copy failed");
+        assert_eq!(err.hint(), Some("denied"));
+        let source = err.source().unwrap();
+        let io_source = source.downcast_ref::<io::Error>().unwrap();
+        assert_eq!(io_source.kind(), io::ErrorKind::PermissionDenied);
+
+        let plain = AppError::config("bad config", None);
+        assert!(plain.source().is_none());
+    }
+}
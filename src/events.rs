@@ -0,0 +1,263 @@
+use std::io::Write;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::config::DigestEncoding;
+use crate::digest::{FileChange, encode_digest};
+use crate::doctor::DoctorIssue;
+use crate::error::{AppError, AppResult};
+use crate::status::{StatusRow, StatusSink};
+use crate::sync::PlanOp;
+use crate::targets::TargetSummary;
+
+/// Output format selected with the global `--format`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable output.
+    #[default]
+    #[value(alias = "table")]
+    Text,
+    /// A single JSON document (`targets` and `doctor` only).
+    Json,
+    /// One JSON [`Event`] per line, written as things happen.
+    Ndjson,
+    /// The `status` table with single-space columns (`status` only).
+    Plain,
+    /// The `status` table as RFC 4180 CSV with full digests (`status --target` only).
+    Csv,
+}
+
+/// One line of `--format ndjson` output, tagged by its `event` field.
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event<'a> {
+    Version {
+        version: &'a str,
+        /// Only with `--verbose`, like the other build fields.
+        commit: Option<&'a str>,
+        rustc: Option<&'a str>,
+        features: Option<&'a [&'a str]>,
+    },
+    Target(&'a TargetSummary),
+    /// A skill listed by `list`; `digest` is set with `--digests`.
+    Skill {
+        skill: &'a str,
+        digest: Option<&'a str>,
+    },
+    /// A skill version rewritten (or, with `dry_run`, computed) by `bump`.
+    Bump {
+        skill: &'a str,
+        previous: &'a str,
+        version: &'a str,
+        dry_run: bool,
+    },
+    Status {
+        target: &'a str,
+        skill: &'a str,
+        state: String,
+        global_digest: Option<&'a str>,
+        target_digest: Option<&'a str>,
+        renamed_to: Option<&'a str>,
+        #[serde(skip_serializing_if = "<[_]>::is_empty")]
+        file_changes: &'a [FileChange],
+    },
+    /// One skill of `status --compare`.
+    Compare {
+        left: &'a str,
+        right: &'a str,
+        skill: &'a str,
+        state: String,
+        left_digest: Option<&'a str>,
+        right_digest: Option<&'a str>,
+    },
+    /// One global skill of `status --since-sync`.
+    SinceSync {
+        target: &'a str,
+        skill: &'a str,
+        state: &'a str,
+        digest: &'a str,
+        synced_digest: Option<&'a str>,
+    },
+    Count {
+        count: usize,
+    },
+    /// One checked skill of `doctor`; `issues` is empty when it is ok.
+    Doctor {
+        scope: &'a str,
+        skill: &'a str,
+        issues: Vec<&'a DoctorIssue>,
+    },
+    DoctorSummary {
+        scope: &'a str,
+        checked: usize,
+        issues: usize,
+    },
+    PlanOp {
+        kind: &'a str,
+        skill: &'a str,
+        src: Option<&'a Path>,
+        dest: Option<&'a Path>,
+        note: Option<&'a str>,
+    },
+    PlanHash {
+        hash: &'a str,
+    },
+    /// A file a planned prune would delete (`push --list-files`).
+    PruneFile {
+        skill: &'a str,
+        path: &'a str,
+    },
+    /// A planned op other than skip finished applying.
+    OpDone {
+        kind: &'a str,
+        skill: &'a str,
+    },
+    /// The substituted argv of `diff --dry-run`.
+    DiffCommand {
+        argv: &'a [String],
+    },
+    /// The diff command exited; `differs` is its exit code 1. Also sent
+    /// with `differs: false` when `--summary-only` skips the command.
+    Diff {
+        skill: &'a str,
+        differs: bool,
+    },
+    Export {
+        skill: &'a str,
+        output: &'a Path,
+    },
+    /// The run failed; always the last event.
+    Error {
+        message: String,
+        hint: Option<String>,
+        exit_code: u8,
+    },
+}
+
+impl<'a> Event<'a> {
+    pub fn plan_op(op: &'a PlanOp) -> Self {
+        Event::PlanOp {
+            kind: op.kind.label(),
+            skill: &op.skill,
+            src: op.src.as_deref(),
+            dest: op.dest.as_deref(),
+            note: op.note.as_deref(),
+        }
+    }
+
+    pub fn op_done(op: &'a PlanOp) -> Self {
+        Event::OpDone {
+            kind: op.kind.label(),
+            skill: &op.skill,
+        }
+    }
+
+    pub fn error(err: &AppError) -> Self {
+        Event::Error {
+            message: err.to_string(),
+            hint: err.hint().map(str::to_string),
+            exit_code: err.exit_status(),
+        }
+    }
+}
+
+/// Writes `event` as one JSON line and flushes, so readers see it at once.
+pub fn write_event(out: &mut dyn Write, event: &Event<'_>) -> AppResult<()> {
+    let write_error = |err: std::io::Error| {
+        AppError::io(
+            crate::tr!("イベントを書き込めません", "Cannot write event"),
+            err,
+        )
+    };
+    let mut line = serde_json::to_vec(event).map_err(|err| write_error(err.into()))?;
+    line.push(b'\n');
+    out.write_all(&line).map_err(write_error)?;
+    out.flush().map_err(write_error)
+}
+
+/// Streams `status` rows of one target as [`Event::Status`] lines.
+pub struct StatusEventWriter<'a, W: Write> {
+    target: &'a str,
+    out: W,
+    digest_encoding: DigestEncoding,
+}
+
+impl<'a, W: Write> StatusEventWriter<'a, W> {
+    pub fn new(target: &'a str, out: W) -> Self {
+        Self {
+            target,
+            out,
+            digest_encoding: DigestEncoding::Hex,
+        }
+    }
+
+    /// Writes digests in `encoding` (`display.digest_encoding`).
+    pub fn with_digest_encoding(mut self, encoding: DigestEncoding) -> Self {
+        self.digest_encoding = encoding;
+        self
+    }
+}
+
+impl<W: Write> StatusSink for StatusEventWriter<'_, W> {
+    fn row(&mut self, row: StatusRow) -> AppResult<()> {
+        let encode =
+            |digest: Option<&str>| digest.map(|digest| encode_digest(digest, self.digest_encoding));
+        let global_digest = encode(row.global_digest.as_deref());
+        let target_digest = encode(row.target_digest.as_deref());
+        write_event(
+            &mut self.out,
+            &Event::Status {
+                target: self.target,
+                skill: &row.skill,
+                state: row.state.to_string(),
+                global_digest: global_digest.as_deref(),
+                target_digest: target_digest.as_deref(),
+                renamed_to: row.renamed_to.as_deref(),
+                file_changes: &row.file_changes,
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::sync::PlanKind;
+
+    #[test]
+    fn events_are_tagged_single_lines() {
+        let op = PlanOp {
+            kind: PlanKind::Install,
+            skill: "a".to_string(),
+            src: Some(PathBuf::from("/g/a")),
+            dest: Some(PathBuf::from("/t/a")),
+            note: None,
+        };
+        let mut out = Vec::new();
+        write_event(&mut out, &Event::plan_op(&op)).unwrap();
+        write_event(&mut out, &Event::op_done(&op)).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            concat!(
+                r#"{"event":"plan_op","kind":"install","skill":"a","src":"/g/a","dest":"/t/a","note":null}"#,
+                "\n",
+                r#"{"event":"op_done","kind":"install","skill":"a"}"#,
+                "\n",
+            )
+        );
+    }
+
+    #[test]
+    fn error_event_carries_hint_and_exit_code() {
+        let err = AppError::config("bad", Some("fix it".to_string()));
+        let mut out = Vec::new();
+        write_event(&mut out, &Event::error(&err)).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "{\"event\":\"error\",\"message\":\"bad\",\"hint\":\"fix it\",\"exit_code\":3}\n"
+        );
+    }
+}
@@ -0,0 +1,289 @@
+use std::fs;
+use std::path::Path;
+
+use flate2::Compression;
+use flate2::GzBuilder;
+use tempfile::NamedTempFile;
+
+use crate::config::SkillsConfig;
+use crate::digest::{IgnoreSet, collect_files, file_mode};
+use crate::error::{AppError, AppResult};
+use crate::skill::{not_found_hint, validate_skill_id_with};
+use crate::status::list_skills_with;
+
+/// Writes `<root>/<skill>` to `output` as a reproducible gzip tarball;
+/// `skill` is validated against `rules` (`skills.*`).
+///
+/// Entries follow digest order and carry zeroed mtimes/owners, so the same
+/// content always produces byte-identical archives.
+pub fn export_skill(
+    root: &Path,
+    skill: &str,
+    rules: &SkillsConfig,
+    ignore: Option<&IgnoreSet>,
+    output: &Path,
+) -> AppResult<()> {
+    validate_skill_id_with(skill, rules)?;
+    let skill_root = root.join(skill);
+    if !skill_root.is_dir() {
+        return Err(AppError::exec(
+            crate::tr!(
+                "skill が存在しません: {}",
+                "Skill does not exist: {}",
+                skill_root.display()
+            ),
+            Some(not_found_hint(
+                skill,
+                list_skills_with(root, rules)
+                    .unwrap_or_default()
+                    .iter()
+                    .map(String::as_str),
+                crate::tr!(
+                    "list で一覧を確認してください",
+                    "Run list to see available skills."
+                ),
+            )),
+        ));
+    }
+    let files = collect_files(&skill_root, ignore)?;
+
+    let parent = match output.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let temp = NamedTempFile::new_in(parent).map_err(|err| {
+        AppError::io(
+            crate::tr!(
+                "一時ファイルの作成に失敗しました: {}",
+                "Failed to create temp file: {}",
+                parent.display()
+            ),
+            err,
+        )
+    })?;
+    let encoder = GzBuilder::new()
+        .mtime(0)
+        .write(temp.as_file(), Compression::default());
+    let mut builder = tar::Builder::new(encoder);
+    builder.mode(tar::HeaderMode::Deterministic);
+
+    let mut header = new_header(tar::EntryType::Directory, 0o755, 0);
+    builder
+        .append_data(&mut header, format!("{skill}/"), std::io::empty())
+        .map_err(|err| write_error(output, err))?;
+    for (rel, full) in files {
+        let data = fs::read(&full).map_err(|err| {
+            AppError::io(
+                crate::tr!(
+                    "ファイルの読み込みに失敗しました: {}",
+                    "Failed to read file: {}",
+                    full.display()
+                ),
+                err,
+            )
+        })?;
+        let mode = entry_mode(file_mode(&full)?);
+        let mut header = new_header(tar::EntryType::Regular, mode, data.len() as u64);
+        builder
+            .append_data(&mut header, Path::new(skill).join(&rel), data.as_slice())
+            .map_err(|err| write_error(output, err))?;
+    }
+    let encoder = builder
+        .into_inner()
+        .map_err(|err| write_error(output, err))?;
+    encoder.finish().map_err(|err| write_error(output, err))?;
+    temp.persist(output)
+        .map_err(|err| write_error(output, err.error))?;
+    Ok(())
+}
+
+/// Normalizes a file's permission bits to `0o755` when any execute bit is
+/// set and `0o644` otherwise, so archives do not depend on the umask.
+fn entry_mode(mode: Option<u32>) -> u32 {
+    match mode {
+        Some(mode) if mode & 0o111 != 0 => 0o755,
+        _ => 0o644,
+    }
+}
+
+fn new_header(kind: tar::EntryType, mode: u32, size: u64) -> tar::Header {
+    let mut header = tar::Header::new_gnu();
+    header.set_entry_type(kind);
+    header.set_mode(mode);
+    header.set_size(size);
+    header.set_mtime(0);
+    header.set_uid(0);
+    header.set_gid(0);
+    header
+}
+
+fn write_error(output: &Path, err: std::io::Error) -> AppError {
+    AppError::io(
+        crate::tr!(
+            "アーカイブの書き込みに失敗しました: {}",
+            "Failed to write archive: {}",
+            output.display()
+        ),
+        err,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::config::HashAlgo;
+    use crate::digest::{DigestOptions, build_ignore_set, digest_dir};
+    use crate::source::{SkillSource, TarSource};
+
+    #[test]
+    fn export_is_reproducible() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path().join("global");
+        fs::create_dir_all(root.join("skill1/nested")).unwrap();
+        fs::write(root.join("skill1/SKILL.md"), "doc").unwrap();
+        fs::write(root.join("skill1/nested/a.txt"), "a").unwrap();
+
+        let first = dir.path().join("first.tar.gz");
+        let second = dir.path().join("second.tar.gz");
+        export_skill(&root, "skill1", &SkillsConfig::default(), None, &first).unwrap();
+        fs::write(root.join("skill1/SKILL.md"), "doc").unwrap();
+        export_skill(&root, "skill1", &SkillsConfig::default(), None, &second).unwrap();
+
+        assert_eq!(fs::read(&first).unwrap(), fs::read(&second).unwrap());
+    }
+
+    #[test]
+    fn export_respects_ignore_and_round_trips() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path().join("global");
+        fs::create_dir_all(root.join("skill1")).unwrap();
+        fs::write(root.join("skill1/SKILL.md"), "doc").unwrap();
+        fs::write(root.join("skill1/skip.tmp"), "noise").unwrap();
+        let ignore = build_ignore_set(&["**/*.tmp".to_string()])
+            .unwrap()
+            .unwrap();
+
+        let output = dir.path().join("skill1.tar.gz");
+        export_skill(
+            &root,
+            "skill1",
+            &SkillsConfig::default(),
+            Some(&ignore),
+            &output,
+        )
+        .unwrap();
+
+        let source = TarSource::open(&output).unwrap();
+        let extracted = source.skill_dir("skill1").unwrap();
+        assert!(!extracted.path().join("skip.tmp").exists());
+        assert_eq!(
+            source
+                .digest_skill("skill1", &DigestOptions::new(HashAlgo::Blake3))
+                .unwrap(),
+            digest_dir(
+                &root.join("skill1"),
+                &DigestOptions {
+                    ignore: Some(&ignore),
+                    ..DigestOptions::new(HashAlgo::Blake3)
+                }
+            )
+            .unwrap()
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn export_keeps_execute_bits() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = TempDir::new().unwrap();
+        let root = dir.path().join("global");
+        fs::create_dir_all(root.join("skill1")).unwrap();
+        fs::write(root.join("skill1/SKILL.md"), "doc").unwrap();
+        fs::write(root.join("skill1/run.sh"), "#!/bin/sh\n").unwrap();
+        fs::set_permissions(
+            root.join("skill1/SKILL.md"),
+            fs::Permissions::from_mode(0o600),
+        )
+        .unwrap();
+        fs::set_permissions(
+            root.join("skill1/run.sh"),
+            fs::Permissions::from_mode(0o700),
+        )
+        .unwrap();
+
+        let output = dir.path().join("skill1.tar.gz");
+        export_skill(&root, "skill1", &SkillsConfig::default(), None, &output).unwrap();
+
+        let file = fs::File::open(&output).unwrap();
+        let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(file));
+        let mut modes = archive
+            .entries()
+            .unwrap()
+            .map(|entry| {
+                let entry = entry.unwrap();
+                let path = entry.path().unwrap().to_string_lossy().into_owned();
+                (path, entry.header().mode().unwrap())
+            })
+            .collect::<Vec<_>>();
+        modes.sort();
+        assert_eq!(
+            modes,
+            vec![
+                ("skill1/".to_string(), 0o755),
+                ("skill1/SKILL.md".to_string(), 0o644),
+                ("skill1/run.sh".to_string(), 0o755),
+            ]
+        );
+    }
+
+    #[test]
+    fn export_validates_names_against_skill_rules() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path().join("global");
+        fs::create_dir_all(root.join(".hidden")).unwrap();
+        fs::write(root.join(".hidden/SKILL.md"), "doc").unwrap();
+        let output = dir.path().join("hidden.tar.gz");
+
+        let err =
+            export_skill(&root, ".hidden", &SkillsConfig::default(), None, &output).unwrap_err();
+        assert!(matches!(err, AppError::Config { .. }));
+
+        let rules = SkillsConfig {
+            allow_hidden: true,
+            ..SkillsConfig::default()
+        };
+        export_skill(&root, ".hidden", &rules, None, &output).unwrap();
+        let source = TarSource::open(&output).unwrap();
+        assert_eq!(source.list_skills().unwrap(), vec![".hidden".to_string()]);
+    }
+
+    #[test]
+    fn export_errors_when_skill_missing() {
+        let dir = TempDir::new().unwrap();
+        let err = export_skill(
+            dir.path(),
+            "missing",
+            &SkillsConfig::default(),
+            None,
+            &dir.path().join("out.tar.gz"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, AppError::Exec { .. }));
+
+        fs::create_dir_all(dir.path().join("skill1")).unwrap();
+        let err = export_skill(
+            dir.path(),
+            "skill1",
+            &SkillsConfig::default(),
+            None,
+            &dir.path().join("missing/out.tar.gz"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, AppError::Io { .. }));
+    }
+}
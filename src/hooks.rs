@@ -0,0 +1,94 @@
+use std::fmt;
+use std::process::Command;
+
+use crate::config::Target;
+use crate::error::{AppError, AppResult};
+
+/// Placeholder replaced with the target name in hook arguments.
+const TARGET_PLACEHOLDER: &str = "{target}";
+
+/// When a hook runs relative to the plan's mutations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookStage {
+    PrePush,
+    PostPush,
+}
+
+impl fmt::Display for HookStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            HookStage::PrePush => "pre_push",
+            HookStage::PostPush => "post_push",
+        })
+    }
+}
+
+/// Commands run around the mutations of a push plan, from the target's
+/// `pre_push` / `post_push`; empty for import plans.
+#[derive(Debug, Clone, Default)]
+pub struct PlanHooks {
+    pub target: String,
+    pub pre_push: Vec<String>,
+    pub post_push: Vec<String>,
+}
+
+impl PlanHooks {
+    pub fn for_target(target: &Target) -> Self {
+        Self {
+            target: target.name.clone(),
+            pre_push: target.pre_push.clone(),
+            post_push: target.post_push.clone(),
+        }
+    }
+
+    /// Runs the hook for `stage`, if any; a non-zero exit is an exec error.
+    pub(crate) fn run(&self, stage: HookStage) -> AppResult<()> {
+        let command = match stage {
+            HookStage::PrePush => &self.pre_push,
+            HookStage::PostPush => &self.post_push,
+        };
+        let Some((program, args)) = command.split_first() else {
+            return Ok(());
+        };
+        let substitute = |arg: &String| arg.replace(TARGET_PLACEHOLDER, &self.target);
+        tracing::debug!(%stage, target = %self.target, ?command, "run hook");
+        // Hook output goes to stderr so porcelain and ndjson stdout stay parseable.
+        let status = Command::new(substitute(program))
+            .args(args.iter().map(substitute))
+            .stdout(std::io::stderr())
+            .status()
+            .map_err(|err| {
+                AppError::exec_with_source(
+                    crate::tr!(
+                        "{} フックの起動に失敗しました: {}",
+                        "Failed to start {} hook: {}",
+                        stage,
+                        program
+                    ),
+                    Some(err.to_string()),
+                    err,
+                )
+            })?;
+        if status.success() {
+            return Ok(());
+        }
+        let code = status
+            .code()
+            .map_or_else(|| "signal".to_string(), |code| code.to_string());
+        Err(AppError::exec(
+            crate::tr!(
+                "{} フックが失敗しました (target: {}, exit code: {})",
+                "{} hook failed (target: {}, exit code: {})",
+                stage,
+                self.target,
+                code
+            ),
+            Some(match stage {
+                HookStage::PrePush => crate::tr!("変更は行っていません", "Nothing was changed."),
+                HookStage::PostPush => {
+                    crate::tr!("push 自体は完了しています", "The push itself was applied.")
+                }
+            }),
+        ))
+    }
+}
@@ -1,19 +1,63 @@
 pub mod cli;
 
+mod audit;
+mod bump;
+mod checkpoint;
+mod color;
 mod config;
 mod diff;
 mod digest;
 mod doctor;
 mod error;
+mod events;
+mod export;
+mod hooks;
 mod i18n;
+mod lock;
+mod merkle;
+mod output;
 mod skill;
+mod source;
 mod status;
+mod store;
 mod sync;
+mod targets;
 
-pub use config::{Config, Target};
-pub use doctor::{DoctorReport, doctor_root, group_issues_by_skill};
-pub use error::{AppError, AppResult};
-pub use skill::validate_skill_id;
+pub use audit::AuditLog;
+pub use bump::{Bump, BumpLevel, bump_skill};
+pub use checkpoint::{CHECKPOINT_FILE, CHECKPOINT_NOTE, Checkpoint};
+pub use color::ColorChoice;
+pub use config::{
+    Config, DigestEncoding, DoctorConfig, HashAlgo, Profile, SkillsConfig, Target, canonical_root,
+    config_schema,
+};
+pub use digest::{
+    DigestOptions, FileChange, FileState, digest_dir, encode_digest, file_changes, fingerprint_dir,
+};
+pub use doctor::{
+    DoctorIssue, DoctorReport, IssueKind, Severity, count_issues_at_least, doctor_root,
+    doctor_root_with, doctor_skill, group_issues_by_skill,
+};
+pub use error::{AppError, AppResult, BoxError};
+pub use events::{Event, OutputFormat, StatusEventWriter, write_event};
+pub use hooks::PlanHooks;
+pub use lock::{LOCK_FILE, RootLock};
+pub use merkle::{Fingerprint, MerkleBuild, MerkleNode, MerkleTree, TreeCache, build_tree};
+pub use skill::{skill_id_is_valid, validate_skill_id, validate_skill_id_with};
+pub use status::{
+    CompareRow, CompareState, SinceSyncRow, SkillTiming, State, StatusCounter, StatusRow,
+    StatusSink, StatusSort, StatusTableWriter, SyncState, TableStyle, compare_targets,
+    render_compare_table, render_since_sync_table, render_status_rows, render_status_table,
+    render_status_table_with, set_root_wait, since_sync, sort_compare_rows, sort_since_sync_rows,
+    sort_status_rows, status_for_target, status_for_target_selected, status_for_target_streaming,
+};
+pub use store::{DigestStore, SYNC_STATE_FILE, StoredDigest};
+pub use sync::{
+    ImportMode, Plan, PlanKind, PlanOp, PushOptions, RenderOpts, Selection, execute_plan,
+    execute_plan_audited, execute_plan_with, plan_all, plan_push, plan_push_with, porcelain_plan,
+    prune_files, record_synced, render_plan, summarize_plan,
+};
+pub use targets::{TargetSummary, summarize_targets};
 
 #[cfg(test)]
 mod core_e2e_tests;
@@ -0,0 +1,113 @@
+use std::fs::{File, OpenOptions, TryLockError};
+use std::path::{Path, PathBuf};
+
+use crate::error::{AppError, AppResult};
+
+/// Lock file created under a root while `push` / `import` mutate it.
+pub const LOCK_FILE: &str = ".skillctl.lock";
+
+/// Exclusive advisory lock on a skill root, released on drop.
+///
+/// The lock file itself is left in place; only the OS lock on it matters.
+#[derive(Debug)]
+pub struct RootLock {
+    _file: File,
+    path: PathBuf,
+}
+
+impl RootLock {
+    /// Locks `root`, failing fast when another run holds it unless `wait` is set.
+    ///
+    /// Returns `None` when `root` is not a directory (e.g. an archive), leaving
+    /// the error to the planner.
+    pub fn acquire(root: &Path, wait: bool) -> AppResult<Option<Self>> {
+        if !root.is_dir() {
+            return Ok(None);
+        }
+        let path = root.join(LOCK_FILE);
+        let lock_error = |err: std::io::Error| {
+            AppError::io(
+                crate::tr!(
+                    "ロックファイルを開けません: {}",
+                    "Cannot open lock file: {}",
+                    path.display()
+                ),
+                err,
+            )
+        };
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)
+            .map_err(lock_error)?;
+        if wait {
+            tracing::debug!(path = %path.display(), "waiting for root lock");
+            file.lock().map_err(lock_error)?;
+        } else {
+            match file.try_lock() {
+                Ok(()) => {}
+                Err(TryLockError::WouldBlock) => {
+                    return Err(AppError::exec(
+                        crate::tr!(
+                            "別の skillctl の操作が実行中です: {}",
+                            "Another skillctl operation is in progress: {}",
+                            root.display()
+                        ),
+                        Some(crate::tr!(
+                            "終了を待つか --wait を指定してください",
+                            "Wait for it to finish or pass --wait."
+                        )),
+                    ));
+                }
+                Err(TryLockError::Error(err)) => return Err(lock_error(err)),
+            }
+        }
+        tracing::debug!(path = %path.display(), "root locked");
+        Ok(Some(Self { _file: file, path }))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn second_lock_fails_fast_and_waiter_blocks_until_release() {
+        let dir = TempDir::new().unwrap();
+        let first = RootLock::acquire(dir.path(), false).unwrap().unwrap();
+        assert!(first.path().ends_with(LOCK_FILE));
+
+        let err = RootLock::acquire(dir.path(), false).unwrap_err();
+        assert!(matches!(err, AppError::Exec { .. }));
+
+        let (tx, rx) = mpsc::channel();
+        let root = dir.path().to_path_buf();
+        let waiter = std::thread::spawn(move || {
+            let lock = RootLock::acquire(&root, true).unwrap();
+            tx.send(()).unwrap();
+            drop(lock);
+        });
+        assert!(rx.recv_timeout(Duration::from_millis(200)).is_err());
+        drop(first);
+        rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        waiter.join().unwrap();
+    }
+
+    #[test]
+    fn non_directory_root_is_not_locked() {
+        let dir = TempDir::new().unwrap();
+        let archive = dir.path().join("skills.tar");
+        std::fs::write(&archive, b"").unwrap();
+        assert!(RootLock::acquire(&archive, false).unwrap().is_none());
+    }
+}
@@ -0,0 +1,563 @@
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
+
+use serde::{Deserialize, Serialize};
+use tempfile::NamedTempFile;
+
+use crate::config::HashAlgo;
+use crate::digest::{
+    DigestHasher, DigestOptions, collect_empty_dirs, collect_hashed_files, encode_component,
+    file_digest, file_mode, pinned_algo,
+};
+use crate::error::{AppError, AppResult};
+
+/// Size, mtime and permission bits of a file when its hash was computed.
+///
+/// A file whose fingerprint is unchanged keeps its stored hash without being read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fingerprint {
+    pub len: u64,
+    pub mtime_ns: i64,
+    pub mode: Option<u32>,
+}
+
+impl Fingerprint {
+    /// `None` when the platform reports no usable mtime; such files are always hashed.
+    pub(crate) fn of(path: &Path) -> AppResult<Option<Self>> {
+        let meta = fs::metadata(path).map_err(|err| {
+            AppError::io(
+                crate::tr!(
+                    "ファイルの読み込みに失敗しました: {}",
+                    "Failed to read file: {}",
+                    path.display()
+                ),
+                err,
+            )
+        })?;
+        let Some(mtime_ns) = meta
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .and_then(|elapsed| i64::try_from(elapsed.as_nanos()).ok())
+        else {
+            return Ok(None);
+        };
+        Ok(Some(Self {
+            len: meta.len(),
+            mtime_ns,
+            mode: file_mode(path)?,
+        }))
+    }
+}
+
+/// One node of a skill's Merkle tree.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum MerkleNode {
+    /// A file: the digest of its content (and mode with `hash.include_mode`).
+    File {
+        hash: String,
+        fingerprint: Option<Fingerprint>,
+    },
+    /// A directory: the hash of its children's names, kinds and hashes in
+    /// name order. An empty directory (`hash.include_empty_dirs`) has no children.
+    Dir {
+        hash: String,
+        children: BTreeMap<String, MerkleNode>,
+    },
+}
+
+impl MerkleNode {
+    pub fn hash(&self) -> &str {
+        match self {
+            Self::File { hash, .. } | Self::Dir { hash, .. } => hash,
+        }
+    }
+}
+
+/// A skill's Merkle tree together with the settings its hashes depend on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MerkleTree {
+    pub algo: HashAlgo,
+    pub normalize_eol: bool,
+    pub include_mode: bool,
+    #[serde(default)]
+    pub skip_larger_than: Option<u64>,
+    pub root: MerkleNode,
+}
+
+impl MerkleTree {
+    /// The rolled-up digest used in place of the flat one under `hash.merkle`.
+    pub fn root_hash(&self) -> &str {
+        self.root.hash()
+    }
+
+    /// Whether hashes in this tree were computed the way `options` would.
+    fn reusable_for(&self, algo: HashAlgo, options: &DigestOptions) -> bool {
+        self.algo == algo
+            && self.normalize_eol == options.normalize_eol
+            && self.include_mode == options.include_mode
+            && self.skip_larger_than == options.skip_larger_than
+    }
+}
+
+/// A tree built by [`build_tree`] and the paths whose hashes it computed.
+#[derive(Debug)]
+pub struct MerkleBuild {
+    pub tree: MerkleTree,
+    /// Relative paths hashed in this build rather than reused from the
+    /// previous tree, in build order; the skill root is the empty path.
+    pub recomputed: Vec<PathBuf>,
+}
+
+/// Builds the Merkle tree of a skill directory.
+///
+/// Files whose fingerprint matches `previous` reuse its hash, and a directory
+/// whose children were all reused keeps its previous hash, so a single changed
+/// file only rehashes that file and its ancestors. `previous` is ignored when
+/// it was built with another algorithm or other content options.
+#[tracing::instrument(skip(options, previous), fields(path = %path.display()))]
+pub fn build_tree(
+    path: &Path,
+    options: &DigestOptions,
+    previous: Option<&MerkleTree>,
+) -> AppResult<MerkleBuild> {
+    let algo = pinned_algo(path)?.unwrap_or(options.algo);
+    let previous = previous.filter(|tree| tree.reusable_for(algo, options));
+    let mut root = Pending::default();
+    for (rel, full) in collect_hashed_files(path, options.ignore)? {
+        root.insert(&rel, Leaf::Disk(full));
+    }
+    if options.include_empty_dirs {
+        for rel in collect_empty_dirs(path, options.ignore)? {
+            root.insert(&rel, Leaf::EmptyDir);
+        }
+    }
+    let mut builder = Builder {
+        options: DigestOptions { algo, ..*options },
+        recomputed: Vec::new(),
+    };
+    let (root, _) = builder.dir(
+        root.children,
+        Path::new(""),
+        previous.map(|tree| &tree.root),
+    )?;
+    tracing::trace!(recomputed = builder.recomputed.len(), "merkle tree built");
+    Ok(MerkleBuild {
+        tree: MerkleTree {
+            algo,
+            normalize_eol: options.normalize_eol,
+            include_mode: options.include_mode,
+            skip_larger_than: options.skip_larger_than,
+            root,
+        },
+        recomputed: builder.recomputed,
+    })
+}
+
+/// Rolls up already hashed `(relative, file digest)` entries and empty
+/// directory paths, for sources that are not on disk (archives).
+pub(crate) fn root_hash_of_entries(
+    files: Vec<(PathBuf, String)>,
+    empty_dirs: Vec<PathBuf>,
+    options: &DigestOptions,
+) -> String {
+    let mut root = Pending::default();
+    for (rel, hash) in files {
+        root.insert(&rel, Leaf::Hashed(hash));
+    }
+    for rel in empty_dirs {
+        root.insert(&rel, Leaf::EmptyDir);
+    }
+    let mut builder = Builder {
+        options: *options,
+        recomputed: Vec::new(),
+    };
+    builder
+        .dir(root.children, Path::new(""), None)
+        .map(|(node, _)| node.hash().to_string())
+        .expect("in-memory entries are never read from disk")
+}
+
+enum Leaf {
+    Disk(PathBuf),
+    Hashed(String),
+    EmptyDir,
+}
+
+#[derive(Default)]
+struct Pending {
+    children: BTreeMap<OsString, PendingNode>,
+}
+
+enum PendingNode {
+    Leaf(Leaf),
+    Dir(Pending),
+}
+
+impl Pending {
+    fn insert(&mut self, rel: &Path, leaf: Leaf) {
+        let mut dir = self;
+        let mut components = rel.components().peekable();
+        while let Some(component) = components.next() {
+            let name = component.as_os_str().to_os_string();
+            if components.peek().is_none() {
+                dir.children.insert(name, PendingNode::Leaf(leaf));
+                return;
+            }
+            let node = dir
+                .children
+                .entry(name)
+                .or_insert_with(|| PendingNode::Dir(Pending::default()));
+            dir = match node {
+                PendingNode::Dir(pending) => pending,
+                // Walks never yield a file with entries below it.
+                PendingNode::Leaf(_) => return,
+            };
+        }
+    }
+}
+
+struct Builder<'a> {
+    options: DigestOptions<'a>,
+    recomputed: Vec<PathBuf>,
+}
+
+impl Builder<'_> {
+    /// Returns the node and whether it was reused unchanged from `previous`.
+    fn dir(
+        &mut self,
+        children: BTreeMap<OsString, PendingNode>,
+        rel: &Path,
+        previous: Option<&MerkleNode>,
+    ) -> AppResult<(MerkleNode, bool)> {
+        let previous_children = match previous {
+            Some(MerkleNode::Dir { children, .. }) => Some(children),
+            _ => None,
+        };
+        let mut all_reused =
+            previous_children.is_some_and(|previous| previous.len() == children.len());
+        let mut hasher = DigestHasher::new(self.options.algo);
+        let mut nodes = BTreeMap::new();
+        for (name, pending) in children {
+            let child_rel = rel.join(&name);
+            // Lossy keys are only trusted for UTF-8 names; raw names always rehash.
+            let key = name.to_str().map(str::to_string);
+            let child_previous = key.as_deref().and_then(|key| previous_children?.get(key));
+            let (node, reused) = match pending {
+                PendingNode::Dir(pending) => {
+                    self.dir(pending.children, &child_rel, child_previous)?
+                }
+                PendingNode::Leaf(Leaf::EmptyDir) => {
+                    self.dir(BTreeMap::new(), &child_rel, child_previous)?
+                }
+                PendingNode::Leaf(leaf) => self.file(leaf, &child_rel, child_previous)?,
+            };
+            all_reused &= reused && key.is_some();
+            let mut encoded = Vec::new();
+            encode_component(name.as_encoded_bytes(), &mut encoded);
+            hasher.update(&encoded);
+            hasher.update(match node {
+                MerkleNode::File { .. } => b"\0f\0",
+                MerkleNode::Dir { .. } => b"\0d\0",
+            });
+            hasher.update(node.hash().as_bytes());
+            hasher.update(b"\0");
+            nodes.insert(name.to_string_lossy().into_owned(), node);
+        }
+        if all_reused && let Some(previous) = previous {
+            return Ok((
+                MerkleNode::Dir {
+                    hash: previous.hash().to_string(),
+                    children: nodes,
+                },
+                true,
+            ));
+        }
+        self.recomputed.push(rel.to_path_buf());
+        Ok((
+            MerkleNode::Dir {
+                hash: hasher.finalize_hex(),
+                children: nodes,
+            },
+            false,
+        ))
+    }
+
+    fn file(
+        &mut self,
+        leaf: Leaf,
+        rel: &Path,
+        previous: Option<&MerkleNode>,
+    ) -> AppResult<(MerkleNode, bool)> {
+        let full = match leaf {
+            Leaf::Disk(full) => full,
+            Leaf::Hashed(hash) => {
+                let node = MerkleNode::File {
+                    hash,
+                    fingerprint: None,
+                };
+                return Ok((node, false));
+            }
+            Leaf::EmptyDir => unreachable!("empty directories are built as dirs"),
+        };
+        let fingerprint = Fingerprint::of(&full)?;
+        if let Some(MerkleNode::File {
+            hash,
+            fingerprint: Some(stored),
+        }) = previous
+            && fingerprint == Some(*stored)
+        {
+            let node = MerkleNode::File {
+                hash: hash.clone(),
+                fingerprint,
+            };
+            return Ok((node, true));
+        }
+        self.recomputed.push(rel.to_path_buf());
+        let node = MerkleNode::File {
+            hash: file_digest(&full, &self.options)?,
+            fingerprint,
+        };
+        Ok((node, false))
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheFile {
+    #[serde(default)]
+    trees: BTreeMap<String, MerkleTree>,
+}
+
+/// Merkle trees kept between runs in `hash.merkle_cache`, keyed by skill path.
+///
+/// The file is only a cache: a missing or unparsable one starts empty.
+#[derive(Debug)]
+pub struct TreeCache {
+    path: PathBuf,
+    trees: Mutex<BTreeMap<String, MerkleTree>>,
+}
+
+impl TreeCache {
+    pub fn load(path: &Path) -> AppResult<Self> {
+        let trees = match fs::read_to_string(path) {
+            Ok(raw) => match serde_json::from_str::<CacheFile>(&raw) {
+                Ok(file) => file.trees,
+                Err(err) => {
+                    tracing::debug!(path = %path.display(), %err, "discarding unparsable merkle cache");
+                    BTreeMap::new()
+                }
+            },
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(err) => {
+                return Err(AppError::io(
+                    crate::tr!(
+                        "ファイルの読み込みに失敗しました: {}",
+                        "Failed to read file: {}",
+                        path.display()
+                    ),
+                    err,
+                ));
+            }
+        };
+        Ok(Self {
+            path: path.to_path_buf(),
+            trees: Mutex::new(trees),
+        })
+    }
+
+    /// Digests `path` with [`build_tree`], reusing and replacing its cached tree.
+    pub fn digest_dir(&self, path: &Path, options: &DigestOptions) -> AppResult<String> {
+        let key = path.to_string_lossy().into_owned();
+        let previous = self.lock().remove(&key);
+        let build = build_tree(path, options, previous.as_ref())?;
+        let digest = build.tree.root_hash().to_string();
+        self.lock().insert(key, build.tree);
+        Ok(digest)
+    }
+
+    /// Writes the cache atomically, creating its directory if needed.
+    pub fn save(&self) -> AppResult<()> {
+        let write_error = |err: std::io::Error| {
+            AppError::io(
+                crate::tr!(
+                    "ファイルの書き込みに失敗しました: {}",
+                    "Failed to write file: {}",
+                    self.path.display()
+                ),
+                err,
+            )
+        };
+        let body = serde_json::to_vec(&CacheFile {
+            trees: self.lock().clone(),
+        })
+        .map_err(|err| write_error(err.into()))?;
+        let parent = match self.path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        fs::create_dir_all(parent).map_err(write_error)?;
+        let mut temp = NamedTempFile::new_in(parent).map_err(write_error)?;
+        temp.write_all(&body).map_err(write_error)?;
+        temp.persist(&self.path)
+            .map_err(|err| write_error(err.error))?;
+        Ok(())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, MerkleTree>> {
+        self.trees
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::digest::digest_dir;
+
+    fn merkle_options() -> DigestOptions<'static> {
+        DigestOptions {
+            merkle: true,
+            ..DigestOptions::new(HashAlgo::Blake3)
+        }
+    }
+
+    fn write(root: &Path, rel: &str, content: &str) {
+        let path = root.join(rel);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn single_file_change_only_rehashes_its_path() {
+        let dir = TempDir::new().unwrap();
+        write(dir.path(), "SKILL.md", "skill");
+        write(dir.path(), "docs/a.md", "a");
+        write(dir.path(), "docs/deep/b.md", "b");
+        write(dir.path(), "scripts/run.sh", "run");
+        let options = merkle_options();
+
+        let first = build_tree(dir.path(), &options, None).unwrap();
+        assert_eq!(first.recomputed.len(), 8);
+
+        let unchanged = build_tree(dir.path(), &options, Some(&first.tree)).unwrap();
+        assert!(unchanged.recomputed.is_empty());
+        assert_eq!(unchanged.tree, first.tree);
+
+        // A different length changes the fingerprint even within one mtime tick.
+        write(dir.path(), "docs/deep/b.md", "changed");
+        let changed = build_tree(dir.path(), &options, Some(&first.tree)).unwrap();
+        assert_eq!(
+            changed.recomputed,
+            vec![
+                PathBuf::from("docs/deep/b.md"),
+                PathBuf::from("docs/deep"),
+                PathBuf::from("docs"),
+                PathBuf::from(""),
+            ]
+        );
+        assert_ne!(changed.tree.root_hash(), first.tree.root_hash());
+        assert_eq!(
+            changed.tree.root_hash(),
+            build_tree(dir.path(), &options, None)
+                .unwrap()
+                .tree
+                .root_hash()
+        );
+    }
+
+    #[test]
+    fn added_and_removed_files_rehash_their_parent() {
+        let dir = TempDir::new().unwrap();
+        write(dir.path(), "a/one.md", "1");
+        write(dir.path(), "b/two.md", "2");
+        let options = merkle_options();
+        let first = build_tree(dir.path(), &options, None).unwrap();
+
+        fs::remove_file(dir.path().join("b/two.md")).unwrap();
+        write(dir.path(), "b/three.md", "3");
+        let next = build_tree(dir.path(), &options, Some(&first.tree)).unwrap();
+        assert_eq!(
+            next.recomputed,
+            vec![
+                PathBuf::from("b/three.md"),
+                PathBuf::from("b"),
+                PathBuf::from(""),
+            ]
+        );
+    }
+
+    #[test]
+    fn previous_tree_is_ignored_for_other_settings() {
+        let dir = TempDir::new().unwrap();
+        write(dir.path(), "SKILL.md", "skill");
+        let first = build_tree(dir.path(), &merkle_options(), None).unwrap();
+        let sha256 = DigestOptions {
+            algo: HashAlgo::Sha256,
+            ..merkle_options()
+        };
+        let next = build_tree(dir.path(), &sha256, Some(&first.tree)).unwrap();
+        assert_eq!(next.recomputed.len(), 2);
+        assert_eq!(next.tree.algo, HashAlgo::Sha256);
+    }
+
+    #[test]
+    fn merkle_root_is_the_digest_and_tracks_content() {
+        let a = TempDir::new().unwrap();
+        let b = TempDir::new().unwrap();
+        write(a.path(), "x/y.md", "same");
+        write(b.path(), "x/y.md", "same");
+        let options = merkle_options();
+        let digest = digest_dir(a.path(), &options).unwrap();
+        assert_eq!(digest, digest_dir(b.path(), &options).unwrap());
+        assert_eq!(digest.len(), 64);
+        assert_ne!(
+            digest,
+            digest_dir(a.path(), &DigestOptions::new(HashAlgo::Blake3)).unwrap()
+        );
+
+        // Moving a file to another directory changes the root.
+        fs::create_dir_all(b.path().join("z")).unwrap();
+        fs::rename(b.path().join("x/y.md"), b.path().join("z/y.md")).unwrap();
+        assert_ne!(digest, digest_dir(b.path(), &options).unwrap());
+    }
+
+    #[test]
+    fn tree_cache_round_trips_and_reuses_trees() {
+        let dir = TempDir::new().unwrap();
+        let skill = dir.path().join("skill");
+        write(&skill, "SKILL.md", "skill");
+        let cache_path = dir.path().join("cache/merkle.json");
+
+        let cache = TreeCache::load(&cache_path).unwrap();
+        let options = DigestOptions {
+            tree_cache: Some(&cache),
+            ..merkle_options()
+        };
+        let digest = digest_dir(&skill, &options).unwrap();
+        cache.save().unwrap();
+
+        let reloaded = TreeCache::load(&cache_path).unwrap();
+        let stored = reloaded
+            .lock()
+            .get(&*skill.to_string_lossy())
+            .cloned()
+            .unwrap();
+        assert_eq!(stored.root_hash(), digest);
+        let rebuilt = build_tree(&skill, &merkle_options(), Some(&stored)).unwrap();
+        assert!(rebuilt.recomputed.is_empty());
+
+        fs::write(&cache_path, "not json").unwrap();
+        assert!(TreeCache::load(&cache_path).unwrap().lock().is_empty());
+    }
+}
@@ -0,0 +1,74 @@
+use std::fmt::Display;
+use std::fs::File;
+use std::io::{IsTerminal, Write};
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::OnceLock;
+
+use crate::error::{AppError, AppResult};
+
+/// File given with `--output`; primary output goes to stdout when unset.
+static OUTPUT_FILE: OnceLock<File> = OnceLock::new();
+
+/// Sends primary output to `path` (created or truncated) for the rest of the
+/// process. Errors, hints and warnings stay on stderr.
+pub(crate) fn set_output_file(path: &Path) -> AppResult<()> {
+    let file = File::create(path).map_err(|err| {
+        AppError::io(
+            crate::tr!(
+                "出力ファイルを作成できません: {}",
+                "Cannot create output file: {}",
+                path.display()
+            ),
+            err,
+        )
+    })?;
+    // Only the first call wins; the CLI sets it once per run.
+    let _ = OUTPUT_FILE.set(file);
+    Ok(())
+}
+
+/// Writer for a command's primary output: the `--output` file, or stdout.
+pub(crate) fn output() -> Box<dyn Write> {
+    match OUTPUT_FILE.get() {
+        Some(file) => Box::new(file),
+        None => Box::new(std::io::stdout().lock()),
+    }
+}
+
+/// Whether primary output reaches a terminal; never when `--output` is set.
+pub(crate) fn output_is_terminal() -> bool {
+    OUTPUT_FILE.get().is_none() && std::io::stdout().is_terminal()
+}
+
+/// Stdout for child processes whose output is primary output (the diff
+/// command), or `None` to inherit stdout.
+pub(crate) fn output_stdio() -> AppResult<Option<Stdio>> {
+    let Some(file) = OUTPUT_FILE.get() else {
+        return Ok(None);
+    };
+    let file = file.try_clone().map_err(output_error)?;
+    Ok(Some(Stdio::from(file)))
+}
+
+/// Writes `text` as is to the primary output.
+pub(crate) fn emit_text(text: impl Display) -> AppResult<()> {
+    write!(output(), "{text}").map_err(output_error)
+}
+
+/// Writes `text` and a newline to the primary output.
+pub(crate) fn emit_line(text: impl Display) -> AppResult<()> {
+    writeln!(output(), "{text}").map_err(output_error)
+}
+
+/// Writes raw `bytes` (e.g. a child's captured stdout) to the primary output.
+pub(crate) fn emit_bytes(bytes: &[u8]) -> AppResult<()> {
+    output().write_all(bytes).map_err(output_error)
+}
+
+fn output_error(err: std::io::Error) -> AppError {
+    AppError::io(
+        crate::tr!("出力を書き込めません", "Cannot write output"),
+        err,
+    )
+}
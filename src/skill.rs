@@ -1,29 +1,267 @@
-use std::path::{Component, Path};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
 
+use crate::config::{SkillLayout, SkillsConfig};
 use crate::error::{AppError, AppResult};
 
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Why a skill id was rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Violation {
+    Empty,
+    NotDirectoryName,
+    Hidden,
+    Char(char),
+}
+
+/// Validates `skill` against the default naming rules.
 pub fn validate_skill_id(skill: &str) -> AppResult<()> {
+    validate_skill_id_with(skill, &SkillsConfig::default())
+}
+
+/// Returns `true` when `skill` satisfies the default naming rules.
+pub fn skill_id_is_valid(skill: &str) -> bool {
+    find_violation(skill, &SkillsConfig::default()).is_none()
+}
+
+/// Validates `skill` against the configured naming rules.
+///
+/// A skill id must be a single directory name made of letters, digits and
+/// `rules.allowed_chars`, and must not start with `.` unless
+/// `rules.allow_hidden` is set.
+pub fn validate_skill_id_with(skill: &str, rules: &SkillsConfig) -> AppResult<()> {
+    let Some(violation) = find_violation(skill, rules) else {
+        return Ok(());
+    };
+    let hint = match violation {
+        Violation::Empty => {
+            return Err(AppError::config(
+                crate::tr!("skill が空です", "skill is empty"),
+                Some(crate::tr!(
+                    "skill 名を指定してください",
+                    "Provide a skill name."
+                )),
+            ));
+        }
+        Violation::NotDirectoryName => crate::tr!(
+            "スキル名はディレクトリ名のみを指定してください",
+            "Use a directory name only."
+        ),
+        Violation::Hidden => crate::tr!(
+            "先頭に . は使えません (skills.allow_hidden で許可できます)",
+            "Leading dots are not allowed (enable skills.allow_hidden to permit them)."
+        ),
+        Violation::Char(c) => crate::tr!(
+            "使用できない文字が含まれています: {:?} (英数字と {} のみ)",
+            "Contains a disallowed character: {:?} (letters, digits and {} only)",
+            c,
+            rules.allowed_chars
+        ),
+    };
+    Err(AppError::config(
+        crate::tr!("skill が不正です: {}", "Skill is invalid: {}", skill),
+        Some(hint),
+    ))
+}
+
+/// Returns the key used to match `skill` across roots.
+pub(crate) fn skill_key(skill: &str, rules: &SkillsConfig) -> String {
+    if rules.case_insensitive {
+        skill.to_lowercase()
+    } else {
+        skill.to_string()
+    }
+}
+
+/// Maps match keys to the on-disk names of `skills` found under `root`.
+///
+/// Errors when two names fold to the same key under `skills.case_insensitive`.
+pub(crate) fn index_skills(
+    root: &Path,
+    skills: Vec<String>,
+    rules: &SkillsConfig,
+) -> AppResult<BTreeMap<String, String>> {
+    let mut index = BTreeMap::new();
+    for skill in skills {
+        let key = skill_key(&skill, rules);
+        if let Some(existing) = index.get(&key) {
+            return Err(AppError::exec(
+                crate::tr!(
+                    "大文字小文字のみ異なる skill があります: {} / {} ({})",
+                    "Skills differ only by case: {} / {} ({})",
+                    existing,
+                    skill,
+                    root.display()
+                ),
+                Some(crate::tr!(
+                    "どちらかをリネームしてください (doctor で確認できます)",
+                    "Rename one of them (doctor reports these)."
+                )),
+            ));
+        }
+        index.insert(key, skill);
+    }
+    Ok(index)
+}
+
+/// Parses a newline-separated skill list (`--skills-from`), skipping blank
+/// lines and `#` comments; every name must pass [`validate_skill_id_with`].
+pub(crate) fn parse_skill_list(text: &str, rules: &SkillsConfig) -> AppResult<Vec<String>> {
+    let mut skills = Vec::new();
+    for line in text.lines() {
+        let skill = line.trim();
+        if skill.is_empty() || skill.starts_with('#') {
+            continue;
+        }
+        validate_skill_id_with(skill, rules)?;
+        skills.push(skill.to_string());
+    }
+    Ok(skills)
+}
+
+/// Parses an `import --rename-map` file: one `old=new` pair per line,
+/// skipping blank lines and `#` comments. Both names must pass
+/// [`validate_skill_id_with`]; a line without `=` or an old name listed twice
+/// is a config error.
+pub(crate) fn parse_rename_map(
+    text: &str,
+    rules: &SkillsConfig,
+) -> AppResult<BTreeMap<String, String>> {
+    let mut renames = BTreeMap::new();
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let invalid = |message: String| {
+            AppError::config(
+                message,
+                Some(crate::tr!(
+                    "1 行に 1 つずつ old=new の形で記載してください",
+                    "Write one old=new pair per line."
+                )),
+            )
+        };
+        let Some((old, new)) = line.split_once('=') else {
+            return Err(invalid(crate::tr!(
+                "rename-map の {} 行目に = がありません: {}",
+                "Line {} of the rename map has no '=': {}",
+                index + 1,
+                line
+            )));
+        };
+        let (old, new) = (old.trim(), new.trim());
+        validate_skill_id_with(old, rules)?;
+        validate_skill_id_with(new, rules)?;
+        if renames.insert(old.to_string(), new.to_string()).is_some() {
+            return Err(invalid(crate::tr!(
+                "rename-map で {} が複数回指定されています",
+                "{} is renamed more than once in the rename map",
+                old
+            )));
+        }
+    }
+    Ok(renames)
+}
+
+/// Contents written for a new skill; `{skill}` is replaced with its name.
+const SKILL_TEMPLATE: &str = "---\nname: {skill}\ndescription: \n---\n\n# {skill}\n";
+
+/// Creates `skill` under `root` from [`SKILL_TEMPLATE`]: a directory holding
+/// `skill_file`, or a single file under the flat layout. Never overwrites an
+/// existing path. Returns the created skill path.
+pub(crate) fn init_skill(
+    root: &Path,
+    skill: &str,
+    rules: &SkillsConfig,
+    skill_file: &str,
+) -> AppResult<PathBuf> {
+    validate_skill_id_with(skill, rules)?;
+    let path = root.join(skill);
+    let file = match rules.layout {
+        SkillLayout::Nested => path.join(skill_file),
+        SkillLayout::Flat => path.clone(),
+    };
+    let create_error = |err: std::io::Error| {
+        AppError::io(
+            crate::tr!(
+                "skill を作成できません: {}",
+                "Failed to create skill: {}",
+                file.display()
+            ),
+            err,
+        )
+    };
+    if let Some(parent) = file.parent() {
+        fs::create_dir_all(parent).map_err(create_error)?;
+    }
+    fs::File::create_new(&file)
+        .and_then(|mut out| out.write_all(SKILL_TEMPLATE.replace("{skill}", skill).as_bytes()))
+        .map_err(create_error)?;
+    tracing::debug!(skill, path = %path.display(), "initialized skill");
+    Ok(path)
+}
+
+/// Up to three names from `candidates` close to `skill`, nearest first: names
+/// within a small edit distance, plus names that `skill` is a prefix of.
+pub(crate) fn suggest_skills<'a>(
+    skill: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Vec<String> {
+    let wanted = skill.to_lowercase();
+    let max_distance = (wanted.chars().count() / 3).max(1);
+    let mut scored = candidates
+        .into_iter()
+        .filter_map(|candidate| {
+            let lowered = candidate.to_lowercase();
+            let distance = strsim::levenshtein(&wanted, &lowered);
+            (distance <= max_distance || (!wanted.is_empty() && lowered.starts_with(&wanted)))
+                .then_some((distance, candidate))
+        })
+        .collect::<Vec<_>>();
+    scored.sort();
+    scored
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, candidate)| candidate.to_string())
+        .collect()
+}
+
+/// Hint for a skill that was not found: "did you mean" when [`suggest_skills`]
+/// finds something, otherwise `fallback`.
+pub(crate) fn not_found_hint<'a>(
+    skill: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+    fallback: String,
+) -> String {
+    let suggestions = suggest_skills(skill, candidates);
+    if suggestions.is_empty() {
+        fallback
+    } else {
+        crate::tr!("もしかして: {}", "did you mean: {}", suggestions.join(", "))
+    }
+}
+
+fn find_violation(skill: &str, rules: &SkillsConfig) -> Option<Violation> {
     if skill.trim().is_empty() {
-        return Err(AppError::config(
-            crate::tr!("skill が空です", "skill is empty"),
-            Some(crate::tr!(
-                "skill 名を指定してください",
-                "Provide a skill name."
-            )),
-        ));
+        return Some(Violation::Empty);
     }
     let path = Path::new(skill);
     let mut components = path.components();
     match (components.next(), components.next()) {
-        (Some(Component::Normal(_)), None) => Ok(()),
-        _ => Err(AppError::config(
-            crate::tr!("skill が不正です: {}", "Skill is invalid: {}", skill),
-            Some(crate::tr!(
-                "スキル名はディレクトリ名のみを指定してください",
-                "Use a directory name only."
-            )),
-        )),
+        (Some(Component::Normal(_)), None) => {}
+        _ => return Some(Violation::NotDirectoryName),
+    }
+    if skill.starts_with('.') && !rules.allow_hidden {
+        return Some(Violation::Hidden);
     }
+    skill
+        .chars()
+        .find(|&c| !c.is_alphanumeric() && !rules.allowed_chars.contains(c))
+        .map(Violation::Char)
 }
 
 #[cfg(test)]
@@ -31,6 +269,17 @@ mod tests {
     use super::*;
     use crate::error::AppError;
 
+    #[test]
+    fn suggest_skills_ranks_near_misses_and_prefixes() {
+        let candidates = ["review", "reviewer", "refactor", "deploy", "reviews-old"];
+        assert_eq!(suggest_skills("reveiw", candidates), ["review"]);
+        assert_eq!(
+            suggest_skills("rev", candidates),
+            ["review", "reviewer", "reviews-old"]
+        );
+        assert!(suggest_skills("zzz", candidates).is_empty());
+    }
+
     #[test]
     fn validate_skill_id_accepts_simple_name() {
         assert!(validate_skill_id("my-skill").is_ok());
@@ -47,4 +296,106 @@ mod tests {
         let err = validate_skill_id("../bad").unwrap_err();
         assert!(matches!(err, AppError::Config { .. }));
     }
+
+    #[test]
+    fn skill_id_is_valid_applies_default_rules() {
+        assert!(skill_id_is_valid("valid-name.v2"));
+        assert!(skill_id_is_valid("snake_case"));
+        assert!(!skill_id_is_valid(".hidden"));
+        assert!(!skill_id_is_valid("my skill"));
+        assert!(!skill_id_is_valid("a+b"));
+    }
+
+    #[test]
+    fn validate_skill_id_with_allows_hidden_when_configured() {
+        let rules = SkillsConfig {
+            allow_hidden: true,
+            ..SkillsConfig::default()
+        };
+        assert!(validate_skill_id_with(".hidden", &rules).is_ok());
+        assert!(validate_skill_id_with("..", &rules).is_err());
+    }
+
+    #[test]
+    fn index_skills_folds_case_when_configured() {
+        let rules = SkillsConfig {
+            case_insensitive: true,
+            ..SkillsConfig::default()
+        };
+        let index = index_skills(Path::new("root"), vec!["MySkill".to_string()], &rules).unwrap();
+        assert_eq!(index.get("myskill").map(String::as_str), Some("MySkill"));
+
+        let err = index_skills(
+            Path::new("root"),
+            vec!["MySkill".to_string(), "myskill".to_string()],
+            &rules,
+        )
+        .unwrap_err();
+        assert!(matches!(err, AppError::Exec { .. }));
+
+        let index = index_skills(
+            Path::new("root"),
+            vec!["MySkill".to_string(), "myskill".to_string()],
+            &SkillsConfig::default(),
+        )
+        .unwrap();
+        assert_eq!(index.len(), 2);
+    }
+
+    #[test]
+    fn validate_skill_id_with_uses_allowed_chars() {
+        let rules = SkillsConfig {
+            allowed_chars: "-_.+".to_string(),
+            ..SkillsConfig::default()
+        };
+        assert!(validate_skill_id_with("a+b", &rules).is_ok());
+        assert!(validate_skill_id_with("my skill", &rules).is_err());
+    }
+
+    #[test]
+    fn parse_skill_list_skips_blank_lines_and_comments() {
+        let text = "# synced nightly\nalpha\n\n  beta  \r\n#gamma\n";
+        let skills = parse_skill_list(text, &SkillsConfig::default()).unwrap();
+        assert_eq!(skills, vec!["alpha", "beta"]);
+
+        let err = parse_skill_list("alpha\n../bad\n", &SkillsConfig::default()).unwrap_err();
+        assert!(matches!(err, AppError::Config { .. }));
+    }
+
+    #[test]
+    fn parse_rename_map_reads_pairs_and_rejects_bad_lines() {
+        let text = "# old=new\nfoo = bar\n\nlegacy-x=x\r\n";
+        let renames = parse_rename_map(text, &SkillsConfig::default()).unwrap();
+        assert_eq!(
+            renames.into_iter().collect::<Vec<_>>(),
+            vec![
+                ("foo".to_string(), "bar".to_string()),
+                ("legacy-x".to_string(), "x".to_string()),
+            ]
+        );
+
+        for text in ["foo\n", "foo=../bar\n", "foo=bar\nfoo=baz\n"] {
+            let err = parse_rename_map(text, &SkillsConfig::default()).unwrap_err();
+            assert!(matches!(err, AppError::Config { .. }), "{text:?}");
+        }
+    }
+
+    #[test]
+    fn init_skill_writes_template_without_overwriting() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = init_skill(dir.path(), "fresh", &SkillsConfig::default(), "SKILL.md").unwrap();
+        let body = fs::read_to_string(path.join("SKILL.md")).unwrap();
+        assert!(body.starts_with("---\nname: fresh\n"));
+
+        let err =
+            init_skill(dir.path(), "fresh", &SkillsConfig::default(), "SKILL.md").unwrap_err();
+        assert!(matches!(err, AppError::Io { .. }));
+
+        let flat = SkillsConfig {
+            layout: SkillLayout::Flat,
+            ..SkillsConfig::default()
+        };
+        let path = init_skill(dir.path(), "single.md", &flat, "SKILL.md").unwrap();
+        assert!(path.is_file());
+    }
 }
@@ -0,0 +1,830 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+
+use flate2::read::GzDecoder;
+use tempfile::TempDir;
+
+use crate::config::HashAlgo;
+use crate::config::{Config, SkillLayout, SkillsConfig};
+use crate::digest::{
+    DigestOptions, SKILL_HASH_FILE, digest_dir, digest_entries, digest_file, fingerprint_dir,
+    fingerprint_file, parse_pinned_algo, pinned_algo,
+};
+use crate::error::{AppError, AppResult};
+use crate::status::{ensure_root_dir, list_skills_with};
+
+/// Read side of a skill root: enumerates skills and digests their contents.
+///
+/// Shared across the worker threads of `status` (see `parallelism`).
+pub trait SkillSource: Sync {
+    fn list_skills(&self) -> AppResult<Vec<String>>;
+    fn digest_skill(&self, skill: &str, options: &DigestOptions) -> AppResult<String>;
+    /// Path/size/mtime fingerprint for `status --fast`; sources without
+    /// cheap metadata return the full digest.
+    fn fingerprint_skill(&self, skill: &str, options: &DigestOptions) -> AppResult<String> {
+        self.digest_skill(skill, options)
+    }
+    /// Hash algorithm pinned by the skill's `.skillhash` marker, if any.
+    fn pinned_algo(&self, skill: &str) -> AppResult<Option<HashAlgo>>;
+    /// Returns a directory holding the skill, extracting it when needed.
+    fn skill_dir(&self, skill: &str) -> AppResult<SkillDir>;
+}
+
+/// A skill directory on disk, kept alive for as long as the value lives.
+pub struct SkillDir {
+    path: PathBuf,
+    _temp: Option<TempDir>,
+}
+
+impl SkillDir {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+pub fn is_archive_path(path: &Path) -> bool {
+    let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+        return false;
+    };
+    let lowered = name.to_ascii_lowercase();
+    lowered.ends_with(".tar") || lowered.ends_with(".tar.gz") || lowered.ends_with(".tgz")
+}
+
+pub fn open_source(root: &Path, rules: &SkillsConfig) -> AppResult<Box<dyn SkillSource>> {
+    if is_archive_path(root) {
+        if rules.layout == SkillLayout::Flat {
+            return Err(AppError::config(
+                crate::tr!(
+                    "flat レイアウトではアーカイブを読み込めません: {}",
+                    "Archives are not supported with the flat layout: {}",
+                    root.display()
+                ),
+                Some(crate::tr!(
+                    "skills.layout = \"nested\" を使うか、展開したディレクトリを指定してください",
+                    "Use skills.layout = \"nested\" or point at an extracted directory."
+                )),
+            ));
+        }
+        Ok(Box::new(TarSource::open(root)?))
+    } else {
+        Ok(Box::new(FsSource::open(root, rules)?))
+    }
+}
+
+/// Opens the read side of `global_root`: the git tree at
+/// `global_git_ref` for a `git:<path>#<ref>` root, the directory otherwise.
+///
+/// Only reads (`status`, `list --global`) go through the revision; push,
+/// import and diff keep working on the checked-out tree at `global_root`.
+pub fn open_global_source(config: &Config) -> AppResult<Box<dyn SkillSource>> {
+    match &config.global_git_ref {
+        Some(git_ref) => {
+            if config.skills.layout == SkillLayout::Flat {
+                return Err(AppError::config(
+                    crate::tr!(
+                        "flat レイアウトでは git の global_root を読み込めません: {}",
+                        "git global roots are not supported with the flat layout: {}",
+                        config.global_root.display()
+                    ),
+                    Some(crate::tr!(
+                        "skills.layout = \"nested\" を使うか、チェックアウトしたディレクトリを指定してください",
+                        "Use skills.layout = \"nested\" or point at a checked-out directory."
+                    )),
+                ));
+            }
+            Ok(Box::new(GitSource::open(&config.global_root, git_ref)?))
+        }
+        None => open_source(&config.global_root, &config.skills),
+    }
+}
+
+pub struct FsSource {
+    root: PathBuf,
+    rules: SkillsConfig,
+}
+
+impl FsSource {
+    pub fn open(root: &Path, rules: &SkillsConfig) -> AppResult<Self> {
+        ensure_root_dir(root)?;
+        Ok(Self {
+            root: root.to_path_buf(),
+            rules: rules.clone(),
+        })
+    }
+}
+
+impl SkillSource for FsSource {
+    fn list_skills(&self) -> AppResult<Vec<String>> {
+        list_skills_with(&self.root, &self.rules)
+    }
+
+    fn digest_skill(&self, skill: &str, options: &DigestOptions) -> AppResult<String> {
+        match self.rules.layout {
+            SkillLayout::Nested => digest_dir(&self.root.join(skill), options),
+            SkillLayout::Flat => digest_file(&self.root.join(skill), options),
+        }
+    }
+
+    fn fingerprint_skill(&self, skill: &str, options: &DigestOptions) -> AppResult<String> {
+        match self.rules.layout {
+            SkillLayout::Nested => fingerprint_dir(&self.root.join(skill), options),
+            SkillLayout::Flat => fingerprint_file(&self.root.join(skill), options),
+        }
+    }
+
+    fn pinned_algo(&self, skill: &str) -> AppResult<Option<HashAlgo>> {
+        match self.rules.layout {
+            SkillLayout::Nested => pinned_algo(&self.root.join(skill)),
+            SkillLayout::Flat => Ok(None),
+        }
+    }
+
+    fn skill_dir(&self, skill: &str) -> AppResult<SkillDir> {
+        Ok(SkillDir {
+            path: self.root.join(skill),
+            _temp: None,
+        })
+    }
+}
+
+#[derive(Debug)]
+enum TarEntry {
+    Dir,
+    /// Content and permission bits from the header.
+    File(Vec<u8>, u32),
+    Unsupported,
+}
+
+pub struct TarSource {
+    archive: PathBuf,
+    skills: BTreeMap<String, Vec<(PathBuf, TarEntry)>>,
+}
+
+impl TarSource {
+    pub fn open(archive: &Path) -> AppResult<Self> {
+        if !archive.is_file() {
+            return Err(AppError::config(
+                crate::tr!(
+                    "root が存在しません: {}",
+                    "Root does not exist: {}",
+                    archive.display()
+                ),
+                Some(crate::tr!(
+                    "config.toml のパスを確認してください",
+                    "Check paths in config.toml"
+                )),
+            ));
+        }
+        let file = File::open(archive).map_err(|err| read_error(archive, err))?;
+        let lowered = archive.to_string_lossy().to_ascii_lowercase();
+        let reader: Box<dyn Read> = if lowered.ends_with(".tar") {
+            Box::new(file)
+        } else {
+            Box::new(GzDecoder::new(file))
+        };
+        let mut tar = tar::Archive::new(reader);
+        let mut skills: BTreeMap<String, Vec<(PathBuf, TarEntry)>> = BTreeMap::new();
+        for entry in tar.entries().map_err(|err| read_error(archive, err))? {
+            let mut entry = entry.map_err(|err| read_error(archive, err))?;
+            let path = entry
+                .path()
+                .map_err(|err| read_error(archive, err))?
+                .into_owned();
+            let Some((skill, rel)) = split_entry_path(archive, &path)? else {
+                continue;
+            };
+            let entry_type = entry.header().entry_type();
+            let kind = if entry_type.is_dir() {
+                TarEntry::Dir
+            } else if entry_type.is_file() {
+                let mode = entry
+                    .header()
+                    .mode()
+                    .map_err(|err| read_error(archive, err))?;
+                let mut data = Vec::new();
+                entry
+                    .read_to_end(&mut data)
+                    .map_err(|err| read_error(archive, err))?;
+                TarEntry::File(data, mode)
+            } else {
+                TarEntry::Unsupported
+            };
+            if rel.as_os_str().is_empty() {
+                if let TarEntry::Dir = kind {
+                    skills.entry(skill).or_default();
+                }
+                continue;
+            }
+            skills.entry(skill).or_default().push((rel, kind));
+        }
+        Ok(Self {
+            archive: archive.to_path_buf(),
+            skills,
+        })
+    }
+}
+
+impl SkillSource for TarSource {
+    fn list_skills(&self) -> AppResult<Vec<String>> {
+        Ok(self.skills.keys().cloned().collect())
+    }
+
+    fn digest_skill(&self, skill: &str, options: &DigestOptions) -> AppResult<String> {
+        let entries = self.skills.get(skill).ok_or_else(|| {
+            AppError::exec(
+                crate::tr!(
+                    "アーカイブに skill が存在しません: {}",
+                    "Skill does not exist in archive: {}",
+                    skill
+                ),
+                Some(self.archive.display().to_string()),
+            )
+        })?;
+        let mut files = Vec::new();
+        let mut empty_dirs = Vec::new();
+        for (rel, entry) in entries {
+            match entry {
+                TarEntry::Dir => {
+                    if !entries
+                        .iter()
+                        .any(|(other, _)| other != rel && other.starts_with(rel))
+                    {
+                        empty_dirs.push(rel.clone());
+                    }
+                }
+                TarEntry::File(data, mode) => {
+                    files.push((rel.clone(), data.as_slice(), cfg!(unix).then_some(*mode)))
+                }
+                TarEntry::Unsupported => {
+                    return Err(AppError::exec(
+                        crate::tr!(
+                            "未対応のファイル種別です: {}",
+                            "Unsupported file type: {}",
+                            Path::new(skill).join(rel).display()
+                        ),
+                        Some(crate::tr!(
+                            "通常ファイルのみを含めてください",
+                            "Include only regular files."
+                        )),
+                    ));
+                }
+            }
+        }
+        let options = DigestOptions {
+            algo: self.pinned_algo(skill)?.unwrap_or(options.algo),
+            ..*options
+        };
+        Ok(digest_entries(files, empty_dirs, &options))
+    }
+
+    fn pinned_algo(&self, skill: &str) -> AppResult<Option<HashAlgo>> {
+        let marker = self.skills.get(skill).and_then(|entries| {
+            entries.iter().find_map(|(rel, entry)| match entry {
+                TarEntry::File(data, _) if rel == Path::new(SKILL_HASH_FILE) => Some(data),
+                _ => None,
+            })
+        });
+        let Some(data) = marker else {
+            return Ok(None);
+        };
+        let marker_path = self.archive.join(skill).join(SKILL_HASH_FILE);
+        parse_pinned_algo(&marker_path, &String::from_utf8_lossy(data)).map(Some)
+    }
+
+    fn skill_dir(&self, skill: &str) -> AppResult<SkillDir> {
+        let Some(entries) = self.skills.get(skill) else {
+            return Ok(SkillDir {
+                path: self.archive.join(skill),
+                _temp: None,
+            });
+        };
+        let temp = TempDir::new().map_err(|err| {
+            AppError::io(
+                crate::tr!(
+                    "一時ディレクトリの作成に失敗しました: {}",
+                    "Failed to create temp directory: {}",
+                    std::env::temp_dir().display()
+                ),
+                err,
+            )
+        })?;
+        let path = temp.path().join(skill);
+        create_dir(&path)?;
+        for (rel, entry) in entries {
+            let dest = path.join(rel);
+            match entry {
+                TarEntry::Dir => create_dir(&dest)?,
+                TarEntry::File(data, _) => {
+                    if let Some(parent) = dest.parent() {
+                        create_dir(parent)?;
+                    }
+                    fs::write(&dest, data).map_err(|err| {
+                        AppError::io(
+                            crate::tr!(
+                                "ファイルの書き込みに失敗しました: {}",
+                                "Failed to write file: {}",
+                                dest.display()
+                            ),
+                            err,
+                        )
+                    })?;
+                }
+                TarEntry::Unsupported => {}
+            }
+        }
+        Ok(SkillDir {
+            path,
+            _temp: Some(temp),
+        })
+    }
+}
+
+/// A blob in a git tree: its object id and whether it is executable.
+#[derive(Debug)]
+enum GitEntry {
+    Blob {
+        id: String,
+        executable: bool,
+    },
+    /// Symlinks and submodules.
+    Unsupported,
+}
+
+/// Skills read from a git revision (`global_root = "git:<path>#<ref>"`)
+/// through the `git` command, without touching the work tree.
+pub struct GitSource {
+    repo: PathBuf,
+    git_ref: String,
+    skills: BTreeMap<String, Vec<(PathBuf, GitEntry)>>,
+}
+
+impl GitSource {
+    /// Lists the tree of `git_ref` at `repo`, which may be a subdirectory of
+    /// the repository (its tree at that revision is used); file contents are
+    /// read per skill when it is digested or extracted.
+    pub fn open(repo: &Path, git_ref: &str) -> AppResult<Self> {
+        ensure_root_dir(repo)?;
+        let prefix = run_git(repo, &["rev-parse", "--show-prefix"], None)
+            .map_err(|err| err.into_app(repo))?;
+        let prefix = String::from_utf8_lossy(&prefix);
+        let prefix = prefix.trim_end().trim_end_matches('/');
+        let tree = run_git(
+            repo,
+            &[
+                "rev-parse",
+                "--verify",
+                "--end-of-options",
+                &format!("{git_ref}:{prefix}"),
+            ],
+            None,
+        )
+        .map_err(|err| match err {
+            GitError::Failed(stderr) => AppError::config(
+                crate::tr!(
+                    "git のリビジョンを解決できません: {}#{}",
+                    "Cannot resolve git revision: {}#{}",
+                    repo.display(),
+                    git_ref
+                ),
+                Some(stderr),
+            ),
+            GitError::App(err) => err,
+        })?;
+        let tree = String::from_utf8_lossy(&tree).trim().to_string();
+        let listing = run_git(repo, &["ls-tree", "-r", "-z", "--full-tree", &tree], None)
+            .map_err(|err| err.into_app(repo))?;
+        let mut skills: BTreeMap<String, Vec<(PathBuf, GitEntry)>> = BTreeMap::new();
+        for record in listing.split(|byte| *byte == 0).filter(|r| !r.is_empty()) {
+            let record = String::from_utf8_lossy(record);
+            let Some((meta, path)) = record.split_once('\t') else {
+                continue;
+            };
+            let mut meta = meta.split(' ');
+            let (Some(mode), Some(kind), Some(id)) = (meta.next(), meta.next(), meta.next()) else {
+                continue;
+            };
+            let Some((skill, rel)) = path.split_once('/') else {
+                // Top-level files are not skills in the nested layout.
+                continue;
+            };
+            let entry = match (kind, mode) {
+                ("blob", "100644" | "100755") => GitEntry::Blob {
+                    id: id.to_string(),
+                    executable: mode == "100755",
+                },
+                _ => GitEntry::Unsupported,
+            };
+            skills
+                .entry(skill.to_string())
+                .or_default()
+                .push((PathBuf::from(rel), entry));
+        }
+        Ok(Self {
+            repo: repo.to_path_buf(),
+            git_ref: git_ref.to_string(),
+            skills,
+        })
+    }
+
+    fn entries(&self, skill: &str) -> AppResult<&[(PathBuf, GitEntry)]> {
+        self.skills.get(skill).map(Vec::as_slice).ok_or_else(|| {
+            AppError::exec(
+                crate::tr!(
+                    "git リビジョンに skill が存在しません: {}",
+                    "Skill does not exist at git revision: {}",
+                    skill
+                ),
+                Some(format!("{}#{}", self.repo.display(), self.git_ref)),
+            )
+        })
+    }
+
+    /// Reads the files of `skill` as `(relative, content, mode)`, failing on
+    /// symlinks and submodules like a directory walk does.
+    fn read_files(&self, skill: &str) -> AppResult<Vec<(PathBuf, Vec<u8>, u32)>> {
+        let entries = self.entries(skill)?;
+        let mut ids = Vec::new();
+        for (rel, entry) in entries {
+            match entry {
+                GitEntry::Blob { id, .. } => ids.push(id.as_str()),
+                GitEntry::Unsupported => {
+                    return Err(AppError::exec(
+                        crate::tr!(
+                            "未対応のファイル種別です: {}",
+                            "Unsupported file type: {}",
+                            Path::new(skill).join(rel).display()
+                        ),
+                        Some(crate::tr!(
+                            "通常ファイルのみを含めてください",
+                            "Include only regular files."
+                        )),
+                    ));
+                }
+            }
+        }
+        let blobs = self.cat_blobs(&ids)?;
+        let files = entries
+            .iter()
+            .zip(blobs)
+            .map(|((rel, entry), data)| {
+                let executable = matches!(
+                    entry,
+                    GitEntry::Blob {
+                        executable: true,
+                        ..
+                    }
+                );
+                (rel.clone(), data, if executable { 0o755 } else { 0o644 })
+            })
+            .collect();
+        Ok(files)
+    }
+
+    /// Contents of the blobs `ids`, in order, from one `git cat-file --batch`.
+    fn cat_blobs(&self, ids: &[&str]) -> AppResult<Vec<Vec<u8>>> {
+        let mut input = String::new();
+        for id in ids {
+            input.push_str(id);
+            input.push('\n');
+        }
+        let mut batch = run_git(
+            &self.repo,
+            &["cat-file", "--batch"],
+            Some(input.into_bytes()),
+        )
+        .map_err(|err| err.into_app(&self.repo))?
+        .into_iter();
+        let mut blobs = Vec::with_capacity(ids.len());
+        for _ in ids {
+            let header = batch
+                .by_ref()
+                .take_while(|byte| *byte != b'\n')
+                .collect::<Vec<_>>();
+            let size = String::from_utf8_lossy(&header)
+                .rsplit(' ')
+                .next()
+                .and_then(|size| size.parse::<usize>().ok())
+                .ok_or_else(|| {
+                    GitError::Failed(String::from_utf8_lossy(&header).into_owned())
+                        .into_app(&self.repo)
+                })?;
+            blobs.push(batch.by_ref().take(size).collect::<Vec<_>>());
+            batch.next();
+        }
+        Ok(blobs)
+    }
+}
+
+impl SkillSource for GitSource {
+    fn list_skills(&self) -> AppResult<Vec<String>> {
+        Ok(self.skills.keys().cloned().collect())
+    }
+
+    fn digest_skill(&self, skill: &str, options: &DigestOptions) -> AppResult<String> {
+        let files = self.read_files(skill)?;
+        let algo = match files
+            .iter()
+            .find(|(rel, _, _)| rel == Path::new(SKILL_HASH_FILE))
+        {
+            Some((_, data, _)) => Some(parse_pinned_algo(
+                &self.repo.join(skill).join(SKILL_HASH_FILE),
+                &String::from_utf8_lossy(data),
+            )?),
+            None => None,
+        };
+        let options = DigestOptions {
+            algo: algo.unwrap_or(options.algo),
+            ..*options
+        };
+        let files = files
+            .iter()
+            .map(|(rel, data, mode)| (rel.clone(), data.as_slice(), cfg!(unix).then_some(*mode)))
+            .collect();
+        // Git does not track directories, so there are never empty ones.
+        Ok(digest_entries(files, Vec::new(), &options))
+    }
+
+    fn pinned_algo(&self, skill: &str) -> AppResult<Option<HashAlgo>> {
+        let marker = self
+            .entries(skill)?
+            .iter()
+            .find_map(|(rel, entry)| match entry {
+                GitEntry::Blob { id, .. } if rel == Path::new(SKILL_HASH_FILE) => Some(id.as_str()),
+                _ => None,
+            });
+        let Some(id) = marker else {
+            return Ok(None);
+        };
+        let data = self.cat_blobs(&[id])?.pop().unwrap_or_default();
+        parse_pinned_algo(
+            &self.repo.join(skill).join(SKILL_HASH_FILE),
+            &String::from_utf8_lossy(&data),
+        )
+        .map(Some)
+    }
+
+    fn skill_dir(&self, skill: &str) -> AppResult<SkillDir> {
+        if !self.skills.contains_key(skill) {
+            return Ok(SkillDir {
+                path: self.repo.join(skill),
+                _temp: None,
+            });
+        }
+        let temp = TempDir::new().map_err(|err| {
+            AppError::io(
+                crate::tr!(
+                    "一時ディレクトリの作成に失敗しました: {}",
+                    "Failed to create temp directory: {}",
+                    std::env::temp_dir().display()
+                ),
+                err,
+            )
+        })?;
+        let path = temp.path().join(skill);
+        create_dir(&path)?;
+        for (rel, data, mode) in self.read_files(skill)? {
+            let dest = path.join(rel);
+            if let Some(parent) = dest.parent() {
+                create_dir(parent)?;
+            }
+            fs::write(&dest, data).map_err(|err| {
+                AppError::io(
+                    crate::tr!(
+                        "ファイルの書き込みに失敗しました: {}",
+                        "Failed to write file: {}",
+                        dest.display()
+                    ),
+                    err,
+                )
+            })?;
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                let _ = fs::set_permissions(&dest, fs::Permissions::from_mode(mode));
+            }
+            #[cfg(not(unix))]
+            let _ = mode;
+        }
+        Ok(SkillDir {
+            path,
+            _temp: Some(temp),
+        })
+    }
+}
+
+enum GitError {
+    /// `git` ran and exited non-zero, with its stderr.
+    Failed(String),
+    App(AppError),
+}
+
+impl GitError {
+    fn into_app(self, repo: &Path) -> AppError {
+        match self {
+            GitError::Failed(stderr) => AppError::exec(
+                crate::tr!(
+                    "git の実行に失敗しました: {}",
+                    "git failed in {}",
+                    repo.display()
+                ),
+                Some(stderr),
+            ),
+            GitError::App(err) => err,
+        }
+    }
+}
+
+/// Runs `git -C <repo> <args>`, feeding `input` on stdin, and returns stdout.
+fn run_git(repo: &Path, args: &[&str], input: Option<Vec<u8>>) -> Result<Vec<u8>, GitError> {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    let mut child = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(args)
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| {
+            GitError::App(AppError::exec_with_source(
+                crate::tr!("git を起動できません", "Failed to start git"),
+                Some(crate::tr!(
+                    "git をインストールして PATH に含めてください",
+                    "Install git and put it on PATH."
+                )),
+                err,
+            ))
+        })?;
+    // Write from another thread so a full stdout pipe cannot deadlock us.
+    let writer = match (input, child.stdin.take()) {
+        (Some(input), Some(mut stdin)) => Some(std::thread::spawn(move || stdin.write_all(&input))),
+        _ => None,
+    };
+    let output = child.wait_with_output().map_err(|err| {
+        GitError::App(AppError::io(
+            crate::tr!(
+                "git の実行に失敗しました: {}",
+                "git failed in {}",
+                repo.display()
+            ),
+            err,
+        ))
+    })?;
+    if let Some(writer) = writer {
+        let _ = writer.join();
+    }
+    if !output.status.success() {
+        return Err(GitError::Failed(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    Ok(output.stdout)
+}
+
+fn split_entry_path(archive: &Path, path: &Path) -> AppResult<Option<(String, PathBuf)>> {
+    let mut parts = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::Normal(part) => parts.push(part),
+            _ => {
+                return Err(AppError::exec(
+                    crate::tr!(
+                        "アーカイブに不正なパスが含まれています: {}",
+                        "Archive contains an invalid path: {}",
+                        path.display()
+                    ),
+                    Some(archive.display().to_string()),
+                ));
+            }
+        }
+    }
+    let mut parts = parts.into_iter();
+    let Some(skill) = parts.next() else {
+        return Ok(None);
+    };
+    let Some(skill) = skill.to_str() else {
+        return Ok(None);
+    };
+    Ok(Some((skill.to_string(), parts.collect())))
+}
+
+fn create_dir(path: &Path) -> AppResult<()> {
+    fs::create_dir_all(path).map_err(|err| {
+        AppError::io(
+            crate::tr!(
+                "ディレクトリ作成に失敗しました: {}",
+                "Failed to create directory: {}",
+                path.display()
+            ),
+            err,
+        )
+    })
+}
+
+fn read_error(archive: &Path, err: std::io::Error) -> AppError {
+    AppError::config_with_source(
+        crate::tr!(
+            "アーカイブを読み込めません: {}",
+            "Cannot read archive: {}",
+            archive.display()
+        ),
+        Some(err.to_string()),
+        err,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::config::HashAlgo;
+
+    fn write_tar_gz(path: &Path, files: &[(&str, &str)]) {
+        let file = File::create(path).unwrap();
+        let encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+        for (name, contents) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, name, contents.as_bytes())
+                .unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap();
+    }
+
+    #[test]
+    fn archive_path_detected_by_extension() {
+        assert!(is_archive_path(Path::new("skills.tar")));
+        assert!(is_archive_path(Path::new("skills.tar.gz")));
+        assert!(is_archive_path(Path::new("skills.TGZ")));
+        assert!(!is_archive_path(Path::new("skills")));
+        assert!(!is_archive_path(Path::new("skills.gz")));
+    }
+
+    #[test]
+    fn tar_source_digest_matches_directory() {
+        let dir = TempDir::new().unwrap();
+        let skill_dir = dir.path().join("fs/skill1");
+        fs::create_dir_all(skill_dir.join("nested")).unwrap();
+        fs::write(skill_dir.join("SKILL.md"), "doc").unwrap();
+        fs::write(skill_dir.join("nested/a.txt"), "a").unwrap();
+        let archive = dir.path().join("skills.tar.gz");
+        write_tar_gz(
+            &archive,
+            &[("./skill1/nested/a.txt", "a"), ("skill1/SKILL.md", "doc")],
+        );
+
+        let source = TarSource::open(&archive).unwrap();
+        assert_eq!(source.list_skills().unwrap(), vec!["skill1".to_string()]);
+        let from_tar = source
+            .digest_skill("skill1", &DigestOptions::new(HashAlgo::Blake3))
+            .unwrap();
+        let from_fs = digest_dir(&skill_dir, &DigestOptions::new(HashAlgo::Blake3)).unwrap();
+        assert_eq!(from_tar, from_fs);
+
+        let merkle = DigestOptions {
+            merkle: true,
+            ..DigestOptions::new(HashAlgo::Blake3)
+        };
+        assert_eq!(
+            source.digest_skill("skill1", &merkle).unwrap(),
+            digest_dir(&skill_dir, &merkle).unwrap()
+        );
+    }
+
+    #[test]
+    fn tar_source_extracts_skill_dir() {
+        let dir = TempDir::new().unwrap();
+        let archive = dir.path().join("skills.tgz");
+        write_tar_gz(&archive, &[("skill1/nested/a.txt", "a")]);
+
+        let source = TarSource::open(&archive).unwrap();
+        let skill_dir = source.skill_dir("skill1").unwrap();
+        let contents = fs::read_to_string(skill_dir.path().join("nested/a.txt")).unwrap();
+        assert_eq!(contents, "a");
+    }
+
+    #[test]
+    fn tar_source_errors_when_missing() {
+        let dir = TempDir::new().unwrap();
+        let err = open_source(&dir.path().join("missing.tar"), &SkillsConfig::default())
+            .err()
+            .unwrap();
+        assert!(matches!(err, AppError::Config { .. }));
+    }
+}
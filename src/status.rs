@@ -1,21 +1,74 @@
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt;
 use std::fs;
 use std::io::Write;
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
 
-use tabwriter::TabWriter;
+use unicode_width::UnicodeWidthStr;
 
-use crate::config::{Config, Target};
-use crate::digest::{build_ignore_set, digest_dir, short_digest};
+use crate::checkpoint::CHECKPOINT_FILE;
+use crate::color::{Color, paint};
+use crate::config::{Config, DigestEncoding, SkillLayout, SkillsConfig, Target};
+use crate::digest::{
+    self, DEFAULT_DIGEST_LEN, DigestOptions, FileChange, build_ignore_set, encode_digest,
+    short_digest, short_digest_width,
+};
 use crate::error::{AppError, AppResult};
+use crate::lock::LOCK_FILE;
+use crate::merkle::TreeCache;
+use crate::skill::{index_skills, not_found_hint, skill_key, validate_skill_id_with};
+use crate::source::{SkillSource, open_global_source, open_source};
+use crate::store::{DigestStore, SYNC_STATE_FILE};
+use crate::sync::Selection;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
 pub enum State {
     Missing,
     Same,
     Diff,
     Extra,
+    /// Missing in target under the global name but present under a name that
+    /// differs only by case (`status.detect_renames`).
+    Renamed,
+    /// Fingerprints match (`status.fast`); content was not compared.
+    LikelySame,
+    /// Fingerprints differ (`status.fast`); content may still be equal.
+    LikelyDiff,
+}
+
+impl State {
+    /// Rank under `--sort state`: what needs attention first.
+    fn sort_priority(self) -> u8 {
+        match self {
+            State::Diff | State::LikelyDiff => 0,
+            State::Renamed => 1,
+            State::Missing => 2,
+            State::Extra => 3,
+            State::Same | State::LikelySame => 4,
+        }
+    }
+}
+
+/// Row order for `status`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum StatusSort {
+    /// By skill name (the order rows are produced in).
+    #[default]
+    Name,
+    /// By state (diff, renamed, missing, extra, same), then by skill name.
+    State,
+}
+
+/// Reorders `rows`, which must already be in name order, for `sort`.
+pub fn sort_status_rows(rows: &mut [StatusRow], sort: StatusSort) {
+    if sort == StatusSort::State {
+        // Stable, so rows keep their name order within a state.
+        rows.sort_by_key(|row| row.state.sort_priority());
+    }
 }
 
 impl fmt::Display for State {
@@ -25,6 +78,9 @@ impl fmt::Display for State {
             State::Same => "same",
             State::Diff => "diff",
             State::Extra => "extra",
+            State::Renamed => "renamed",
+            State::LikelySame => "likely-same",
+            State::LikelyDiff => "likely-diff",
         };
         f.write_str(s)
     }
@@ -36,11 +92,27 @@ pub struct StatusRow {
     pub state: State,
     pub global_digest: Option<String>,
     pub target_digest: Option<String>,
+    /// Target-side name of a [`State::Renamed`] skill.
+    pub renamed_to: Option<String>,
+    /// Differing files of a [`State::Diff`] skill; filled only with
+    /// `status.detail`.
+    pub file_changes: Vec<FileChange>,
+}
+
+impl StatusRow {
+    /// Text for the SKILL column: `Foo -> foo` for renamed skills.
+    pub fn label(&self) -> String {
+        match &self.renamed_to {
+            Some(target) => format!("{} -> {}", self.skill, target),
+            None => self.skill.clone(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(crate) enum RootEntryKind {
     Directory,
+    File,
     Symlink,
 }
 
@@ -55,34 +127,34 @@ pub(crate) fn root_entries(root: &Path) -> AppResult<Vec<RootEntry>> {
     ensure_root_dir(root)?;
     let mut entries = Vec::new();
     for entry in fs::read_dir(root).map_err(|err| {
-        AppError::config(
+        AppError::io(
             crate::tr!(
                 "ディレクトリを読み込めません: {}",
                 "Cannot read directory: {}",
                 root.display()
             ),
-            Some(err.to_string()),
+            err,
         )
     })? {
         let entry = entry.map_err(|err| {
-            AppError::config(
+            AppError::io(
                 crate::tr!(
                     "ディレクトリを読み込めません: {}",
                     "Cannot read directory: {}",
                     root.display()
                 ),
-                Some(err.to_string()),
+                err,
             )
         })?;
         let path = entry.path();
         let file_type = entry.file_type().map_err(|err| {
-            AppError::exec(
+            AppError::io(
                 crate::tr!(
                     "ディレクトリを読み込めません: {}",
                     "Cannot read directory: {}",
                     root.display()
                 ),
-                Some(err.to_string()),
+                err,
             )
         })?;
         let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
@@ -100,15 +172,41 @@ pub(crate) fn root_entries(root: &Path) -> AppResult<Vec<RootEntry>> {
                 path,
                 kind: RootEntryKind::Directory,
             });
+        } else if file_type.is_file() {
+            entries.push(RootEntry {
+                name: name.to_string(),
+                path,
+                kind: RootEntryKind::File,
+            });
         }
     }
     entries.sort_by(|a, b| a.name.cmp(&b.name));
     Ok(entries)
 }
 
-pub fn list_skills(root: &Path) -> AppResult<Vec<String>> {
+/// Nested layout: every top-level directory is a skill, and so is a symlink
+/// to a directory under `skills.allow_symlink_dirs`.
+fn list_nested_skills(root: &Path, rules: &SkillsConfig) -> AppResult<Vec<String>> {
     let mut skills = Vec::new();
     for entry in root_entries(root)? {
+        if entry.kind == RootEntryKind::Symlink && rules.allow_symlink_dirs {
+            // `is_dir` follows the link; dangling links and links to files fail it.
+            if !entry.path.is_dir() {
+                return Err(AppError::exec(
+                    crate::tr!(
+                        "シンボリックリンクの参照先がディレクトリではありません: {}",
+                        "Symlink does not point to a directory: {}",
+                        entry.path.display()
+                    ),
+                    Some(crate::tr!(
+                        "リンク先を確認するか、リンクを削除してください",
+                        "Fix the link target or remove the link."
+                    )),
+                ));
+            }
+            skills.push(entry.name);
+            continue;
+        }
         if entry.kind == RootEntryKind::Symlink {
             return Err(AppError::exec(
                 crate::tr!(
@@ -129,110 +227,1064 @@ pub fn list_skills(root: &Path) -> AppResult<Vec<String>> {
     Ok(skills)
 }
 
+/// Lists skills under `root` according to `skills.layout`.
+pub fn list_skills_with(root: &Path, rules: &SkillsConfig) -> AppResult<Vec<String>> {
+    match rules.layout {
+        SkillLayout::Nested => list_nested_skills(root, rules),
+        SkillLayout::Flat => list_flat_skills(root, rules),
+    }
+}
+
+/// Flat layout: every top-level regular file is a skill; dotfiles are skipped
+/// unless `skills.allow_hidden` is set.
+fn list_flat_skills(root: &Path, rules: &SkillsConfig) -> AppResult<Vec<String>> {
+    let mut skills = Vec::new();
+    for entry in root_entries(root)? {
+        match entry.kind {
+            RootEntryKind::Symlink => {
+                return Err(AppError::exec(
+                    crate::tr!(
+                        "シンボリックリンクは未対応です: {}",
+                        "Symlinks are not supported: {}",
+                        entry.path.display()
+                    ),
+                    Some(crate::tr!(
+                        "通常のファイルを配置してください",
+                        "Use a regular file."
+                    )),
+                ));
+            }
+            RootEntryKind::File
+                if entry.name == LOCK_FILE
+                    || entry.name == CHECKPOINT_FILE
+                    || entry.name == SYNC_STATE_FILE => {}
+            RootEntryKind::File if rules.allow_hidden || !entry.name.starts_with('.') => {
+                skills.push(entry.name);
+            }
+            RootEntryKind::File | RootEntryKind::Directory => {}
+        }
+    }
+    Ok(skills)
+}
+
+/// Lists the skills of `source` with their full digests, using the configured algo and ignore set.
+pub fn list_skill_digests(
+    config: &Config,
+    source: &dyn SkillSource,
+) -> AppResult<Vec<(String, String)>> {
+    let ignore = build_ignore_set(&config.hash.ignore)?;
+    let options = DigestOptions::from_config(&config.hash, ignore.as_ref());
+    source
+        .list_skills()?
+        .into_iter()
+        .map(|skill| {
+            let digest = source.digest_skill(&skill, &options)?;
+            Ok((skill, digest))
+        })
+        .collect()
+}
+
+/// Full digest of one skill of `source`, using the configured algo and
+/// ignore set; an invalid id is a config error, a missing skill an exec error.
+pub fn skill_digest(config: &Config, source: &dyn SkillSource, skill: &str) -> AppResult<String> {
+    validate_skill_id_with(skill, &config.skills)?;
+    let skills = source.list_skills()?;
+    if !skills.iter().any(|name| name == skill) {
+        return Err(AppError::exec(
+            crate::tr!(
+                "skill が存在しません: {}",
+                "Skill does not exist: {}",
+                skill
+            ),
+            Some(not_found_hint(
+                skill,
+                skills.iter().map(String::as_str),
+                crate::tr!(
+                    "list で一覧を確認してください",
+                    "Run list to see available skills."
+                ),
+            )),
+        ));
+    }
+    let ignore = build_ignore_set(&config.hash.ignore)?;
+    let options = DigestOptions::from_config(&config.hash, ignore.as_ref());
+    source.digest_skill(skill, &options)
+}
+
+/// Wall-clock time spent digesting one skill on both sides.
+#[derive(Debug, Clone)]
+pub struct SkillTiming {
+    pub target: String,
+    pub skill: String,
+    pub elapsed: Duration,
+}
+
+/// Renders timings slowest first, one `<millis>ms <target>/<skill>` line each.
+pub fn render_timings(timings: &[SkillTiming]) -> String {
+    let mut sorted = timings.iter().collect::<Vec<_>>();
+    sorted.sort_by(|a, b| {
+        b.elapsed
+            .cmp(&a.elapsed)
+            .then_with(|| a.skill.cmp(&b.skill))
+    });
+    sorted
+        .into_iter()
+        .map(|timing| {
+            format!(
+                "{:>10.3}ms {}/{}\n",
+                timing.elapsed.as_secs_f64() * 1000.0,
+                timing.target,
+                timing.skill
+            )
+        })
+        .collect()
+}
+
 pub fn status_for_target(config: &Config, target: &Target) -> AppResult<Vec<StatusRow>> {
-    ensure_root_dir(&config.global_root)?;
-    ensure_root_dir(&target.root)?;
+    let mut rows = Vec::new();
+    status_rows(config, target, Selection::All, None, &mut rows)?;
+    Ok(rows)
+}
 
-    let global_skills = list_skills(&config.global_root)?;
-    let target_skills = list_skills(&target.root)?;
+/// Receives status rows one at a time, in skill order.
+pub trait StatusSink {
+    /// Called once with every skill name before any digest is computed.
+    fn begin(&mut self, _skills: &[&str]) -> AppResult<()> {
+        Ok(())
+    }
+
+    fn row(&mut self, row: StatusRow) -> AppResult<()>;
+}
+
+/// Counts rows in any of the given states without keeping them (`status --count`).
+pub struct StatusCounter<'a> {
+    states: &'a [State],
+    count: usize,
+}
+
+impl<'a> StatusCounter<'a> {
+    pub fn new(states: &'a [State]) -> Self {
+        Self { states, count: 0 }
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+}
+
+impl StatusSink for StatusCounter<'_> {
+    fn row(&mut self, row: StatusRow) -> AppResult<()> {
+        if self.states.contains(&row.state) {
+            self.count += 1;
+        }
+        Ok(())
+    }
+}
+
+impl StatusSink for Vec<StatusRow> {
+    fn row(&mut self, row: StatusRow) -> AppResult<()> {
+        self.push(row);
+        Ok(())
+    }
+}
+
+/// Like [`status_for_target`], but hands each row to `sink` as soon as it is
+/// computed instead of collecting them, so memory does not grow with digests.
+/// When `timings` is given, per-skill digest timings are pushed onto it.
+pub fn status_for_target_streaming(
+    config: &Config,
+    target: &Target,
+    timings: Option<&mut Vec<SkillTiming>>,
+    sink: &mut dyn StatusSink,
+) -> AppResult<()> {
+    status_rows(config, target, Selection::All, timings, sink)
+}
+
+/// Like [`status_for_target_streaming`], limited to the skills in
+/// `selection`; a named skill found in neither root is an error.
+pub fn status_for_target_selected(
+    config: &Config,
+    target: &Target,
+    selection: Selection<'_>,
+    timings: Option<&mut Vec<SkillTiming>>,
+    sink: &mut dyn StatusSink,
+) -> AppResult<()> {
+    status_rows(config, target, selection, timings, sink)
+}
+
+fn status_rows(
+    config: &Config,
+    target: &Target,
+    selection: Selection<'_>,
+    mut timings: Option<&mut Vec<SkillTiming>>,
+    sink: &mut dyn StatusSink,
+) -> AppResult<()> {
+    let target_label = target.name.clone();
+    let global = open_global_source(config)?;
+    let target_root = &target.root;
+    let target = open_source(target_root, &config.skills)?;
+
+    let global_skills = index_skills(&config.global_root, global.list_skills()?, &config.skills)?;
+    let target_skills = index_skills(target_root, target.list_skills()?, &config.skills)?;
+
+    let renames = if config.status.detect_renames {
+        case_renames(&global_skills, &target_skills)
+    } else {
+        BTreeMap::new()
+    };
+    let renamed_targets = renames.values().collect::<BTreeSet<_>>();
 
     let mut all = BTreeSet::new();
-    all.extend(global_skills.iter().cloned());
-    all.extend(target_skills.iter().cloned());
+    all.extend(global_skills.keys().cloned());
+    all.extend(
+        target_skills
+            .keys()
+            .filter(|key| !renamed_targets.contains(key))
+            .cloned(),
+    );
+    if let Some(skills) = selection.names() {
+        let mut selected = BTreeSet::new();
+        for skill in skills {
+            validate_skill_id_with(skill, &config.skills)?;
+            let key = skill_key(skill, &config.skills);
+            if !global_skills.contains_key(&key) && !target_skills.contains_key(&key) {
+                return Err(AppError::exec(
+                    crate::tr!(
+                        "global にもターゲットにも skill が存在しません: {}",
+                        "Skill exists in neither global nor target: {}",
+                        skill
+                    ),
+                    Some(crate::tr!(
+                        "list --global / list --target <name> で一覧を確認してください",
+                        "Run list --global or list --target <name> to see available skills."
+                    )),
+                ));
+            }
+            selected.insert(key);
+        }
+        all.retain(|key| {
+            selected.contains(key) || renames.get(key).is_some_and(|to| selected.contains(to))
+        });
+    }
+
+    let labels = all
+        .iter()
+        .filter_map(|key| {
+            let name = global_skills.get(key).or_else(|| target_skills.get(key))?;
+            Some(match renames.get(key) {
+                Some(target_key) => format!("{} -> {}", name, target_skills[target_key]),
+                None => name.clone(),
+            })
+        })
+        .collect::<Vec<_>>();
+    let names = labels.iter().map(String::as_str).collect::<Vec<_>>();
+    sink.begin(&names)?;
 
     let ignore = build_ignore_set(&config.hash.ignore)?;
-    let mut rows = Vec::new();
-    for skill in all {
-        let global_path = config.global_root.join(&skill);
-        let target_path = target.root.join(&skill);
-        let global_exists = global_path.is_dir();
-        let target_exists = target_path.is_dir();
-        let (state, global_digest, target_digest) = match (global_exists, target_exists) {
-            (true, true) => {
-                let g = digest_dir(&global_path, config.hash.algo, ignore.as_ref())?;
-                let t = digest_dir(&target_path, config.hash.algo, ignore.as_ref())?;
-                if g == t {
-                    (State::Same, Some(g), Some(t))
+    let tree_cache = config
+        .hash
+        .merkle_cache
+        .as_deref()
+        .map(TreeCache::load)
+        .transpose()?;
+    let options = DigestOptions {
+        tree_cache: tree_cache.as_ref(),
+        ..DigestOptions::from_config(&config.hash, ignore.as_ref())
+    };
+    let skill_digest = |source: &dyn SkillSource, skill: &str| {
+        if config.status.fast {
+            source.fingerprint_skill(skill, &options)
+        } else {
+            source.digest_skill(skill, &options)
+        }
+    };
+    let all = all.into_iter().collect::<Vec<_>>();
+    let digest_row = |key: &String| -> AppResult<Option<(StatusRow, Duration)>> {
+        let started = Instant::now();
+        let global_name = global_skills.get(key);
+        let renamed_to = renames
+            .get(key)
+            .map(|target_key| &target_skills[target_key]);
+        let target_name = target_skills.get(key).or(renamed_to);
+        let mut file_changes = Vec::new();
+        let (skill, state, global_digest, target_digest) = match (global_name, target_name) {
+            (Some(g_name), Some(t_name)) => {
+                // A pin on either side applies to both so the digests stay comparable.
+                let pinned = match global.pinned_algo(g_name)? {
+                    Some(algo) => Some(algo),
+                    None => target.pinned_algo(t_name)?,
+                };
+                let options = DigestOptions {
+                    algo: pinned.unwrap_or(options.algo),
+                    ..options
+                };
+                let (g, t) = if config.status.fast {
+                    (
+                        global.fingerprint_skill(g_name, &options)?,
+                        target.fingerprint_skill(t_name, &options)?,
+                    )
                 } else {
-                    (State::Diff, Some(g), Some(t))
+                    (
+                        global.digest_skill(g_name, &options)?,
+                        target.digest_skill(t_name, &options)?,
+                    )
+                };
+                let state = match (renamed_to.is_some(), g == t, config.status.fast) {
+                    (true, _, _) => State::Renamed,
+                    (false, true, false) => State::Same,
+                    (false, false, false) => State::Diff,
+                    (false, true, true) => State::LikelySame,
+                    (false, false, true) => State::LikelyDiff,
+                };
+                if state == State::Diff
+                    && config.status.detail
+                    && config.skills.layout == SkillLayout::Nested
+                {
+                    let g_dir = global.skill_dir(g_name)?;
+                    let t_dir = target.skill_dir(t_name)?;
+                    file_changes = digest::file_changes(g_dir.path(), t_dir.path(), &options)?;
                 }
+                (g_name.clone(), state, Some(g), Some(t))
             }
-            (true, false) => (
+            (Some(g_name), None) => (
+                g_name.clone(),
                 State::Missing,
-                Some(digest_dir(&global_path, config.hash.algo, ignore.as_ref())?),
+                Some(skill_digest(&*global, g_name)?),
                 None,
             ),
-            (false, true) => (
+            (None, Some(t_name)) => (
+                t_name.clone(),
                 State::Extra,
                 None,
-                Some(digest_dir(&target_path, config.hash.algo, ignore.as_ref())?),
+                Some(skill_digest(&*target, t_name)?),
             ),
-            (false, false) => continue,
+            (None, None) => return Ok(None),
         };
-        rows.push(StatusRow {
+        let row = StatusRow {
             skill,
             state,
             global_digest,
             target_digest,
-        });
+            renamed_to: renamed_to.cloned(),
+            file_changes,
+        };
+        Ok(Some((row, started.elapsed())))
+    };
+    for_each_ordered(&all, config.jobs(), digest_row, |digested| {
+        let Some((row, elapsed)) = digested else {
+            return Ok(());
+        };
+        if let Some(timings) = timings.as_deref_mut() {
+            timings.push(SkillTiming {
+                target: target_label.clone(),
+                skill: row.skill.clone(),
+                elapsed,
+            });
+        }
+        sink.row(row)
+    })?;
+    if let Some(cache) = &tree_cache {
+        cache.save()?;
     }
-    Ok(rows)
+    Ok(())
 }
 
-pub fn render_status_table(rows: &[StatusRow]) -> AppResult<String> {
-    let mut tw = TabWriter::new(vec![]);
-    writeln!(tw, "SKILL\tSTATE\tGLOBAL_DIGEST\tTARGET_DIGEST").map_err(|err| {
-        AppError::exec(
-            crate::tr!(
-                "status 出力の整形に失敗しました",
-                "Failed to format status output"
-            ),
-            Some(err.to_string()),
+/// Runs `work` on each item with up to `jobs` scoped worker threads and
+/// hands the results to `emit` in item order, each as soon as every earlier
+/// item is done. `jobs <= 1` runs everything on the calling thread.
+///
+/// Stops at the first error in item order; workers finish the item they
+/// are on and then exit.
+fn for_each_ordered<T: Sync, R: Send>(
+    items: &[T],
+    jobs: usize,
+    work: impl Fn(&T) -> AppResult<R> + Sync,
+    mut emit: impl FnMut(R) -> AppResult<()>,
+) -> AppResult<()> {
+    if jobs <= 1 || items.len() <= 1 {
+        for item in items {
+            emit(work(item)?)?;
+        }
+        return Ok(());
+    }
+    let next = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+    thread::scope(|scope| {
+        let (sender, receiver) = mpsc::channel();
+        for _ in 0..jobs.min(items.len()) {
+            let sender = sender.clone();
+            let (next, stop, work) = (&next, &stop, &work);
+            scope.spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(item) = items.get(index) else {
+                        break;
+                    };
+                    if sender.send((index, work(item))).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(sender);
+        let result = emit_in_order(receiver, &mut emit);
+        stop.store(true, Ordering::Relaxed);
+        result
+    })
+}
+
+/// Reorders `(index, result)` pairs from workers and emits them by index.
+fn emit_in_order<R>(
+    receiver: mpsc::Receiver<(usize, AppResult<R>)>,
+    emit: &mut impl FnMut(R) -> AppResult<()>,
+) -> AppResult<()> {
+    let mut pending = BTreeMap::new();
+    let mut expected = 0;
+    for (index, result) in receiver {
+        pending.insert(index, result);
+        while let Some(result) = pending.remove(&expected) {
+            expected += 1;
+            emit(result?)?;
+        }
+    }
+    Ok(())
+}
+
+/// Pairs global-only keys with target-only keys that differ only by case.
+///
+/// A pair is reported only when the lowercase name matches exactly one
+/// global-only and one target-only skill; ambiguous groups stay
+/// missing/extra.
+fn case_renames(
+    global_skills: &BTreeMap<String, String>,
+    target_skills: &BTreeMap<String, String>,
+) -> BTreeMap<String, String> {
+    let mut groups: BTreeMap<String, (Vec<&String>, Vec<&String>)> = BTreeMap::new();
+    for key in global_skills
+        .keys()
+        .filter(|key| !target_skills.contains_key(*key))
+    {
+        groups.entry(key.to_lowercase()).or_default().0.push(key);
+    }
+    for key in target_skills
+        .keys()
+        .filter(|key| !global_skills.contains_key(*key))
+    {
+        groups.entry(key.to_lowercase()).or_default().1.push(key);
+    }
+    groups
+        .into_values()
+        .filter_map(
+            |(global, target)| match (global.as_slice(), target.as_slice()) {
+                ([global], [target]) => Some(((*global).clone(), (*target).clone())),
+                _ => None,
+            },
         )
-    })?;
-    for row in rows {
-        let g = row
-            .global_digest
-            .as_deref()
-            .map(short_digest)
-            .unwrap_or_else(|| "-".to_string());
-        let t = row
-            .target_digest
-            .as_deref()
-            .map(short_digest)
-            .unwrap_or_else(|| "-".to_string());
-        writeln!(tw, "{}\t{}\t{}\t{}", row.skill, row.state, g, t).map_err(|err| {
-            AppError::exec(
+        .collect()
+}
+
+const STATUS_HEADERS: [&str; 4] = ["SKILL", "STATE", "GLOBAL_DIGEST", "TARGET_DIGEST"];
+const STATUS_PADDING: usize = 2;
+
+/// How [`StatusTableWriter`] lays out its cells (`--format text|plain|csv`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TableStyle {
+    /// Columns padded to a common width.
+    #[default]
+    Aligned,
+    /// Cells separated by a single space.
+    Plain,
+    /// RFC 4180 comma-separated values (CRLF line endings) with full digests
+    /// and no `--detail` lines; a missing digest is an empty field.
+    Csv,
+}
+
+/// Quotes `cell` for CSV when it holds a comma, quote or line break.
+fn csv_field(cell: &str) -> String {
+    if cell.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
+
+/// Writes the status table row by row.
+///
+/// Only the skill column depends on the data and its width comes from
+/// [`StatusSink::begin`]; the other columns have fixed widths (the longest
+/// state, the digest headers), so no row needs to be buffered.
+pub struct StatusTableWriter<W: Write> {
+    out: W,
+    skill_width: usize,
+    state_width: usize,
+    color: bool,
+    digest_len: usize,
+    digest_encoding: DigestEncoding,
+    style: TableStyle,
+    target: Option<String>,
+    header: bool,
+}
+
+impl<W: Write> StatusTableWriter<W> {
+    pub fn new(out: W) -> Self {
+        Self {
+            out,
+            skill_width: STATUS_HEADERS[0].len(),
+            state_width: "missing".len(),
+            color: false,
+            digest_len: DEFAULT_DIGEST_LEN,
+            digest_encoding: DigestEncoding::Hex,
+            style: TableStyle::Aligned,
+            target: None,
+            header: true,
+        }
+    }
+
+    /// Under [`TableStyle::Csv`], starts every record with a `TARGET` column
+    /// holding `name`, so several targets fit one CSV (`status --all`).
+    pub fn with_target_column(mut self, name: &str) -> Self {
+        self.target = Some(name.to_string());
+        self
+    }
+
+    /// Leaves out the header row, e.g. for every target of one CSV but the first.
+    pub fn with_header(mut self, enabled: bool) -> Self {
+        self.header = enabled;
+        self
+    }
+
+    /// Lays the cells out as `style`; only [`TableStyle::Aligned`] is colored.
+    pub fn with_style(mut self, style: TableStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Colors the STATE cell; padding is computed on the uncolored text.
+    pub fn with_color(mut self, enabled: bool) -> Self {
+        self.color = enabled;
+        self
+    }
+
+    /// Keeps `len` characters at each end of shortened digests (`display.digest_len`).
+    pub fn with_digest_len(mut self, len: usize) -> Self {
+        self.digest_len = len;
+        self
+    }
+
+    /// Writes digests in `encoding` (`display.digest_encoding`).
+    pub fn with_digest_encoding(mut self, encoding: DigestEncoding) -> Self {
+        self.digest_encoding = encoding;
+        self
+    }
+
+    /// Widens STATE for the `likely-*` states of `status --fast`.
+    pub fn with_fast(mut self, enabled: bool) -> Self {
+        if enabled {
+            self.state_width = "likely-same".len();
+        }
+        self
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+
+    fn write_line(&mut self, cells: [&str; 4], state_color: Option<Color>) -> AppResult<()> {
+        let line = match self.style {
+            TableStyle::Aligned => self.aligned_line(cells, state_color),
+            TableStyle::Plain => cells.join(" "),
+            TableStyle::Csv => self
+                .target
+                .as_deref()
+                .into_iter()
+                .chain(cells)
+                .map(csv_field)
+                .collect::<Vec<_>>()
+                .join(","),
+        };
+        self.write_text(&line)
+    }
+
+    fn aligned_line(&self, cells: [&str; 4], state_color: Option<Color>) -> String {
+        let widths = [
+            self.skill_width,
+            self.state_width,
+            STATUS_HEADERS[2]
+                .len()
+                .max(short_digest_width(self.digest_len, self.digest_encoding)),
+            0,
+        ];
+        let mut line = String::new();
+        for (index, (cell, width)) in cells.iter().zip(widths).take(3).enumerate() {
+            match state_color {
+                Some(color) if index == 1 => line.push_str(&paint(cell, color, self.color)),
+                _ => line.push_str(cell),
+            }
+            let pad = width.saturating_sub(cell.width()) + STATUS_PADDING;
+            line.extend(std::iter::repeat_n(' ', pad));
+        }
+        line.push_str(cells[3]);
+        line
+    }
+
+    fn write_text(&mut self, line: &str) -> AppResult<()> {
+        let eol = if self.style == TableStyle::Csv {
+            "\r\n"
+        } else {
+            "\n"
+        };
+        write!(self.out, "{}{}", line, eol).map_err(|err| {
+            AppError::io(
                 crate::tr!(
                     "status 出力の整形に失敗しました",
                     "Failed to format status output"
                 ),
-                Some(err.to_string()),
+                err,
             )
-        })?;
+        })
     }
-    let output = tw.into_inner().map_err(|err| {
-        AppError::exec(
-            crate::tr!(
-                "status 出力の整形に失敗しました",
-                "Failed to format status output"
+}
+
+impl<W: Write> StatusSink for StatusTableWriter<W> {
+    fn begin(&mut self, skills: &[&str]) -> AppResult<()> {
+        self.skill_width = skills
+            .iter()
+            .map(|skill| skill.width())
+            .chain([STATUS_HEADERS[0].len()])
+            .max()
+            .unwrap_or_default();
+        if !self.header {
+            return Ok(());
+        }
+        if self.style == TableStyle::Csv && self.target.is_some() {
+            let line = std::iter::once("TARGET")
+                .chain(STATUS_HEADERS)
+                .collect::<Vec<_>>()
+                .join(",");
+            return self.write_text(&line);
+        }
+        self.write_line(STATUS_HEADERS, None)
+    }
+
+    fn row(&mut self, row: StatusRow) -> AppResult<()> {
+        let csv = self.style == TableStyle::Csv;
+        let short = |digest: Option<&str>| match digest {
+            Some(digest) if csv => encode_digest(digest, self.digest_encoding),
+            Some(digest) => short_digest(
+                &encode_digest(digest, self.digest_encoding),
+                self.digest_len,
             ),
-            Some(err.to_string()),
-        )
-    })?;
-    String::from_utf8(output).map_err(|err| {
-        AppError::exec(
+            None if csv => String::new(),
+            None => "-".to_string(),
+        };
+        let g = short(row.global_digest.as_deref());
+        let t = short(row.target_digest.as_deref());
+        let state = row.state.to_string();
+        let color = match row.state {
+            State::Same | State::LikelySame => Color::Green,
+            State::Diff | State::LikelyDiff => Color::Red,
+            State::Missing => Color::Yellow,
+            State::Extra => Color::Magenta,
+            State::Renamed => Color::Cyan,
+        };
+        self.write_line([&row.label(), &state, &g, &t], Some(color))?;
+        if csv {
+            return Ok(());
+        }
+        for change in &row.file_changes {
+            writeln!(self.out, "  {:<8} {}", change.state, change.path).map_err(|err| {
+                AppError::io(
+                    crate::tr!(
+                        "status 出力の整形に失敗しました",
+                        "Failed to format status output"
+                    ),
+                    err,
+                )
+            })?;
+        }
+        Ok(())
+    }
+}
+
+pub fn render_status_table(rows: &[StatusRow]) -> AppResult<String> {
+    render_status_table_with(rows, DEFAULT_DIGEST_LEN)
+}
+
+/// Like [`render_status_table`], keeping `digest_len` characters at each end
+/// of shortened digests.
+pub fn render_status_table_with(rows: &[StatusRow], digest_len: usize) -> AppResult<String> {
+    render_status_rows(rows, TableStyle::Aligned, digest_len)
+}
+
+/// Renders `rows` with a header row in `style`; see [`TableStyle`].
+pub fn render_status_rows(
+    rows: &[StatusRow],
+    style: TableStyle,
+    digest_len: usize,
+) -> AppResult<String> {
+    let fast = rows
+        .iter()
+        .any(|row| matches!(row.state, State::LikelySame | State::LikelyDiff));
+    let mut writer = StatusTableWriter::new(Vec::new())
+        .with_style(style)
+        .with_digest_len(digest_len)
+        .with_fast(fast);
+    let names = rows
+        .iter()
+        .map(|row| row.skill.as_str())
+        .collect::<Vec<_>>();
+    writer.begin(&names)?;
+    for row in rows {
+        writer.row(row.clone())?;
+    }
+    String::from_utf8(writer.into_inner()).map_err(|err| {
+        AppError::exec_with_source(
             crate::tr!(
                 "status 出力の整形に失敗しました",
                 "Failed to format status output"
             ),
             Some(err.to_string()),
+            err,
         )
     })
 }
 
-fn ensure_root_dir(root: &Path) -> AppResult<()> {
-    if !root.is_dir() {
+/// How a skill relates across the two targets of `status --compare`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareState {
+    Same,
+    Diff,
+    /// Only in the first (left) target.
+    OnlyLeft,
+    /// Only in the second (right) target.
+    OnlyRight,
+}
+
+impl CompareState {
+    /// `same`, `diff` or `only-in-<target>`.
+    pub fn label(self, left: &str, right: &str) -> String {
+        match self {
+            CompareState::Same => "same".to_string(),
+            CompareState::Diff => "diff".to_string(),
+            CompareState::OnlyLeft => format!("only-in-{}", left),
+            CompareState::OnlyRight => format!("only-in-{}", right),
+        }
+    }
+
+    /// Rank under `--sort state`, like [`State`].
+    fn sort_priority(self) -> u8 {
+        match self {
+            CompareState::Diff => 0,
+            CompareState::OnlyLeft => 1,
+            CompareState::OnlyRight => 2,
+            CompareState::Same => 3,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CompareRow {
+    pub skill: String,
+    pub state: CompareState,
+    pub left_digest: Option<String>,
+    pub right_digest: Option<String>,
+}
+
+/// Compares two targets skill by skill, ignoring global.
+///
+/// `left` takes the place of global in [`status_for_target`], so `hash`
+/// settings, `.skillhash` pins and case-insensitive matching apply as they do
+/// there; case renames are not detected.
+pub fn compare_targets(
+    config: &Config,
+    left: &Target,
+    right: &Target,
+    selection: Selection<'_>,
+) -> AppResult<Vec<CompareRow>> {
+    let mut config = config.clone();
+    config.global_root = left.root.clone();
+    config.status.detect_renames = false;
+    config.status.fast = false;
+    let mut rows = Vec::new();
+    status_rows(&config, right, selection, None, &mut rows)?;
+    Ok(rows
+        .into_iter()
+        .map(|row| CompareRow {
+            skill: row.skill,
+            state: match row.state {
+                State::Same => CompareState::Same,
+                State::Diff => CompareState::Diff,
+                State::Missing => CompareState::OnlyLeft,
+                State::Extra => CompareState::OnlyRight,
+                State::Renamed | State::LikelySame | State::LikelyDiff => {
+                    unreachable!("renames are not detected and fast mode is off")
+                }
+            },
+            left_digest: row.global_digest,
+            right_digest: row.target_digest,
+        })
+        .collect())
+}
+
+/// Reorders `rows`, which must already be in name order, for `sort`.
+pub fn sort_compare_rows(rows: &mut [CompareRow], sort: StatusSort) {
+    if sort == StatusSort::State {
+        rows.sort_by_key(|row| row.state.sort_priority());
+    }
+}
+
+/// Renders `status --compare` rows with the digest columns headed by the
+/// target names, keeping `digest_len` characters at each end of digests.
+pub fn render_compare_table(
+    left: &str,
+    right: &str,
+    rows: &[CompareRow],
+    digest_len: usize,
+    color: bool,
+) -> String {
+    let short = |digest: &Option<String>| {
+        digest
+            .as_deref()
+            .map(|digest| short_digest(digest, digest_len))
+            .unwrap_or_else(|| "-".to_string())
+    };
+    let mut lines = vec![(
+        [
+            "SKILL".to_string(),
+            "STATE".to_string(),
+            left.to_string(),
+            right.to_string(),
+        ],
+        None,
+    )];
+    for row in rows {
+        let color = match row.state {
+            CompareState::Same => Color::Green,
+            CompareState::Diff => Color::Red,
+            CompareState::OnlyLeft => Color::Yellow,
+            CompareState::OnlyRight => Color::Magenta,
+        };
+        lines.push((
+            [
+                row.skill.clone(),
+                row.state.label(left, right),
+                short(&row.left_digest),
+                short(&row.right_digest),
+            ],
+            Some(color),
+        ));
+    }
+    render_state_table(&lines, color)
+}
+
+/// Aligns `SKILL STATE <digest> <digest>` rows, painting each row's state
+/// cell in its color.
+fn render_state_table(lines: &[([String; 4], Option<Color>)], color: bool) -> String {
+    let mut widths = [0; 3];
+    for (cells, _) in lines {
+        for (width, cell) in widths.iter_mut().zip(cells) {
+            *width = (*width).max(cell.width());
+        }
+    }
+    let mut out = String::new();
+    for (cells, state_color) in lines {
+        for (index, (cell, width)) in cells.iter().zip(widths).enumerate() {
+            match state_color {
+                Some(state_color) if index == 1 => out.push_str(&paint(cell, *state_color, color)),
+                _ => out.push_str(cell),
+            }
+            out.extend(std::iter::repeat_n(
+                ' ',
+                width.saturating_sub(cell.width()) + STATUS_PADDING,
+            ));
+        }
+        out.push_str(&cells[3]);
+        out.push('\n');
+    }
+    out
+}
+
+/// How a global skill relates to the digest recorded by the last push to a
+/// target (`status --since-sync`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncState {
+    Unchanged,
+    /// Global changed since the last push.
+    Changed,
+    /// The recorded digest was computed under another algorithm, so it
+    /// cannot tell; the next push records it again.
+    ResyncNeeded,
+    /// No push to the target has recorded the skill.
+    NeverSynced,
+}
+
+impl SyncState {
+    pub fn label(self) -> &'static str {
+        match self {
+            SyncState::Unchanged => "unchanged",
+            SyncState::Changed => "changed",
+            SyncState::ResyncNeeded => "resync-needed",
+            SyncState::NeverSynced => "never-synced",
+        }
+    }
+
+    /// Rank under `--sort state`, like [`State`].
+    fn sort_priority(self) -> u8 {
+        match self {
+            SyncState::Changed => 0,
+            SyncState::ResyncNeeded => 1,
+            SyncState::NeverSynced => 2,
+            SyncState::Unchanged => 3,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SinceSyncRow {
+    pub skill: String,
+    pub state: SyncState,
+    pub digest: String,
+    pub synced_digest: Option<String>,
+}
+
+/// Classifies global skills against the digests recorded in `target`'s
+/// [`SYNC_STATE_FILE`]; the target's current contents are not read.
+///
+/// Global is read from the checked-out tree at `global_root`, which push
+/// copies from, even for a `git:` global root.
+pub fn since_sync(
+    config: &Config,
+    target: &Target,
+    selection: Selection<'_>,
+) -> AppResult<Vec<SinceSyncRow>> {
+    let global = open_source(&config.global_root, &config.skills)?;
+    let mut skills = global.list_skills()?;
+    if let Some(names) = selection.names() {
+        let keys = skills
+            .iter()
+            .map(|skill| skill_key(skill, &config.skills))
+            .collect::<BTreeSet<_>>();
+        let mut selected = BTreeSet::new();
+        for name in names {
+            validate_skill_id_with(name, &config.skills)?;
+            let key = skill_key(name, &config.skills);
+            if !keys.contains(&key) {
+                return Err(AppError::exec(
+                    crate::tr!(
+                        "global に skill が存在しません: {}",
+                        "Skill does not exist in global: {}",
+                        name
+                    ),
+                    Some(crate::tr!(
+                        "list --global で一覧を確認してください",
+                        "Run list --global to see available skills."
+                    )),
+                ));
+            }
+            selected.insert(key);
+        }
+        skills.retain(|skill| selected.contains(&skill_key(skill, &config.skills)));
+    }
+    let store = DigestStore::load(&target.root.join(SYNC_STATE_FILE))?;
+    let ignore = build_ignore_set(&config.hash.ignore)?;
+    let options = DigestOptions::from_config(&config.hash, ignore.as_ref());
+    skills
+        .into_iter()
+        .map(|skill| {
+            let algo = global.pinned_algo(&skill)?.unwrap_or(options.algo);
+            let digest = global.digest_skill(&skill, &options)?;
+            let synced = store.get(&skill, algo);
+            let state = match synced {
+                Some(synced) if synced == digest => SyncState::Unchanged,
+                Some(_) => SyncState::Changed,
+                None if store.contains(&skill) => SyncState::ResyncNeeded,
+                None => SyncState::NeverSynced,
+            };
+            Ok(SinceSyncRow {
+                synced_digest: synced.map(String::from),
+                skill,
+                state,
+                digest,
+            })
+        })
+        .collect()
+}
+
+/// Reorders `rows`, which must already be in name order, for `sort`.
+pub fn sort_since_sync_rows(rows: &mut [SinceSyncRow], sort: StatusSort) {
+    if sort == StatusSort::State {
+        rows.sort_by_key(|row| row.state.sort_priority());
+    }
+}
+
+/// Renders `status --since-sync` as `SKILL STATE GLOBAL SYNCED` columns.
+pub fn render_since_sync_table(rows: &[SinceSyncRow], digest_len: usize, color: bool) -> String {
+    let mut lines = vec![(
+        [
+            "SKILL".to_string(),
+            "STATE".to_string(),
+            "GLOBAL".to_string(),
+            "SYNCED".to_string(),
+        ],
+        None,
+    )];
+    for row in rows {
+        let state_color = match row.state {
+            SyncState::Unchanged => Color::Green,
+            SyncState::Changed => Color::Red,
+            SyncState::ResyncNeeded | SyncState::NeverSynced => Color::Yellow,
+        };
+        lines.push((
+            [
+                row.skill.clone(),
+                row.state.label().to_string(),
+                short_digest(&row.digest, digest_len),
+                row.synced_digest
+                    .as_deref()
+                    .map(|digest| short_digest(digest, digest_len))
+                    .unwrap_or_else(|| "-".to_string()),
+            ],
+            Some(state_color),
+        ));
+    }
+    render_state_table(&lines, color)
+}
+
+/// How long [`ensure_root_dir`] waits for a missing root, in milliseconds.
+static ROOT_WAIT_MS: AtomicU64 = AtomicU64::new(0);
+
+/// Interval between checks while waiting for a root to appear.
+const ROOT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Makes every later root check in this process wait up to `timeout` for a
+/// missing root (e.g. a mount still coming up) instead of failing at once.
+pub fn set_root_wait(timeout: Duration) {
+    let millis = u64::try_from(timeout.as_millis()).unwrap_or(u64::MAX);
+    ROOT_WAIT_MS.store(millis, Ordering::Relaxed);
+}
+
+/// Polls until `path` is a directory or `timeout` has passed; returns whether
+/// it is one.
+fn wait_for_dir(path: &Path, timeout: Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if path.is_dir() {
+            return true;
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            return false;
+        }
+        thread::sleep(ROOT_POLL_INTERVAL.min(deadline - now));
+    }
+}
+
+pub(crate) fn ensure_root_dir(root: &Path) -> AppResult<()> {
+    let wait = Duration::from_millis(ROOT_WAIT_MS.load(Ordering::Relaxed));
+    if !wait.is_zero() && !root.is_dir() {
+        tracing::debug!(root = %root.display(), ?wait, "waiting for root");
+    }
+    if !wait_for_dir(root, wait) {
         return Err(AppError::config(
             crate::tr!(
                 "root が存在しません: {}",
@@ -256,7 +1308,10 @@ mod tests {
     use tempfile::TempDir;
 
     use super::*;
-    use crate::config::{Config, DiffConfig, HashAlgo, HashConfig, Target};
+    use crate::config::{
+        AuditConfig, Config, DiffConfig, DisplayConfig, DoctorConfig, HashAlgo, HashConfig,
+        SkillsConfig, StatusConfig, SyncConfig, Target,
+    };
 
     fn make_config(global_root: PathBuf, target_root: PathBuf) -> Config {
         Config {
@@ -264,17 +1319,99 @@ mod tests {
             targets: vec![Target {
                 name: "t1".to_string(),
                 root: target_root,
+                pre_push: Vec::new(),
+                post_push: Vec::new(),
             }],
             hash: HashConfig {
                 algo: HashAlgo::Blake3,
                 ignore: Vec::new(),
+                ignore_file: None,
+                normalize_eol: false,
+                include_mode: false,
+                include_empty_dirs: false,
+                merkle: false,
+                merkle_cache: None,
+                skip_larger_than: None,
             },
             diff: DiffConfig {
                 command: vec!["diff".to_string()],
+                cwd: None,
+                use_env: false,
+                timeout_secs: None,
             },
+            sync: SyncConfig::default(),
+            skills: SkillsConfig::default(),
+            doctor: DoctorConfig::default(),
+            audit: AuditConfig::default(),
+            status: StatusConfig::default(),
+            display: DisplayConfig::default(),
+            profiles: BTreeMap::new(),
+            strict_targets: false,
+            parallelism: None,
+            global_git_ref: None,
         }
     }
 
+    #[test]
+    fn longer_digest_len_shows_more_characters() {
+        let digest = "0123456789abcdef".repeat(4);
+        let rows = vec![StatusRow {
+            skill: "skill".to_string(),
+            state: State::Diff,
+            global_digest: Some(digest.clone()),
+            target_digest: Some(digest),
+            renamed_to: None,
+            file_changes: Vec::new(),
+        }];
+        let default = render_status_table(&rows).unwrap();
+        let longer = render_status_table_with(&rows, 8).unwrap();
+        assert!(default.contains("012...def") && !default.contains("0123"));
+        assert!(longer.contains("01234567...89abcdef  01234567...89abcdef"));
+        assert!(longer.lines().nth(1).unwrap().len() > default.lines().nth(1).unwrap().len());
+
+        // The target column stays aligned under its header.
+        let lines = longer.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0].find("TARGET_DIGEST"), lines[1].rfind("01234567"));
+    }
+
+    #[test]
+    fn render_status_rows_as_csv_quotes_commas() {
+        let digest = "0123456789abcdef".repeat(4);
+        let rows = vec![
+            StatusRow {
+                skill: "a,b".to_string(),
+                state: State::Diff,
+                global_digest: Some(digest.clone()),
+                target_digest: Some(digest.clone()),
+                renamed_to: None,
+                file_changes: Vec::new(),
+            },
+            StatusRow {
+                skill: "say \"hi\"".to_string(),
+                state: State::Missing,
+                global_digest: Some(digest.clone()),
+                target_digest: None,
+                renamed_to: None,
+                file_changes: Vec::new(),
+            },
+        ];
+        let csv = render_status_rows(&rows, TableStyle::Csv, DEFAULT_DIGEST_LEN).unwrap();
+        assert_eq!(
+            csv,
+            format!(
+                "SKILL,STATE,GLOBAL_DIGEST,TARGET_DIGEST\r\n\
+                 \"a,b\",diff,{digest},{digest}\r\n\
+                 \"say \"\"hi\"\"\",missing,{digest},\r\n"
+            )
+        );
+
+        let plain = render_status_rows(&rows, TableStyle::Plain, 3).unwrap();
+        assert_eq!(
+            plain.lines().nth(1).unwrap(),
+            "a,b diff 012...def 012...def"
+        );
+    }
+
     #[test]
     fn status_detects_states() {
         let global_dir = TempDir::new().unwrap();
@@ -325,7 +1462,75 @@ mod tests {
         let link = dir.path().join("link");
         symlink(&real, &link).unwrap();
 
-        let err = list_skills(dir.path()).unwrap_err();
+        let err = list_skills_with(dir.path(), &SkillsConfig::default()).unwrap_err();
+        assert!(matches!(err, AppError::Exec { .. }));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn allow_symlink_dirs_lists_dir_links_only() {
+        use std::os::unix::fs::symlink;
+
+        let dir = TempDir::new().unwrap();
+        let root = dir.path().join("root");
+        let real = dir.path().join("elsewhere/shared");
+        fs::create_dir_all(&root).unwrap();
+        fs::create_dir_all(&real).unwrap();
+        fs::write(real.join("SKILL.md"), "x").unwrap();
+        fs::create_dir_all(root.join("local")).unwrap();
+        symlink(&real, root.join("shared")).unwrap();
+
+        let rules = SkillsConfig {
+            allow_symlink_dirs: true,
+            ..SkillsConfig::default()
+        };
+        assert_eq!(
+            list_skills_with(&root, &rules).unwrap(),
+            vec!["local".to_string(), "shared".to_string()]
+        );
+        assert!(list_skills_with(&root, &SkillsConfig::default()).is_err());
+
+        symlink(dir.path().join("missing"), root.join("dangling")).unwrap();
+        let err = list_skills_with(&root, &rules).unwrap_err();
         assert!(matches!(err, AppError::Exec { .. }));
+        assert!(err.to_string().contains("dangling"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn allow_symlink_dirs_rejects_links_to_files() {
+        use std::os::unix::fs::symlink;
+
+        let dir = TempDir::new().unwrap();
+        let root = dir.path().join("root");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(dir.path().join("file.txt"), "x").unwrap();
+        symlink(dir.path().join("file.txt"), root.join("linked")).unwrap();
+
+        let rules = SkillsConfig {
+            allow_symlink_dirs: true,
+            ..SkillsConfig::default()
+        };
+        let err = list_skills_with(&root, &rules).unwrap_err();
+        assert!(matches!(err, AppError::Exec { .. }));
+    }
+
+    #[test]
+    fn wait_for_dir_returns_once_the_directory_appears() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path().join("mounted");
+        assert!(!wait_for_dir(&root, Duration::ZERO));
+
+        let late = root.clone();
+        let creator = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(200));
+            fs::create_dir(&late).unwrap();
+        });
+        assert!(wait_for_dir(&root, Duration::from_secs(10)));
+        creator.join().unwrap();
+        assert!(!wait_for_dir(
+            &dir.path().join("never"),
+            Duration::from_millis(150)
+        ));
     }
 }
@@ -0,0 +1,180 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tempfile::NamedTempFile;
+
+use crate::config::HashAlgo;
+use crate::error::{AppError, AppResult};
+
+/// Store under a target root holding each skill's global digest as of its
+/// last push to that target.
+pub const SYNC_STATE_FILE: &str = ".skillctl-synced.toml";
+
+/// A digest persisted to disk, tagged with the algorithm that produced it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredDigest {
+    pub algo: HashAlgo,
+    pub digest: String,
+}
+
+impl StoredDigest {
+    pub fn new(algo: HashAlgo, digest: impl Into<String>) -> Self {
+        Self {
+            algo,
+            digest: digest.into(),
+        }
+    }
+
+    /// Returns the digest only when it was computed with `algo`.
+    pub fn for_algo(&self, algo: HashAlgo) -> Option<&str> {
+        (self.algo == algo).then_some(self.digest.as_str())
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct StoreFile {
+    #[serde(default)]
+    skills: BTreeMap<String, StoredDigest>,
+}
+
+/// Per-skill stored digests backed by a TOML file.
+///
+/// Lookups are always scoped to an algorithm, so a digest written under one
+/// `hash.algo` is never compared against one computed under another.
+#[derive(Debug)]
+pub struct DigestStore {
+    path: PathBuf,
+    entries: BTreeMap<String, StoredDigest>,
+}
+
+impl DigestStore {
+    /// Loads `path`, starting empty when the file does not exist yet.
+    pub fn load(path: &Path) -> AppResult<Self> {
+        let entries = match fs::read_to_string(path) {
+            Ok(raw) => {
+                toml::from_str::<StoreFile>(&raw)
+                    .map_err(|err| {
+                        AppError::config_with_source(
+                            crate::tr!(
+                                "digest の保存ファイルを解析できません: {}",
+                                "Cannot parse stored digests: {}",
+                                path.display()
+                            ),
+                            Some(err.to_string()),
+                            err,
+                        )
+                    })?
+                    .skills
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(err) => {
+                return Err(AppError::io(
+                    crate::tr!(
+                        "digest の保存ファイルを読み込めません: {}",
+                        "Cannot read stored digests: {}",
+                        path.display()
+                    ),
+                    err,
+                ));
+            }
+        };
+        Ok(Self {
+            path: path.to_path_buf(),
+            entries,
+        })
+    }
+
+    /// Writes the store atomically next to its final path.
+    pub fn save(&self) -> AppResult<()> {
+        let body = toml::to_string(&StoreFile {
+            skills: self.entries.clone(),
+        })
+        .map_err(|err| self.write_error(std::io::Error::other(err)))?;
+        let parent = match self.path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        let mut temp = NamedTempFile::new_in(parent).map_err(|err| self.write_error(err))?;
+        temp.write_all(body.as_bytes())
+            .map_err(|err| self.write_error(err))?;
+        temp.persist(&self.path)
+            .map_err(|err| self.write_error(err.error))?;
+        Ok(())
+    }
+
+    /// Returns the stored digest for `skill` if it was computed with `algo`.
+    pub fn get(&self, skill: &str, algo: HashAlgo) -> Option<&str> {
+        self.entries.get(skill)?.for_algo(algo)
+    }
+
+    /// Whether `skill` has an entry under any algorithm.
+    pub fn contains(&self, skill: &str) -> bool {
+        self.entries.contains_key(skill)
+    }
+
+    pub fn insert(&mut self, skill: impl Into<String>, stored: StoredDigest) {
+        self.entries.insert(skill.into(), stored);
+    }
+
+    pub fn remove(&mut self, skill: &str) -> Option<StoredDigest> {
+        self.entries.remove(skill)
+    }
+
+    fn write_error(&self, err: std::io::Error) -> AppError {
+        AppError::io(
+            crate::tr!(
+                "digest の保存ファイルを書き込めません: {}",
+                "Cannot write stored digests: {}",
+                self.path.display()
+            ),
+            err,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::digest::{DigestOptions, digest_dir};
+
+    #[test]
+    fn store_lookups_are_scoped_to_algo() {
+        let dir = TempDir::new().unwrap();
+        let skill_dir = dir.path().join("skill1");
+        fs::create_dir_all(&skill_dir).unwrap();
+        fs::write(skill_dir.join("SKILL.md"), "doc").unwrap();
+        let path = dir.path().join("digests.toml");
+
+        let sha = digest_dir(&skill_dir, &DigestOptions::new(HashAlgo::Sha256)).unwrap();
+        let mut store = DigestStore::load(&path).unwrap();
+        store.insert("skill1", StoredDigest::new(HashAlgo::Sha256, sha.clone()));
+        store.save().unwrap();
+
+        let store = DigestStore::load(&path).unwrap();
+        assert_eq!(store.get("skill1", HashAlgo::Sha256), Some(sha.as_str()));
+        assert_eq!(store.get("skill1", HashAlgo::Blake3), None);
+        assert!(store.contains("skill1"));
+        assert!(!store.contains("skill2"));
+    }
+
+    #[test]
+    fn store_load_errors_on_invalid_file() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("digests.toml");
+        fs::write(&path, "skills = 1").unwrap();
+        let err = DigestStore::load(&path).unwrap_err();
+        assert!(matches!(err, AppError::Config { .. }));
+
+        let err = DigestStore::load(dir.path()).unwrap_err();
+        assert!(matches!(err, AppError::Io { .. }));
+
+        let store = DigestStore::load(&dir.path().join("missing/digests.toml")).unwrap();
+        let err = store.save().unwrap_err();
+        assert!(matches!(err, AppError::Io { .. }));
+    }
+}
@@ -1,15 +1,25 @@
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs;
-use std::io;
+use std::io::{self, Read, Write};
 use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
 
 use tempfile::TempDir;
 
-use crate::config::{Config, Target};
-use crate::digest::{build_ignore_set, digest_dir};
+use crate::audit::{AuditLog, is_audited};
+use crate::checkpoint::Checkpoint;
+use crate::config::{Config, ReflinkMode, RetryConfig, SyncConfig, Target, UpdateMode};
+use crate::digest::{
+    DigestOptions, build_ignore_set, digest_bytes, digest_dir, digest_file, dirs_equal,
+};
 use crate::error::{AppError, AppResult};
-use crate::skill::validate_skill_id;
-use crate::status::list_skills;
+use crate::events::{Event, write_event};
+use crate::hooks::{HookStage, PlanHooks};
+use crate::skill::{index_skills, not_found_hint, skill_key, validate_skill_id_with};
+use crate::source::{is_archive_path, open_source};
+use crate::status::list_skills_with;
+use crate::store::{DigestStore, SYNC_STATE_FILE, StoredDigest};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PlanKind {
@@ -17,6 +27,7 @@ pub enum PlanKind {
     Update,
     Skip,
     Prune,
+    Merge,
 }
 
 #[derive(Debug, Clone)]
@@ -31,63 +42,167 @@ pub struct PlanOp {
 #[derive(Debug, Clone)]
 pub struct Plan {
     pub ops: Vec<PlanOp>,
+    /// Run around the ops when at least one of them changes something.
+    pub hooks: PlanHooks,
+    /// Root the ops copy from; no op may write or delete inside or above it.
+    pub source_root: Option<PathBuf>,
+}
+
+/// How `import` treats skills that differ between target and global.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImportMode {
+    /// Leave differing skills untouched.
+    #[default]
+    AddOnly,
+    /// Replace the global skill with the target's version.
+    Overwrite,
+    /// Copy target files over global, keeping global-only files.
+    Merge,
 }
 
 #[derive(Debug, Clone, Copy)]
 pub enum Selection<'a> {
     All,
     One(&'a str),
+    /// Several named skills, planned together.
+    Many(&'a [String]),
 }
 
+impl<'a> Selection<'a> {
+    /// Named skills, or `None` for `All`.
+    pub(crate) fn names(self) -> Option<Vec<&'a str>> {
+        match self {
+            Selection::All => None,
+            Selection::One(skill) => Some(vec![skill]),
+            Selection::Many(skills) => Some(skills.iter().map(String::as_str).collect()),
+        }
+    }
+}
+
+/// Extra switches for [`plan_push_with`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PushOptions {
+    /// Include target extras as prune ops.
+    pub prune: bool,
+    /// Plan an update even when global and target already match.
+    pub force: bool,
+    /// Plan only prune ops for target extras; implies `prune`.
+    pub prune_only: bool,
+    /// Plan only installs and updates: skills that match or exist only in
+    /// the target are left out of the plan, and nothing is pruned.
+    pub changed_only: bool,
+}
+
+/// Note on updates planned only because of [`PushOptions::force`]; these
+/// always replace the whole directory, even with `sync.update_mode = "incremental"`.
+pub const FORCE_NOTE: &str = "force";
+
 pub fn plan_push(
     config: &Config,
     target: &Target,
     selection: Selection<'_>,
     prune: bool,
 ) -> AppResult<Plan> {
-    let global_skills = list_skills(&config.global_root)?;
-    let target_skills = list_skills(&target.root)?;
+    plan_push_with(
+        config,
+        target,
+        selection,
+        PushOptions {
+            prune,
+            ..PushOptions::default()
+        },
+    )
+}
+
+#[tracing::instrument(
+    skip_all,
+    fields(
+        target = %target.name,
+        prune = push.prune,
+        force = push.force,
+        prune_only = push.prune_only,
+        changed_only = push.changed_only,
+    )
+)]
+pub fn plan_push_with(
+    config: &Config,
+    target: &Target,
+    selection: Selection<'_>,
+    push: PushOptions,
+) -> AppResult<Plan> {
+    let PushOptions {
+        prune,
+        force,
+        prune_only,
+        changed_only,
+    } = push;
+    let prune = (prune || prune_only) && !changed_only;
+    ensure_directory_target(target)?;
+    let global_skills = index_skills(
+        &config.global_root,
+        list_skills_with(&config.global_root, &config.skills)?,
+        &config.skills,
+    )?;
+    let target_skills = index_skills(
+        &target.root,
+        list_skills_with(&target.root, &config.skills)?,
+        &config.skills,
+    )?;
 
-    let mut skills = BTreeSet::new();
-    match selection {
-        Selection::All => {
-            skills.extend(global_skills.iter().cloned());
+    let mut keys = BTreeSet::new();
+    match selection.names() {
+        None => {
+            keys.extend(global_skills.keys().cloned());
             if prune {
-                skills.extend(target_skills.iter().cloned());
+                keys.extend(target_skills.keys().cloned());
             }
         }
-        Selection::One(skill) => {
-            validate_skill_id(skill)?;
-            skills.insert(skill.to_string());
-        }
-    }
-
-    if let Selection::One(skill) = selection {
-        validate_skill_id(skill)?;
-        let in_global = global_skills.iter().any(|s| s == skill);
-        let in_target = target_skills.iter().any(|s| s == skill);
-        if !in_global && (!prune || !in_target) {
-            return Err(AppError::exec(
-                crate::tr!(
-                    "global に skill が存在しません: {}",
-                    "Skill does not exist in global: {}",
-                    skill
-                ),
-                Some(crate::tr!(
-                    "list --global で一覧を確認してください",
-                    "Run list --global to see available skills."
-                )),
-            ));
+        Some(skills) => {
+            // Check every name before planning so one bad name fails the whole run.
+            for skill in skills {
+                validate_skill_id_with(skill, &config.skills)?;
+                let key = skill_key(skill, &config.skills);
+                let in_global = global_skills.contains_key(&key);
+                let in_target = target_skills.contains_key(&key);
+                if !in_global && (!prune || !in_target) {
+                    return Err(AppError::exec(
+                        crate::tr!(
+                            "global に skill が存在しません: {}",
+                            "Skill does not exist in global: {}",
+                            skill
+                        ),
+                        Some(not_found_hint(
+                            skill,
+                            global_skills.values().map(String::as_str),
+                            crate::tr!(
+                                "list --global で一覧を確認してください",
+                                "Run list --global to see available skills."
+                            ),
+                        )),
+                    ));
+                }
+                keys.insert(key);
+            }
         }
     }
 
     let ignore = build_ignore_set(&config.hash.ignore)?;
+    let options = DigestOptions::from_config(&config.hash, ignore.as_ref());
     let mut ops = Vec::new();
-    for skill in skills {
-        let global_path = config.global_root.join(&skill);
-        let target_path = target.root.join(&skill);
-        let global_exists = global_path.is_dir();
-        let target_exists = target_path.is_dir();
+    for key in keys {
+        let global_name = global_skills.get(&key);
+        let target_name = target_skills.get(&key);
+        // Name ops after the source side so case-insensitive matches keep its spelling.
+        let Some(skill) = global_name.or(target_name).cloned() else {
+            continue;
+        };
+        let global_path = config.global_root.join(global_name.unwrap_or(&skill));
+        let target_path = target.root.join(target_name.unwrap_or(&skill));
+        let global_exists = global_name.is_some();
+        let target_exists = target_name.is_some();
+        if (prune_only && global_exists) || (changed_only && !global_exists) {
+            continue;
+        }
         let op = match (global_exists, target_exists) {
             (true, false) => PlanOp {
                 kind: PlanKind::Install,
@@ -97,9 +212,11 @@ pub fn plan_push(
                 note: None,
             },
             (true, true) => {
-                let g = digest_dir(&global_path, config.hash.algo, ignore.as_ref())?;
-                let t = digest_dir(&target_path, config.hash.algo, ignore.as_ref())?;
-                if g == t {
+                let equal = dirs_equal(&global_path, &target_path, &options)?;
+                if equal && changed_only {
+                    continue;
+                }
+                if equal && !force {
                     PlanOp {
                         kind: PlanKind::Skip,
                         skill,
@@ -113,7 +230,7 @@ pub fn plan_push(
                         skill,
                         src: Some(global_path),
                         dest: Some(target_path),
-                        note: None,
+                        note: equal.then(|| FORCE_NOTE.to_string()),
                     }
                 }
             }
@@ -123,8 +240,8 @@ pub fn plan_push(
                         kind: PlanKind::Prune,
                         skill,
                         src: None,
+                        note: Some(prune_note(&target_path)?),
                         dest: Some(target_path),
-                        note: None,
                     }
                 } else {
                     PlanOp {
@@ -138,56 +255,222 @@ pub fn plan_push(
             }
             (false, false) => continue,
         };
+        tracing::debug!(skill = %op.skill, kind = ?op.kind, note = ?op.note, "planned op");
         ops.push(op);
     }
-    Ok(Plan { ops })
+    Ok(Plan {
+        ops,
+        hooks: PlanHooks::for_target(target),
+        source_root: Some(config.global_root.clone()),
+    })
+}
+
+/// Describes what pruning `path` deletes, e.g. `12 files, 3.4 MB`.
+///
+/// Computed for every plan (not only dry-runs) so the note, and with it the
+/// plan hash, is the same when a reviewed plan is applied. It only reads
+/// metadata, which is cheap next to the digests planning already computes.
+fn prune_note(path: &Path) -> AppResult<String> {
+    let entries = prune_entries(path)?;
+    let files = entries.len();
+    let bytes = entries.iter().map(|(_, len)| len).sum::<u64>();
+    let unit = if files == 1 { "file" } else { "files" };
+    Ok(format!("{files} {unit}, {}", format_size(bytes)))
+}
+
+/// Every non-directory entry pruning `path` would delete, as `/`-separated
+/// paths relative to `path` in walk order (sorted by name), for
+/// `push --list-files`.
+pub fn prune_files(path: &Path) -> AppResult<Vec<String>> {
+    Ok(prune_entries(path)?
+        .into_iter()
+        .map(|(rel, _)| rel)
+        .collect())
+}
+
+/// Relative path and size of every non-directory entry below `path`.
+fn prune_entries(path: &Path) -> AppResult<Vec<(String, u64)>> {
+    let mut entries = Vec::new();
+    for entry in walkdir::WalkDir::new(path)
+        .min_depth(1)
+        .follow_links(false)
+        .sort_by_file_name()
+    {
+        let entry = entry.map_err(|err| {
+            AppError::io(
+                crate::tr!(
+                    "ファイル走査に失敗しました: {}",
+                    "Failed to scan files: {}",
+                    path.display()
+                ),
+                err.into(),
+            )
+        })?;
+        if entry.file_type().is_dir() {
+            continue;
+        }
+        let meta = entry.metadata().map_err(|err| {
+            AppError::io(
+                crate::tr!(
+                    "ファイル走査に失敗しました: {}",
+                    "Failed to scan files: {}",
+                    entry.path().display()
+                ),
+                err.into(),
+            )
+        })?;
+        let rel = entry
+            .path()
+            .strip_prefix(path)
+            .unwrap_or(entry.path())
+            .components()
+            .map(|part| part.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        entries.push((rel, meta.len()));
+    }
+    Ok(entries)
+}
+
+/// Formats `bytes` with decimal units and one fractional digit above 1 kB.
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["kB", "MB", "GB", "TB"];
+    if bytes < 1000 {
+        return format!("{bytes} B");
+    }
+    let mut value = bytes as f64 / 1000.0;
+    let mut unit = 0;
+    while value >= 1000.0 && unit + 1 < UNITS.len() {
+        value /= 1000.0;
+        unit += 1;
+    }
+    format!("{value:.1} {}", UNITS[unit])
+}
+
+/// Plans a push to every configured target without touching the filesystem.
+///
+/// Stops at the first target whose planning fails; the error names that target.
+pub fn plan_all(
+    config: &Config,
+    selection: Selection<'_>,
+    prune: bool,
+) -> AppResult<Vec<(String, Plan)>> {
+    config
+        .targets
+        .iter()
+        .map(|target| {
+            plan_push(config, target, selection, prune)
+                .map(|plan| (target.name.clone(), plan))
+                .map_err(|err| {
+                    err.with_context(crate::tr!("ターゲット {}", "Target {}", target.name))
+                })
+        })
+        .collect()
 }
 
+#[tracing::instrument(skip_all, fields(target = %target.name, mode = ?mode))]
 pub fn plan_import(
     config: &Config,
     target: &Target,
     selection: Selection<'_>,
-    overwrite: bool,
+    mode: ImportMode,
 ) -> AppResult<Plan> {
-    let target_skills = list_skills(&target.root)?;
+    plan_import_with(config, target, selection, mode, &BTreeMap::new())
+}
 
-    let mut skills = BTreeSet::new();
-    match selection {
-        Selection::All => {
-            skills.extend(target_skills.iter().cloned());
+/// Like [`plan_import`], importing each target skill named in `renames`
+/// (old name to new name, `import --rename-map`) into global under its new
+/// name. `selection` names target skills; ops that copy a renamed skill are
+/// noted `from <old>`. Two planned skills landing on the same global name is
+/// a config error.
+pub fn plan_import_with(
+    config: &Config,
+    target: &Target,
+    selection: Selection<'_>,
+    mode: ImportMode,
+    renames: &BTreeMap<String, String>,
+) -> AppResult<Plan> {
+    ensure_directory_target(target)?;
+    let global_skills = index_skills(
+        &config.global_root,
+        list_skills_with(&config.global_root, &config.skills)?,
+        &config.skills,
+    )?;
+    let target_skills = index_skills(
+        &target.root,
+        list_skills_with(&target.root, &config.skills)?,
+        &config.skills,
+    )?;
+
+    let mut keys = BTreeSet::new();
+    match selection.names() {
+        None => {
+            keys.extend(target_skills.keys().cloned());
         }
-        Selection::One(skill) => {
-            validate_skill_id(skill)?;
-            skills.insert(skill.to_string());
+        Some(skills) => {
+            for skill in skills {
+                validate_skill_id_with(skill, &config.skills)?;
+                let key = skill_key(skill, &config.skills);
+                if !target_skills.contains_key(&key) {
+                    return Err(AppError::exec(
+                        crate::tr!(
+                            "ターゲットに skill が存在しません: {}",
+                            "Skill does not exist in target: {}",
+                            skill
+                        ),
+                        Some(not_found_hint(
+                            skill,
+                            target_skills.values().map(String::as_str),
+                            crate::tr!(
+                                "list --target <name> で一覧を確認してください",
+                                "Run list --target <name> to see available skills."
+                            ),
+                        )),
+                    ));
+                }
+                keys.insert(key);
+            }
         }
     }
 
-    if let Selection::One(skill) = selection {
-        validate_skill_id(skill)?;
-        let in_target = target_skills.iter().any(|s| s == skill);
-        if !in_target {
-            return Err(AppError::exec(
+    let renames = renames
+        .iter()
+        .map(|(old, new)| (skill_key(old, &config.skills), new))
+        .collect::<BTreeMap<_, _>>();
+    let ignore = build_ignore_set(&config.hash.ignore)?;
+    let options = DigestOptions::from_config(&config.hash, ignore.as_ref());
+    let mut ops = Vec::new();
+    let mut imported_from: BTreeMap<String, &String> = BTreeMap::new();
+    for key in keys {
+        let target_name = target_skills.get(&key);
+        let renamed = target_name.and(renames.get(&key).copied());
+        let global_key = renamed.map_or_else(|| key.clone(), |new| skill_key(new, &config.skills));
+        if let Some(name) = target_name
+            && let Some(other) = imported_from.insert(global_key.clone(), name)
+        {
+            return Err(AppError::config(
                 crate::tr!(
-                    "ターゲットに skill が存在しません: {}",
-                    "Skill does not exist in target: {}",
-                    skill
+                    "{} と {} がどちらも global の同じ名前に取り込まれます: {}",
+                    "{} and {} would both be imported into global as {}",
+                    other,
+                    name,
+                    renamed.unwrap_or(name)
                 ),
                 Some(crate::tr!(
-                    "list --target <name> で一覧を確認してください",
-                    "Run list --target <name> to see available skills."
+                    "--rename-map の対応を見直してください",
+                    "Fix the pairs in --rename-map."
                 )),
             ));
         }
-    }
-
-    let ignore = build_ignore_set(&config.hash.ignore)?;
-    let mut ops = Vec::new();
-    for skill in skills {
-        let global_path = config.global_root.join(&skill);
-        let target_path = target.root.join(&skill);
-        let global_exists = global_path.is_dir();
-        let target_exists = target_path.is_dir();
-        let op = match (global_exists, target_exists) {
+        let global_name = global_skills.get(&global_key);
+        let Some(skill) = renamed.or(target_name).or(global_name).cloned() else {
+            continue;
+        };
+        let global_path = config.global_root.join(global_name.unwrap_or(&skill));
+        let target_path = target.root.join(target_name.unwrap_or(&skill));
+        let global_exists = global_name.is_some();
+        let target_exists = target_name.is_some();
+        let mut op = match (global_exists, target_exists) {
             (false, true) => PlanOp {
                 kind: PlanKind::Install,
                 skill,
@@ -196,9 +479,7 @@ pub fn plan_import(
                 note: None,
             },
             (true, true) => {
-                let g = digest_dir(&global_path, config.hash.algo, ignore.as_ref())?;
-                let t = digest_dir(&target_path, config.hash.algo, ignore.as_ref())?;
-                if g == t {
+                if dirs_equal(&global_path, &target_path, &options)? {
                     PlanOp {
                         kind: PlanKind::Skip,
                         skill,
@@ -206,9 +487,13 @@ pub fn plan_import(
                         dest: None,
                         note: None,
                     }
-                } else if overwrite {
+                } else if mode != ImportMode::AddOnly {
                     PlanOp {
-                        kind: PlanKind::Update,
+                        kind: if mode == ImportMode::Merge {
+                            PlanKind::Merge
+                        } else {
+                            PlanKind::Update
+                        },
                         skill,
                         src: Some(target_path),
                         dest: Some(global_path),
@@ -233,76 +518,366 @@ pub fn plan_import(
             },
             (false, false) => continue,
         };
+        if let (Some(old), Some(_)) = (target_name, renamed)
+            && op.kind != PlanKind::Skip
+        {
+            op.note = Some(format!("from {old}"));
+        }
+        tracing::debug!(skill = %op.skill, kind = ?op.kind, note = ?op.note, "planned op");
         ops.push(op);
     }
-    Ok(Plan { ops })
+    Ok(Plan {
+        ops,
+        hooks: PlanHooks::default(),
+        source_root: Some(target.root.clone()),
+    })
 }
 
-pub fn execute_plan(plan: &Plan, dry_run: bool) -> AppResult<()> {
-    for op in &plan.ops {
-        match op.kind {
-            PlanKind::Install | PlanKind::Update => {
-                let src = op.src.as_ref().ok_or_else(|| {
-                    AppError::exec(
-                        crate::tr!("src が未設定です: {}", "src is not set: {}", op.skill),
-                        Some(crate::tr!(
-                            "実装に問題があります",
-                            "There is an implementation bug."
-                        )),
-                    )
-                })?;
-                let dest = op.dest.as_ref().ok_or_else(|| {
-                    AppError::exec(
-                        crate::tr!("dest が未設定です: {}", "dest is not set: {}", op.skill),
-                        Some(crate::tr!(
-                            "実装に問題があります",
-                            "There is an implementation bug."
-                        )),
-                    )
-                })?;
-                if !dry_run {
-                    replace_dir(src, dest)?;
+/// Note on the skip [`plan_import_all`] plans for a conflicting skill.
+pub const CONFLICT_NOTE: &str = "conflict";
+
+/// A skill that several targets hold with different content.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportConflict {
+    pub skill: String,
+    /// The targets holding the skill, in config order.
+    pub targets: Vec<String>,
+}
+
+/// Plans `import --all` from every directory target at once (archive
+/// targets are left out).
+///
+/// Each skill is taken from the first target holding it when all targets
+/// that hold it agree on its digest. When they differ, the skill is taken
+/// from `prefer` if that target holds it, and otherwise skipped with the
+/// note [`CONFLICT_NOTE`] and returned as an [`ImportConflict`]. Ops that
+/// copy something are noted `from <target>`.
+pub fn plan_import_all(
+    config: &Config,
+    mode: ImportMode,
+    prefer: Option<&str>,
+) -> AppResult<(Plan, Vec<ImportConflict>)> {
+    if let Some(name) = prefer {
+        config.target_by_name(name)?;
+    }
+    let ignore = build_ignore_set(&config.hash.ignore)?;
+    let options = DigestOptions::from_config(&config.hash, ignore.as_ref());
+    let mut candidates: BTreeMap<String, Vec<(&str, PlanOp, String)>> = BTreeMap::new();
+    for target in &config.targets {
+        if is_archive_path(&target.root) {
+            continue;
+        }
+        for op in plan_import(config, target, Selection::All, mode)?.ops {
+            let digest = digest_skill_path(&target.root.join(&op.skill), &options)?;
+            candidates
+                .entry(skill_key(&op.skill, &config.skills))
+                .or_default()
+                .push((target.name.as_str(), op, digest));
+        }
+    }
+    let mut ops = Vec::new();
+    let mut conflicts = Vec::new();
+    for (_, mut held) in candidates {
+        let agree = held.iter().all(|(_, _, digest)| *digest == held[0].2);
+        let chosen = if agree {
+            Some(0)
+        } else {
+            held.iter().position(|(name, _, _)| Some(*name) == prefer)
+        };
+        let op = match chosen {
+            Some(index) => {
+                let (name, mut op, _) = held.swap_remove(index);
+                if op.kind != PlanKind::Skip {
+                    op.note = Some(format!("from {name}"));
                 }
+                op
             }
-            PlanKind::Prune => {
-                let dest = op.dest.as_ref().ok_or_else(|| {
-                    AppError::exec(
-                        crate::tr!("dest が未設定です: {}", "dest is not set: {}", op.skill),
-                        Some(crate::tr!(
-                            "実装に問題があります",
-                            "There is an implementation bug."
-                        )),
-                    )
-                })?;
-                if !dry_run {
-                    fs::remove_dir_all(dest).map_err(|err| {
-                        AppError::exec(
-                            crate::tr!(
-                                "削除に失敗しました: {}",
-                                "Failed to remove: {}",
-                                dest.display()
-                            ),
-                            Some(err.to_string()),
-                        )
-                    })?;
+            None => {
+                let skill = held[0].1.skill.clone();
+                conflicts.push(ImportConflict {
+                    skill: skill.clone(),
+                    targets: held.iter().map(|(name, _, _)| name.to_string()).collect(),
+                });
+                PlanOp {
+                    kind: PlanKind::Skip,
+                    skill,
+                    src: None,
+                    dest: None,
+                    note: Some(CONFLICT_NOTE.to_string()),
                 }
             }
-            PlanKind::Skip => {}
+        };
+        ops.push(op);
+    }
+    Ok((
+        Plan {
+            ops,
+            hooks: PlanHooks::default(),
+            source_root: None,
+        },
+        conflicts,
+    ))
+}
+
+fn ensure_directory_target(target: &Target) -> AppResult<()> {
+    if is_archive_path(&target.root) {
+        return Err(AppError::config(
+            crate::tr!(
+                "アーカイブのターゲットには push/import できません: {}",
+                "push/import is not supported for archive targets: {}",
+                target.name
+            ),
+            Some(crate::tr!(
+                "アーカイブを展開したディレクトリをターゲットに設定してください",
+                "Extract the archive and point the target at the directory."
+            )),
+        ));
+    }
+    Ok(())
+}
+
+#[tracing::instrument(skip_all, fields(ops = plan.ops.len(), dry_run))]
+pub fn execute_plan(plan: &Plan, dry_run: bool, sync: &SyncConfig) -> AppResult<()> {
+    execute_plan_audited(plan, dry_run, sync, None)
+}
+
+/// Like [`execute_plan`], appending an entry to `audit` for every op other
+/// than skip, including the op that failed.
+pub fn execute_plan_audited(
+    plan: &Plan,
+    dry_run: bool,
+    sync: &SyncConfig,
+    audit: Option<&AuditLog>,
+) -> AppResult<()> {
+    execute_plan_with(plan, dry_run, sync, audit, None, None)
+}
+
+/// Like [`execute_plan_audited`], also recording every completed op other
+/// than skip in `checkpoint` and writing an [`Event::OpDone`] line for it to
+/// `events` (neither on dry-runs).
+pub fn execute_plan_with(
+    plan: &Plan,
+    dry_run: bool,
+    sync: &SyncConfig,
+    audit: Option<&AuditLog>,
+    mut checkpoint: Option<&mut Checkpoint>,
+    mut events: Option<&mut dyn Write>,
+) -> AppResult<()> {
+    for op in &plan.ops {
+        ensure_no_overlap(op, plan.source_root.as_deref())?;
+    }
+    let run_hooks = !dry_run && plan.ops.iter().any(|op| op.kind != PlanKind::Skip);
+    if run_hooks {
+        plan.hooks.run(HookStage::PrePush)?;
+    }
+    for op in &plan.ops {
+        tracing::debug!(skill = %op.skill, kind = ?op.kind, note = ?op.note, "execute op");
+        let result = execute_op(op, dry_run, sync);
+        if let Some(audit) = audit
+            && is_audited(op)
+        {
+            audit.record(op, dry_run, result.is_ok());
         }
+        result?;
+        if let Some(checkpoint) = checkpoint.as_deref_mut()
+            && !dry_run
+            && op.kind != PlanKind::Skip
+        {
+            checkpoint.record(op)?;
+        }
+        if let Some(out) = events.as_deref_mut()
+            && !dry_run
+            && op.kind != PlanKind::Skip
+        {
+            write_event(out, &Event::op_done(op))?;
+        }
+    }
+    if run_hooks {
+        plan.hooks.run(HookStage::PostPush)?;
     }
     Ok(())
 }
 
-pub fn summarize_plan(plan: &Plan) -> Vec<String> {
-    let mut lines = Vec::new();
+/// Refuses `op` when, after resolving symlinks, its destination is the same
+/// directory as its source or `source_root`, or lies inside or above either:
+/// replacing or pruning it would delete the data being copied.
+fn ensure_no_overlap(op: &PlanOp, source_root: Option<&Path>) -> AppResult<()> {
+    let Some(dest) = op.dest.as_deref().filter(|_| op.kind != PlanKind::Skip) else {
+        return Ok(());
+    };
+    let resolved = resolve_path(dest);
+    for src in op.src.as_deref().into_iter().chain(source_root) {
+        let src = resolve_path(src);
+        if resolved.starts_with(&src) || src.starts_with(&resolved) {
+            return Err(AppError::exec(
+                crate::tr!(
+                    "{} {} を中止しました: {} が {} と重なっています（シンボリックリンク解決後: {}）",
+                    "Refusing to {} {}: {} overlaps {} (resolves to {})",
+                    op.kind.label(),
+                    op.skill,
+                    dest.display(),
+                    src.display(),
+                    resolved.display()
+                ),
+                Some(crate::tr!(
+                    "ターゲットの root や skill がシンボリックリンクで global の中を指していないか確認してください",
+                    "Check that no symlink makes the target root or skill point into global (or the other way round)."
+                )),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// `path` with symlinks resolved; a path that does not exist yet is resolved
+/// through its parent directory.
+fn resolve_path(path: &Path) -> PathBuf {
+    if let Ok(resolved) = fs::canonicalize(path) {
+        return resolved;
+    }
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => fs::canonicalize(parent)
+            .map(|parent| parent.join(name))
+            .unwrap_or_else(|_| path.to_path_buf()),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Records in `target`'s [`SYNC_STATE_FILE`] the global digest of every skill
+/// a successfully executed push `plan` left matching global: installs,
+/// updates and skips of unchanged skills. Pruned skills are forgotten; other
+/// skips keep their previous entry.
+pub fn record_synced(config: &Config, target: &Target, plan: &Plan) -> AppResult<()> {
+    let ignore = build_ignore_set(&config.hash.ignore)?;
+    let options = DigestOptions::from_config(&config.hash, ignore.as_ref());
+    let global = open_source(&config.global_root, &config.skills)?;
+    let mut store = DigestStore::load(&target.root.join(SYNC_STATE_FILE))?;
     for op in &plan.ops {
-        let label = match op.kind {
+        match (op.kind, &op.note) {
+            (PlanKind::Install | PlanKind::Update, _) | (PlanKind::Skip, None) => {
+                let algo = global.pinned_algo(&op.skill)?.unwrap_or(options.algo);
+                let digest = global.digest_skill(&op.skill, &options)?;
+                store.insert(op.skill.clone(), StoredDigest::new(algo, digest));
+            }
+            (PlanKind::Prune, _) => {
+                store.remove(&op.skill);
+            }
+            _ => {}
+        }
+    }
+    store.save()
+}
+
+fn execute_op(op: &PlanOp, dry_run: bool, sync: &SyncConfig) -> AppResult<()> {
+    let retry = &sync.retry;
+    let mut copier = FileCopier::new(sync);
+    match op.kind {
+        PlanKind::Install | PlanKind::Update | PlanKind::Merge => {
+            let src = op.src.as_ref().ok_or_else(|| {
+                AppError::exec(
+                    crate::tr!("src が未設定です: {}", "src is not set: {}", op.skill),
+                    Some(crate::tr!(
+                        "実装に問題があります",
+                        "There is an implementation bug."
+                    )),
+                )
+            })?;
+            let dest = op.dest.as_ref().ok_or_else(|| {
+                AppError::exec(
+                    crate::tr!("dest が未設定です: {}", "dest is not set: {}", op.skill),
+                    Some(crate::tr!(
+                        "実装に問題があります",
+                        "There is an implementation bug."
+                    )),
+                )
+            })?;
+            if dry_run {
+                return Ok(());
+            }
+            if src.is_file() {
+                write_file_atomic(src, dest, retry, &mut copier)?;
+            } else if op.kind == PlanKind::Merge {
+                overlay_dir(src, dest, retry, &mut copier, false)?;
+            } else if op.kind == PlanKind::Update
+                && sync.update_mode == UpdateMode::Incremental
+                && op.note.as_deref() != Some(FORCE_NOTE)
+                && dest.is_dir()
+            {
+                overlay_dir(src, dest, retry, &mut copier, true)?;
+            } else {
+                replace_dir(src, dest, retry, &mut copier)?;
+            }
+        }
+        PlanKind::Prune => {
+            let dest = op.dest.as_ref().ok_or_else(|| {
+                AppError::exec(
+                    crate::tr!("dest が未設定です: {}", "dest is not set: {}", op.skill),
+                    Some(crate::tr!(
+                        "実装に問題があります",
+                        "There is an implementation bug."
+                    )),
+                )
+            })?;
+            if !dry_run {
+                remove_path(dest, retry)?;
+            }
+        }
+        PlanKind::Skip => {}
+    }
+    Ok(())
+}
+
+/// Fingerprints `plan` from its op kinds, skills and the digest of the content
+/// each op acts on (the source, or the removed directory for prune).
+///
+/// Re-planning after the filesystem changed yields a different hash, which lets
+/// an apply verify it matches a reviewed dry-run.
+pub fn plan_hash(config: &Config, plan: &Plan) -> AppResult<String> {
+    let ignore = build_ignore_set(&config.hash.ignore)?;
+    let options = DigestOptions::from_config(&config.hash, ignore.as_ref());
+    let mut buf = Vec::new();
+    for op in &plan.ops {
+        let content = match (&op.src, &op.dest) {
+            (Some(src), _) => digest_skill_path(src, &options)?,
+            (None, Some(dest)) => digest_skill_path(dest, &options)?,
+            (None, None) => String::new(),
+        };
+        let line = format!(
+            "{:?}\0{}\0{}\0{}\n",
+            op.kind,
+            op.skill,
+            op.note.as_deref().unwrap_or(""),
+            content
+        );
+        buf.extend_from_slice(line.as_bytes());
+    }
+    Ok(digest_bytes(config.hash.algo, &buf))
+}
+
+fn digest_skill_path(path: &Path, options: &DigestOptions) -> AppResult<String> {
+    if path.is_file() {
+        digest_file(path, options)
+    } else {
+        digest_dir(path, options)
+    }
+}
+
+impl PlanKind {
+    pub(crate) fn label(self) -> &'static str {
+        match self {
             PlanKind::Install => "install",
             PlanKind::Update => "update",
             PlanKind::Skip => "skip",
             PlanKind::Prune => "prune",
-        };
-        let mut line = format!("{} {}", label, op.skill);
+            PlanKind::Merge => "merge",
+        }
+    }
+}
+
+pub fn summarize_plan(plan: &Plan) -> Vec<String> {
+    let mut lines = Vec::new();
+    for op in &plan.ops {
+        let mut line = format!("{} {}", op.kind.label(), op.skill);
         if let Some(note) = &op.note {
             line.push_str(&format!(" ({})", note));
         }
@@ -311,6 +886,87 @@ pub fn summarize_plan(plan: &Plan) -> Vec<String> {
     lines
 }
 
+/// Machine-readable plan: one `<kind>\t<skill>\t<note>` line per op.
+///
+/// Unlike `summarize_plan`, this format is stable across minor versions;
+/// the note column is empty when an op has none.
+pub fn porcelain_plan(plan: &Plan) -> Vec<String> {
+    plan.ops
+        .iter()
+        .map(|op| {
+            format!(
+                "{}\t{}\t{}",
+                op.kind.label(),
+                op.skill,
+                op.note.as_deref().unwrap_or("")
+            )
+        })
+        .collect()
+}
+
+/// Options for [`render_plan`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RenderOpts {
+    /// Use the stable [`porcelain_plan`] lines instead of [`summarize_plan`].
+    pub porcelain: bool,
+    /// Append a footer with the number of ops per kind.
+    pub counts: bool,
+}
+
+/// Renders `plan` as one text block, one op per line, each ending in a
+/// newline; an empty plan without `counts` renders as an empty string.
+///
+/// The footer is `<n> ops: <kind> <count>, ...` (porcelain:
+/// `total\t<n>\t<kind>=<count>,...`), listing only kinds that occur.
+pub fn render_plan(plan: &Plan, opts: RenderOpts) -> String {
+    let lines = if opts.porcelain {
+        porcelain_plan(plan)
+    } else {
+        summarize_plan(plan)
+    };
+    let mut out = String::new();
+    for line in lines {
+        out.push_str(&line);
+        out.push('\n');
+    }
+    if opts.counts {
+        let counts = [
+            PlanKind::Install,
+            PlanKind::Update,
+            PlanKind::Skip,
+            PlanKind::Prune,
+            PlanKind::Merge,
+        ]
+        .into_iter()
+        .filter_map(|kind| {
+            let count = plan.ops.iter().filter(|op| op.kind == kind).count();
+            (count > 0).then_some((kind.label(), count))
+        })
+        .collect::<Vec<_>>();
+        let total = plan.ops.len();
+        let footer = if opts.porcelain {
+            let counts = counts
+                .iter()
+                .map(|(kind, count)| format!("{kind}={count}"))
+                .collect::<Vec<_>>()
+                .join(",");
+            format!("total\t{total}\t{counts}")
+        } else if counts.is_empty() {
+            format!("{total} ops")
+        } else {
+            let counts = counts
+                .iter()
+                .map(|(kind, count)| format!("{kind} {count}"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("{total} ops: {counts}")
+        };
+        out.push_str(&footer);
+        out.push('\n');
+    }
+    out
+}
+
 #[cfg(test)]
 #[derive(Debug, Default)]
 struct RenameTestHooks {
@@ -363,13 +1019,41 @@ enum RenamePhase {
     Restore,
 }
 
-fn rename_dir(from: &Path, to: &Path, _phase: RenamePhase) -> io::Result<()> {
+fn rename_dir(from: &Path, to: &Path, _phase: RenamePhase, retry: &RetryConfig) -> io::Result<()> {
     #[cfg(test)]
     maybe_fail_rename_for_test(_phase)?;
-    fs::rename(from, to)
+    with_retry(retry, || fs::rename(from, to))
 }
 
-fn replace_dir(src: &Path, dest: &Path) -> AppResult<()> {
+/// Runs `op`, retrying transient IO failures with exponential backoff.
+fn with_retry<T>(retry: &RetryConfig, mut op: impl FnMut() -> io::Result<T>) -> io::Result<T> {
+    let mut delay = Duration::from_millis(retry.backoff_ms);
+    let mut attempt = 1;
+    loop {
+        match op() {
+            Err(err) if attempt < retry.attempts && is_transient(&err) => {
+                thread::sleep(delay);
+                delay = delay.saturating_mul(2);
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+fn is_transient(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::Interrupted | io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+    )
+}
+
+fn replace_dir(
+    src: &Path,
+    dest: &Path,
+    retry: &RetryConfig,
+    copier: &mut FileCopier,
+) -> AppResult<()> {
     let parent = dest.parent().ok_or_else(|| {
         AppError::exec(
             crate::tr!(
@@ -384,26 +1068,26 @@ fn replace_dir(src: &Path, dest: &Path) -> AppResult<()> {
         )
     })?;
     let temp_dir = TempDir::new_in(parent).map_err(|err| {
-        AppError::exec(
+        AppError::io(
             crate::tr!(
                 "一時ディレクトリの作成に失敗しました: {}",
                 "Failed to create temp directory: {}",
                 parent.display()
             ),
-            Some(err.to_string()),
+            err,
         )
     })?;
-    copy_dir(src, temp_dir.path())?;
+    copy_dir(src, temp_dir.path(), retry, copier)?;
     let backup_path = if dest.exists() {
         let backup = next_backup_path(dest)?;
-        rename_dir(dest, &backup, RenamePhase::Backup).map_err(|err| {
-            AppError::exec(
+        rename_dir(dest, &backup, RenamePhase::Backup, retry).map_err(|err| {
+            AppError::io(
                 crate::tr!(
                     "既存ディレクトリの退避に失敗しました: {}",
                     "Failed to back up existing directory: {}",
                     dest.display()
                 ),
-                Some(err.to_string()),
+                err,
             )
         })?;
         Some(backup)
@@ -411,17 +1095,17 @@ fn replace_dir(src: &Path, dest: &Path) -> AppResult<()> {
         None
     };
 
-    match rename_dir(temp_dir.path(), dest, RenamePhase::Publish) {
+    match rename_dir(temp_dir.path(), dest, RenamePhase::Publish, retry) {
         Ok(()) => {
             if let Some(backup) = backup_path {
-                fs::remove_dir_all(&backup).map_err(|err| {
-                    AppError::exec(
+                with_retry(retry, || fs::remove_dir_all(&backup)).map_err(|err| {
+                    AppError::io(
                         crate::tr!(
                             "バックアップの削除に失敗しました: {}",
                             "Failed to remove backup directory: {}",
                             backup.display()
                         ),
-                        Some(err.to_string()),
+                        err,
                     )
                 })?;
             }
@@ -429,14 +1113,14 @@ fn replace_dir(src: &Path, dest: &Path) -> AppResult<()> {
         }
         Err(publish_err) => {
             if let Some(backup) = backup_path {
-                match rename_dir(&backup, dest, RenamePhase::Restore) {
-                    Ok(()) => Err(AppError::exec(
+                match rename_dir(&backup, dest, RenamePhase::Restore, retry) {
+                    Ok(()) => Err(AppError::io(
                         crate::tr!(
                             "ディレクトリの置換に失敗しました: {}",
                             "Failed to replace directory: {}",
                             dest.display()
                         ),
-                        Some(publish_err.to_string()),
+                        publish_err,
                     )),
                     Err(restore_err) => Err(AppError::exec(
                         crate::tr!(
@@ -458,19 +1142,218 @@ fn replace_dir(src: &Path, dest: &Path) -> AppResult<()> {
                     )),
                 }
             } else {
-                Err(AppError::exec(
+                Err(AppError::io(
                     crate::tr!(
                         "ディレクトリの置換に失敗しました: {}",
                         "Failed to replace directory: {}",
                         dest.display()
                     ),
-                    Some(publish_err.to_string()),
+                    publish_err,
                 ))
             }
         }
     }
 }
 
+/// Writes the files of `src` into `dest`, touching only files that differ.
+///
+/// Each changed file is written to a temp file beside it and renamed into
+/// place, so a crash leaves either the old or the new content, never a mix.
+/// Unlike `replace_dir`, the skill as a whole is not swapped atomically.
+/// With `delete_stale`, paths absent from `src` are removed from `dest`.
+fn overlay_dir(
+    src: &Path,
+    dest: &Path,
+    retry: &RetryConfig,
+    copier: &mut FileCopier,
+    delete_stale: bool,
+) -> AppResult<()> {
+    let mut wanted = BTreeSet::new();
+    for entry in walkdir::WalkDir::new(src).follow_links(false) {
+        let entry = entry.map_err(|err| {
+            AppError::io(
+                crate::tr!(
+                    "ディレクトリコピーに失敗しました: {}",
+                    "Failed to copy directory: {}",
+                    src.display()
+                ),
+                err.into(),
+            )
+        })?;
+        let rel = relative_to(src, entry.path())?;
+        if rel.as_os_str().is_empty() {
+            continue;
+        }
+        let dest_path = dest.join(&rel);
+        let dest_meta = fs::symlink_metadata(&dest_path).ok();
+        if entry.file_type().is_dir() {
+            if dest_meta.as_ref().is_some_and(|meta| !meta.is_dir()) {
+                remove_path(&dest_path, retry)?;
+            }
+            with_retry(retry, || fs::create_dir_all(&dest_path)).map_err(|err| {
+                AppError::io(
+                    crate::tr!(
+                        "ディレクトリ作成に失敗しました: {}",
+                        "Failed to create directory: {}",
+                        dest_path.display()
+                    ),
+                    err,
+                )
+            })?;
+        } else if entry.file_type().is_file() {
+            let unchanged = match dest_meta {
+                Some(meta) if meta.is_file() => files_equal(entry.path(), &dest_path)?,
+                Some(_) => {
+                    remove_path(&dest_path, retry)?;
+                    false
+                }
+                None => false,
+            };
+            if !unchanged {
+                write_file_atomic(entry.path(), &dest_path, retry, copier)?;
+            }
+        } else {
+            return Err(AppError::exec(
+                crate::tr!(
+                    "未対応のファイル種別です: {}",
+                    "Unsupported file type: {}",
+                    entry.path().display()
+                ),
+                Some(crate::tr!(
+                    "通常ファイルのみを含めてください",
+                    "Include only regular files."
+                )),
+            ));
+        }
+        wanted.insert(rel);
+    }
+    if !delete_stale {
+        return Ok(());
+    }
+
+    // Deepest paths first so directories are empty by the time they are removed.
+    let mut stale = Vec::new();
+    for entry in walkdir::WalkDir::new(dest)
+        .follow_links(false)
+        .contents_first(true)
+    {
+        let entry = entry.map_err(|err| {
+            AppError::io(
+                crate::tr!(
+                    "ディレクトリの走査に失敗しました: {}",
+                    "Failed to scan directory: {}",
+                    dest.display()
+                ),
+                err.into(),
+            )
+        })?;
+        let rel = relative_to(dest, entry.path())?;
+        if !rel.as_os_str().is_empty() && !wanted.contains(&rel) {
+            stale.push(entry.into_path());
+        }
+    }
+    for path in stale {
+        remove_path(&path, retry)?;
+    }
+    Ok(())
+}
+
+fn relative_to(root: &Path, path: &Path) -> AppResult<PathBuf> {
+    path.strip_prefix(root)
+        .map(Path::to_path_buf)
+        .map_err(|err| {
+            AppError::exec_with_source(
+                crate::tr!(
+                    "相対パスの取得に失敗しました: {}",
+                    "Failed to get relative path: {}",
+                    path.display()
+                ),
+                Some(err.to_string()),
+                err,
+            )
+        })
+}
+
+fn files_equal(a: &Path, b: &Path) -> AppResult<bool> {
+    let read_error = |path: &Path, err: io::Error| {
+        AppError::io(
+            crate::tr!(
+                "ファイルの読み込みに失敗しました: {}",
+                "Failed to read file: {}",
+                path.display()
+            ),
+            err,
+        )
+    };
+    let meta_a = fs::metadata(a).map_err(|err| read_error(a, err))?;
+    let meta_b = fs::metadata(b).map_err(|err| read_error(b, err))?;
+    // Permissions count too, so an incremental update carries mode changes over.
+    if meta_a.len() != meta_b.len() || meta_a.permissions() != meta_b.permissions() {
+        return Ok(false);
+    }
+    let mut reader_a = io::BufReader::new(fs::File::open(a).map_err(|err| read_error(a, err))?);
+    let mut reader_b = io::BufReader::new(fs::File::open(b).map_err(|err| read_error(b, err))?);
+    let mut buf_a = [0u8; 8192];
+    let mut buf_b = [0u8; 8192];
+    loop {
+        let read = io::Read::read(&mut reader_a, &mut buf_a).map_err(|err| read_error(a, err))?;
+        if read == 0 {
+            return Ok(true);
+        }
+        io::Read::read_exact(&mut reader_b, &mut buf_b[..read])
+            .map_err(|err| read_error(b, err))?;
+        if buf_a[..read] != buf_b[..read] {
+            return Ok(false);
+        }
+    }
+}
+
+fn write_file_atomic(
+    src: &Path,
+    dest: &Path,
+    retry: &RetryConfig,
+    copier: &mut FileCopier,
+) -> AppResult<()> {
+    let write_error = |err: io::Error| {
+        AppError::io(
+            crate::tr!(
+                "ファイルコピーに失敗しました: {} -> {}",
+                "Failed to copy file: {} -> {}",
+                src.display(),
+                dest.display()
+            ),
+            err,
+        )
+    };
+    let parent = dest.parent().unwrap_or(Path::new("."));
+    let temp =
+        with_retry(retry, || tempfile::NamedTempFile::new_in(parent)).map_err(write_error)?;
+    with_retry(retry, || copier.copy(src, temp.path())).map_err(write_error)?;
+    temp.persist(dest).map_err(|err| write_error(err.error))?;
+    Ok(())
+}
+
+fn remove_path(path: &Path, retry: &RetryConfig) -> AppResult<()> {
+    let is_dir = fs::symlink_metadata(path).is_ok_and(|meta| meta.is_dir());
+    with_retry(retry, || {
+        if is_dir {
+            fs::remove_dir_all(path)
+        } else {
+            fs::remove_file(path)
+        }
+    })
+    .map_err(|err| {
+        AppError::io(
+            crate::tr!(
+                "削除に失敗しました: {}",
+                "Failed to remove: {}",
+                path.display()
+            ),
+            err,
+        )
+    })
+}
+
 fn next_backup_path(dest: &Path) -> AppResult<PathBuf> {
     let parent = dest.parent().ok_or_else(|| {
         AppError::exec(
@@ -520,36 +1403,153 @@ fn next_backup_path(dest: &Path) -> AppResult<PathBuf> {
     ))
 }
 
-fn copy_dir(src: &Path, dest: &Path) -> AppResult<()> {
-    fs::create_dir_all(dest).map_err(|err| {
-        AppError::exec(
+const COPY_CHUNK_BYTES: u64 = 64 * 1024;
+
+/// Copies file contents for one op, following `sync.reflink` and
+/// `sync.throttle_bytes_per_sec`. The throttle budget spans every file the
+/// op copies.
+pub(crate) struct FileCopier {
+    reflink: ReflinkMode,
+    throttle: Option<Throttle>,
+}
+
+impl FileCopier {
+    pub(crate) fn new(sync: &SyncConfig) -> Self {
+        Self {
+            reflink: sync.reflink,
+            throttle: sync.throttle_bytes_per_sec.map(Throttle::new),
+        }
+    }
+
+    /// Copies one regular file, replacing `dest`.
+    fn copy(&mut self, src: &Path, dest: &Path) -> io::Result<()> {
+        if self.reflink != ReflinkMode::Never {
+            // Clones are created with O_EXCL, so clear the destination first.
+            match fs::remove_file(dest) {
+                Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+                _ => {}
+            }
+            match reflink_copy::reflink(src, dest) {
+                Ok(()) => return Ok(()),
+                Err(err) if self.reflink == ReflinkMode::Always => {
+                    return Err(io::Error::new(
+                        err.kind(),
+                        crate::tr!(
+                            "reflink に失敗しました（sync.reflink = \"always\"）: {}",
+                            "reflink failed (sync.reflink = \"always\"): {}",
+                            err
+                        ),
+                    ));
+                }
+                Err(err)
+                    if matches!(
+                        err.kind(),
+                        io::ErrorKind::NotFound
+                            | io::ErrorKind::PermissionDenied
+                            | io::ErrorKind::AlreadyExists
+                    ) =>
+                {
+                    return Err(err);
+                }
+                Err(err) => {
+                    tracing::trace!(src = %src.display(), error = %err, "reflink unsupported, copying");
+                }
+            }
+        }
+        match &mut self.throttle {
+            None => fs::copy(src, dest).map(|_| ()),
+            Some(throttle) => copy_throttled(src, dest, throttle),
+        }
+    }
+}
+
+/// Sleeps so the bytes reported through [`Throttle::consume`] average at
+/// most `bytes_per_sec` since the throttle was created.
+struct Throttle {
+    bytes_per_sec: u64,
+    started: Instant,
+    written: u64,
+}
+
+impl Throttle {
+    fn new(bytes_per_sec: u64) -> Self {
+        Self {
+            bytes_per_sec,
+            started: Instant::now(),
+            written: 0,
+        }
+    }
+
+    /// Largest write that keeps bursts within about one second of budget.
+    fn chunk_len(&self) -> usize {
+        COPY_CHUNK_BYTES.min(self.bytes_per_sec) as usize
+    }
+
+    fn consume(&mut self, bytes: u64) {
+        self.written += bytes;
+        let due = Duration::from_secs_f64(self.written as f64 / self.bytes_per_sec as f64);
+        if let Some(wait) = due.checked_sub(self.started.elapsed()) {
+            thread::sleep(wait);
+        }
+    }
+}
+
+/// `fs::copy` as a read/write loop that reports each chunk to `throttle`.
+fn copy_throttled(src: &Path, dest: &Path, throttle: &mut Throttle) -> io::Result<()> {
+    let mut reader = fs::File::open(src)?;
+    let permissions = reader.metadata()?.permissions();
+    let mut writer = fs::File::create(dest)?;
+    let mut buf = vec![0; throttle.chunk_len()];
+    loop {
+        let read = match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        };
+        writer.write_all(&buf[..read])?;
+        throttle.consume(read as u64);
+    }
+    writer.flush()?;
+    fs::set_permissions(dest, permissions)
+}
+
+fn copy_dir(
+    src: &Path,
+    dest: &Path,
+    retry: &RetryConfig,
+    copier: &mut FileCopier,
+) -> AppResult<()> {
+    with_retry(retry, || fs::create_dir_all(dest)).map_err(|err| {
+        AppError::io(
             crate::tr!(
                 "ディレクトリ作成に失敗しました: {}",
                 "Failed to create directory: {}",
                 dest.display()
             ),
-            Some(err.to_string()),
+            err,
         )
     })?;
     for entry in walkdir::WalkDir::new(src).follow_links(false) {
         let entry = entry.map_err(|err| {
-            AppError::exec(
+            AppError::io(
                 crate::tr!(
                     "ディレクトリコピーに失敗しました: {}",
                     "Failed to copy directory: {}",
                     src.display()
                 ),
-                Some(err.to_string()),
+                err.into(),
             )
         })?;
         let rel = entry.path().strip_prefix(src).map_err(|err| {
-            AppError::exec(
+            AppError::exec_with_source(
                 crate::tr!(
                     "相対パスの取得に失敗しました: {}",
                     "Failed to get relative path: {}",
                     entry.path().display()
                 ),
                 Some(err.to_string()),
+                err,
             )
         })?;
         if rel.as_os_str().is_empty() {
@@ -557,38 +1557,38 @@ fn copy_dir(src: &Path, dest: &Path) -> AppResult<()> {
         }
         let dest_path = dest.join(rel);
         if entry.file_type().is_dir() {
-            fs::create_dir_all(&dest_path).map_err(|err| {
-                AppError::exec(
+            with_retry(retry, || fs::create_dir_all(&dest_path)).map_err(|err| {
+                AppError::io(
                     crate::tr!(
                         "ディレクトリ作成に失敗しました: {}",
                         "Failed to create directory: {}",
                         dest_path.display()
                     ),
-                    Some(err.to_string()),
+                    err,
                 )
             })?;
         } else if entry.file_type().is_file() {
             if let Some(parent) = dest_path.parent() {
-                fs::create_dir_all(parent).map_err(|err| {
-                    AppError::exec(
+                with_retry(retry, || fs::create_dir_all(parent)).map_err(|err| {
+                    AppError::io(
                         crate::tr!(
                             "ディレクトリ作成に失敗しました: {}",
                             "Failed to create directory: {}",
                             parent.display()
                         ),
-                        Some(err.to_string()),
+                        err,
                     )
                 })?;
             }
-            fs::copy(entry.path(), &dest_path).map_err(|err| {
-                AppError::exec(
+            with_retry(retry, || copier.copy(entry.path(), &dest_path)).map_err(|err| {
+                AppError::io(
                     crate::tr!(
                         "ファイルコピーに失敗しました: {} -> {}",
                         "Failed to copy file: {} -> {}",
                         entry.path().display(),
                         dest_path.display()
                     ),
-                    Some(err.to_string()),
+                    err,
                 )
             })?;
         } else {
@@ -607,3 +1607,231 @@ fn copy_dir(src: &Path, dest: &Path) -> AppResult<()> {
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+
+    fn sample_plan() -> Plan {
+        let op = |kind, skill: &str, note: Option<&str>| PlanOp {
+            kind,
+            skill: skill.to_string(),
+            src: None,
+            dest: None,
+            note: note.map(str::to_string),
+        };
+        Plan {
+            ops: vec![
+                op(PlanKind::Install, "alpha", Some("missing")),
+                op(PlanKind::Update, "beta", Some("diff")),
+                op(PlanKind::Skip, "gamma", None),
+                op(PlanKind::Prune, "delta", Some("extra")),
+                op(PlanKind::Merge, "eps", Some("diff")),
+            ],
+            hooks: PlanHooks::default(),
+            source_root: None,
+        }
+    }
+
+    #[test]
+    fn porcelain_plan_is_tab_separated() {
+        let plan = sample_plan();
+        assert_eq!(
+            porcelain_plan(&plan),
+            vec![
+                "install\talpha\tmissing",
+                "update\tbeta\tdiff",
+                "skip\tgamma\t",
+                "prune\tdelta\textra",
+                "merge\teps\tdiff",
+            ]
+        );
+    }
+
+    #[test]
+    fn render_plan_summary() {
+        assert_eq!(
+            render_plan(&sample_plan(), RenderOpts::default()),
+            "install alpha (missing)\nupdate beta (diff)\nskip gamma\n\
+             prune delta (extra)\nmerge eps (diff)\n"
+        );
+    }
+
+    #[test]
+    fn render_plan_summary_with_counts() {
+        let mut plan = sample_plan();
+        plan.ops.push(plan.ops[0].clone());
+        let opts = RenderOpts {
+            counts: true,
+            ..RenderOpts::default()
+        };
+        let rendered = render_plan(&plan, opts);
+        assert!(rendered.starts_with("install alpha (missing)\n"));
+        assert!(rendered.ends_with(
+            "merge eps (diff)\ninstall alpha (missing)\n\
+                 6 ops: install 2, update 1, skip 1, prune 1, merge 1\n"
+        ));
+
+        let empty = Plan {
+            ops: Vec::new(),
+            hooks: PlanHooks::default(),
+            source_root: None,
+        };
+        assert_eq!(render_plan(&empty, RenderOpts::default()), "");
+        assert_eq!(render_plan(&empty, opts), "0 ops\n");
+    }
+
+    #[test]
+    fn render_plan_porcelain() {
+        let opts = RenderOpts {
+            porcelain: true,
+            ..RenderOpts::default()
+        };
+        assert_eq!(
+            render_plan(&sample_plan(), opts),
+            "install\talpha\tmissing\nupdate\tbeta\tdiff\nskip\tgamma\t\n\
+             prune\tdelta\textra\nmerge\teps\tdiff\n"
+        );
+    }
+
+    #[test]
+    fn render_plan_porcelain_with_counts() {
+        let mut plan = sample_plan();
+        plan.ops.retain(|op| op.kind != PlanKind::Merge);
+        let opts = RenderOpts {
+            porcelain: true,
+            counts: true,
+        };
+        assert_eq!(
+            render_plan(&plan, opts),
+            "install\talpha\tmissing\nupdate\tbeta\tdiff\nskip\tgamma\t\n\
+             prune\tdelta\textra\ntotal\t4\tinstall=1,update=1,skip=1,prune=1\n"
+        );
+    }
+
+    fn retry(attempts: u32) -> RetryConfig {
+        RetryConfig {
+            attempts,
+            backoff_ms: 1,
+        }
+    }
+
+    #[test]
+    fn with_retry_recovers_from_transient_errors() {
+        let calls = Cell::new(0);
+        let result = with_retry(&retry(3), || {
+            calls.set(calls.get() + 1);
+            if calls.get() <= 2 {
+                Err(io::Error::from(io::ErrorKind::Interrupted))
+            } else {
+                Ok(calls.get())
+            }
+        });
+        assert_eq!(result.unwrap(), 3);
+        assert_eq!(calls.get(), 3);
+    }
+
+    #[test]
+    fn with_retry_gives_up_after_attempts() {
+        let calls = Cell::new(0);
+        let result: io::Result<()> = with_retry(&retry(2), || {
+            calls.set(calls.get() + 1);
+            Err(io::Error::from(io::ErrorKind::WouldBlock))
+        });
+        assert!(result.is_err());
+        assert_eq!(calls.get(), 2);
+    }
+
+    #[test]
+    fn with_retry_fails_fast_on_permanent_errors() {
+        let calls = Cell::new(0);
+        let result: io::Result<()> = with_retry(&retry(5), || {
+            calls.set(calls.get() + 1);
+            Err(io::Error::from(io::ErrorKind::PermissionDenied))
+        });
+        assert!(result.is_err());
+        assert_eq!(calls.get(), 1);
+    }
+
+    /// A temp dir on tmpfs when available, which cannot clone files.
+    fn no_clone_dir() -> tempfile::TempDir {
+        let shm = Path::new("/dev/shm");
+        if shm.is_dir() {
+            tempfile::TempDir::new_in(shm).unwrap()
+        } else {
+            tempfile::TempDir::new().unwrap()
+        }
+    }
+
+    fn copier(reflink: ReflinkMode, throttle_bytes_per_sec: Option<u64>) -> FileCopier {
+        FileCopier::new(&SyncConfig {
+            reflink,
+            throttle_bytes_per_sec,
+            ..SyncConfig::default()
+        })
+    }
+
+    #[test]
+    fn copier_auto_falls_back_when_clone_is_unsupported() {
+        let temp = no_clone_dir();
+        let src = temp.path().join("src.txt");
+        let dest = temp.path().join("dest.txt");
+        fs::write(&src, "content").unwrap();
+        fs::write(&dest, "stale").unwrap();
+
+        copier(ReflinkMode::Auto, None).copy(&src, &dest).unwrap();
+        assert_eq!(fs::read_to_string(&dest).unwrap(), "content");
+
+        let skill = temp.path().join("skill");
+        fs::create_dir_all(skill.join("sub")).unwrap();
+        fs::write(skill.join("sub/file.txt"), "nested").unwrap();
+        let copied = temp.path().join("copied");
+        copy_dir(
+            &skill,
+            &copied,
+            &retry(1),
+            &mut copier(ReflinkMode::Auto, None),
+        )
+        .unwrap();
+        assert_eq!(
+            fs::read_to_string(copied.join("sub/file.txt")).unwrap(),
+            "nested"
+        );
+    }
+
+    #[test]
+    fn copier_always_errors_when_clone_is_unsupported() {
+        let temp = no_clone_dir();
+        let src = temp.path().join("src.txt");
+        fs::write(&src, "content").unwrap();
+        let supported = reflink_copy::reflink(&src, temp.path().join("probe")).is_ok();
+
+        let result = copier(ReflinkMode::Always, None).copy(&src, &temp.path().join("dest.txt"));
+        assert_eq!(result.is_ok(), supported);
+    }
+
+    #[test]
+    fn throttled_copy_takes_at_least_size_over_rate() {
+        let temp = no_clone_dir();
+        let skill = temp.path().join("skill");
+        fs::create_dir_all(&skill).unwrap();
+        fs::write(skill.join("a.bin"), vec![1u8; 6_000]).unwrap();
+        fs::write(skill.join("b.bin"), vec![2u8; 6_000]).unwrap();
+        let copied = temp.path().join("copied");
+
+        // 12_000 bytes at 40_000 B/s across both files of the op.
+        let started = Instant::now();
+        copy_dir(
+            &skill,
+            &copied,
+            &retry(1),
+            &mut copier(ReflinkMode::Never, Some(40_000)),
+        )
+        .unwrap();
+        assert!(started.elapsed() >= Duration::from_millis(300));
+        assert_eq!(fs::read(copied.join("a.bin")).unwrap(), vec![1u8; 6_000]);
+        assert_eq!(fs::read(copied.join("b.bin")).unwrap(), vec![2u8; 6_000]);
+    }
+}
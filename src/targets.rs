@@ -0,0 +1,126 @@
+use std::path::PathBuf;
+
+use serde::Serialize;
+use unicode_width::UnicodeWidthStr;
+
+use crate::config::Config;
+use crate::error::{AppError, AppResult};
+use crate::source::open_source;
+
+const TARGETS_HEADERS: [&str; 4] = ["NAME", "ROOT", "EXISTS", "SKILLS"];
+const TARGETS_PADDING: usize = 2;
+
+/// One row of `targets --long`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TargetSummary {
+    pub name: String,
+    pub root: PathBuf,
+    pub exists: bool,
+    /// `None` when the root could not be listed (missing, unreadable, invalid).
+    pub skills: Option<usize>,
+}
+
+/// Summarizes every configured target; a root that cannot be listed yields
+/// `skills: None` instead of failing the whole listing.
+pub fn summarize_targets(config: &Config) -> Vec<TargetSummary> {
+    config
+        .targets
+        .iter()
+        .map(|target| {
+            let skills = open_source(&target.root, &config.skills)
+                .and_then(|source| source.list_skills())
+                .map(|skills| skills.len());
+            if let Err(err) = &skills {
+                tracing::debug!(target = %target.name, error = %err, "skip skill count");
+            }
+            TargetSummary {
+                name: target.name.clone(),
+                root: target.root.clone(),
+                exists: target.root.exists(),
+                skills: skills.ok(),
+            }
+        })
+        .collect()
+}
+
+/// Renders `summaries` as a left-aligned table with a header row.
+pub fn render_targets_table(summaries: &[TargetSummary]) -> String {
+    let rows = summaries
+        .iter()
+        .map(|summary| {
+            [
+                summary.name.clone(),
+                summary.root.display().to_string(),
+                if summary.exists { "yes" } else { "no" }.to_string(),
+                summary
+                    .skills
+                    .map(|count| count.to_string())
+                    .unwrap_or_else(|| "N/A".to_string()),
+            ]
+        })
+        .collect::<Vec<_>>();
+    let mut widths = TARGETS_HEADERS.map(str::len);
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.width());
+        }
+    }
+    let mut out = String::new();
+    let header = TARGETS_HEADERS.map(str::to_string);
+    for row in std::iter::once(&header).chain(&rows) {
+        let mut line = String::new();
+        for (index, (cell, width)) in row.iter().zip(widths).enumerate() {
+            line.push_str(cell);
+            if index + 1 < row.len() {
+                let pad = width.saturating_sub(cell.width()) + TARGETS_PADDING;
+                line.extend(std::iter::repeat_n(' ', pad));
+            }
+        }
+        out.push_str(&line);
+        out.push('\n');
+    }
+    out
+}
+
+/// Renders `summaries` as a pretty-printed JSON array.
+pub fn render_targets_json(summaries: &[TargetSummary]) -> AppResult<String> {
+    serde_json::to_string_pretty(summaries).map_err(|err| {
+        AppError::exec_with_source(
+            crate::tr!(
+                "targets の JSON 出力に失敗しました",
+                "Failed to render targets as JSON"
+            ),
+            None,
+            err,
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_targets_table_aligns_columns() {
+        let summaries = vec![
+            TargetSummary {
+                name: "codex".to_string(),
+                root: PathBuf::from("/skills/codex"),
+                exists: true,
+                skills: Some(12),
+            },
+            TargetSummary {
+                name: "x".to_string(),
+                root: PathBuf::from("/gone"),
+                exists: false,
+                skills: None,
+            },
+        ];
+        assert_eq!(
+            render_targets_table(&summaries),
+            "NAME   ROOT           EXISTS  SKILLS\n\
+             codex  /skills/codex  yes     12\n\
+             x      /gone          no      N/A\n"
+        );
+    }
+}
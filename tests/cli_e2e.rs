@@ -209,6 +209,489 @@ fn status_outputs_table_snapshot() {
     insta::assert_snapshot!(stdout);
 }
 
+#[test]
+fn list_digests_prints_tab_separated_full_digests() {
+    let (_root, _global_root, target_root, config_path) = setup_fixture();
+
+    write_file(&target_root.join("skill_a/file.txt"), "a");
+    write_file(&target_root.join("skill_b/file.txt"), "b");
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.arg("list").arg("--target").arg("t1").arg("--digests");
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stdout = normalize_output(&output);
+
+    let rows = stdout
+        .lines()
+        .map(|line| line.split('\t').collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0][0], "skill_a");
+    assert_eq!(rows[1][0], "skill_b");
+    for row in &rows {
+        assert_eq!(row.len(), 2);
+        assert_eq!(row[1].len(), 64);
+    }
+    assert_ne!(rows[0][1], rows[1][1]);
+}
+
+#[test]
+fn status_algo_override_detects_diff() {
+    let (_root, global_root, target_root, config_path) = setup_fixture();
+
+    write_file(&global_root.join("skill_same/file.txt"), "same");
+    write_file(&target_root.join("skill_same/file.txt"), "same");
+    write_file(&global_root.join("skill_diff/file.txt"), "g");
+    write_file(&target_root.join("skill_diff/file.txt"), "t");
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["status", "--target", "t1", "--algo", "sha256"]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stdout = normalize_output(&output);
+    let state_of = |skill: &str| {
+        stdout
+            .lines()
+            .find(|line| line.starts_with(skill))
+            .and_then(|line| line.split_whitespace().nth(1))
+            .map(str::to_string)
+    };
+    assert_eq!(state_of("skill_diff").as_deref(), Some("diff"));
+    assert_eq!(state_of("skill_same").as_deref(), Some("same"));
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args([
+        "push",
+        "--all",
+        "--target",
+        "t1",
+        "--dry-run",
+        "--algo",
+        "sha256",
+    ]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    assert!(normalize_output(&output).contains("skill_diff"));
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["status", "--target", "t1", "--algo", "md5"]);
+    cmd.assert().code(2);
+}
+
+#[test]
+fn no_ignore_compares_ignored_files() {
+    let (_root, global_root, target_root, config_path) = setup_fixture();
+    let mut body = fs::read_to_string(&config_path).unwrap();
+    body.push_str("\n[hash]\nignore = [\"**/*.log\"]\n");
+    body.push_str("\n[diff]\ncommand = [\"__no_such_command__\", \"{left}\", \"{right}\"]\n");
+    fs::write(&config_path, body).unwrap();
+
+    write_file(&global_root.join("skill_a/file.txt"), "same");
+    write_file(&target_root.join("skill_a/file.txt"), "same");
+    write_file(&global_root.join("skill_a/debug.log"), "global");
+    write_file(&target_root.join("skill_a/debug.log"), "target");
+
+    let state = |extra: &[&str]| {
+        let mut cmd = cargo_bin_cmd!("skillctl");
+        set_config_env(&mut cmd, &config_path);
+        cmd.args(["status", "--target", "t1"]).args(extra);
+        let output = cmd.assert().success().get_output().stdout.clone();
+        normalize_output(&output)
+            .lines()
+            .find(|line| line.starts_with("skill_a"))
+            .and_then(|line| line.split_whitespace().nth(1))
+            .map(str::to_string)
+    };
+    assert_eq!(state(&[]).as_deref(), Some("same"));
+    assert_eq!(state(&["--no-ignore"]).as_deref(), Some("diff"));
+
+    // Matching digests skip the command; with --no-ignore the log makes it run (and fail).
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["diff", "skill_a", "--target", "t1", "--summary-only"]);
+    cmd.assert().success().stdout("");
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args([
+        "diff",
+        "skill_a",
+        "--target",
+        "t1",
+        "--summary-only",
+        "--no-ignore",
+    ]);
+    cmd.assert().code(4);
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["push", "skill_a", "--target", "t1", "--no-ignore"]);
+    cmd.assert().success();
+    assert_eq!(
+        fs::read_to_string(target_root.join("skill_a/debug.log")).unwrap(),
+        "global"
+    );
+}
+
+#[test]
+fn status_count_prints_only_the_number() {
+    let (_root, global_root, target_root, config_path) = setup_fixture();
+
+    write_file(&global_root.join("skill_a/file.txt"), "g");
+    write_file(&target_root.join("skill_a/file.txt"), "t");
+    write_file(&global_root.join("skill_b/file.txt"), "g");
+    write_file(&target_root.join("skill_b/file.txt"), "t");
+    write_file(&global_root.join("skill_c/file.txt"), "same");
+    write_file(&target_root.join("skill_c/file.txt"), "same");
+    write_file(&global_root.join("skill_d/file.txt"), "missing");
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["status", "--target", "t1", "--count", "diff"]);
+    cmd.assert().success().stdout("2\n");
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["status", "--all", "--count", "diff,missing"]);
+    cmd.assert().success().stdout("3\n");
+}
+
+#[test]
+fn status_all_only_shows_named_skill_under_each_target() {
+    let (_root, global_root, t1_root, t2_root, config_path) = setup_fixture_two_targets();
+
+    for root in [&global_root, &t1_root, &t2_root] {
+        write_file(&root.join("wanted/file.txt"), "same");
+        write_file(&root.join("noise/file.txt"), "same");
+    }
+    write_file(&t2_root.join("wanted/file.txt"), "changed");
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["status", "--all", "--only", "wanted", "--only", "absent"]);
+    let assert = cmd.assert().success();
+    let stdout = normalize_output(&assert.get_output().stdout);
+    let sections = stdout
+        .split("ターゲット: ")
+        .skip(1)
+        .map(|section| {
+            section
+                .lines()
+                .skip(2)
+                .map(|line| {
+                    line.split_whitespace()
+                        .take(2)
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        sections,
+        vec![
+            vec!["wanted same".to_string()],
+            vec!["wanted diff".to_string()]
+        ]
+    );
+    assert!(normalize_output(&assert.get_output().stderr).contains("absent"));
+}
+
+#[test]
+fn status_jobs_one_matches_parallel_output() {
+    let (_root, global_root, target_root, config_path) = setup_fixture();
+
+    for index in 0..24 {
+        let skill = format!("skill{index:02}");
+        write_file(&global_root.join(&skill).join("file.txt"), &skill);
+        match index % 4 {
+            0 => write_file(&target_root.join(&skill).join("file.txt"), &skill),
+            1 => write_file(&target_root.join(&skill).join("file.txt"), "changed"),
+            2 => {}
+            _ => write_file(&target_root.join(format!("extra{index:02}/file.txt")), "x"),
+        }
+    }
+
+    let status = |jobs: Option<&str>| {
+        let mut cmd = cargo_bin_cmd!("skillctl");
+        set_config_env(&mut cmd, &config_path);
+        cmd.args(["status", "--target", "t1", "--format", "ndjson"]);
+        if let Some(jobs) = jobs {
+            cmd.args(["--jobs", jobs]);
+        }
+        normalize_output(&cmd.assert().success().get_output().stdout)
+    };
+    let sequential = status(Some("1"));
+    assert_eq!(sequential.lines().count(), 30);
+    assert_eq!(status(None), sequential);
+    assert_eq!(status(Some("8")), sequential);
+}
+
+#[test]
+fn push_check_exits_nonzero_only_when_out_of_sync() {
+    let (_root, global_root, target_root, config_path) = setup_fixture();
+
+    write_file(&global_root.join("skill1/file.txt"), "new");
+    write_file(&target_root.join("skill1/file.txt"), "old");
+
+    let check = || {
+        let mut cmd = cargo_bin_cmd!("skillctl");
+        set_config_env(&mut cmd, &config_path);
+        cmd.args(["push", "--all", "--target", "t1", "--check"]);
+        cmd
+    };
+    check()
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("同期していません"));
+    assert_eq!(
+        fs::read_to_string(target_root.join("skill1/file.txt")).unwrap(),
+        "old"
+    );
+
+    let mut push = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut push, &config_path);
+    push.args(["push", "--all", "--target", "t1"]);
+    push.assert().success();
+
+    check().assert().success();
+}
+
+#[test]
+fn config_schema_describes_targets_and_hash() {
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    cmd.env("SKILLCTL_CONFIG", "/nonexistent/config.toml");
+    cmd.args(["config", "schema"]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let schema: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    let properties = &schema["properties"];
+    assert_eq!(properties["targets"]["type"], "array");
+    assert!(properties["hash"].is_object());
+    assert!(properties["global_root"].is_object());
+    let required = schema["required"].as_array().unwrap();
+    assert!(required.contains(&"targets".into()));
+}
+
+#[test]
+fn dry_run_env_forces_push_into_dry_run() {
+    let (_root, global_root, target_root, config_path) = setup_fixture();
+
+    write_file(&global_root.join("skill1/file.txt"), "new");
+    write_file(&target_root.join("skill1/file.txt"), "old");
+    write_file(&global_root.join("skill2/file.txt"), "added");
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.env("SKILLCTL_DRY_RUN", "1");
+    cmd.args(["push", "--all", "--target", "t1"]);
+    cmd.assert()
+        .success()
+        .stdout("update skill1\ninstall skill2\n")
+        .stderr(predicate::str::contains("SKILLCTL_DRY_RUN"));
+    assert_eq!(
+        fs::read_to_string(target_root.join("skill1/file.txt")).unwrap(),
+        "old"
+    );
+    assert!(!target_root.join("skill2").exists());
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.env("SKILLCTL_DRY_RUN", "0");
+    cmd.args(["push", "--all", "--target", "t1"]);
+    cmd.assert().success();
+    assert!(target_root.join("skill2").exists());
+}
+
+#[test]
+fn push_target_env_reads_target_name_from_variable() {
+    let (_root, global_root, target_one, target_two, config_path) = setup_fixture_two_targets();
+
+    write_file(&global_root.join("skill1/file.txt"), "global");
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.env("CI_SKILL_TARGET", "t2");
+    cmd.args(["push", "--all", "--target-env", "CI_SKILL_TARGET"]);
+    cmd.assert().success();
+    assert!(target_two.join("skill1/file.txt").exists());
+    assert!(!target_one.join("skill1").exists());
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.env_remove("CI_SKILL_TARGET");
+    cmd.args(["push", "--all", "--target-env", "CI_SKILL_TARGET"]);
+    cmd.assert()
+        .code(3)
+        .stderr(predicate::str::contains("CI_SKILL_TARGET"));
+}
+
+#[test]
+fn push_changed_only_touches_diff_and_missing_skills_only() {
+    let (_root, global_root, target_root, config_path) = setup_fixture();
+
+    write_file(&global_root.join("same/file.txt"), "same");
+    write_file(&target_root.join("same/file.txt"), "same");
+    write_file(&global_root.join("changed/file.txt"), "new");
+    write_file(&target_root.join("changed/file.txt"), "old");
+    write_file(&global_root.join("missing/file.txt"), "added");
+    write_file(&target_root.join("extra/file.txt"), "extra");
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["push", "--all", "--changed-only", "--target", "t1"]);
+    cmd.assert()
+        .success()
+        .stdout("update changed\ninstall missing\n");
+    assert_eq!(
+        fs::read_to_string(target_root.join("changed/file.txt")).unwrap(),
+        "new"
+    );
+    assert!(target_root.join("missing/file.txt").exists());
+    assert!(target_root.join("extra/file.txt").exists());
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args([
+        "push",
+        "--all",
+        "--changed-only",
+        "--prune",
+        "--target",
+        "t1",
+    ]);
+    cmd.assert().code(2);
+}
+
+#[test]
+fn push_prune_dry_run_lists_files_to_delete() {
+    let (_root, global_root, target_root, config_path) = setup_fixture();
+
+    write_file(&global_root.join("kept/file.txt"), "kept");
+    write_file(&target_root.join("kept/file.txt"), "kept");
+    write_file(&target_root.join("extra/SKILL.md"), "extra");
+    write_file(&target_root.join("extra/scripts/run.sh"), "run");
+    write_file(&target_root.join("extra/a.txt"), "a");
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["push", "--all", "--prune", "--dry-run", "--list-files"])
+        .args(["--target", "t1"]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stdout = normalize_output(&output);
+    let listed = stdout
+        .lines()
+        .filter_map(|line| line.strip_prefix("  "))
+        .collect::<Vec<_>>();
+    assert_eq!(listed, ["SKILL.md", "a.txt", "scripts/run.sh"]);
+    assert!(stdout.starts_with("prune extra (3 files, "));
+    assert!(target_root.join("extra/a.txt").exists());
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["push", "--all", "--prune", "--dry-run", "--list-files"])
+        .args(["--porcelain", "--target", "t1"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("file\textra\tscripts/run.sh\n"));
+}
+
+#[test]
+fn push_expect_plan_rejects_changed_plan() {
+    let (_root, global_root, target_root, config_path) = setup_fixture();
+
+    write_file(&global_root.join("skill1/file.txt"), "reviewed");
+
+    let plan_hash = || {
+        let mut cmd = cargo_bin_cmd!("skillctl");
+        set_config_env(&mut cmd, &config_path);
+        cmd.args([
+            "push",
+            "--all",
+            "--target",
+            "t1",
+            "--dry-run",
+            "--plan-hash",
+        ]);
+        let output = cmd.assert().success().get_output().stdout.clone();
+        normalize_output(&output)
+            .lines()
+            .find_map(|line| line.strip_prefix("plan-hash "))
+            .map(str::to_string)
+            .unwrap()
+    };
+    let reviewed = plan_hash();
+    assert_eq!(plan_hash(), reviewed);
+
+    write_file(&global_root.join("skill1/file.txt"), "changed after review");
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args([
+        "push",
+        "--all",
+        "--target",
+        "t1",
+        "--expect-plan",
+        &reviewed,
+    ]);
+    cmd.assert().code(4);
+    assert!(!target_root.join("skill1").exists());
+
+    let current = plan_hash();
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["push", "--all", "--target", "t1", "--expect-plan", &current]);
+    cmd.assert().success();
+    assert!(target_root.join("skill1/file.txt").exists());
+}
+
+#[test]
+fn status_timings_go_to_stderr() {
+    let (_root, global_root, target_root, config_path) = setup_fixture();
+
+    write_file(&global_root.join("skill_a/file.txt"), "a");
+    write_file(&target_root.join("skill_b/file.txt"), "b");
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["status", "--target", "t1", "--timings"]);
+    let assert = cmd.assert().success();
+    let output = assert.get_output();
+    let stdout = normalize_output(&output.stdout);
+    let stderr = normalize_output(&output.stderr);
+
+    assert!(!stdout.contains("ms "));
+    let timed = stderr
+        .lines()
+        .filter(|line| line.contains("ms t1/"))
+        .count();
+    assert_eq!(timed, 2);
+}
+
+#[test]
+fn status_color_flag_controls_ansi_output() {
+    let (_root, global_root, target_root, config_path) = setup_fixture();
+
+    write_file(&global_root.join("skill_diff/file.txt"), "g");
+    write_file(&target_root.join("skill_diff/file.txt"), "t");
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["status", "--target", "t1", "--color", "never"]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    assert!(!normalize_output(&output).contains('\x1B'));
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.env("NO_COLOR", "1");
+    cmd.args(["--color", "always", "status", "--target", "t1"]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stdout = normalize_output(&output);
+    assert!(stdout.contains("\x1B[31mdiff\x1B[0m"));
+    assert!(!stdout.lines().next().unwrap().contains('\x1B'));
+}
+
 #[test]
 fn push_dry_run_snapshot() {
     let (_root, global_root, target_root, config_path) = setup_fixture();
@@ -237,110 +720,1067 @@ fn push_dry_run_snapshot() {
 }
 
 #[test]
-fn status_all_outputs_table_snapshot() {
+fn push_log_level_writes_events_to_stderr_only() {
+    let (_root, global_root, target_root, config_path) = setup_fixture();
+
+    write_file(&global_root.join("skill_diff/file.txt"), "g");
+    write_file(&target_root.join("skill_diff/file.txt"), "t");
+
+    let mut quiet = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut quiet, &config_path);
+    quiet.env_remove("RUST_LOG");
+    quiet.args(["push", "--all", "--target", "t1", "--dry-run"]);
+    let quiet_output = quiet.assert().success().get_output().clone();
+    assert!(quiet_output.stderr.is_empty());
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args([
+        "--log-level",
+        "debug",
+        "push",
+        "--all",
+        "--target",
+        "t1",
+        "--dry-run",
+    ]);
+    let output = cmd.assert().success().get_output().clone();
+    assert_eq!(output.stdout, quiet_output.stdout);
+    let stderr = normalize_output(&output.stderr);
+    assert!(stderr.contains("plan_push"));
+    assert!(stderr.contains("skill_diff"));
+}
+
+#[test]
+fn chdir_resolves_relative_config_and_roots() {
+    let root = TempDir::new().unwrap();
+    let project = root.path().join("project");
+    let elsewhere = root.path().join("elsewhere");
+    fs::create_dir_all(&elsewhere).unwrap();
+    write_file(
+        &project.join("skillctl.toml"),
+        "global_root = \"skills/global\"\n\n[[targets]]\nname = \"t1\"\nroot = \"skills/target\"\n",
+    );
+    write_file(&project.join("skills/global/skill_a/file.txt"), "a");
+    fs::create_dir_all(project.join("skills/target")).unwrap();
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    cmd.current_dir(&elsewhere)
+        .env("SKILLCTL_CONFIG", "skillctl.toml")
+        .env("SKILLCTL_LANG", "ja")
+        .arg("--chdir")
+        .arg(&project)
+        .args(["push", "--all", "--target", "t1"]);
+    cmd.assert().success();
+    assert!(project.join("skills/target/skill_a/file.txt").exists());
+    assert!(!elsewhere.join("skills").exists());
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    cmd.current_dir(&elsewhere)
+        .env("SKILLCTL_CONFIG", "skillctl.toml")
+        .env("SKILLCTL_LANG", "ja")
+        .args(["list", "--global"]);
+    cmd.assert().code(3);
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    cmd.env("SKILLCTL_CONFIG", "skillctl.toml")
+        .env("SKILLCTL_LANG", "ja")
+        .arg("-C")
+        .arg(root.path().join("missing"))
+        .args(["list", "--global"]);
+    cmd.assert().code(3);
+}
+
+#[test]
+fn version_verbose_prints_build_info_without_config() {
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    cmd.env("SKILLCTL_CONFIG", "/nonexistent/skillctl/config.toml");
+    cmd.args(["version", "--verbose"]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stdout = normalize_output(&output);
+    let mut lines = stdout.lines();
+    assert_eq!(
+        lines.next(),
+        Some(format!("skillctl {}", env!("CARGO_PKG_VERSION")).as_str())
+    );
+    assert!(stdout.lines().any(|line| line.starts_with("commit: ")));
+    assert!(stdout.lines().any(|line| line.starts_with("rustc: ")));
+    assert!(stdout.lines().any(|line| line.starts_with("features: ")));
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    cmd.arg("--version");
+    cmd.assert()
+        .success()
+        .stdout(format!("skillctl {}\n", env!("CARGO_PKG_VERSION")));
+}
+
+#[test]
+fn completion_offers_configured_target_names() {
+    let (_root, _global_root, _target_one_root, _target_two_root, config_path) =
+        setup_fixture_two_targets();
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.env("COMPLETE", "fish")
+        .args(["--", "skillctl", "push", "--all", "--target", ""]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let names = normalize_output(&output)
+        .lines()
+        .map(|line| line.split('\t').next().unwrap().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["t1", "t2"]);
+}
+
+#[test]
+fn status_full_digest_prints_whole_digests() {
+    let (_root, global_root, target_root, config_path) = setup_fixture();
+
+    write_file(&global_root.join("skill_a/file.txt"), "g");
+    write_file(&target_root.join("skill_a/file.txt"), "t");
+    write_file(&global_root.join("skill_missing/file.txt"), "m");
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["list", "--global", "--digests"]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let listed = normalize_output(&output);
+    let digest = listed.lines().next().unwrap().split('\t').nth(1).unwrap();
+    assert_eq!(digest.len(), 64);
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["status", "--target", "t1", "--full-digest"]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stdout = normalize_output(&output);
+    assert!(stdout.contains(digest));
+    assert!(!stdout.contains("..."));
+    // The target column starts at the same offset on every line.
+    let lines = stdout.lines().collect::<Vec<_>>();
+    let column = lines[0].find("TARGET_DIGEST").unwrap();
+    for line in &lines[1..] {
+        assert_eq!(line.rfind(' ').map(|at| at + 1), Some(column), "{line}");
+    }
+}
+
+#[test]
+fn status_compare_classifies_two_targets_snapshot() {
+    let (_root, global_root, target_one_root, target_two_root, config_path) =
+        setup_fixture_two_targets();
+
+    // Global is ignored: it holds none of the compared skills.
+    write_file(&global_root.join("skill_global/file.txt"), "g");
+    write_file(&target_one_root.join("skill_same/file.txt"), "same");
+    write_file(&target_two_root.join("skill_same/file.txt"), "same");
+    write_file(&target_one_root.join("skill_diff/file.txt"), "one");
+    write_file(&target_two_root.join("skill_diff/file.txt"), "two");
+    write_file(&target_one_root.join("skill_one/file.txt"), "1");
+    write_file(&target_two_root.join("skill_two/file.txt"), "2");
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["status", "--compare", "t1", "t2"]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stdout = normalize_output(&output);
+    insta::assert_snapshot!(stdout);
+}
+
+#[test]
+fn status_since_sync_shows_skills_changed_after_push() {
+    let (_root, global_root, target_root, config_path) = setup_fixture();
+
+    write_file(&global_root.join("skill_a/file.txt"), "a");
+    write_file(&global_root.join("skill_b/file.txt"), "b");
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["push", "--all", "--target", "t1"]);
+    cmd.assert().success();
+    assert!(target_root.join(".skillctl-synced.toml").exists());
+
+    write_file(&global_root.join("skill_b/file.txt"), "b2");
+    write_file(&global_root.join("skill_c/file.txt"), "c");
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["status", "--target", "t1", "--since-sync"]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let states = normalize_output(&output)
+        .lines()
+        .skip(1)
+        .map(|line| {
+            line.split_whitespace()
+                .take(2)
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        states,
+        [
+            "skill_a unchanged",
+            "skill_b changed",
+            "skill_c never-synced"
+        ]
+    );
+}
+
+#[test]
+fn status_output_writes_table_to_file() {
+    let (root, global_root, target_one_root, target_two_root, config_path) =
+        setup_fixture_two_targets();
+
+    write_file(&global_root.join("skill_a/file.txt"), "a");
+    write_file(&target_one_root.join("skill_a/file.txt"), "a");
+    write_file(&target_two_root.join("skill_b/file.txt"), "b");
+    let out_path = root.path().join("status.txt");
+    fs::write(&out_path, "stale contents that must be truncated\n").unwrap();
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["status", "--all", "--color", "always", "-o"])
+        .arg(&out_path);
+    let output = cmd.assert().success().get_output().clone();
+    assert!(output.stdout.is_empty());
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["status", "--all", "--color", "always"]);
+    let expected = cmd.assert().success().get_output().stdout.clone();
+    assert_eq!(fs::read(&out_path).unwrap(), expected);
+    let written = fs::read_to_string(&out_path).unwrap();
+    assert!(written.contains("skill_a"));
+    assert!(written.contains("skill_b"));
+    assert!(!written.contains("stale"));
+
+    // Errors stay on stderr and do not end up in the file.
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["status", "--target", "nope", "-o"])
+        .arg(&out_path);
+    cmd.assert()
+        .code(3)
+        .stdout(predicate::str::is_empty())
+        .stderr(predicate::str::contains("nope"));
+    assert!(fs::read(&out_path).unwrap().is_empty());
+}
+
+#[test]
+fn wait_root_polls_until_target_root_appears() {
+    let (root, global_root, target_root, config_path) = setup_fixture();
+
+    write_file(&global_root.join("skill_a/file.txt"), "a");
+    fs::remove_dir_all(&target_root).unwrap();
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["status", "--target", "t1"]);
+    cmd.assert().code(3);
+
+    // Build the root aside and rename it in, so it appears complete at once.
+    let staged = root.path().join("staged");
+    write_file(&staged.join("skill_a/file.txt"), "a");
+    let late_root = target_root.clone();
+    let creator = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(500));
+        fs::rename(&staged, &late_root).unwrap();
+    });
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["status", "--target", "t1", "--wait-root", "10"]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    creator.join().unwrap();
+    let row = normalize_output(&output)
+        .lines()
+        .nth(1)
+        .unwrap()
+        .to_string();
+    assert_eq!(row.split_whitespace().nth(1), Some("same"));
+}
+
+#[test]
+fn targets_long_reports_missing_root_without_aborting() {
+    let (_root, _global_root, target_one_root, target_two_root, config_path) =
+        setup_fixture_two_targets();
+
+    write_file(&target_one_root.join("skill_a/file.txt"), "a");
+    write_file(&target_one_root.join("skill_b/file.txt"), "b");
+    fs::remove_dir_all(&target_two_root).unwrap();
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["targets", "--long"]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let lines = normalize_output(&output)
+        .lines()
+        .map(|line| line.split_whitespace().map(str::to_string).collect())
+        .collect::<Vec<Vec<String>>>();
+    assert_eq!(lines[0], ["NAME", "ROOT", "EXISTS", "SKILLS"]);
+    assert_eq!(
+        lines[1],
+        ["t1", &target_one_root.display().to_string(), "yes", "2"]
+    );
+    assert_eq!(
+        lines[2],
+        ["t2", &target_two_root.display().to_string(), "no", "N/A"]
+    );
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["targets", "--format", "json"]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let targets: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(targets[0]["name"], "t1");
+    assert_eq!(targets[0]["exists"], true);
+    assert_eq!(targets[0]["skills"], 2);
+    assert_eq!(targets[1]["name"], "t2");
+    assert_eq!(targets[1]["exists"], false);
+    assert!(targets[1]["skills"].is_null());
+}
+
+#[test]
+fn status_sort_state_groups_rows_per_target() {
+    let (_root, global_root, target_one_root, target_two_root, config_path) =
+        setup_fixture_two_targets();
+
+    write_file(&global_root.join("a_same/file.txt"), "same");
+    write_file(&global_root.join("b_missing/file.txt"), "m");
+    write_file(&global_root.join("c_diff/file.txt"), "g");
+    write_file(&global_root.join("d_diff/file.txt"), "g");
+    write_file(&target_one_root.join("a_same/file.txt"), "same");
+    write_file(&target_one_root.join("c_diff/file.txt"), "t");
+    write_file(&target_one_root.join("d_diff/file.txt"), "t");
+    write_file(&target_one_root.join("a_extra/file.txt"), "e");
+    write_file(&target_two_root.join("a_same/file.txt"), "same");
+    write_file(&target_two_root.join("d_diff/file.txt"), "t");
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["status", "--all", "--sort", "state"]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stdout = normalize_output(&output);
+    let sections = stdout
+        .split("ターゲット: ")
+        .skip(1)
+        .map(|section| {
+            section
+                .lines()
+                .skip(2)
+                .map(|line| {
+                    let mut cells = line.split_whitespace();
+                    let skill = cells.next().unwrap().to_string();
+                    let state = cells.next().unwrap().to_string();
+                    (skill, state)
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let expect = |rows: &[(&str, &str)]| {
+        rows.iter()
+            .map(|(skill, state)| (skill.to_string(), state.to_string()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        sections,
+        vec![
+            expect(&[
+                ("c_diff", "diff"),
+                ("d_diff", "diff"),
+                ("b_missing", "missing"),
+                ("a_extra", "extra"),
+                ("a_same", "same"),
+            ]),
+            expect(&[
+                ("d_diff", "diff"),
+                ("b_missing", "missing"),
+                ("c_diff", "missing"),
+                ("a_same", "same"),
+            ]),
+        ]
+    );
+}
+
+#[test]
+fn status_all_outputs_table_snapshot() {
+    let (_root, global_root, target_one_root, target_two_root, config_path) =
+        setup_fixture_two_targets();
+
+    write_file(&global_root.join("skill_same/file.txt"), "same");
+    write_file(&global_root.join("skill_missing/file.txt"), "m");
+    write_file(&target_one_root.join("skill_same/file.txt"), "same");
+    write_file(&target_one_root.join("skill_diff/file.txt"), "t1");
+    write_file(&global_root.join("skill_diff/file.txt"), "g");
+    write_file(&target_two_root.join("skill_same/file.txt"), "same");
+    write_file(&target_two_root.join("skill_extra/file.txt"), "e");
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.arg("status").arg("--all");
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stdout = normalize_output(&output);
+    insta::assert_snapshot!(stdout);
+}
+
+#[test]
+fn push_execute_converges() {
+    let (_root, global_root, target_root, config_path) = setup_fixture();
+
+    write_file(&global_root.join("skill_missing/file.txt"), "m");
+    write_file(&global_root.join("skill_diff/file.txt"), "g");
+    write_file(&target_root.join("skill_diff/file.txt"), "t");
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.arg("push").arg("--all").arg("--target").arg("t1");
+    cmd.assert().success();
+
+    let missing = fs::read_to_string(target_root.join("skill_missing/file.txt")).unwrap();
+    let diff = fs::read_to_string(target_root.join("skill_diff/file.txt")).unwrap();
+    assert_eq!(missing, "m");
+    assert_eq!(diff, "g");
+}
+
+#[test]
+fn push_ndjson_emits_plan_ops_then_op_done_events() {
+    let (_root, global_root, target_root, config_path) = setup_fixture();
+
+    write_file(&global_root.join("skill_a/file.txt"), "a");
+    write_file(&global_root.join("skill_b/file.txt"), "b");
+    write_file(&global_root.join("skill_same/file.txt"), "s");
+    write_file(&target_root.join("skill_b/file.txt"), "old");
+    write_file(&target_root.join("skill_same/file.txt"), "s");
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["--format", "ndjson", "push", "--all", "--target", "t1"]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let events = normalize_output(&output)
+        .lines()
+        .map(|line| {
+            let value: serde_json::Value = serde_json::from_str(line).unwrap();
+            format!("{} {} {}", value["event"], value["kind"], value["skill"])
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        events,
+        [
+            r#""plan_op" "install" "skill_a""#,
+            r#""plan_op" "update" "skill_b""#,
+            r#""plan_op" "skip" "skill_same""#,
+            r#""op_done" "install" "skill_a""#,
+            r#""op_done" "update" "skill_b""#,
+        ]
+    );
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["--format", "ndjson", "push", "--all", "--target", "nope"]);
+    let output = cmd.assert().code(3).get_output().stdout.clone();
+    let error: serde_json::Value = serde_json::from_str(normalize_output(&output).trim()).unwrap();
+    assert_eq!(error["event"], "error");
+    assert_eq!(error["exit_code"], 3);
+}
+
+#[test]
+fn push_appends_audit_line_per_executed_op() {
+    let (root, global_root, target_root, config_path) = setup_fixture();
+    let audit_path = root.path().join("audit.jsonl");
+    let mut body = fs::read_to_string(&config_path).unwrap();
+    body.push_str(&format!(
+        "\n[audit]\nlog_path = \"{}\"\n",
+        escape_toml_path(&audit_path)
+    ));
+    fs::write(&config_path, body).unwrap();
+
+    write_file(&global_root.join("skill_a/file.txt"), "a");
+    write_file(&global_root.join("skill_b/file.txt"), "b");
+    write_file(&global_root.join("skill_same/file.txt"), "s");
+    write_file(&target_root.join("skill_same/file.txt"), "s");
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["push", "--all", "--target", "t1"]);
+    cmd.assert().success();
+
+    let log = fs::read_to_string(&audit_path).unwrap();
+    let entries = log
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(entries.len(), 2);
+    for (entry, skill) in entries.iter().zip(["skill_a", "skill_b"]) {
+        assert_eq!(entry["command"], "push");
+        assert_eq!(entry["target"], "t1");
+        assert_eq!(entry["skill"], skill);
+        assert_eq!(entry["kind"], "install");
+        assert_eq!(entry["dry_run"], false);
+        assert_eq!(entry["success"], true);
+        assert!(entry["timestamp"].is_u64());
+    }
+}
+
+#[test]
+fn push_multiple_named_skills() {
+    let (_root, global_root, target_root, config_path) = setup_fixture();
+
+    for skill in ["skill_a", "skill_b", "skill_c", "skill_d"] {
+        write_file(&global_root.join(format!("{skill}/file.txt")), skill);
+    }
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["push", "skill_a", "skill_b", "skill_nope", "--target", "t1"]);
+    cmd.assert().code(4);
+    assert!(!target_root.join("skill_a").exists());
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["push", "skill_a", "skill_b", "skill_c", "--target", "t1"]);
+    cmd.assert().success();
+
+    for skill in ["skill_a", "skill_b", "skill_c"] {
+        let copied = fs::read_to_string(target_root.join(format!("{skill}/file.txt"))).unwrap();
+        assert_eq!(copied, skill);
+    }
+    assert!(!target_root.join("skill_d").exists());
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["push", "skill_a", "--all", "--target", "t1"]);
+    cmd.assert().code(2);
+}
+
+#[test]
+fn push_init_global_scaffolds_missing_skill() {
+    let (_root, global_root, target_root, config_path) = setup_fixture();
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["push", "skill_new", "--target", "t1"]);
+    cmd.assert().code(4);
+    assert!(!global_root.join("skill_new").exists());
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args([
+        "push",
+        "skill_new",
+        "--init-global",
+        "--target",
+        "t1",
+        "--dry-run",
+    ]);
+    cmd.assert().code(3);
+    assert!(!global_root.join("skill_new").exists());
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["push", "skill_new", "--init-global", "--target", "t1"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("install skill_new"));
+    let created = fs::read_to_string(global_root.join("skill_new/SKILL.md")).unwrap();
+    assert!(created.contains("name: skill_new"));
+    assert_eq!(
+        fs::read_to_string(target_root.join("skill_new/SKILL.md")).unwrap(),
+        created
+    );
+}
+
+#[test]
+fn skills_from_stdin_selects_push_and_status_skills() {
+    let (_root, global_root, target_root, config_path) = setup_fixture();
+
+    for skill in ["skill_a", "skill_b", "skill_c"] {
+        write_file(&global_root.join(format!("{skill}/file.txt")), skill);
+    }
+    let list = "# from the pipeline\nskill_a\n\nskill_c\n";
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["push", "--skills-from", "-", "--target", "t1"])
+        .write_stdin(list);
+    cmd.assert().success();
+    assert!(target_root.join("skill_a/file.txt").exists());
+    assert!(!target_root.join("skill_b").exists());
+    assert!(target_root.join("skill_c/file.txt").exists());
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["status", "--target", "t1", "--skills-from", "-"])
+        .write_stdin("skill_b\nskill_c\n");
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let skills = normalize_output(&output)
+        .lines()
+        .skip(1)
+        .map(|line| {
+            line.split_whitespace()
+                .take(2)
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(skills, ["skill_b missing", "skill_c same"]);
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["push", "--all", "--skills-from", "-", "--target", "t1"])
+        .write_stdin(list);
+    cmd.assert().code(2);
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["push", "--skills-from", "-", "--target", "t1"])
+        .write_stdin("skill_a\n../escape\n");
+    cmd.assert().code(3);
+}
+
+#[cfg(feature = "interactive")]
+#[test]
+fn push_interactive_requires_terminal() {
+    let (_root, global_root, target_root, config_path) = setup_fixture();
+
+    write_file(&global_root.join("skill_a/file.txt"), "a");
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["push", "--interactive", "--target", "t1"])
+        .write_stdin("");
+    cmd.assert()
+        .code(3)
+        .stderr(predicate::str::contains("--interactive には端末が必要です"));
+    assert!(!target_root.join("skill_a").exists());
+}
+
+#[test]
+fn push_fails_fast_while_target_is_locked() {
+    let (_root, global_root, target_root, config_path) = setup_fixture();
+
+    write_file(&global_root.join("skill_a/file.txt"), "a");
+
+    let lock = skillctl::RootLock::acquire(&target_root, false)
+        .unwrap()
+        .unwrap();
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["push", "skill_a", "--target", "t1"]);
+    cmd.assert()
+        .code(4)
+        .stderr(predicate::str::contains("別の skillctl の操作が実行中です"));
+    assert!(!target_root.join("skill_a").exists());
+
+    // Dry-runs do not mutate, so they do not need the lock.
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["push", "skill_a", "--target", "t1", "--dry-run"]);
+    cmd.assert().success();
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["push", "skill_a", "--target", "t1", "--wait"]);
+    let child = std::thread::spawn(move || cmd.assert().success());
+    std::thread::sleep(std::time::Duration::from_millis(200));
+    assert!(!target_root.join("skill_a").exists());
+    drop(lock);
+    child.join().unwrap();
+    assert!(target_root.join("skill_a/file.txt").exists());
+}
+
+#[test]
+fn push_prune_only_removes_extras_without_copying() {
+    let (_root, global_root, target_root, config_path) = setup_fixture();
+
+    write_file(&global_root.join("skill_same/file.txt"), "same");
+    write_file(&target_root.join("skill_same/file.txt"), "same");
+    write_file(&global_root.join("skill_diff/file.txt"), "global");
+    write_file(&target_root.join("skill_diff/file.txt"), "target");
+    write_file(&global_root.join("skill_new/file.txt"), "new");
+    write_file(&target_root.join("skill_old/file.txt"), "old");
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args([
+        "push",
+        "--prune-only",
+        "--target",
+        "t1",
+        "--dry-run",
+        "--porcelain",
+    ]);
+    cmd.assert()
+        .success()
+        .stdout("prune\tskill_old\t1 file, 3 B\n");
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["push", "--prune-only", "--target", "t1"]);
+    cmd.assert().success();
+    assert!(!target_root.join("skill_old").exists());
+    assert!(!target_root.join("skill_new").exists());
+    assert_eq!(
+        fs::read_to_string(target_root.join("skill_diff/file.txt")).unwrap(),
+        "target"
+    );
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["push", "skill_same", "--prune-only", "--target", "t1"]);
+    cmd.assert().code(2);
+}
+
+#[test]
+fn push_applies_by_default_and_rejects_apply_with_dry_run() {
+    let (_root, global_root, target_root, config_path) = setup_fixture();
+
+    write_file(&global_root.join("skill_a/file.txt"), "a");
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["push", "skill_a", "--target", "t1", "--dry-run", "--apply"]);
+    cmd.assert().code(2);
+    assert!(!target_root.join("skill_a").exists());
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["push", "skill_a", "--target", "t1"]);
+    cmd.assert().success();
+    assert!(target_root.join("skill_a/file.txt").exists());
+}
+
+#[test]
+fn require_apply_makes_push_and_import_dry_run() {
+    let (_root, global_root, target_root, config_path) = setup_fixture();
+    let mut body = fs::read_to_string(&config_path).unwrap();
+    body.push_str("\n[sync]\nrequire_apply = true\n");
+    fs::write(&config_path, body).unwrap();
+
+    write_file(&global_root.join("skill_a/file.txt"), "a");
+    write_file(&target_root.join("skill_b/file.txt"), "b");
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["push", "skill_a", "--target", "t1"]);
+    let output = cmd.assert().success().get_output().clone();
+    assert_eq!(normalize_output(&output.stdout), "install skill_a\n");
+    assert!(normalize_output(&output.stderr).contains("--apply"));
+    assert!(!target_root.join("skill_a").exists());
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["import", "skill_b", "--from", "t1"]);
+    cmd.assert().success();
+    assert!(!global_root.join("skill_b").exists());
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["push", "skill_a", "--target", "t1", "--apply"]);
+    cmd.assert().success();
+    assert!(target_root.join("skill_a/file.txt").exists());
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["import", "skill_b", "--from", "t1", "--apply"]);
+    cmd.assert().success();
+    assert!(global_root.join("skill_b/file.txt").exists());
+}
+
+#[test]
+fn import_execute_add_only() {
+    let (_root, global_root, target_root, config_path) = setup_fixture();
+
+    write_file(&global_root.join("skill_keep/file.txt"), "global");
+    write_file(&target_root.join("skill_keep/file.txt"), "target");
+    write_file(&target_root.join("skill_extra/file.txt"), "e");
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.arg("import").arg("--all").arg("--from").arg("t1");
+    cmd.assert().success();
+
+    let keep = fs::read_to_string(global_root.join("skill_keep/file.txt")).unwrap();
+    let extra = fs::read_to_string(global_root.join("skill_extra/file.txt")).unwrap();
+    assert_eq!(keep, "global");
+    assert_eq!(extra, "e");
+}
+
+#[test]
+fn import_warn_skips_summarizes_diff_skips() {
+    let (_root, global_root, target_root, config_path) = setup_fixture();
+
+    write_file(&global_root.join("skill_a/file.txt"), "global");
+    write_file(&target_root.join("skill_a/file.txt"), "target");
+    write_file(&global_root.join("skill_b/file.txt"), "global");
+    write_file(&target_root.join("skill_b/file.txt"), "target");
+    write_file(&global_root.join("skill_same/file.txt"), "same");
+    write_file(&target_root.join("skill_same/file.txt"), "same");
+    write_file(&target_root.join("skill_new/file.txt"), "n");
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["import", "--all", "--from", "t1", "--warn-skips"]);
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "2 件の skill をスキップしました: skill_a (diff), skill_b (diff)",
+        ))
+        .stderr(predicate::str::contains("skill_same").not());
+
+    let new = fs::read_to_string(global_root.join("skill_new/file.txt")).unwrap();
+    assert_eq!(new, "n");
+}
+
+#[test]
+fn doctor_global_snapshot() {
+    let (_root, global_root, _target_root, config_path) = setup_fixture();
+
+    write_file(&global_root.join("a_missing/notes.txt"), "x");
+    write_file(&global_root.join("b_ok/SKILL.md"), "ok");
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.arg("doctor").arg("--global");
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stdout = normalize_output(&output);
+    insta::assert_snapshot!(stdout);
+}
+
+#[test]
+fn doctor_target_reports_missing_skill_md() {
+    let (_root, global_root, target_root, config_path) = setup_fixture();
+
+    write_file(&global_root.join("a_missing/SKILL.md"), "ok");
+    write_file(&target_root.join("a_missing/notes.txt"), "x");
+    write_file(&target_root.join("b_ok/SKILL.md"), "ok");
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.env("SKILLCTL_LANG", "en");
+    cmd.args(["doctor", "--target", "t1"]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stdout = normalize_output(&output);
+
+    let lines = stdout.lines().collect::<Vec<_>>();
+    assert_eq!(lines[0], "Target: t1");
+    assert!(lines[1].starts_with("issue a_missing "));
+    assert!(lines[1].contains("SKILL.md"));
+    assert_eq!(lines[2], "ok b_ok");
+    assert_eq!(lines[3], "checked: 2 issues: 1");
+}
+
+#[test]
+fn doctor_fail_on_threshold_controls_exit_code() {
+    use std::os::unix::fs::symlink;
+
+    let (_root, global_root, _target_root, config_path) = setup_fixture();
+
+    write_file(&global_root.join("linked/SKILL.md"), "ok");
+    symlink(
+        global_root.join("linked/SKILL.md"),
+        global_root.join("linked/alias.md"),
+    )
+    .unwrap();
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["doctor", "--global", "--fail-on", "error"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("issue linked [warning] "));
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["doctor", "--global", "--fail-on", "warning"]);
+    cmd.assert().code(4).stderr(predicate::str::contains(
+        "warning 以上の問題が 1 件あります",
+    ));
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["--format", "json", "doctor", "--global"]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let reports: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(reports[0]["scope"], "global");
+    assert_eq!(reports[0]["issues"][0]["kind"], "symlink");
+    assert_eq!(reports[0]["issues"][0]["severity"], "warning");
+}
+
+#[test]
+fn doctor_all_snapshot() {
     let (_root, global_root, target_one_root, target_two_root, config_path) =
         setup_fixture_two_targets();
 
-    write_file(&global_root.join("skill_same/file.txt"), "same");
-    write_file(&global_root.join("skill_missing/file.txt"), "m");
-    write_file(&target_one_root.join("skill_same/file.txt"), "same");
-    write_file(&target_one_root.join("skill_diff/file.txt"), "t1");
-    write_file(&global_root.join("skill_diff/file.txt"), "g");
-    write_file(&target_two_root.join("skill_same/file.txt"), "same");
-    write_file(&target_two_root.join("skill_extra/file.txt"), "e");
+    write_file(&global_root.join("unused/SKILL.md"), "ok");
+    write_file(&target_one_root.join("a_missing/notes.txt"), "x");
+    write_file(&target_one_root.join("b_ok/SKILL.md"), "ok");
+    write_file(&target_two_root.join("c_ok/SKILL.md"), "ok");
 
     let mut cmd = cargo_bin_cmd!("skillctl");
     set_config_env(&mut cmd, &config_path);
-    cmd.arg("status").arg("--all");
+    cmd.arg("doctor").arg("--all");
     let output = cmd.assert().success().get_output().stdout.clone();
     let stdout = normalize_output(&output);
     insta::assert_snapshot!(stdout);
 }
 
 #[test]
-fn push_execute_converges() {
-    let (_root, global_root, target_root, config_path) = setup_fixture();
+fn diff_exit_code_one_is_success() {
+    let diff_command = diff_exit_one_command();
+    let (_root, global_root, target_root, config_path) =
+        setup_fixture_with_diff_command(&diff_command);
 
-    write_file(&global_root.join("skill_missing/file.txt"), "m");
     write_file(&global_root.join("skill_diff/file.txt"), "g");
     write_file(&target_root.join("skill_diff/file.txt"), "t");
 
     let mut cmd = cargo_bin_cmd!("skillctl");
     set_config_env(&mut cmd, &config_path);
-    cmd.arg("push").arg("--all").arg("--target").arg("t1");
+    cmd.arg("diff").arg("skill_diff").arg("--target").arg("t1");
     cmd.assert().success();
-
-    let missing = fs::read_to_string(target_root.join("skill_missing/file.txt")).unwrap();
-    let diff = fs::read_to_string(target_root.join("skill_diff/file.txt")).unwrap();
-    assert_eq!(missing, "m");
-    assert_eq!(diff, "g");
 }
 
 #[test]
-fn import_execute_add_only() {
-    let (_root, global_root, target_root, config_path) = setup_fixture();
+fn diff_dry_run_prints_resolved_command() {
+    let (_root, global_root, target_root, config_path) =
+        setup_fixture_with_diff_command(&["__no_such_command__", "{left}", "{right}"]);
 
-    write_file(&global_root.join("skill_keep/file.txt"), "global");
-    write_file(&target_root.join("skill_keep/file.txt"), "target");
-    write_file(&target_root.join("skill_extra/file.txt"), "e");
+    write_file(&global_root.join("skill_diff/file.txt"), "g");
+    write_file(&target_root.join("skill_diff/file.txt"), "t");
 
     let mut cmd = cargo_bin_cmd!("skillctl");
     set_config_env(&mut cmd, &config_path);
-    cmd.arg("import").arg("--all").arg("--from").arg("t1");
-    cmd.assert().success();
-
-    let keep = fs::read_to_string(global_root.join("skill_keep/file.txt")).unwrap();
-    let extra = fs::read_to_string(global_root.join("skill_extra/file.txt")).unwrap();
-    assert_eq!(keep, "global");
-    assert_eq!(extra, "e");
+    cmd.arg("diff")
+        .arg("skill_diff")
+        .arg("--target")
+        .arg("t1")
+        .arg("--dry-run");
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stdout = normalize_output(&output);
+    let left = global_root.join("skill_diff");
+    let right = target_root.join("skill_diff");
+    assert!(stdout.starts_with("__no_such_command__ "));
+    assert!(stdout.contains(left.to_string_lossy().as_ref()));
+    assert!(stdout.contains(right.to_string_lossy().as_ref()));
 }
 
 #[test]
-fn doctor_global_snapshot() {
-    let (_root, global_root, _target_root, config_path) = setup_fixture();
+fn diff_dry_run_prints_command_for_identical_skill() {
+    let (_root, global_root, target_root, config_path) =
+        setup_fixture_with_diff_command(&["__no_such_command__", "{left}", "{right}"]);
 
-    write_file(&global_root.join("a_missing/notes.txt"), "x");
-    write_file(&global_root.join("b_ok/SKILL.md"), "ok");
+    write_file(&global_root.join("skill_same/file.txt"), "x");
+    write_file(&target_root.join("skill_same/file.txt"), "x");
 
     let mut cmd = cargo_bin_cmd!("skillctl");
     set_config_env(&mut cmd, &config_path);
-    cmd.arg("doctor").arg("--global");
+    cmd.args(["diff", "skill_same", "--target", "t1", "--dry-run"])
+        .arg("--summary-only");
     let output = cmd.assert().success().get_output().stdout.clone();
-    let stdout = normalize_output(&output);
-    insta::assert_snapshot!(stdout);
+    assert!(normalize_output(&output).starts_with("__no_such_command__ "));
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["--format", "ndjson", "diff", "skill_same", "--target", "t1"])
+        .args(["--dry-run", "--summary-only"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("\"event\":\"diff_command\""));
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["--format", "ndjson", "diff", "skill_same", "--target", "t1"])
+        .arg("--summary-only");
+    cmd.assert().success().stdout(predicate::str::contains(
+        "\"event\":\"diff\",\"skill\":\"skill_same\",\"differs\":false",
+    ));
+
+    // Without --summary-only the command always runs.
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["diff", "skill_same", "--target", "t1"]);
+    cmd.assert().code(4);
 }
 
 #[test]
-fn doctor_all_snapshot() {
-    let (_root, global_root, target_one_root, target_two_root, config_path) =
-        setup_fixture_two_targets();
+fn diff_file_compares_single_changed_file() {
+    let (_root, global_root, target_root, config_path) =
+        setup_fixture_with_diff_command(&["__no_such_command__", "{left}", "{right}"]);
 
-    write_file(&global_root.join("unused/SKILL.md"), "ok");
-    write_file(&target_one_root.join("a_missing/notes.txt"), "x");
-    write_file(&target_one_root.join("b_ok/SKILL.md"), "ok");
-    write_file(&target_two_root.join("c_ok/SKILL.md"), "ok");
+    write_file(&global_root.join("skill_diff/same.txt"), "same");
+    write_file(&target_root.join("skill_diff/same.txt"), "same");
+    write_file(&global_root.join("skill_diff/docs/file.txt"), "g");
+    write_file(&target_root.join("skill_diff/docs/file.txt"), "t");
+    write_file(&target_root.join("skill_diff/extra.txt"), "t");
 
     let mut cmd = cargo_bin_cmd!("skillctl");
     set_config_env(&mut cmd, &config_path);
-    cmd.arg("doctor").arg("--all");
+    cmd.args(["diff", "skill_diff", "--target", "t1", "--dry-run"])
+        .args(["--file", "docs/file.txt"]);
     let output = cmd.assert().success().get_output().stdout.clone();
     let stdout = normalize_output(&output);
-    insta::assert_snapshot!(stdout);
+    let left = global_root.join("skill_diff").join("docs/file.txt");
+    let right = target_root.join("skill_diff").join("docs/file.txt");
+    assert!(stdout.contains(left.to_string_lossy().as_ref()));
+    assert!(stdout.contains(right.to_string_lossy().as_ref()));
+    assert!(!stdout.contains("same.txt"));
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args([
+        "diff",
+        "skill_diff",
+        "--target",
+        "t1",
+        "--file",
+        "extra.txt",
+    ]);
+    cmd.assert().success().stdout("added extra.txt\n");
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["diff", "skill_diff", "--target", "t1", "--file", "../x"]);
+    cmd.assert().code(3);
 }
 
 #[test]
-fn diff_exit_code_one_is_success() {
-    let diff_command = diff_exit_one_command();
-    let (_root, global_root, target_root, config_path) =
-        setup_fixture_with_diff_command(&diff_command);
+fn global_root_override_compares_ad_hoc_snapshot() {
+    let (root, global_root, target_root, config_path) =
+        setup_fixture_with_diff_command(&["__no_such_command__", "{left}", "{right}"]);
+    let snapshot = root.path().join("home/snapshot");
 
-    write_file(&global_root.join("skill_diff/file.txt"), "g");
-    write_file(&target_root.join("skill_diff/file.txt"), "t");
+    write_file(&global_root.join("skill_a/file.txt"), "t");
+    write_file(&target_root.join("skill_a/file.txt"), "t");
+    write_file(&snapshot.join("skill_a/file.txt"), "snapshot");
 
     let mut cmd = cargo_bin_cmd!("skillctl");
     set_config_env(&mut cmd, &config_path);
-    cmd.arg("diff").arg("skill_diff").arg("--target").arg("t1");
-    cmd.assert().success();
+    cmd.env("HOME", root.path().join("home")).args([
+        "status",
+        "--target",
+        "t1",
+        "--global-root",
+        "~/snapshot",
+    ]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stdout = normalize_output(&output);
+    let row = stdout
+        .lines()
+        .find(|line| line.starts_with("skill_a"))
+        .unwrap();
+    assert_eq!(row.split_whitespace().nth(1), Some("diff"));
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args([
+        "diff",
+        "skill_a",
+        "--target",
+        "t1",
+        "--dry-run",
+        "--global-root",
+    ])
+    .arg(&snapshot);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stdout = normalize_output(&output);
+    assert!(stdout.contains(snapshot.join("skill_a").to_string_lossy().as_ref()));
+    assert!(stdout.contains(target_root.join("skill_a").to_string_lossy().as_ref()));
 }
 
 #[test]
@@ -356,6 +1796,28 @@ fn diff_rejects_invalid_skill_cli() {
         .stderr(predicate::str::contains("skill が不正です"));
 }
 
+#[test]
+fn unknown_skill_suggests_near_miss() {
+    let (_root, global_root, target_root, config_path) = setup_fixture();
+
+    write_file(&global_root.join("code-review/file.txt"), "g");
+    write_file(&target_root.join("code-review/file.txt"), "t");
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["push", "code-reveiw", "--target", "t1"]);
+    cmd.assert()
+        .code(4)
+        .stderr(predicate::str::contains("もしかして: code-review"));
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["diff", "code-rev", "--target", "t1"]);
+    cmd.assert()
+        .code(4)
+        .stderr(predicate::str::contains("もしかして: code-review"));
+}
+
 #[test]
 fn status_rejects_unknown_target() {
     let (_root, _global_root, _target_root, config_path) = setup_fixture();
@@ -382,3 +1844,319 @@ fn status_errors_when_config_missing() {
         .code(3)
         .stderr(predicate::str::contains("設定ファイルが見つかりません"));
 }
+
+#[test]
+fn import_explain_says_why_diff_is_skipped() {
+    let (_root, global_root, target_root, config_path) = setup_fixture();
+
+    write_file(&global_root.join("skill_x/file.txt"), "global");
+    write_file(&target_root.join("skill_x/file.txt"), "target");
+    write_file(&target_root.join("skill_y/file.txt"), "y");
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.env("SKILLCTL_LANG", "en");
+    cmd.args(["import", "--all", "--from", "t1", "--dry-run", "--explain"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "skip skill_x (diff: target and global differ; use --overwrite to replace)\n",
+        ))
+        .stdout(predicate::str::contains(
+            "install skill_y (not present in global)\n",
+        ));
+}
+
+#[test]
+fn import_rename_map_imports_foo_as_bar() {
+    let (root, global_root, target_root, config_path) = setup_fixture();
+
+    write_file(&target_root.join("foo/SKILL.md"), "foo");
+    write_file(&target_root.join("keep/SKILL.md"), "keep");
+    let map_path = root.path().join("renames.txt");
+    write_file(&map_path, "# target=global\nfoo=bar\n");
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["import", "--all", "--from", "t1", "--rename-map"])
+        .arg(&map_path);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("install bar (from foo)"));
+    assert_eq!(
+        fs::read_to_string(global_root.join("bar/SKILL.md")).unwrap(),
+        "foo"
+    );
+    assert!(!global_root.join("foo").exists());
+    assert!(global_root.join("keep/SKILL.md").is_file());
+
+    write_file(&map_path, "foo=keep\n");
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.env("SKILLCTL_LANG", "en");
+    cmd.args([
+        "import",
+        "--all",
+        "--from",
+        "t1",
+        "--dry-run",
+        "--rename-map",
+    ])
+    .arg(&map_path);
+    cmd.assert().code(3).stderr(predicate::str::contains(
+        "foo and keep would both be imported into global as keep",
+    ));
+}
+
+#[test]
+fn digest_prints_same_digest_as_digest_dir() {
+    use skillctl::{DigestOptions, HashAlgo, digest_dir};
+
+    let (_root, global_root, target_root, config_path) = setup_fixture();
+
+    write_file(&global_root.join("skill_a/SKILL.md"), "global");
+    write_file(&target_root.join("skill_a/SKILL.md"), "target");
+    write_file(&target_root.join("skill_a/notes/a.txt"), "a");
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["digest", "skill_a", "--target", "t1", "--algo", "sha256"]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let expected = digest_dir(
+        &target_root.join("skill_a"),
+        &DigestOptions::new(HashAlgo::Sha256),
+    )
+    .unwrap();
+    assert_eq!(normalize_output(&output), format!("{expected}\n"));
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["digest", "skill_a", "--global"]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let expected = digest_dir(
+        &global_root.join("skill_a"),
+        &DigestOptions::new(HashAlgo::Blake3),
+    )
+    .unwrap();
+    assert_eq!(normalize_output(&output), format!("{expected}\n"));
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["digest", "skill_missing", "--global"]);
+    cmd.assert().code(4);
+}
+
+#[test]
+fn bump_minor_rewrites_only_the_front_matter_version() {
+    let (_root, global_root, _target_root, config_path) = setup_fixture();
+
+    let skill_file = global_root.join("skill_a/SKILL.md");
+    write_file(
+        &skill_file,
+        "---\nname: skill_a\nversion: 1.2.3\ndescription: demo\n---\n\nversion: 1.2.3 stays\n",
+    );
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["bump", "skill_a", "--global", "--minor", "--dry-run"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("1.2.3 -> 1.3.0"));
+    assert!(
+        fs::read_to_string(&skill_file)
+            .unwrap()
+            .contains("version: 1.2.3\n")
+    );
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["bump", "skill_a", "--global", "--minor"]);
+    cmd.assert().success();
+    assert_eq!(
+        fs::read_to_string(&skill_file).unwrap(),
+        "---\nname: skill_a\nversion: 1.3.0\ndescription: demo\n---\n\nversion: 1.2.3 stays\n"
+    );
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.env("SKILLCTL_LANG", "en").env("SKILLCTL_DRY_RUN", "1");
+    cmd.args(["bump", "skill_a", "--global"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("1.3.0 -> 1.3.1"))
+        .stderr(predicate::str::contains(
+            "Running as dry-run because SKILLCTL_DRY_RUN is set",
+        ));
+    assert!(
+        fs::read_to_string(&skill_file)
+            .unwrap()
+            .contains("version: 1.3.0\n")
+    );
+
+    write_file(
+        &global_root.join("skill_b/SKILL.md"),
+        "---\nname: skill_b\n---\n",
+    );
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.env("SKILLCTL_LANG", "en");
+    cmd.args(["bump", "skill_b", "--global"]);
+    cmd.assert()
+        .code(4)
+        .stderr(predicate::str::contains("No version in the front matter"));
+}
+
+#[test]
+fn import_from_all_reports_conflicting_skills() {
+    let (_root, global_root, target_one_root, target_two_root, config_path) =
+        setup_fixture_two_targets();
+
+    write_file(&target_one_root.join("skill_shared/file.txt"), "one");
+    write_file(&target_two_root.join("skill_shared/file.txt"), "two");
+    write_file(&target_one_root.join("skill_same/file.txt"), "same");
+    write_file(&target_two_root.join("skill_same/file.txt"), "same");
+    write_file(&target_two_root.join("skill_two/file.txt"), "t2");
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.env("SKILLCTL_LANG", "en");
+    cmd.args(["import", "--all", "--from-all"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("skip skill_shared (conflict)\n"))
+        .stdout(predicate::str::contains("install skill_same (from t1)\n"))
+        .stdout(predicate::str::contains("install skill_two (from t2)\n"))
+        .stderr(predicate::str::contains(
+            "warning: skipping skill skill_shared: its content differs between targets t1, t2",
+        ));
+    assert!(!global_root.join("skill_shared").exists());
+    assert_eq!(
+        fs::read_to_string(global_root.join("skill_two/file.txt")).unwrap(),
+        "t2"
+    );
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["import", "--all", "--from-all", "--prefer", "t2"]);
+    cmd.assert().success();
+    assert_eq!(
+        fs::read_to_string(global_root.join("skill_shared/file.txt")).unwrap(),
+        "two"
+    );
+}
+
+#[test]
+fn status_format_csv_prints_full_digests_with_header() {
+    let (_root, global_root, target_root, config_path) = setup_fixture();
+
+    write_file(&global_root.join("skill_same/file.txt"), "same");
+    write_file(&target_root.join("skill_same/file.txt"), "same");
+    write_file(&global_root.join("skill_missing/file.txt"), "g");
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["status", "--target", "t1", "--format", "csv"]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    assert!(
+        String::from_utf8_lossy(&output).starts_with("SKILL,STATE,GLOBAL_DIGEST,TARGET_DIGEST\r\n")
+    );
+    let stdout = normalize_output(&output);
+    let lines = stdout.lines().collect::<Vec<_>>();
+    assert_eq!(lines[0], "SKILL,STATE,GLOBAL_DIGEST,TARGET_DIGEST");
+    let missing = lines[1].split(',').collect::<Vec<_>>();
+    assert_eq!(missing[..2], ["skill_missing", "missing"]);
+    assert_eq!(missing[2].len(), 64);
+    assert_eq!(missing[3], "");
+    let same = lines[2].split(',').collect::<Vec<_>>();
+    assert_eq!(same[..2], ["skill_same", "same"]);
+    assert_eq!(same[2], same[3]);
+    assert!(!stdout.contains("..."));
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["list", "--global", "--format", "csv"]);
+    cmd.assert().code(3);
+}
+
+#[test]
+fn status_all_format_csv_adds_target_column() {
+    let (_root, global_root, target_one_root, target_two_root, config_path) =
+        setup_fixture_two_targets();
+
+    write_file(&global_root.join("skill_a/file.txt"), "a");
+    write_file(&target_one_root.join("skill_a/file.txt"), "a");
+    write_file(&target_two_root.join("skill_a/file.txt"), "changed");
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["status", "--all", "--format", "csv"]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stdout = normalize_output(&output);
+    let records = stdout
+        .lines()
+        .map(|line| line.split(',').take(3).collect::<Vec<_>>().join(","))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        records,
+        ["TARGET,SKILL,STATE", "t1,skill_a,same", "t2,skill_a,diff"]
+    );
+}
+
+#[test]
+fn profile_limits_status_all_to_its_targets() {
+    let (_root, global_root, target_one_root, target_two_root, config_path) =
+        setup_fixture_two_targets();
+    let mut body = fs::read_to_string(&config_path).unwrap();
+    body.push_str("\n[profiles.work]\ntargets = [\"t1\"]\n");
+    fs::write(&config_path, body).unwrap();
+
+    write_file(&global_root.join("skill_a/file.txt"), "a");
+    write_file(&target_one_root.join("skill_a/file.txt"), "a");
+    fs::remove_dir_all(&target_two_root).unwrap();
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.env("SKILLCTL_LANG", "en");
+    cmd.args(["status", "--all", "--profile", "work"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Target: t1"))
+        .stdout(predicate::str::contains("Target: t2").not());
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["targets", "--profile", "work"]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    assert_eq!(normalize_output(&output), "t1\n");
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["status", "--all", "--profile", "home"]);
+    cmd.assert().code(3);
+}
+
+#[cfg(not(windows))]
+#[test]
+fn diff_batch_gives_command_eof_on_stdin() {
+    let script = "if read line; then echo \"read $line\"; else echo eof; fi; echo note >&2; exit 1";
+    let (_root, global_root, target_root, config_path) =
+        setup_fixture_with_diff_command(&["sh", "-c", script, "sh", "{left}", "{right}"]);
+
+    write_file(&global_root.join("skill_diff/file.txt"), "g");
+    write_file(&target_root.join("skill_diff/file.txt"), "t");
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["diff", "skill_diff", "--target", "t1", "--batch"]);
+    cmd.write_stdin("input\n");
+    cmd.assert()
+        .success()
+        .stdout("eof\n")
+        .stderr(predicate::str::contains("note"));
+
+    let mut cmd = cargo_bin_cmd!("skillctl");
+    set_config_env(&mut cmd, &config_path);
+    cmd.args(["diff", "skill_diff", "--target", "t1"]);
+    cmd.write_stdin("input\n");
+    cmd.assert().success().stdout("read input\n");
+}